@@ -214,6 +214,19 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		);
 	}
 
+	let light_client_fetcher = sc_service::build_light_client_fetcher(
+		&config,
+		client.clone(),
+		network.clone(),
+		NativeElseWasmExecutor::<ExecutorDispatch>::new(
+			config.wasm_method,
+			config.default_heap_pages,
+			config.max_runtime_instances,
+			config.runtime_cache_size,
+		),
+		task_manager.spawn_handle(),
+	);
+
 	let role = config.role.clone();
 	let force_authoring = config.force_authoring;
 	let backoff_authoring_blocks: Option<()> = None;
@@ -243,6 +256,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		system_rpc_tx,
 		config,
 		telemetry: telemetry.as_mut(),
+		light_client_fetcher,
 	})?;
 
 	if role.is_authority() {
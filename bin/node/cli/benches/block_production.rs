@@ -100,7 +100,13 @@ fn new_node(tokio_handle: Handle) -> node_cli::service::NewFullBase {
 		prometheus_config: None,
 		telemetry_endpoints: None,
 		default_heap_pages: None,
-		offchain_worker: OffchainWorkerConfig { enabled: true, indexing_enabled: false },
+		offchain_worker: OffchainWorkerConfig {
+			enabled: true,
+			indexing_enabled: false,
+			http_policy: Default::default(),
+			scheduling: Default::default(),
+			http_backend: None,
+		},
 		force_authoring: false,
 		disable_grandpa: false,
 		dev_key_seed: Some(Sr25519Keyring::Alice.to_seed()),
@@ -112,9 +118,10 @@ fn new_node(tokio_handle: Handle) -> node_cli::service::NewFullBase {
 		base_path: Some(base_path),
 		informant_output_format: Default::default(),
 		wasm_runtime_overrides: None,
+		runtime_call_recording: None,
 	};
 
-	node_cli::service::new_full_base(config, false, |_, _| ())
+	node_cli::service::new_full_base(config, Default::default(), |_, _| ())
 		.expect("creating a full node doesn't fail")
 }
 
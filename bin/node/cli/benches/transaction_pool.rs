@@ -92,7 +92,13 @@ fn new_node(tokio_handle: Handle) -> node_cli::service::NewFullBase {
 		prometheus_config: None,
 		telemetry_endpoints: None,
 		default_heap_pages: None,
-		offchain_worker: OffchainWorkerConfig { enabled: true, indexing_enabled: false },
+		offchain_worker: OffchainWorkerConfig {
+			enabled: true,
+			indexing_enabled: false,
+			http_policy: Default::default(),
+			scheduling: Default::default(),
+			http_backend: None,
+		},
 		force_authoring: false,
 		disable_grandpa: false,
 		dev_key_seed: Some(Sr25519Keyring::Alice.to_seed()),
@@ -104,9 +110,10 @@ fn new_node(tokio_handle: Handle) -> node_cli::service::NewFullBase {
 		base_path: Some(base_path),
 		informant_output_format: Default::default(),
 		wasm_runtime_overrides: None,
+		runtime_call_recording: None,
 	};
 
-	node_cli::service::new_full_base(config, false, |_, _| ()).expect("Creates node")
+	node_cli::service::new_full_base(config, Default::default(), |_, _| ()).expect("Creates node")
 }
 
 fn create_accounts(num: usize) -> Vec<sr25519::Pair> {
@@ -27,7 +27,7 @@ use node_runtime::{
 	StakerStatus, StakingConfig, SudoConfig, SystemConfig, TechnicalCommitteeConfig,
 };
 use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
-use sc_chain_spec::ChainSpecExtension;
+use sc_chain_spec::{ChainSpecExtension, GenesisPresets};
 use sc_service::ChainType;
 use sc_telemetry::TelemetryEndpoints;
 use serde::{Deserialize, Serialize};
@@ -59,6 +59,10 @@ pub struct Extensions {
 	pub bad_blocks: sc_client_api::BadBlocks<Block>,
 	/// The light sync state extension used by the sync-state rpc.
 	pub light_sync_state: sc_sync_state_rpc::LightSyncStateExtension,
+	/// The hardware requirements for authorities of this chain.
+	///
+	/// The Substrate reference hardware is used if not set.
+	pub hardware_requirements: Option<sc_sysinfo::Requirements>,
 }
 
 /// Specialized `ChainSpec`.
@@ -417,6 +421,14 @@ pub fn local_testnet_config() -> ChainSpec {
 	)
 }
 
+/// The genesis presets of the node, named like the corresponding `--chain` values.
+pub fn presets() -> GenesisPresets<GenesisConfig, Extensions> {
+	GenesisPresets::new()
+		.with_preset("dev", development_config)
+		.with_preset("local", local_testnet_config)
+		.with_preset("staging", staging_testnet_config)
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
 	use super::*;
@@ -472,7 +484,7 @@ pub(crate) mod tests {
 
 		sc_service_test::connectivity(integration_test_config_with_two_authorities(), |config| {
 			let NewFullBase { task_manager, client, network, transaction_pool, .. } =
-				new_full_base(config, false, |_, _| ())?;
+				new_full_base(config, Default::default(), |_, _| ())?;
 			Ok(sc_service_test::TestNetComponents::new(
 				task_manager,
 				client,
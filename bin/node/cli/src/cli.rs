@@ -36,6 +36,21 @@ pub struct Cli {
 	/// telemetry, if telemetry is enabled.
 	#[clap(long)]
 	pub no_hardware_benchmarks: bool,
+
+	/// Path to a JSON file with the hardware requirements to check the hardware benchmarks
+	/// against.
+	///
+	/// Overrides the requirements of the chain spec, which default to the Substrate reference
+	/// hardware. The file uses the format of the requirements of `benchmark machine`, and the
+	/// requirements are checked with the same default fault tolerance of 10%.
+	#[clap(long, value_name = "PATH", conflicts_with = "no-hardware-benchmarks")]
+	pub hardware_requirements: Option<std::path::PathBuf>,
+
+	/// Refuse to start as an authority if the hardware does not meet the requirements.
+	///
+	/// By default a warning is logged instead.
+	#[clap(long, conflicts_with = "no-hardware-benchmarks")]
+	pub enforce_hardware_requirements: bool,
 }
 
 /// Possible subcommands of the main binary.
@@ -86,6 +101,13 @@ pub enum Subcommand {
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Compare the genesis state of two chain specs.
+	DiffSpec(substrate_frame_cli::DiffSpecCmd),
+
+	/// Database inspection utilities.
+	#[clap(subcommand)]
+	Db(substrate_frame_cli::DbSubcommand),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
@@ -97,4 +119,7 @@ pub enum Subcommand {
 
 	/// Db meta columns information.
 	ChainInfo(sc_cli::ChainInfoCmd),
+
+	/// Execute a runtime call recorded with `--record-runtime-calls` against its storage proof.
+	ReplayCall(sc_cli::ReplayCallCmd),
 }
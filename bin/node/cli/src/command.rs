@@ -27,9 +27,10 @@ use node_executor::ExecutorDispatch;
 use node_primitives::Block;
 use node_runtime::RuntimeApi;
 use sc_cli::{ChainSpec, Result, RuntimeVersion, SubstrateCli};
+use sc_executor::NativeElseWasmExecutor;
 use sc_service::PartialComponents;
 
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 impl SubstrateCli for Cli {
 	fn impl_name() -> String {
@@ -86,8 +87,17 @@ pub fn run() -> Result<()> {
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
 			runner.run_node_until_exit(|config| async move {
-				service::new_full(config, cli.no_hardware_benchmarks)
-					.map_err(sc_cli::Error::Service)
+				let hardware_benchmarks = service::HardwareBenchmarks {
+					disabled: cli.no_hardware_benchmarks,
+					requirements: cli
+						.hardware_requirements
+						.as_deref()
+						.map(read_hardware_requirements)
+						.transpose()?,
+					enforce: cli.enforce_hardware_requirements,
+				};
+
+				service::new_full(config, hardware_benchmarks).map_err(sc_cli::Error::Service)
 			})
 		},
 		Some(Subcommand::Inspect(cmd)) => {
@@ -131,7 +141,7 @@ pub fn run() -> Result<()> {
 						cmd.run(config, client, inherent_benchmark_data()?, Arc::new(ext_builder))
 					},
 					BenchmarkCmd::Machine(cmd) =>
-						cmd.run(&config, SUBSTRATE_REFERENCE_HARDWARE.clone()),
+						cmd.run(&config, service::hardware_requirements(&*config.chain_spec)),
 				}
 			})
 		},
@@ -143,6 +153,13 @@ pub fn run() -> Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
 		},
+		Some(Subcommand::CheckBlock(cmd)) if cmd.is_range() => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = new_partial(&config)?;
+				cmd.run_range(client)
+			})
+		},
 		Some(Subcommand::CheckBlock(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -155,7 +172,12 @@ pub fn run() -> Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, .. } = new_partial(&config)?;
-				Ok((cmd.run(client, config.database), task_manager))
+				let decoder = substrate_frame_cli::MetadataBlockDecoder::<
+					_,
+					sc_service::TFullBackend<Block>,
+					_,
+				>::new(client.clone());
+				Ok((cmd.run_with_decoder(client, config.database, decoder), task_manager))
 			})
 		},
 		Some(Subcommand::ExportState(cmd)) => {
@@ -165,6 +187,14 @@ pub fn run() -> Result<()> {
 				Ok((cmd.run(client, config.chain_spec), task_manager))
 			})
 		},
+		Some(Subcommand::DiffSpec(cmd)) => cmd.run(&cli),
+		Some(Subcommand::Db(substrate_frame_cli::DbSubcommand::Stats(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, backend, .. } = new_partial(&config)?;
+				cmd.run(client, backend)
+			})
+		},
 		Some(Subcommand::ImportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -211,5 +241,23 @@ pub fn run() -> Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run::<Block>(&config))
 		},
+		Some(Subcommand::ReplayCall(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				cmd.run::<Block, _>(NativeElseWasmExecutor::<ExecutorDispatch>::new(
+					config.wasm_method,
+					config.default_heap_pages,
+					config.max_runtime_instances,
+					config.runtime_cache_size,
+				))
+			})
+		},
 	}
 }
+
+/// Read the hardware requirements from the JSON file at `path`.
+fn read_hardware_requirements(path: &Path) -> Result<Requirements> {
+	let raw = std::fs::read(path)?;
+	serde_json::from_slice(&raw)
+		.map_err(|e| format!("Invalid hardware requirements in {}: {}", path.display(), e).into())
+}
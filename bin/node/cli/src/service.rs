@@ -31,6 +31,7 @@ use sc_consensus_babe::{self, SlotProportion};
 use sc_executor::NativeElseWasmExecutor;
 use sc_network::{Event, NetworkService};
 use sc_service::{config::Configuration, error::Error as ServiceError, RpcHandlers, TaskManager};
+use sc_sysinfo::{Requirements, SUBSTRATE_REFERENCE_HARDWARE};
 use sc_telemetry::{Telemetry, TelemetryWorker};
 use sp_api::ProvideRuntimeApi;
 use sp_core::crypto::Pair;
@@ -134,6 +135,7 @@ pub fn new_partial(
 			impl Fn(
 				node_rpc::DenyUnsafe,
 				sc_rpc::SubscriptionTaskExecutor,
+				Option<sc_authority_discovery::Service>,
 			) -> Result<jsonrpsee::RpcModule<()>, sc_service::Error>,
 			(
 				sc_consensus_babe::BabeBlockImport<Block, FullClient, FullGrandpaBlockImport>,
@@ -253,7 +255,7 @@ pub fn new_partial(
 		let chain_spec = config.chain_spec.cloned_box();
 
 		let rpc_backend = backend.clone();
-		let rpc_extensions_builder = move |deny_unsafe, subscription_executor| {
+		let rpc_extensions_builder = move |deny_unsafe, subscription_executor, auth_disc| {
 			let deps = node_rpc::FullDeps {
 				client: client.clone(),
 				pool: pool.clone(),
@@ -272,6 +274,7 @@ pub fn new_partial(
 					subscription_executor,
 					finality_provider: finality_proof_provider.clone(),
 				},
+				authority_discovery: auth_disc,
 			};
 
 			node_rpc::create_full(deps, rpc_backend.clone()).map_err(Into::into)
@@ -306,16 +309,40 @@ pub struct NewFullBase {
 	pub rpc_handlers: RpcHandlers,
 }
 
+/// The hardware benchmarks run at startup.
+#[derive(Clone, Default)]
+pub struct HardwareBenchmarks {
+	/// Do not run the hardware benchmarks.
+	pub disabled: bool,
+	/// The requirements to check the benchmark results against.
+	///
+	/// Falls back to the requirements of the chain spec, see [`hardware_requirements`].
+	pub requirements: Option<Requirements>,
+	/// Refuse to start as an authority if the hardware does not meet the requirements.
+	pub enforce: bool,
+}
+
+/// The hardware requirements for authorities of the chain.
+///
+/// Returns the requirements of the chain spec, or the Substrate reference hardware if the chain
+/// spec has none.
+pub fn hardware_requirements(chain_spec: &dyn sc_service::ChainSpec) -> Requirements {
+	sc_chain_spec::get_extension::<Option<Requirements>>(chain_spec.extensions())
+		.cloned()
+		.flatten()
+		.unwrap_or_else(|| SUBSTRATE_REFERENCE_HARDWARE.clone())
+}
+
 /// Creates a full service from the configuration.
 pub fn new_full_base(
 	mut config: Configuration,
-	disable_hardware_benchmarks: bool,
+	hardware_benchmarks: HardwareBenchmarks,
 	with_startup_data: impl FnOnce(
 		&sc_consensus_babe::BabeBlockImport<Block, FullClient, FullGrandpaBlockImport>,
 		&sc_consensus_babe::BabeLink<Block>,
 	),
 ) -> Result<NewFullBase, ServiceError> {
-	let hwbench = if !disable_hardware_benchmarks {
+	let hwbench = if !hardware_benchmarks.disabled {
 		config.database.path().map(|database_path| {
 			let _ = std::fs::create_dir_all(&database_path);
 			sc_sysinfo::gather_hwbench(Some(database_path))
@@ -324,6 +351,29 @@ pub fn new_full_base(
 		None
 	};
 
+	if let Some(ref hwbench) = hwbench {
+		sc_sysinfo::print_hwbench(hwbench);
+
+		if config.role.is_authority() {
+			let requirements = hardware_benchmarks
+				.requirements
+				.unwrap_or_else(|| hardware_requirements(&*config.chain_spec));
+			let failures = requirements.check_hardware(hwbench);
+
+			for failure in &failures {
+				log::warn!("⚠️  Hardware requirement not met: {}", failure);
+			}
+			if !failures.is_empty() {
+				if hardware_benchmarks.enforce {
+					return Err(ServiceError::Other(
+						"The hardware does not meet the requirements for role 'Authority'".into(),
+					))
+				}
+				log::warn!("⚠️  The hardware does not meet the requirements for role 'Authority'.");
+			}
+		}
+	}
+
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -337,6 +387,7 @@ pub fn new_full_base(
 
 	let shared_voter_state = rpc_setup;
 	let auth_disc_publish_non_global_ips = config.network.allow_non_globals_in_dht;
+	let auth_disc_public_addresses = config.network.public_addresses.clone();
 	let grandpa_protocol_name = grandpa::protocol_standard_name(
 		&client.block_hash(0).ok().flatten().expect("Genesis block exists; qed"),
 		&config.chain_spec,
@@ -372,6 +423,19 @@ pub fn new_full_base(
 		);
 	}
 
+	let light_client_fetcher = sc_service::build_light_client_fetcher(
+		&config,
+		client.clone(),
+		network.clone(),
+		NativeElseWasmExecutor::<ExecutorDispatch>::new(
+			config.wasm_method,
+			config.default_heap_pages,
+			config.max_runtime_instances,
+			config.runtime_cache_size,
+		),
+		task_manager.spawn_handle(),
+	);
+
 	let role = config.role.clone();
 	let force_authoring = config.force_authoring;
 	let backoff_authoring_blocks =
@@ -380,6 +444,46 @@ pub fn new_full_base(
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
 
+	// Spawn authority discovery module.
+	let authority_discovery_service = if role.is_authority() {
+		let authority_discovery_role =
+			sc_authority_discovery::Role::PublishAndDiscover(keystore_container.keystore());
+		let dht_event_stream =
+			network.event_stream("authority-discovery").filter_map(|e| async move {
+				match e {
+					Event::Dht(e) => Some(e),
+					_ => None,
+				}
+			});
+		let (authority_discovery_worker, service) =
+			sc_authority_discovery::new_worker_and_service_with_config(
+				sc_authority_discovery::WorkerConfig {
+					publish_non_global_ips: auth_disc_publish_non_global_ips,
+					public_addresses: auth_disc_public_addresses,
+					..Default::default()
+				},
+				client.clone(),
+				network.clone(),
+				Box::pin(dht_event_stream),
+				authority_discovery_role,
+				prometheus_registry.clone(),
+			);
+
+		task_manager.spawn_handle().spawn(
+			"authority-discovery-worker",
+			Some("networking"),
+			authority_discovery_worker.run(),
+		);
+
+		Some(service)
+	} else {
+		None
+	};
+
+	let rpc_builder = move |deny_unsafe, subscription_executor| {
+		rpc_builder(deny_unsafe, subscription_executor, authority_discovery_service.clone())
+	};
+
 	let rpc_handlers = sc_service::spawn_tasks(sc_service::SpawnTasksParams {
 		config,
 		backend,
@@ -391,11 +495,10 @@ pub fn new_full_base(
 		task_manager: &mut task_manager,
 		system_rpc_tx,
 		telemetry: telemetry.as_mut(),
+		light_client_fetcher,
 	})?;
 
 	if let Some(hwbench) = hwbench {
-		sc_sysinfo::print_hwbench(&hwbench);
-
 		if let Some(ref mut telemetry) = telemetry {
 			let telemetry_handle = telemetry.handle();
 			task_manager.spawn_handle().spawn(
@@ -474,37 +577,6 @@ pub fn new_full_base(
 		);
 	}
 
-	// Spawn authority discovery module.
-	if role.is_authority() {
-		let authority_discovery_role =
-			sc_authority_discovery::Role::PublishAndDiscover(keystore_container.keystore());
-		let dht_event_stream =
-			network.event_stream("authority-discovery").filter_map(|e| async move {
-				match e {
-					Event::Dht(e) => Some(e),
-					_ => None,
-				}
-			});
-		let (authority_discovery_worker, _service) =
-			sc_authority_discovery::new_worker_and_service_with_config(
-				sc_authority_discovery::WorkerConfig {
-					publish_non_global_ips: auth_disc_publish_non_global_ips,
-					..Default::default()
-				},
-				client.clone(),
-				network.clone(),
-				Box::pin(dht_event_stream),
-				authority_discovery_role,
-				prometheus_registry.clone(),
-			);
-
-		task_manager.spawn_handle().spawn(
-			"authority-discovery-worker",
-			Some("networking"),
-			authority_discovery_worker.run(),
-		);
-	}
-
 	// if the node isn't actively participating in consensus then it doesn't
 	// need a keystore, regardless of which protocol we use below.
 	let keystore =
@@ -555,9 +627,9 @@ pub fn new_full_base(
 /// Builds a new service for a full client.
 pub fn new_full(
 	config: Configuration,
-	disable_hardware_benchmarks: bool,
+	hardware_benchmarks: HardwareBenchmarks,
 ) -> Result<TaskManager, ServiceError> {
-	new_full_base(config, disable_hardware_benchmarks, |_, _| ())
+	new_full_base(config, hardware_benchmarks, |_, _| ())
 		.map(|NewFullBase { task_manager, .. }| task_manager)
 }
 
@@ -625,7 +697,7 @@ mod tests {
 				let NewFullBase { task_manager, client, network, transaction_pool, .. } =
 					new_full_base(
 						config,
-						false,
+						Default::default(),
 						|block_import: &sc_consensus_babe::BabeBlockImport<Block, _, _>,
 						 babe_link: &sc_consensus_babe::BabeLink<Block>| {
 							setup_handles = Some((block_import.clone(), babe_link.clone()));
@@ -803,7 +875,7 @@ mod tests {
 			crate::chain_spec::tests::integration_test_config_with_two_authorities(),
 			|config| {
 				let NewFullBase { task_manager, client, network, transaction_pool, .. } =
-					new_full_base(config, false, |_, _| ())?;
+					new_full_base(config, Default::default(), |_, _| ())?;
 				Ok(sc_service_test::TestNetComponents::new(
 					task_manager,
 					client,
@@ -93,6 +93,8 @@ pub struct FullDeps<C, P, SC, B> {
 	pub babe: BabeDeps,
 	/// GRANDPA specific dependencies.
 	pub grandpa: GrandpaDeps<B>,
+	/// The authority discovery service, if the node runs as an authority.
+	pub authority_discovery: Option<sc_authority_discovery::Service>,
 }
 
 /// Instantiate all Full RPC extensions.
@@ -110,6 +112,8 @@ where
 		+ Send
 		+ 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
+	C::Api: substrate_frame_rpc_system::DryRunApi<Block, AccountId, Balance>,
+	C::Api: sp_api::ApiExt<Block, StateBackend = B::State>,
 	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber, Hash>,
 	C::Api: pallet_mmr_rpc::MmrRuntimeApi<Block, <Block as sp_runtime::traits::Block>::Hash>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
@@ -123,15 +127,27 @@ where
 	use pallet_contracts_rpc::{ContractsApiServer, ContractsRpc};
 	use pallet_mmr_rpc::{MmrApiServer, MmrRpc};
 	use pallet_transaction_payment_rpc::{TransactionPaymentApiServer, TransactionPaymentRpc};
+	use sc_authority_discovery_rpc::{AuthorityDiscovery, AuthorityDiscoveryApiServer};
 	use sc_consensus_babe_rpc::BabeApiServer;
 	use sc_finality_grandpa_rpc::GrandpaApiServer;
 	use sc_rpc::dev::{Dev, DevApiServer};
 	use sc_sync_state_rpc::{SyncStateRpc, SyncStateRpcApiServer};
-	use substrate_frame_rpc_system::{SystemApiServer, SystemRpc};
+	use substrate_frame_rpc_system::{
+		SystemApiServer, SystemDryRun, SystemDryRunApiServer, SystemRpc,
+	};
 	use substrate_state_trie_migration_rpc::StateMigrationApiServer;
 
 	let mut io = RpcModule::new(());
-	let FullDeps { client, pool, select_chain, chain_spec, deny_unsafe, babe, grandpa } = deps;
+	let FullDeps {
+		client,
+		pool,
+		select_chain,
+		chain_spec,
+		deny_unsafe,
+		babe,
+		grandpa,
+		authority_discovery,
+	} = deps;
 
 	let BabeDeps { keystore, babe_config, shared_epoch_changes } = babe;
 	let GrandpaDeps {
@@ -143,6 +159,7 @@ where
 	} = grandpa;
 
 	io.merge(SystemRpc::new(client.clone(), pool, deny_unsafe).into_rpc())?;
+	io.merge(SystemDryRun::new(client.clone(), backend.clone(), deny_unsafe).into_rpc())?;
 	// Making synchronous calls in light client freezes the browser currently,
 	// more context: https://github.com/paritytech/substrate/pull/3480
 	// These RPCs should use an asynchronous caller instead.
@@ -182,5 +199,9 @@ where
 	)?;
 	io.merge(Dev::new(client, deny_unsafe).into_rpc())?;
 
+	if let Some(service) = authority_discovery {
+		io.merge(AuthorityDiscovery::new(service, deny_unsafe).into_rpc())?;
+	}
+
 	Ok(io)
 }
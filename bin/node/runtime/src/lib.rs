@@ -124,7 +124,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 268,
+	spec_version: 269,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 2,
@@ -1581,6 +1581,134 @@ mod mmr {
 	pub type Hashing = <Runtime as pallet_mmr::Config>::Hashing;
 }
 
+/// Helpers for implementing `DryRunApi`.
+mod dry_run {
+	use super::*;
+	use frame_support::weights::{GetDispatchInfo, PostDispatchInfo};
+	use frame_system_rpc_runtime_api::{DryRunEffects, DryRunEvent};
+	use sp_runtime::{traits::Dispatchable, transaction_validity::InvalidTransaction};
+
+	/// Apply `extrinsic` like a block author would and collect its effects.
+	pub fn extrinsic(extrinsic: <Block as BlockT>::Extrinsic) -> DryRunEffects<Balance> {
+		let len = extrinsic.encoded_size() as u32;
+		let first_event = System::event_count();
+		let result = Executive::apply_extrinsic(extrinsic);
+		let events = events_since(first_event);
+
+		// The weight reported by the system events is already corrected for refunds.
+		let info = events.iter().find_map(|record| match &record.event {
+			Event::System(frame_system::Event::ExtrinsicSuccess { dispatch_info }) |
+			Event::System(frame_system::Event::ExtrinsicFailed { dispatch_info, .. }) =>
+				Some(*dispatch_info),
+			_ => None,
+		});
+		let (actual_weight, fee) = match info {
+			Some(info) => {
+				let post_info = PostDispatchInfo { actual_weight: None, pays_fee: info.pays_fee };
+				(info.weight, TransactionPayment::compute_actual_fee(len, &info, &post_info, 0))
+			},
+			None => (0, 0),
+		};
+
+		DryRunEffects { result, actual_weight, fee, events: into_dry_run_events(events) }
+	}
+
+	/// Dispatch the encoded `call` as if it was signed by `origin` and collect its effects.
+	///
+	/// The fee is computed for the length of a signed extrinsic wrapping `call`, as it would be
+	/// submitted by `origin`.
+	pub fn call(origin: AccountId, call: Vec<u8>) -> DryRunEffects<Balance> {
+		let call = match Call::decode(&mut &call[..]) {
+			Ok(call) => call,
+			Err(_) =>
+				return DryRunEffects {
+					result: Err(InvalidTransaction::Call.into()),
+					actual_weight: 0,
+					fee: 0,
+					events: Vec::new(),
+				},
+		};
+
+		let len = signed_len(&call, &origin);
+		let info = call.get_dispatch_info();
+		let first_event = System::event_count();
+		let outcome = call.dispatch(Origin::signed(origin));
+		let post_info = match outcome {
+			Ok(post_info) => post_info,
+			Err(err) => err.post_info,
+		};
+
+		DryRunEffects {
+			result: Ok(outcome.map(|_| ()).map_err(|err| err.error)),
+			actual_weight: post_info.calc_actual_weight(&info),
+			fee: TransactionPayment::compute_actual_fee(len, &info, &post_info, 0),
+			events: into_dry_run_events(events_since(first_event)),
+		}
+	}
+
+	/// The encoded length of `call` wrapped in an extrinsic signed by `origin`.
+	///
+	/// The signature is a placeholder of the same size as a sr25519 signature and the extensions
+	/// are the ones `create_transaction` would use.
+	fn signed_len(call: &Call, origin: &AccountId) -> u32 {
+		let period =
+			BlockHashCount::get().checked_next_power_of_two().map(|c| c / 2).unwrap_or(2) as u64;
+		let current_block = System::block_number().saturated_into::<u64>().saturating_sub(1);
+		let extra: SignedExtra = (
+			frame_system::CheckNonZeroSender::<Runtime>::new(),
+			frame_system::CheckSpecVersion::<Runtime>::new(),
+			frame_system::CheckTxVersion::<Runtime>::new(),
+			frame_system::CheckGenesis::<Runtime>::new(),
+			frame_system::CheckEra::<Runtime>::from(Era::mortal(period, current_block)),
+			frame_system::CheckNonce::<Runtime>::from(System::account_nonce(origin)),
+			frame_system::CheckWeight::<Runtime>::new(),
+			pallet_asset_tx_payment::ChargeAssetTxPayment::<Runtime>::from(0, None),
+		);
+		let signature = Signature::Sr25519(sp_core::sr25519::Signature::from_raw([0; 64]));
+
+		UncheckedExtrinsic::new_signed(
+			call.clone(),
+			Indices::unlookup(origin.clone()),
+			signature,
+			extra,
+		)
+		.encoded_size() as u32
+	}
+
+	/// Split the outer `Event` of each record into the pallet index, event index and fields.
+	fn into_dry_run_events(
+		records: Vec<frame_system::EventRecord<Event, Hash>>,
+	) -> Vec<DryRunEvent> {
+		records
+			.into_iter()
+			.map(|record| {
+				// The outer event encodes as the pallet index followed by the pallet event,
+				// which in turn starts with the variant index.
+				let event = record.event.encode();
+				DryRunEvent {
+					extrinsic_index: match record.phase {
+						frame_system::Phase::ApplyExtrinsic(index) => Some(index),
+						_ => None,
+					},
+					pallet_index: event[0],
+					event_index: event.get(1).copied().unwrap_or_default(),
+					fields: event.get(2..).unwrap_or_default().to_vec(),
+					topics: record.topics.iter().map(Encode::encode).collect(),
+				}
+			})
+			.collect()
+	}
+
+	/// All event records deposited after the first `first` ones.
+	fn events_since(first: u32) -> Vec<frame_system::EventRecord<Event, Hash>> {
+		System::read_events_no_consensus()
+			.into_iter()
+			.skip(first as usize)
+			.map(|record| *record)
+			.collect()
+	}
+}
+
 #[cfg(feature = "runtime-benchmarks")]
 #[macro_use]
 extern crate frame_benchmarking;
@@ -1790,6 +1918,21 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl frame_system_rpc_runtime_api::DryRunApi<Block, AccountId, Balance> for Runtime {
+		fn dry_run_extrinsic(
+			extrinsic: <Block as BlockT>::Extrinsic,
+		) -> frame_system_rpc_runtime_api::DryRunEffects<Balance> {
+			dry_run::extrinsic(extrinsic)
+		}
+
+		fn dry_run_call(
+			origin: AccountId,
+			call: Vec<u8>,
+		) -> frame_system_rpc_runtime_api::DryRunEffects<Balance> {
+			dry_run::call(origin, call)
+		}
+	}
+
 	impl pallet_contracts_rpc_runtime_api::ContractsApi<
 		Block, AccountId, Balance, BlockNumber, Hash,
 	>
@@ -35,7 +35,8 @@ use sp_core::{
 use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
 
 /// A utility to easily create a testnet chain spec definition with a given set
-/// of authorities and endowed accounts and/or generate random accounts.
+/// of authorities and endowed accounts and/or generate random accounts, or to
+/// derive one from a named genesis preset.
 #[derive(Parser)]
 #[clap(rename_all = "kebab-case")]
 enum ChainSpecBuilder {
@@ -84,16 +85,43 @@ enum ChainSpecBuilder {
 		#[clap(long, short)]
 		keystore_path: Option<PathBuf>,
 	},
-}
-
-impl ChainSpecBuilder {
-	/// Returns the path where the chain spec should be saved.
-	fn chain_spec_path(&self) -> &Path {
-		match self {
-			ChainSpecBuilder::New { chain_spec_path, .. } => chain_spec_path.as_path(),
-			ChainSpecBuilder::Generate { chain_spec_path, .. } => chain_spec_path.as_path(),
-		}
-	}
+	/// List the names of the available genesis presets.
+	ListPresets,
+	/// Create a new chain spec from a named genesis preset.
+	FromPreset {
+		/// Name of the preset, see `list-presets`.
+		preset: String,
+		/// Path to a JSON file that is merged into the genesis config of the preset.
+		#[clap(long, short)]
+		patch: Option<PathBuf>,
+		/// Output the genesis storage in raw format.
+		#[clap(long)]
+		raw: bool,
+		/// The path where the chain spec should be saved.
+		#[clap(long, short, default_value = "./chain_spec.json")]
+		chain_spec_path: PathBuf,
+	},
+	/// Merge a JSON patch into the genesis config of an existing chain spec.
+	Patch {
+		/// Path to the chain spec that should be patched.
+		input: PathBuf,
+		/// Path to a JSON file that is merged into the genesis config.
+		patch: PathBuf,
+		/// Output the genesis storage in raw format.
+		#[clap(long)]
+		raw: bool,
+		/// The path where the chain spec should be saved.
+		#[clap(long, short, default_value = "./chain_spec.json")]
+		chain_spec_path: PathBuf,
+	},
+	/// Convert an existing chain spec to raw format.
+	ConvertToRaw {
+		/// Path to the chain spec that should be converted.
+		input: PathBuf,
+		/// The path where the chain spec should be saved.
+		#[clap(long, short, default_value = "./chain_spec.json")]
+		chain_spec_path: PathBuf,
+	},
 }
 
 fn genesis_constructor(
@@ -162,6 +190,14 @@ fn generate_chain_spec(
 	chain_spec.as_json(false)
 }
 
+fn read_patch(path: &Path) -> Result<serde_json::Value, String> {
+	let patch = fs::read(path)
+		.map_err(|err| format!("Error reading patch file `{}`: {}", path.display(), err))?;
+
+	serde_json::from_slice(&patch)
+		.map_err(|err| format!("Error parsing patch file `{}`: {}", path.display(), err))
+}
+
 fn generate_authority_keys_and_store(seeds: &[String], keystore_path: &Path) -> Result<(), String> {
 	for (n, seed) in seeds.iter().enumerate() {
 		let keystore: SyncCryptoStorePtr = Arc::new(
@@ -237,53 +273,102 @@ fn main() -> Result<(), String> {
 	);
 
 	let builder = ChainSpecBuilder::parse();
-	let chain_spec_path = builder.chain_spec_path().to_path_buf();
-
-	let (authority_seeds, nominator_accounts, endowed_accounts, sudo_account) = match builder {
-		ChainSpecBuilder::Generate { authorities, nominators, endowed, keystore_path, .. } => {
-			let authorities = authorities.max(1);
-			let rand_str = || -> String {
-				OsRng.sample_iter(&Alphanumeric).take(32).map(char::from).collect()
-			};
-
-			let authority_seeds = (0..authorities).map(|_| rand_str()).collect::<Vec<_>>();
-			let nominator_seeds = (0..nominators).map(|_| rand_str()).collect::<Vec<_>>();
-			let endowed_seeds = (0..endowed).map(|_| rand_str()).collect::<Vec<_>>();
-			let sudo_seed = rand_str();
-
-			print_seeds(&authority_seeds, &nominator_seeds, &endowed_seeds, &sudo_seed);
-
-			if let Some(keystore_path) = keystore_path {
-				generate_authority_keys_and_store(&authority_seeds, &keystore_path)?;
-			}
-
-			let nominator_accounts = nominator_seeds
-				.into_iter()
-				.map(|seed| {
-					chain_spec::get_account_id_from_seed::<sr25519::Public>(&seed).to_ss58check()
-				})
-				.collect();
-
-			let endowed_accounts = endowed_seeds
-				.into_iter()
-				.map(|seed| {
-					chain_spec::get_account_id_from_seed::<sr25519::Public>(&seed).to_ss58check()
-				})
-				.collect();
-
-			let sudo_account =
-				chain_spec::get_account_id_from_seed::<sr25519::Public>(&sudo_seed).to_ss58check();
-
-			(authority_seeds, nominator_accounts, endowed_accounts, sudo_account)
-		},
-		ChainSpecBuilder::New {
-			authority_seeds,
-			nominator_accounts,
-			endowed_accounts,
-			sudo_account,
-			..
-		} => (authority_seeds, nominator_accounts, endowed_accounts, sudo_account),
-	};
+	let (authority_seeds, nominator_accounts, endowed_accounts, sudo_account, chain_spec_path) =
+		match builder {
+			ChainSpecBuilder::Generate {
+				authorities,
+				nominators,
+				endowed,
+				keystore_path,
+				chain_spec_path,
+			} => {
+				let authorities = authorities.max(1);
+				let rand_str = || -> String {
+					OsRng.sample_iter(&Alphanumeric).take(32).map(char::from).collect()
+				};
+
+				let authority_seeds = (0..authorities).map(|_| rand_str()).collect::<Vec<_>>();
+				let nominator_seeds = (0..nominators).map(|_| rand_str()).collect::<Vec<_>>();
+				let endowed_seeds = (0..endowed).map(|_| rand_str()).collect::<Vec<_>>();
+				let sudo_seed = rand_str();
+
+				print_seeds(&authority_seeds, &nominator_seeds, &endowed_seeds, &sudo_seed);
+
+				if let Some(keystore_path) = keystore_path {
+					generate_authority_keys_and_store(&authority_seeds, &keystore_path)?;
+				}
+
+				let nominator_accounts = nominator_seeds
+					.into_iter()
+					.map(|seed| {
+						chain_spec::get_account_id_from_seed::<sr25519::Public>(&seed)
+							.to_ss58check()
+					})
+					.collect();
+
+				let endowed_accounts = endowed_seeds
+					.into_iter()
+					.map(|seed| {
+						chain_spec::get_account_id_from_seed::<sr25519::Public>(&seed)
+							.to_ss58check()
+					})
+					.collect();
+
+				let sudo_account =
+					chain_spec::get_account_id_from_seed::<sr25519::Public>(&sudo_seed)
+						.to_ss58check();
+
+				(
+					authority_seeds,
+					nominator_accounts,
+					endowed_accounts,
+					sudo_account,
+					chain_spec_path,
+				)
+			},
+			ChainSpecBuilder::New {
+				authority_seeds,
+				nominator_accounts,
+				endowed_accounts,
+				sudo_account,
+				chain_spec_path,
+			} => (
+				authority_seeds,
+				nominator_accounts,
+				endowed_accounts,
+				sudo_account,
+				chain_spec_path,
+			),
+			ChainSpecBuilder::ListPresets => {
+				for name in chain_spec::presets().names() {
+					println!("{}", name);
+				}
+
+				return Ok(())
+			},
+			ChainSpecBuilder::FromPreset { preset, patch, raw, chain_spec_path } => {
+				let presets = chain_spec::presets();
+				let chain_spec = match patch {
+					Some(patch) => presets.build_with_patch(&preset, read_patch(&patch)?)?,
+					None => presets.build(&preset)?,
+				};
+
+				return fs::write(chain_spec_path, chain_spec.as_json(raw)?)
+					.map_err(|err| err.to_string())
+			},
+			ChainSpecBuilder::Patch { input, patch, raw, chain_spec_path } => {
+				let mut chain_spec = chain_spec::ChainSpec::from_json_file(input)?;
+				chain_spec.apply_genesis_patch(read_patch(&patch)?)?;
+
+				return fs::write(chain_spec_path, chain_spec.as_json(raw)?)
+					.map_err(|err| err.to_string())
+			},
+			ChainSpecBuilder::ConvertToRaw { input, chain_spec_path } => {
+				let json = chain_spec::ChainSpec::from_json_file(input)?.as_json(true)?;
+
+				return fs::write(chain_spec_path, json).map_err(|err| err.to_string())
+			},
+		};
 
 	let json =
 		generate_chain_spec(authority_seeds, nominator_accounts, endowed_accounts, sudo_account)?;
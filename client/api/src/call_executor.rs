@@ -18,13 +18,21 @@
 
 //! A method call executor interface.
 
-use codec::{Decode, Encode};
+use codec::{Codec, Decode, Encode};
+use hash_db::Hasher;
 use sc_executor::{RuntimeVersion, RuntimeVersionOf};
-use sp_core::NativeOrEncoded;
+use sp_core::{
+	storage::ChildInfo,
+	traits::{CodeExecutor, SpawnNamed},
+	ExecutionContext, NativeOrEncoded, NeverNativeValue,
+};
 use sp_externalities::Extensions;
 use sp_runtime::{generic::BlockId, traits::Block as BlockT};
-use sp_state_machine::{ExecutionManager, ExecutionStrategy, OverlayedChanges, StorageProof};
-use std::{cell::RefCell, panic::UnwindSafe, result};
+use sp_state_machine::{
+	backend::BackendRuntimeCode, ExecutionManager, ExecutionStrategy, OverlayedChanges,
+	StateMachine, StorageKey, StorageProof, StorageValue,
+};
+use std::{cell::RefCell, panic::UnwindSafe, path::PathBuf, result};
 
 use crate::execution_extensions::ExecutionExtensions;
 use sp_api::{ProofRecorder, StorageTransactionCache};
@@ -41,6 +49,163 @@ pub trait ExecutorProvider<Block: BlockT> {
 	fn execution_extensions(&self) -> &ExecutionExtensions<Block>;
 }
 
+/// The context a runtime call is made in.
+///
+/// Used to label the execution metrics of runtime calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallContext {
+	/// Importing or syncing a block.
+	Import,
+	/// Building a new block.
+	BlockProduction,
+	/// Validating a transaction for the transaction pool.
+	TransactionValidation,
+	/// Running an offchain worker.
+	Offchain,
+	/// Serving an RPC request.
+	Rpc,
+	/// Any other call.
+	Other,
+}
+
+impl CallContext {
+	/// Derive the call context of a runtime API call from its [`ExecutionContext`] and method.
+	///
+	/// Runtime API calls without an explicit context are executed as offchain calls without
+	/// offchain externalities, so transaction validation is recognized by its method name.
+	pub fn from_execution_context(context: &ExecutionContext, method: &str) -> Self {
+		match context {
+			ExecutionContext::Importing | ExecutionContext::Syncing => Self::Import,
+			ExecutionContext::BlockConstruction => Self::BlockProduction,
+			ExecutionContext::OffchainCall(Some(_)) => Self::Offchain,
+			ExecutionContext::OffchainCall(None)
+				if method.starts_with("TaggedTransactionQueue_") =>
+				Self::TransactionValidation,
+			ExecutionContext::OffchainCall(None) => Self::Other,
+		}
+	}
+
+	/// The label used for this context in metrics.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::Import => "import",
+			Self::BlockProduction => "block_production",
+			Self::TransactionValidation => "transaction_validation",
+			Self::Offchain => "offchain",
+			Self::Rpc => "rpc",
+			Self::Other => "other",
+		}
+	}
+}
+
+/// Configuration of the runtime calls to record, see [`RecordedCall`].
+#[derive(Debug, Clone)]
+pub struct CallRecordingConfig {
+	/// Directory the recorded calls are written to.
+	///
+	/// Every call that fails is recorded.
+	pub path: PathBuf,
+	/// Runtime methods that are recorded even if the call succeeds.
+	pub methods: Vec<String>,
+}
+
+impl CallRecordingConfig {
+	/// Whether a call of `method` should be recorded.
+	pub fn should_record(&self, method: &str, failed: bool) -> bool {
+		failed || self.methods.iter().any(|m| m == method)
+	}
+}
+
+/// Storage changes, grouped by child trie, that a recorded call was executed on top of.
+pub type RecordedChanges = Vec<(Option<ChildInfo>, Vec<(StorageKey, Option<StorageValue>)>)>;
+
+/// A runtime call together with everything needed to execute it again without a database.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct RecordedCall<Hash> {
+	/// The runtime method that was called.
+	pub method: String,
+	/// The input of the call.
+	pub call_data: Vec<u8>,
+	/// Hash of the runtime code the call was executed with.
+	pub code_hash: Vec<u8>,
+	/// Root of the state the call was executed on.
+	pub state_root: Hash,
+	/// Proof of all storage accessed by the call, including the runtime code.
+	pub proof: StorageProof,
+	/// Changes that were not yet committed to the state when the call was made, e.g. the ones
+	/// of previously applied extrinsics of the block being built.
+	pub prior_changes: RecordedChanges,
+	/// The error the call failed with, if it failed.
+	pub error: Option<String>,
+}
+
+impl<Hash: Codec + Clone + Ord + 'static> RecordedCall<Hash> {
+	/// Collect the storage changes of `overlay`, to be stored as [`Self::prior_changes`].
+	pub fn collect_changes(overlay: &OverlayedChanges) -> RecordedChanges {
+		let mut changes =
+			vec![(None, overlay.changes().map(|(k, v)| (k.clone(), v.value().cloned())).collect())];
+		for (child_changes, child_info) in overlay.children() {
+			changes.push((
+				Some(child_info.clone()),
+				child_changes.map(|(k, v)| (k.clone(), v.value().cloned())).collect(),
+			));
+		}
+		changes
+	}
+
+	/// The [`OverlayedChanges`] the call was executed on top of.
+	pub fn overlay(&self) -> OverlayedChanges {
+		OverlayedChanges::from_changes(self.prior_changes.clone())
+	}
+
+	/// Execute the call again, using only the recorded proof as state.
+	///
+	/// Returns the encoded result of the call. Extensions the call was originally executed with,
+	/// e.g. the offchain database or the keystore, are not available.
+	pub fn replay<H, Exec>(
+		&self,
+		executor: &Exec,
+		strategy: ExecutionStrategy,
+		spawn_handle: impl SpawnNamed + Send + 'static,
+	) -> sp_blockchain::Result<Vec<u8>>
+	where
+		H: Hasher<Out = Hash>,
+		Exec: CodeExecutor + Clone + 'static,
+	{
+		let backend = sp_state_machine::create_proof_check_backend::<H>(
+			self.state_root.clone(),
+			self.proof.clone(),
+		)?;
+
+		let state_runtime_code = BackendRuntimeCode::new(&backend);
+		let runtime_code =
+			state_runtime_code.runtime_code().map_err(sp_blockchain::Error::RuntimeCode)?;
+		if runtime_code.hash != self.code_hash {
+			return Err(sp_blockchain::Error::RuntimeCode(
+				"The call was recorded with a runtime code override that is not part of the proof",
+			))
+		}
+
+		let mut overlay = self.overlay();
+		StateMachine::new(
+			&backend,
+			&mut overlay,
+			executor,
+			&self.method,
+			&self.call_data,
+			Extensions::default(),
+			&runtime_code,
+			spawn_handle,
+		)
+		.execute_using_consensus_failure_handler::<_, NeverNativeValue, fn() -> _>(
+			strategy.get_manager(),
+			None,
+		)
+		.map(NativeOrEncoded::into_encoded)
+		.map_err(Into::into)
+	}
+}
+
 /// Method call executor.
 pub trait CallExecutor<B: BlockT>: RuntimeVersionOf {
 	/// Externalities error type.
@@ -59,6 +224,7 @@ pub trait CallExecutor<B: BlockT>: RuntimeVersionOf {
 		call_data: &[u8],
 		strategy: ExecutionStrategy,
 		extensions: Option<Extensions>,
+		context: CallContext,
 	) -> Result<Vec<u8>, sp_blockchain::Error>;
 
 	/// Execute a contextual call on top of state in a block of a given hash.
@@ -88,6 +254,7 @@ pub trait CallExecutor<B: BlockT>: RuntimeVersionOf {
 		native_call: Option<NC>,
 		proof_recorder: &Option<ProofRecorder<B>>,
 		extensions: Option<Extensions>,
+		context: CallContext,
 	) -> sp_blockchain::Result<NativeOrEncoded<R>>
 	where
 		ExecutionManager<EM>: Clone;
@@ -0,0 +1,98 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Substrate light client interfaces.
+//!
+//! These interfaces only cover fetching state from full nodes: storage and runtime calls are
+//! answered by requesting proofs and checking them against the state root of a header that was
+//! already verified.
+//!
+//! There is no light client built on them yet. Nodes started with `--light` import and execute
+//! blocks and keep a state database like full nodes do, only their state RPCs go through a
+//! [`RemoteFetcher`]. Syncing headers alone from warp proofs and running without a state
+//! database are not implemented.
+
+use std::collections::HashMap;
+
+use futures::future::BoxFuture;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use sp_storage::PrefixedStorageKey;
+
+/// Remote call request.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RemoteCallRequest<Header: HeaderT> {
+	/// Call at state of given block.
+	pub block: Header::Hash,
+	/// Header of block at which call is performed.
+	pub header: Header,
+	/// Method to call.
+	pub method: String,
+	/// Call data.
+	pub call_data: Vec<u8>,
+}
+
+/// Remote storage read request.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RemoteReadRequest<Header: HeaderT> {
+	/// Read at state of given block.
+	pub block: Header::Hash,
+	/// Header of block at which read is performed.
+	pub header: Header,
+	/// Storage keys to read.
+	pub keys: Vec<Vec<u8>>,
+}
+
+/// Remote storage read child request.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RemoteReadChildRequest<Header: HeaderT> {
+	/// Read at state of given block.
+	pub block: Header::Hash,
+	/// Header of block at which read is performed.
+	pub header: Header,
+	/// Storage key for child.
+	pub storage_key: PrefixedStorageKey,
+	/// Child storage keys to read.
+	pub keys: Vec<Vec<u8>>,
+}
+
+/// Result of a remote read: the value of each requested key, `None` if the key is not set.
+pub type RemoteReadResult = HashMap<Vec<u8>, Option<Vec<u8>>>;
+
+/// Fetches data from full nodes on behalf of a light client.
+///
+/// Every answer is checked against the state root of the request header before it is
+/// returned, so callers can treat the result the same way as data read from local state.
+pub trait RemoteFetcher<Block: BlockT>: Send + Sync {
+	/// Fetch and check the values of storage keys.
+	fn remote_read(
+		&self,
+		request: RemoteReadRequest<Block::Header>,
+	) -> BoxFuture<'static, sp_blockchain::Result<RemoteReadResult>>;
+
+	/// Fetch and check the values of child storage keys.
+	fn remote_read_child(
+		&self,
+		request: RemoteReadChildRequest<Block::Header>,
+	) -> BoxFuture<'static, sp_blockchain::Result<RemoteReadResult>>;
+
+	/// Fetch an execution proof and re-execute the call on it locally.
+	fn remote_call(
+		&self,
+		request: RemoteCallRequest<Block::Header>,
+	) -> BoxFuture<'static, sp_blockchain::Result<Vec<u8>>>;
+}
@@ -0,0 +1,129 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! RPC api for authority discovery.
+
+#![warn(missing_docs)]
+
+use jsonrpsee::{
+	core::{async_trait, RpcResult},
+	proc_macros::rpc,
+};
+use sc_authority_discovery::Service;
+use sc_rpc_api::DenyUnsafe;
+use sp_authority_discovery::AuthorityId;
+
+/// Provides rpc methods for interacting with authority discovery.
+#[rpc(client, server)]
+pub trait AuthorityDiscoveryApi {
+	/// Returns the addresses discovered for the given authority id.
+	///
+	/// The list is empty if no addresses are known for the authority.
+	#[method(name = "authorityDiscovery_addresses")]
+	async fn addresses(&self, authority_id: AuthorityId) -> RpcResult<Vec<String>>;
+}
+
+/// Provides RPC methods for interacting with authority discovery.
+pub struct AuthorityDiscovery {
+	/// Handle to the authority discovery worker.
+	service: Service,
+	/// Whether to deny unsafe calls
+	deny_unsafe: DenyUnsafe,
+}
+
+impl AuthorityDiscovery {
+	/// Creates a new instance of the authority discovery RPC handler.
+	pub fn new(service: Service, deny_unsafe: DenyUnsafe) -> Self {
+		Self { service, deny_unsafe }
+	}
+}
+
+#[async_trait]
+impl AuthorityDiscoveryApiServer for AuthorityDiscovery {
+	async fn addresses(&self, authority_id: AuthorityId) -> RpcResult<Vec<String>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let mut addresses = self
+			.service
+			.clone()
+			.get_addresses_by_authority_id(authority_id)
+			.await
+			.unwrap_or_default()
+			.into_iter()
+			.map(|address| address.to_string())
+			.collect::<Vec<_>>();
+		addresses.sort();
+
+		Ok(addresses)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use assert_matches::assert_matches;
+	use jsonrpsee::{core::Error as JsonRpseeError, types::error::CallError};
+	use sc_network::Multiaddr;
+	use sp_core::sr25519;
+	use std::collections::HashMap;
+
+	fn authority(byte: u8) -> AuthorityId {
+		sr25519::Public::from_raw([byte; 32]).into()
+	}
+
+	fn address(port: u16) -> Multiaddr {
+		format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap()
+	}
+
+	fn test_rpc(deny_unsafe: DenyUnsafe) -> jsonrpsee::RpcModule<AuthorityDiscovery> {
+		let addresses =
+			HashMap::from([(authority(1), [address(30334), address(30333)].into_iter().collect())]);
+		let service = sc_authority_discovery::new_test_service(addresses);
+		AuthorityDiscovery::new(service, deny_unsafe).into_rpc()
+	}
+
+	#[tokio::test]
+	async fn addresses_are_returned_sorted() {
+		let api = test_rpc(DenyUnsafe::No);
+
+		let addresses: Vec<String> =
+			api.call("authorityDiscovery_addresses", [authority(1)]).await.unwrap();
+
+		assert_eq!(addresses, vec![address(30333).to_string(), address(30334).to_string()]);
+	}
+
+	#[tokio::test]
+	async fn addresses_of_unknown_authority_are_empty() {
+		let api = test_rpc(DenyUnsafe::No);
+
+		let addresses: Vec<String> =
+			api.call("authorityDiscovery_addresses", [authority(2)]).await.unwrap();
+
+		assert!(addresses.is_empty());
+	}
+
+	#[tokio::test]
+	async fn addresses_deny_unsafe() {
+		let api = test_rpc(DenyUnsafe::Yes);
+
+		assert_matches!(
+			api.call::<_, Vec<String>>("authorityDiscovery_addresses", [authority(1)]).await,
+			Err(JsonRpseeError::Call(CallError::Custom(err))) if err.message().contains("RPC call is unsafe to be called externally")
+		);
+	}
+}
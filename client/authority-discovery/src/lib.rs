@@ -83,6 +83,14 @@ pub struct WorkerConfig {
 	///
 	/// Defaults to `false` to provide compatibility with old versions
 	pub strict_record_validation: bool,
+
+	/// Addresses to publish on the DHT instead of the external addresses reported by the network.
+	///
+	/// Useful for nodes behind a NAT or a proxy that know their publicly reachable addresses.
+	/// These are published regardless of `publish_non_global_ips`.
+	///
+	/// Defaults to an empty list, in which case the external addresses are published.
+	pub public_addresses: Vec<Multiaddr>,
 }
 
 impl Default for WorkerConfig {
@@ -104,6 +112,7 @@ impl Default for WorkerConfig {
 			max_query_interval: Duration::from_secs(10 * 60),
 			publish_non_global_ips: true,
 			strict_record_validation: false,
+			public_addresses: Vec::new(),
 		}
 	}
 }
@@ -162,6 +171,36 @@ where
 	(worker, service)
 }
 
+/// Create a [`Service`] that answers address queries from the given `addresses` instead of
+/// asking a [`Worker`].
+///
+/// Queries for the authorities of a peer are answered with `None`. The answering thread stops
+/// once all clones of the [`Service`] are dropped.
+#[cfg(any(test, feature = "test-helpers"))]
+pub fn new_test_service(
+	addresses: std::collections::HashMap<AuthorityId, HashSet<Multiaddr>>,
+) -> Service {
+	use futures::StreamExt;
+
+	let (to_worker, mut from_service) = mpsc::channel(0);
+	std::thread::spawn(move || {
+		futures::executor::block_on(async move {
+			while let Some(msg) = from_service.next().await {
+				match msg {
+					ServicetoWorkerMsg::GetAddressesByAuthorityId(authority, sender) => {
+						let _ = sender.send(addresses.get(&authority).cloned());
+					},
+					ServicetoWorkerMsg::GetAuthorityIdsByPeerId(_, sender) => {
+						let _ = sender.send(None);
+					},
+				}
+			}
+		})
+	});
+
+	Service::new(to_worker)
+}
+
 /// Message send from the [`Service`] to the [`Worker`].
 pub(crate) enum ServicetoWorkerMsg {
 	/// See [`Service::get_addresses_by_authority_id`].
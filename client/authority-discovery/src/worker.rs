@@ -26,14 +26,14 @@ use std::{
 	collections::{HashMap, HashSet},
 	marker::PhantomData,
 	sync::Arc,
-	time::Duration,
+	time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use futures::{channel::mpsc, future, stream::Fuse, FutureExt, Stream, StreamExt};
 
 use addr_cache::AddrCache;
 use async_trait::async_trait;
-use codec::Decode;
+use codec::{Decode, Encode};
 use ip_network::IpNetwork;
 use libp2p::{
 	core::multiaddr,
@@ -85,13 +85,14 @@ pub enum Role {
 ///
 /// When constructed with [`Role::PublishAndDiscover`] a [`Worker`] will
 ///
-///    1. Retrieve its external addresses (including peer id).
+///    1. Retrieve its external addresses (including peer id), or the configured public addresses
+///    if any.
 ///
 ///    2. Get the list of keys owned by the local node participating in the current authority set.
 ///
-///    3. Sign the addresses with the keys.
+///    3. Sign the addresses and their creation time with the keys and the local network identity.
 ///
-///    4. Put addresses and signature as a record with the authority id as a key on a Kademlia DHT.
+///    4. Put addresses and signatures as a record with the authority id as a key on a Kademlia DHT.
 ///
 /// When constructed with either [`Role::PublishAndDiscover`] or [`Role::Discover`] a [`Worker`]
 /// will
@@ -100,7 +101,8 @@ pub enum Role {
 ///
 ///    2. Start DHT queries for the ids of the authorities.
 ///
-///    3. Validate the signatures of the retrieved key value pairs.
+///    3. Validate the signatures of the retrieved key value pairs, keeping only the newest record
+///    of each authority.
 ///
 ///    4. Add the retrieved external addresses as priority nodes to the
 ///    network peerset.
@@ -128,6 +130,8 @@ pub struct Worker<Client, Network, Block, DhtEventStream> {
 	/// Same value as in the configuration.
 	publish_non_global_ips: bool,
 	/// Same value as in the configuration.
+	public_addresses: Vec<Multiaddr>,
+	/// Same value as in the configuration.
 	strict_record_validation: bool,
 
 	/// Interval at which to request addresses of authorities, refilling the pending lookups queue.
@@ -140,6 +144,9 @@ pub struct Worker<Client, Network, Block, DhtEventStream> {
 
 	addr_cache: addr_cache::AddrCache,
 
+	/// Creation time of the newest record seen per authority. Records older than this are ignored.
+	last_known_records: HashMap<AuthorityId, u128>,
+
 	metrics: Option<Metrics>,
 
 	role: Role,
@@ -202,11 +209,13 @@ where
 			publish_if_changed_interval,
 			latest_published_keys: HashSet::new(),
 			publish_non_global_ips: config.publish_non_global_ips,
+			public_addresses: config.public_addresses,
 			strict_record_validation: config.strict_record_validation,
 			query_interval,
 			pending_lookups: Vec::new(),
 			in_flight_lookups: HashMap::new(),
 			addr_cache,
+			last_known_records: HashMap::new(),
 			role,
 			metrics,
 			phantom: PhantomData,
@@ -275,29 +284,37 @@ where
 	fn addresses_to_publish(&self) -> impl Iterator<Item = Multiaddr> {
 		let peer_id: Multihash = self.network.local_peer_id().into();
 		let publish_non_global_ips = self.publish_non_global_ips;
-		self.network
-			.external_addresses()
-			.into_iter()
-			.filter(move |a| {
-				if publish_non_global_ips {
-					return true
-				}
+		// Explicitly configured public addresses take precedence over the addresses reported by
+		// the network and are published as is.
+		let addresses = if self.public_addresses.is_empty() {
+			self.network
+				.external_addresses()
+				.into_iter()
+				.filter(move |a| {
+					if publish_non_global_ips {
+						return true
+					}
 
-				a.iter().all(|p| match p {
-					// The `ip_network` library is used because its `is_global()` method is stable,
-					// while `is_global()` in the standard library currently isn't.
-					multiaddr::Protocol::Ip4(ip) if !IpNetwork::from(ip).is_global() => false,
-					multiaddr::Protocol::Ip6(ip) if !IpNetwork::from(ip).is_global() => false,
-					_ => true,
+					a.iter().all(|p| match p {
+						// The `ip_network` library is used because its `is_global()` method is
+						// stable, while `is_global()` in the standard library currently isn't.
+						multiaddr::Protocol::Ip4(ip) if !IpNetwork::from(ip).is_global() => false,
+						multiaddr::Protocol::Ip6(ip) if !IpNetwork::from(ip).is_global() => false,
+						_ => true,
+					})
 				})
-			})
-			.map(move |a| {
-				if a.iter().any(|p| matches!(p, multiaddr::Protocol::P2p(_))) {
-					a
-				} else {
-					a.with(multiaddr::Protocol::P2p(peer_id))
-				}
-			})
+				.collect::<Vec<_>>()
+		} else {
+			self.public_addresses.clone()
+		};
+
+		addresses.into_iter().map(move |a| {
+			if a.iter().any(|p| matches!(p, multiaddr::Protocol::P2p(_))) {
+				a
+			} else {
+				a.with(multiaddr::Protocol::P2p(peer_id))
+			}
+		})
 	}
 
 	/// Publish own public addresses.
@@ -328,7 +345,7 @@ where
 				.set(addresses.len().try_into().unwrap_or(std::u64::MAX));
 		}
 
-		let serialized_record = serialize_authority_record(addresses)?;
+		let serialized_record = serialize_authority_record(addresses, Some(build_creation_time()))?;
 		let peer_signature = sign_record_with_peer_id(&serialized_record, self.network.as_ref())?;
 
 		let keys_vec = keys.iter().cloned().collect::<Vec<_>>();
@@ -372,6 +389,7 @@ where
 			.collect::<Vec<_>>();
 
 		self.addr_cache.retain_ids(&authorities);
+		self.last_known_records.retain(|id, _| authorities.contains(id));
 
 		authorities.shuffle(&mut thread_rng());
 		self.pending_lookups = authorities;
@@ -480,7 +498,7 @@ where
 
 		let local_peer_id = self.network.local_peer_id();
 
-		let remote_addresses: Vec<Multiaddr> = values
+		let records = values
 			.into_iter()
 			.map(|(_k, v)| {
 				let schema::SignedAuthorityRecord { record, auth_signature, peer_signature } =
@@ -494,9 +512,19 @@ where
 					return Err(Error::VerifyingDhtPayload)
 				}
 
-				let addresses: Vec<Multiaddr> = schema::AuthorityRecord::decode(record.as_slice())
-					.map(|a| a.addresses)
-					.map_err(Error::DecodingProto)?
+				let schema::AuthorityRecord { addresses, creation_time } =
+					schema::AuthorityRecord::decode(record.as_slice())
+						.map_err(Error::DecodingProto)?;
+
+				// Records published by old versions carry no creation time and are thus treated as
+				// older than any record that does.
+				let creation_time = creation_time
+					.map(|t| u128::decode(&mut &t.timestamp[..]))
+					.transpose()
+					.map_err(Error::EncodingDecodingScale)?
+					.unwrap_or_default();
+
+				let addresses: Vec<Multiaddr> = addresses
 					.into_iter()
 					.map(|a| a.try_into())
 					.collect::<std::result::Result<_, _>>()
@@ -540,15 +568,34 @@ where
 						"Received unsigned authority discovery record from {}", authority_id
 					);
 				}
-				Ok(addresses)
+				Ok((creation_time, addresses))
 			})
-			.collect::<Result<Vec<Vec<Multiaddr>>>>()?
+			.collect::<Result<Vec<(u128, Vec<Multiaddr>)>>>()?;
+
+		// Only the newest records are taken into account, so that an authority changing its
+		// addresses does not have the stale ones linger in the cache.
+		let newest = records.iter().map(|(creation_time, _)| *creation_time).max().unwrap_or(0);
+		if self
+			.last_known_records
+			.get(&authority_id)
+			.map_or(false, |known| newest < *known)
+		{
+			debug!(
+				target: LOG_TARGET,
+				"Ignoring outdated authority discovery record of {}", authority_id
+			);
+			return Ok(())
+		}
+
+		let remote_addresses: Vec<Multiaddr> = records
 			.into_iter()
-			.flatten()
+			.filter(|(creation_time, _)| *creation_time == newest)
+			.flat_map(|(_, addresses)| addresses)
 			.take(MAX_ADDRESSES_PER_AUTHORITY)
 			.collect();
 
 		if !remote_addresses.is_empty() {
+			self.last_known_records.insert(authority_id.clone(), newest);
 			self.addr_cache.insert(authority_id, remote_addresses);
 			if let Some(metrics) = &self.metrics {
 				metrics
@@ -660,9 +707,21 @@ fn serialize_addresses(addresses: impl Iterator<Item = Multiaddr>) -> Vec<Vec<u8
 	addresses.map(|a| a.to_vec()).collect()
 }
 
-fn serialize_authority_record(addresses: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+/// Build a [`schema::TimestampInfo`] holding the current time.
+fn build_creation_time() -> schema::TimestampInfo {
+	let creation_time = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|time| time.as_nanos())
+		.unwrap_or_default();
+	schema::TimestampInfo { timestamp: creation_time.encode() }
+}
+
+fn serialize_authority_record(
+	addresses: Vec<Vec<u8>>,
+	creation_time: Option<schema::TimestampInfo>,
+) -> Result<Vec<u8>> {
 	let mut serialized_record = vec![];
-	schema::AuthorityRecord { addresses }
+	schema::AuthorityRecord { addresses, creation_time }
 		.encode(&mut serialized_record)
 		.map_err(Error::EncodingProto)?;
 	Ok(serialized_record)
@@ -200,9 +200,27 @@ async fn build_dht_event<Signer: NetworkSigner>(
 	public_key: AuthorityId,
 	key_store: &dyn CryptoStore,
 	network: Option<&Signer>,
+) -> Vec<(sc_network::KademliaKey, Vec<u8>)> {
+	build_dht_event_with_creation_time(
+		addresses,
+		public_key,
+		key_store,
+		network,
+		Some(build_creation_time()),
+	)
+	.await
+}
+
+async fn build_dht_event_with_creation_time<Signer: NetworkSigner>(
+	addresses: Vec<Multiaddr>,
+	public_key: AuthorityId,
+	key_store: &dyn CryptoStore,
+	network: Option<&Signer>,
+	creation_time: Option<schema::TimestampInfo>,
 ) -> Vec<(sc_network::KademliaKey, Vec<u8>)> {
 	let serialized_record =
-		serialize_authority_record(serialize_addresses(addresses.into_iter())).unwrap();
+		serialize_authority_record(serialize_addresses(addresses.into_iter()), creation_time)
+			.unwrap();
 
 	let peer_signature = network.map(|n| sign_record_with_peer_id(&serialized_record, n).unwrap());
 	let kv_pairs = sign_record_with_authority_ids(
@@ -557,6 +575,78 @@ impl DhtValueFoundTester {
 			})
 			.unwrap()
 	}
+
+	/// Feed `values` into the worker created by a previous call to
+	/// [`DhtValueFoundTester::process_value_found`].
+	fn process_further_values(
+		&mut self,
+		values: Vec<(sc_network::KademliaKey, Vec<u8>)>,
+	) -> Option<&HashSet<Multiaddr>> {
+		let authority_id: AuthorityId = self.remote_authority_public.clone().into();
+		let local_worker = self.local_worker.as_mut().expect("a worker has been created; qed");
+
+		block_on(local_worker.refill_pending_lookups_queue()).unwrap();
+		local_worker.start_new_lookups();
+
+		drop(local_worker.handle_dht_value_found_event(values));
+
+		local_worker.addr_cache.get_addresses_by_authority_id(&authority_id)
+	}
+
+	fn build_dht_event_at(
+		&self,
+		addresses: Vec<Multiaddr>,
+		creation_time: u128,
+	) -> Vec<(sc_network::KademliaKey, Vec<u8>)> {
+		block_on(build_dht_event_with_creation_time(
+			addresses,
+			self.remote_authority_public.clone().into(),
+			&self.remote_key_store,
+			Some(&self.remote_node_key),
+			Some(schema::TimestampInfo { timestamp: creation_time.encode() }),
+		))
+	}
+}
+
+#[test]
+fn newest_record_within_event_wins() {
+	let mut tester = DhtValueFoundTester::new();
+	let old_addr = tester.multiaddr_with_peer_id(1);
+	let new_addr = tester.multiaddr_with_peer_id(2);
+	let mut kv_pairs = tester.build_dht_event_at(vec![old_addr], 1);
+	kv_pairs.extend(tester.build_dht_event_at(vec![new_addr.clone()], 2));
+
+	let cached_remote_addresses = tester.process_value_found(true, kv_pairs);
+
+	assert_eq!(
+		Some(&HashSet::from([new_addr])),
+		cached_remote_addresses,
+		"Expect worker to only cache the addresses of the newest record.",
+	);
+}
+
+#[test]
+fn outdated_record_is_ignored() {
+	let mut tester = DhtValueFoundTester::new();
+	let old_addr = tester.multiaddr_with_peer_id(1);
+	let new_addr = tester.multiaddr_with_peer_id(2);
+
+	let kv_pairs = tester.build_dht_event_at(vec![new_addr.clone()], 2);
+	tester.process_value_found(true, kv_pairs);
+
+	let kv_pairs = tester.build_dht_event_at(vec![old_addr.clone()], 1);
+	assert_eq!(
+		Some(&HashSet::from([new_addr])),
+		tester.process_further_values(kv_pairs),
+		"Expect worker to ignore a record older than the one it already knows.",
+	);
+
+	let kv_pairs = tester.build_dht_event_at(vec![old_addr.clone()], 3);
+	assert_eq!(
+		Some(&HashSet::from([old_addr])),
+		tester.process_further_values(kv_pairs),
+		"Expect worker to replace the known record with a newer one.",
+	);
 }
 
 #[test]
@@ -703,6 +793,34 @@ fn addresses_to_publish_adds_p2p() {
 	);
 }
 
+#[test]
+fn addresses_to_publish_prefers_public_addresses() {
+	let (_dht_event_tx, dht_event_rx) = channel(1000);
+	let network: Arc<TestNetwork> = Arc::new(Default::default());
+	let public_address: Multiaddr = "/ip4/10.0.0.1/tcp/30333".parse().unwrap();
+
+	let (_to_worker, from_service) = mpsc::channel(0);
+	let worker = Worker::new(
+		from_service,
+		Arc::new(TestApi { authorities: vec![] }),
+		network.clone(),
+		Box::pin(dht_event_rx),
+		Role::PublishAndDiscover(Arc::new(KeyStore::new())),
+		None,
+		WorkerConfig {
+			public_addresses: vec![public_address.clone()],
+			publish_non_global_ips: false,
+			..Default::default()
+		},
+	);
+
+	assert_eq!(
+		vec![public_address.with(multiaddr::Protocol::P2p(network.local_peer_id().into()))],
+		worker.addresses_to_publish().collect::<Vec<_>>(),
+		"Expected configured public addresses to replace the network's external addresses.",
+	);
+}
+
 /// Ensure [`Worker::addresses_to_publish`] does not add an additional `p2p` protocol component in
 /// case one already exists.
 #[test]
@@ -85,6 +85,8 @@ pub struct ProposerFactory<A, B, C, PR> {
 	telemetry: Option<TelemetryHandle>,
 	/// When estimating the block size, should the proof be included?
 	include_proof_in_block_size_estimation: bool,
+	/// The maximum size of the storage proof of the transactions in a block.
+	proof_size_limit: Option<usize>,
 	/// phantom member to pin the `Backend`/`ProofRecording` type.
 	_phantom: PhantomData<(B, PR)>,
 }
@@ -110,6 +112,7 @@ impl<A, B, C> ProposerFactory<A, B, C, DisableProofRecording> {
 			telemetry,
 			client,
 			include_proof_in_block_size_estimation: false,
+			proof_size_limit: None,
 			_phantom: PhantomData,
 		}
 	}
@@ -138,6 +141,7 @@ impl<A, B, C> ProposerFactory<A, B, C, EnableProofRecording> {
 			soft_deadline_percent: DEFAULT_SOFT_DEADLINE_PERCENT,
 			telemetry,
 			include_proof_in_block_size_estimation: true,
+			proof_size_limit: None,
 			_phantom: PhantomData,
 		}
 	}
@@ -146,6 +150,14 @@ impl<A, B, C> ProposerFactory<A, B, C, EnableProofRecording> {
 	pub fn disable_proof_in_block_size_estimation(&mut self) {
 		self.include_proof_in_block_size_estimation = false;
 	}
+
+	/// Set the maximum size of the storage proof in bytes, or `None` for no limit.
+	///
+	/// Transactions that would make the proof of a block exceed the limit are skipped, like the
+	/// ones exhausting the resources of the block. Inherents are not subject to the limit.
+	pub fn set_proof_size_limit(&mut self, limit: Option<usize>) {
+		self.proof_size_limit = limit;
+	}
 }
 
 impl<A, B, C, PR> ProposerFactory<A, B, C, PR> {
@@ -216,6 +228,7 @@ where
 			telemetry: self.telemetry.clone(),
 			_phantom: PhantomData,
 			include_proof_in_block_size_estimation: self.include_proof_in_block_size_estimation,
+			proof_size_limit: self.proof_size_limit,
 		};
 
 		proposer
@@ -258,6 +271,7 @@ pub struct Proposer<B, Block: BlockT, C, A: TransactionPool, PR> {
 	metrics: PrometheusMetrics,
 	default_block_size_limit: usize,
 	include_proof_in_block_size_estimation: bool,
+	proof_size_limit: Option<usize>,
 	soft_deadline_percent: Percent,
 	telemetry: Option<TelemetryHandle>,
 	_phantom: PhantomData<(B, PR)>,
@@ -381,6 +395,8 @@ where
 		}
 
 		// proceed with transactions
+		block_builder.set_proof_size_limit(self.proof_size_limit);
+
 		// We calculate soft deadline used only in case we start skipping transactions.
 		let now = (self.now)();
 		let left = deadline.saturating_duration_since(now);
@@ -940,6 +956,54 @@ mod tests {
 		assert_eq!(block.extrinsics().len(), extrinsics_num - 2);
 	}
 
+	#[test]
+	fn should_skip_transactions_exceeding_the_proof_size_limit() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let txpool = BasicPool::new_full(
+			Default::default(),
+			true.into(),
+			None,
+			spawner.clone(),
+			client.clone(),
+		);
+		let genesis_header = client
+			.header(&BlockId::Number(0u64))
+			.expect("header get error")
+			.expect("there should be header");
+
+		let extrinsics_num = 4;
+		block_on(txpool.submit_at(
+			&BlockId::number(0),
+			SOURCE,
+			(0..extrinsics_num).map(|nonce| extrinsic(nonce as u64)).collect(),
+		))
+		.unwrap();
+		block_on(txpool.maintain(chain_event(genesis_header.clone())));
+
+		let mut proposer_factory = ProposerFactory::with_proof_recording(
+			spawner.clone(),
+			client.clone(),
+			txpool.clone(),
+			None,
+			None,
+		);
+		let deadline = time::Duration::from_secs(300);
+		let propose = |proposer_factory: &mut ProposerFactory<_, _, _, _>| {
+			let proposer = block_on(proposer_factory.init(&genesis_header)).unwrap();
+			block_on(proposer.propose(Default::default(), Default::default(), deadline, None))
+				.map(|r| r.block)
+				.unwrap()
+		};
+
+		// Initializing the block alone already records a proof above the limit.
+		proposer_factory.set_proof_size_limit(Some(0));
+		assert_eq!(propose(&mut proposer_factory).extrinsics().len(), 0);
+
+		proposer_factory.set_proof_size_limit(None);
+		assert_eq!(propose(&mut proposer_factory).extrinsics().len(), extrinsics_num);
+	}
+
 	#[test]
 	fn should_keep_adding_transactions_after_exhausts_resources_before_soft_deadline() {
 		// given
@@ -138,6 +138,10 @@ impl<B> Validator<B> for GossipValidator<B>
 where
 	B: Block,
 {
+	fn message_topic(&self, _sender: &PeerId, _data: &[u8]) -> Option<B::Hash> {
+		Some(self.topic)
+	}
+
 	fn validate(
 		&self,
 		_context: &mut dyn ValidatorContext<B>,
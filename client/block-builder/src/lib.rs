@@ -37,6 +37,7 @@ use sp_runtime::{
 	generic::BlockId,
 	legacy,
 	traits::{Block as BlockT, Hash, HashFor, Header as HeaderT, NumberFor, One},
+	transaction_validity::InvalidTransaction,
 	Digest,
 };
 
@@ -142,6 +143,8 @@ pub struct BlockBuilder<'a, Block: BlockT, A: ProvideRuntimeApi<Block>, B> {
 	backend: &'a B,
 	/// The estimated size of the block header.
 	estimated_header_size: usize,
+	/// The maximum size of the storage proof, only enforced while recording a proof.
+	proof_size_limit: Option<usize>,
 }
 
 impl<'a, Block, A, B> BlockBuilder<'a, Block, A, B>
@@ -197,9 +200,18 @@ where
 			block_id,
 			backend,
 			estimated_header_size,
+			proof_size_limit: None,
 		})
 	}
 
+	/// Set the maximum size of the storage proof recorded while building the block.
+	///
+	/// Extrinsics that would make the proof exceed `limit` are rejected by [`Self::push`] as
+	/// exhausting the block's resources. Has no effect when proof recording is disabled.
+	pub fn set_proof_size_limit(&mut self, limit: Option<usize>) {
+		self.proof_size_limit = limit;
+	}
+
 	/// Push onto the block's list of extrinsics.
 	///
 	/// This will ensure the extrinsic can be validly executed (by executing it).
@@ -207,8 +219,15 @@ where
 		let block_id = &self.block_id;
 		let extrinsics = &mut self.extrinsics;
 		let version = self.version;
+		let proof_size_limit = self.proof_size_limit;
+
+		// The proof of a rejected extrinsic must not count towards the proof of the block.
+		let proof_recorder = self.api.proof_recorder();
+		if let Some(recorder) = &proof_recorder {
+			recorder.start_transaction();
+		}
 
-		self.api.execute_in_transaction(|api| {
+		let result = self.api.execute_in_transaction(|api| {
 			let res = if version < 6 {
 				#[allow(deprecated)]
 				api.apply_extrinsic_before_version_6_with_context(
@@ -227,6 +246,15 @@ where
 
 			match res {
 				Ok(Ok(_)) => {
+					let proof_size = proof_recorder.as_ref().map(|r| r.estimate_encoded_size());
+					if proof_size.zip(proof_size_limit).map_or(false, |(size, limit)| size > limit)
+					{
+						return TransactionOutcome::Rollback(Err(ApplyExtrinsicFailed::Validity(
+							InvalidTransaction::ExhaustsResources.into(),
+						)
+						.into()))
+					}
+
 					extrinsics.push(xt);
 					TransactionOutcome::Commit(Ok(()))
 				},
@@ -235,7 +263,17 @@ where
 				)),
 				Err(e) => TransactionOutcome::Rollback(Err(Error::from(e))),
 			}
-		})
+		});
+
+		if let Some(recorder) = &proof_recorder {
+			if result.is_ok() {
+				recorder.commit_transaction();
+			} else {
+				recorder.rollback_transaction();
+			}
+		}
+
+		result
 	}
 
 	/// Consume the builder to build a valid `Block` containing all pushed extrinsics.
@@ -315,7 +353,9 @@ mod tests {
 	use sp_blockchain::HeaderBackend;
 	use sp_core::Blake2Hasher;
 	use sp_state_machine::Backend;
-	use substrate_test_runtime_client::{DefaultTestClientBuilderExt, TestClientBuilderExt};
+	use substrate_test_runtime_client::{
+		runtime::Transfer, AccountKeyring, DefaultTestClientBuilderExt, TestClientBuilderExt,
+	};
 
 	#[test]
 	fn block_building_storage_proof_does_not_include_runtime_by_default() {
@@ -348,4 +388,49 @@ mod tests {
 			.unwrap_err()
 			.contains("Database missing expected key"),);
 	}
+
+	#[test]
+	fn extrinsics_exceeding_the_proof_size_limit_are_rejected() {
+		let builder = substrate_test_runtime_client::TestClientBuilder::new();
+		let backend = builder.backend();
+		let client = builder.build();
+
+		let mut block_builder = BlockBuilder::new(
+			&client,
+			client.info().best_hash,
+			client.info().best_number,
+			RecordProof::Yes,
+			Default::default(),
+			&*backend,
+		)
+		.unwrap();
+
+		let transfer = |nonce| {
+			Transfer {
+				from: AccountKeyring::Alice.into(),
+				to: AccountKeyring::Bob.into(),
+				amount: 1,
+				nonce,
+			}
+			.into_signed_tx()
+		};
+		let proof_size = |block_builder: &BlockBuilder<_, _, _>| {
+			block_builder.api.proof_recorder().unwrap().estimate_encoded_size()
+		};
+
+		let initial_proof_size = proof_size(&block_builder);
+		block_builder.set_proof_size_limit(Some(initial_proof_size));
+
+		assert!(matches!(
+			block_builder.push(transfer(0)),
+			Err(Error::ApplyExtrinsicFailed(ApplyExtrinsicFailed::Validity(e)))
+				if e.exhausted_resources()
+		));
+		assert_eq!(proof_size(&block_builder), initial_proof_size);
+		assert!(block_builder.extrinsics.is_empty());
+
+		block_builder.set_proof_size_limit(None);
+		block_builder.push(transfer(0)).unwrap();
+		assert!(proof_size(&block_builder) > initial_proof_size);
+	}
 }
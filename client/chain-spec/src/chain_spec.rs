@@ -36,6 +36,8 @@ enum GenesisSource<G> {
 	Binary(Cow<'static, [u8]>),
 	Factory(Arc<dyn Fn() -> G + Send + Sync>),
 	Storage(Storage),
+	/// Runtime genesis config in JSON form, e.g. after a patch was applied.
+	Json(Arc<json::Value>),
 }
 
 impl<G> Clone for GenesisSource<G> {
@@ -45,6 +47,7 @@ impl<G> Clone for GenesisSource<G> {
 			Self::Binary(ref d) => Self::Binary(d.clone()),
 			Self::Factory(ref f) => Self::Factory(f.clone()),
 			Self::Storage(ref s) => Self::Storage(s.clone()),
+			Self::Json(ref v) => Self::Json(v.clone()),
 		}
 	}
 }
@@ -71,6 +74,9 @@ impl<G: RuntimeGenesis> GenesisSource<G> {
 				Ok(genesis.genesis)
 			},
 			Self::Factory(f) => Ok(Genesis::Runtime(f())),
+			Self::Json(value) => json::from_value(json::Value::clone(value))
+				.map(Genesis::Runtime)
+				.map_err(|e| format!("Error parsing genesis config: {}", e)),
 			Self::Storage(storage) => {
 				let top = storage
 					.top
@@ -359,6 +365,32 @@ impl<G: RuntimeGenesis, E: serde::Serialize + Clone + 'static> ChainSpec<G, E> {
 		Ok(JsonContainer { client_spec: self.client_spec.clone(), genesis })
 	}
 
+	/// Returns the runtime genesis config of this spec as JSON.
+	///
+	/// Fails if the genesis is only available in raw form.
+	pub fn genesis_config_json(&self) -> Result<json::Value, String> {
+		match self.genesis.resolve()? {
+			Genesis::Runtime(g) =>
+				json::to_value(&g).map_err(|e| format!("Error serializing genesis config: {}", e)),
+			_ => Err("Genesis config is not available for raw chain specs".into()),
+		}
+	}
+
+	/// Merge the given JSON `patch` into the runtime genesis config of this spec.
+	///
+	/// See [`crate::json_merge`] for the merge semantics. The patched config is deserialized
+	/// into `G` right away, so patches with unknown or mistyped fields are rejected here.
+	pub fn apply_genesis_patch(&mut self, patch: json::Value) -> Result<(), String> {
+		let mut config = self.genesis_config_json()?;
+		crate::json_merge(&mut config, patch);
+
+		json::from_value::<G>(config.clone())
+			.map_err(|e| format!("Invalid genesis config patch: {}", e))?;
+
+		self.genesis = GenesisSource::Json(Arc::new(config));
+		Ok(())
+	}
+
 	/// Dump to json string.
 	pub fn as_json(&self, raw: bool) -> Result<String, String> {
 		let container = self.json_container(raw)?;
@@ -494,6 +526,35 @@ mod tests {
 
 	type TestSpec2 = ChainSpec<Genesis, Extension1>;
 
+	#[derive(Debug, Serialize, Deserialize)]
+	#[serde(rename_all = "camelCase", deny_unknown_fields)]
+	struct StrictGenesis {
+		sudo_key: String,
+		endowed: Vec<String>,
+	}
+
+	impl BuildStorage for StrictGenesis {
+		fn assimilate_storage(&self, storage: &mut Storage) -> Result<(), String> {
+			storage.top.insert(b"sudo".to_vec(), self.sudo_key.clone().into_bytes());
+			Ok(())
+		}
+	}
+
+	fn strict_spec() -> ChainSpec<StrictGenesis> {
+		ChainSpec::from_genesis(
+			"Strict",
+			"strict",
+			ChainType::Local,
+			|| StrictGenesis { sudo_key: "alice".into(), endowed: vec!["alice".into()] },
+			vec![],
+			None,
+			None,
+			None,
+			None,
+			None,
+		)
+	}
+
 	#[test]
 	fn should_deserialize_chain_spec_with_extensions() {
 		let spec = TestSpec2::from_json_bytes(Cow::Owned(
@@ -534,4 +595,27 @@ mod tests {
 			);
 		}
 	}
+
+	#[test]
+	fn genesis_patch_is_merged_into_runtime_genesis() {
+		let mut spec = strict_spec();
+
+		spec.apply_genesis_patch(json::json!({ "sudoKey": "bob" })).unwrap();
+
+		assert_eq!(
+			spec.genesis_config_json().unwrap(),
+			json::json!({ "sudoKey": "bob", "endowed": ["alice"] }),
+		);
+		assert_eq!(spec.build_storage().unwrap().top.get(&b"sudo"[..]), Some(&b"bob".to_vec()));
+	}
+
+	#[test]
+	fn genesis_patch_with_unknown_field_is_rejected() {
+		let mut spec = strict_spec();
+
+		let err = spec.apply_genesis_patch(json::json!({ "sudoKye": "bob" })).unwrap_err();
+
+		assert!(err.contains("unknown field `sudoKye`"), "{}", err);
+		assert_eq!(spec.genesis_config_json().unwrap()["sudoKey"], "alice");
+	}
 }
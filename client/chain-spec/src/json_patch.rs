@@ -0,0 +1,119 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! JSON merge patch support for the genesis config.
+
+use serde_json::Value;
+
+/// Recursively merge `patch` into `base`.
+///
+/// Objects are merged key by key, a `null` value in `patch` removes the key from `base` and
+/// any other value (including arrays) replaces the value found in `base`. This follows the
+/// semantics of [RFC 7386](https://www.rfc-editor.org/rfc/rfc7386).
+pub fn merge(base: &mut Value, patch: Value) {
+	let patch = match patch {
+		Value::Object(patch) => patch,
+		patch => {
+			*base = patch;
+			return
+		},
+	};
+
+	if !base.is_object() {
+		*base = Value::Object(Default::default());
+	}
+	let base = base.as_object_mut().expect("`base` was made an object above; qed");
+
+	for (key, value) in patch {
+		if value.is_null() {
+			base.remove(&key);
+		} else {
+			merge(base.entry(key).or_insert(Value::Null), value);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn merge_replaces_nested_values() {
+		let mut base = json!({
+			"balances": { "balances": [["a", 1]] },
+			"sudo": { "key": "a" },
+		});
+
+		merge(
+			&mut base,
+			json!({
+				"balances": { "balances": [["b", 2]] },
+				"sudo": { "key": "b" },
+			}),
+		);
+
+		assert_eq!(
+			base,
+			json!({
+				"balances": { "balances": [["b", 2]] },
+				"sudo": { "key": "b" },
+			})
+		);
+	}
+
+	#[test]
+	fn merge_keeps_untouched_and_adds_new_keys() {
+		let mut base = json!({ "system": { "code": "0x00" }, "sudo": { "key": "a" } });
+
+		merge(&mut base, json!({ "sudo": { "extra": 1 }, "indices": { "indices": [] } }));
+
+		assert_eq!(
+			base,
+			json!({
+				"system": { "code": "0x00" },
+				"sudo": { "key": "a", "extra": 1 },
+				"indices": { "indices": [] },
+			})
+		);
+	}
+
+	#[test]
+	fn merge_null_removes_key() {
+		let mut base = json!({ "sudo": { "key": "a" }, "system": {} });
+
+		merge(&mut base, json!({ "sudo": null }));
+
+		assert_eq!(base, json!({ "system": {} }));
+	}
+
+	#[test]
+	fn merge_strips_nulls_from_new_and_replaced_objects() {
+		let mut base = json!({ "sudo": "a" });
+
+		merge(
+			&mut base,
+			json!({
+				"sudo": { "key": "b", "removed": null },
+				"indices": { "indices": null, "deposit": 1 },
+			}),
+		);
+
+		assert_eq!(base, json!({ "sudo": { "key": "b" }, "indices": { "deposit": 1 } }));
+	}
+}
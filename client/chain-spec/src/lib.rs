@@ -177,11 +177,15 @@
 
 mod chain_spec;
 mod extension;
+mod json_patch;
+mod presets;
 
 pub use chain_spec::{ChainSpec as GenericChainSpec, NoExtension};
 pub use extension::{
 	get_extension, get_extension_mut, Extension, Fork, Forks, GetExtension, Group,
 };
+pub use json_patch::merge as json_merge;
+pub use presets::GenesisPresets;
 pub use sc_chain_spec_derive::{ChainSpecExtension, ChainSpecGroup};
 
 use sc_network::config::MultiaddrWithPeerId;
@@ -0,0 +1,154 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Named genesis presets from which chain specs can be built.
+
+use crate::{GenericChainSpec, NoExtension, RuntimeGenesis};
+use serde_json as json;
+
+/// A set of named genesis presets.
+///
+/// Each preset builds a complete chain spec. A JSON patch can be merged into the genesis
+/// config of the spec built from a preset, see [`GenesisPresets::build_with_patch`].
+pub struct GenesisPresets<G, E = NoExtension> {
+	presets: Vec<(&'static str, Box<dyn Fn() -> GenericChainSpec<G, E>>)>,
+}
+
+impl<G, E> Default for GenesisPresets<G, E> {
+	fn default() -> Self {
+		Self { presets: Vec::new() }
+	}
+}
+
+impl<G, E> GenesisPresets<G, E> {
+	/// Create an empty set of presets.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a preset with the given `name`.
+	///
+	/// A preset that was added before with the same `name` is replaced.
+	pub fn with_preset(
+		mut self,
+		name: &'static str,
+		build: impl Fn() -> GenericChainSpec<G, E> + 'static,
+	) -> Self {
+		self.presets.retain(|(n, _)| *n != name);
+		self.presets.push((name, Box::new(build)));
+		self
+	}
+
+	/// Returns the names of all presets, in the order they were added.
+	pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+		self.presets.iter().map(|(name, _)| *name)
+	}
+
+	/// Build the chain spec of the preset with the given `name`.
+	pub fn build(&self, name: &str) -> Result<GenericChainSpec<G, E>, String> {
+		self.presets
+			.iter()
+			.find(|(n, _)| *n == name)
+			.map(|(_, build)| build())
+			.ok_or_else(|| {
+				format!(
+					"Unknown preset `{}`, available presets: {}",
+					name,
+					self.names().collect::<Vec<_>>().join(", "),
+				)
+			})
+	}
+}
+
+impl<G: RuntimeGenesis, E: serde::Serialize + Clone + 'static> GenesisPresets<G, E> {
+	/// Build the chain spec of the preset with the given `name` and merge `patch` into its
+	/// genesis config.
+	///
+	/// See [`GenericChainSpec::apply_genesis_patch`] for how the patch is applied and validated.
+	pub fn build_with_patch(
+		&self,
+		name: &str,
+		patch: json::Value,
+	) -> Result<GenericChainSpec<G, E>, String> {
+		let mut spec = self.build(name)?;
+		spec.apply_genesis_patch(patch)?;
+		Ok(spec)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ChainType;
+	use serde::{Deserialize, Serialize};
+	use sp_core::storage::Storage;
+	use sp_runtime::BuildStorage;
+
+	#[derive(Debug, Serialize, Deserialize)]
+	#[serde(deny_unknown_fields)]
+	struct Genesis {
+		value: u32,
+	}
+
+	impl BuildStorage for Genesis {
+		fn assimilate_storage(&self, storage: &mut Storage) -> Result<(), String> {
+			storage.top.insert(b"value".to_vec(), self.value.to_le_bytes().to_vec());
+			Ok(())
+		}
+	}
+
+	fn spec(id: &str, value: u32) -> GenericChainSpec<Genesis> {
+		GenericChainSpec::from_genesis(
+			id,
+			id,
+			ChainType::Local,
+			move || Genesis { value },
+			vec![],
+			None,
+			None,
+			None,
+			None,
+			None,
+		)
+	}
+
+	fn presets() -> GenesisPresets<Genesis> {
+		GenesisPresets::new()
+			.with_preset("one", || spec("one", 1))
+			.with_preset("two", || spec("two", 2))
+	}
+
+	#[test]
+	fn presets_are_listed_and_built_by_name() {
+		let presets = presets();
+
+		assert_eq!(presets.names().collect::<Vec<_>>(), vec!["one", "two"]);
+		assert_eq!(presets.build("two").unwrap().id(), "two");
+
+		let err = presets.build("three").unwrap_err();
+		assert_eq!(err, "Unknown preset `three`, available presets: one, two");
+	}
+
+	#[test]
+	fn preset_is_built_with_patch() {
+		let spec = presets().build_with_patch("one", json::json!({ "value": 5 })).unwrap();
+
+		assert_eq!(spec.genesis_config_json().unwrap(), json::json!({ "value": 5 }));
+		assert!(presets().build_with_patch("one", json::json!({ "other": 5 })).is_err());
+	}
+}
@@ -18,20 +18,44 @@
 
 use crate::{
 	error,
-	params::{BlockNumberOrHash, ImportParams, SharedParams},
+	params::{BlockNumberOrHash, GenericNumber, ImportParams, SharedParams},
 	CliConfiguration,
 };
 use clap::Parser;
-use sc_client_api::{BlockBackend, HeaderBackend};
-use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
-use std::{fmt::Debug, str::FromStr, sync::Arc};
+use sc_block_builder::{BlockBuilderApi, BlockBuilderProvider};
+use sc_client_api::{backend, BlockBackend, HeaderBackend};
+use sc_service::chain_ops::{BlockCheck, BlockCheckOutcome};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor};
+use std::{fmt::Debug, fs, io::Write, path::PathBuf, str::FromStr, sync::Arc};
 
 /// The `check-block` command used to validate blocks.
 #[derive(Debug, Clone, Parser)]
 pub struct CheckBlockCmd {
 	/// Block hash or number
-	#[clap(value_name = "HASH or NUMBER")]
-	pub input: BlockNumberOrHash,
+	#[clap(value_name = "HASH or NUMBER", required_unless_present = "from")]
+	pub input: Option<BlockNumberOrHash>,
+
+	/// Re-execute all blocks starting with this number on top of their parent state, instead of
+	/// re-importing a single block.
+	///
+	/// The state of every parent block has to be available, which usually requires an archive
+	/// node.
+	#[clap(long, value_name = "BLOCK", conflicts_with = "input", requires = "to")]
+	pub from: Option<GenericNumber>,
+
+	/// Last block (inclusive) of the range given with `--from`.
+	#[clap(long, value_name = "BLOCK", requires = "from")]
+	pub to: Option<GenericNumber>,
+
+	/// Number of blocks of the range that are executed in parallel.
+	#[clap(long, value_name = "COUNT", default_value = "1")]
+	pub jobs: usize,
+
+	/// Write a report with the outcome and execution time of every block of the range to this
+	/// file, one JSON object per line.
+	#[clap(long, value_name = "PATH", requires = "from")]
+	pub report: Option<PathBuf>,
 
 	/// The default number of 64KB pages to ever allocate for Wasm execution.
 	///
@@ -59,12 +83,95 @@ impl CheckBlockCmd {
 		<B::Hash as FromStr>::Err: Debug,
 		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
 	{
+		let input = self.input.as_ref().ok_or("A block hash or number is required")?;
+
 		let start = std::time::Instant::now();
-		sc_service::chain_ops::check_block(client, import_queue, self.input.parse()?).await?;
+		sc_service::chain_ops::check_block(client, import_queue, input.parse()?).await?;
 		println!("Completed in {} ms.", start.elapsed().as_millis());
 
 		Ok(())
 	}
+
+	/// Returns `true` if a range of blocks should be checked with [`Self::run_range`].
+	pub fn is_range(&self) -> bool {
+		self.from.is_some()
+	}
+
+	/// Re-execute the range of blocks given with `--from` and `--to` and compare the resulting
+	/// state roots with the ones found in the headers.
+	pub fn run_range<B, BA, C>(&self, client: Arc<C>) -> error::Result<()>
+	where
+		B: BlockT,
+		BA: backend::Backend<B> + 'static,
+		C: BlockBackend<B>
+			+ HeaderBackend<B>
+			+ BlockBuilderProvider<BA, B, C>
+			+ ProvideRuntimeApi<B>
+			+ Send
+			+ Sync
+			+ 'static,
+		C::Api: ApiExt<B, StateBackend = backend::StateBackendFor<BA, B>> + BlockBuilderApi<B>,
+		NumberFor<B>: FromStr,
+		<NumberFor<B> as FromStr>::Err: Debug,
+	{
+		let (from, to) = match (&self.from, &self.to) {
+			(Some(from), Some(to)) => (from.parse::<NumberFor<B>>()?, to.parse::<NumberFor<B>>()?),
+			_ => return Err("Both `--from` and `--to` are required".into()),
+		};
+		let mut report = self.report.as_ref().map(fs::File::create).transpose()?;
+
+		let start = std::time::Instant::now();
+		let (mut checked, mut failed) = (0, 0);
+		sc_service::chain_ops::check_block_range(client, from, to, self.jobs, |check| {
+			checked += 1;
+			if check.outcome != BlockCheckOutcome::Ok {
+				failed += 1;
+				println!("#{} ({}): {:?}", check.number, check.hash, check.outcome);
+			}
+
+			if let Some(report) = &mut report {
+				writeln!(report, "{}", report_line(&check))
+					.map_err(|e| format!("Failed to write report: {}", e))?;
+			}
+
+			Ok(())
+		})?;
+
+		println!(
+			"Checked {} blocks in {} ms, {} failed.",
+			checked,
+			start.elapsed().as_millis(),
+			failed,
+		);
+
+		if failed > 0 {
+			return Err(format!("{} blocks failed the check", failed).into())
+		}
+
+		Ok(())
+	}
+}
+
+fn report_line<B: BlockT>(check: &BlockCheck<B>) -> serde_json::Value {
+	let (outcome, details) = match &check.outcome {
+		BlockCheckOutcome::Ok => ("ok", serde_json::Value::Null),
+		BlockCheckOutcome::StateRootMismatch { expected, computed } => (
+			"stateRootMismatch",
+			serde_json::json!({
+				"expected": format!("{:?}", expected),
+				"computed": format!("{:?}", computed),
+			}),
+		),
+		BlockCheckOutcome::Failed(error) => ("failed", error.as_str().into()),
+	};
+
+	serde_json::json!({
+		"number": check.number.to_string(),
+		"hash": format!("{:?}", check.hash),
+		"outcome": outcome,
+		"details": details,
+		"executionMs": check.duration.as_millis() as u64,
+	})
 }
 
 impl CliConfiguration for CheckBlockCmd {
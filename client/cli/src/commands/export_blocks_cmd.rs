@@ -24,9 +24,25 @@ use crate::{
 use clap::Parser;
 use log::info;
 use sc_client_api::{BlockBackend, UsageProvider};
-use sc_service::{chain_ops::export_blocks, config::DatabaseSource};
-use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
-use std::{fmt::Debug, fs, io, path::PathBuf, str::FromStr, sync::Arc};
+use sc_service::{
+	chain_ops::{
+		export_blocks, export_blocks_ndjson, BlockDecoder, ExportCursor, ExtrinsicFilter,
+		OpaqueBlockDecoder,
+	},
+	config::DatabaseSource,
+};
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT},
+	SaturatedConversion,
+};
+use std::{
+	fmt::Debug,
+	fs,
+	io::{self, Seek, SeekFrom},
+	path::PathBuf,
+	str::FromStr,
+	sync::Arc,
+};
 
 /// The `export-blocks` command used to export blocks.
 #[derive(Debug, Clone, Parser)]
@@ -51,6 +67,34 @@ pub struct ExportBlocksCmd {
 	#[clap(long)]
 	pub binary: bool,
 
+	/// Write one JSON object per line and block, including the extrinsics, events and
+	/// justifications of the block.
+	///
+	/// Extrinsics and events are only decoded if the node supports it. Extrinsics that can't be
+	/// decoded are exported as hex together with the error, events that can't be read, e.g.
+	/// because the state was pruned, are exported as `null` together with the error.
+	#[clap(long, conflicts_with = "binary")]
+	pub ndjson: bool,
+
+	/// Only export extrinsics of the given pallet, can be passed multiple times.
+	///
+	/// Blocks without a matching extrinsic are skipped. Requires `--ndjson`.
+	#[clap(long, value_name = "PALLET", requires = "ndjson", multiple_occurrences = true)]
+	pub pallet: Vec<String>,
+
+	/// Only export extrinsics of the given call, can be passed multiple times.
+	///
+	/// Blocks without a matching extrinsic are skipped. Requires `--ndjson`.
+	#[clap(long, value_name = "CALL", requires = "ndjson", multiple_occurrences = true)]
+	pub call: Vec<String>,
+
+	/// Store the progress of the export in this file and resume from it when it exists.
+	///
+	/// Requires `--ndjson` and an output file. The output is truncated to the last position
+	/// stored in the cursor before the export continues.
+	#[clap(long, value_name = "PATH", requires_all = &["ndjson", "output"])]
+	pub cursor: Option<PathBuf>,
+
 	#[allow(missing_docs)]
 	#[clap(flatten)]
 	pub shared_params: SharedParams,
@@ -75,6 +119,22 @@ impl ExportBlocksCmd {
 		B: BlockT,
 		C: BlockBackend<B> + UsageProvider<B> + 'static,
 		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
+	{
+		self.run_with_decoder(client, database_config, OpaqueBlockDecoder).await
+	}
+
+	/// Run the export-blocks command, using `decoder` to decode blocks for `--ndjson`.
+	pub async fn run_with_decoder<B, C, D>(
+		&self,
+		client: Arc<C>,
+		database_config: DatabaseSource,
+		decoder: D,
+	) -> error::Result<()>
+	where
+		B: BlockT,
+		C: BlockBackend<B> + UsageProvider<B> + 'static,
+		D: BlockDecoder<B> + 'static,
+		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
 	{
 		if let Some(path) = database_config.path() {
 			info!("DB path: {}", path.display());
@@ -83,6 +143,10 @@ impl ExportBlocksCmd {
 		let from = self.from.as_ref().and_then(|f| f.parse().ok()).unwrap_or(1u32);
 		let to = self.to.as_ref().and_then(|t| t.parse().ok());
 
+		if self.ndjson {
+			return self.export_ndjson(client, from.into(), to, decoder).await
+		}
+
 		let binary = self.binary;
 
 		let file: Box<dyn io::Write> = match &self.output {
@@ -92,6 +156,45 @@ impl ExportBlocksCmd {
 
 		export_blocks(client, file, from.into(), to, binary).await.map_err(Into::into)
 	}
+
+	async fn export_ndjson<B, C, D>(
+		&self,
+		client: Arc<C>,
+		mut from: <B::Header as HeaderT>::Number,
+		to: Option<<B::Header as HeaderT>::Number>,
+		decoder: D,
+	) -> error::Result<()>
+	where
+		B: BlockT,
+		C: BlockBackend<B> + UsageProvider<B> + 'static,
+		D: BlockDecoder<B> + 'static,
+	{
+		let filter = ExtrinsicFilter { pallets: self.pallet.clone(), calls: self.call.clone() };
+
+		let (file, cursor): (Box<dyn io::Write>, _) = match (&self.output, &self.cursor) {
+			(Some(filename), Some(cursor)) => {
+				let mut file = fs::OpenOptions::new().create(true).write(true).open(filename)?;
+				let position = ExportCursor::load(cursor)?.unwrap_or_default();
+
+				if position.next > 0 {
+					info!("Resuming export at #{}", position.next);
+					from = position.next.saturated_into();
+				}
+				// Drop anything written after the cursor was stored.
+				file.set_len(position.offset)?;
+				file.seek(SeekFrom::End(0))?;
+
+				(Box::new(io::BufWriter::new(file)), Some((cursor.clone(), position.offset)))
+			},
+			(Some(filename), None) =>
+				(Box::new(io::BufWriter::new(fs::File::create(filename)?)), None),
+			(None, _) => (Box::new(io::stdout()), None),
+		};
+
+		export_blocks_ndjson(client, file, from, to, decoder, filter, cursor)
+			.await
+			.map_err(Into::into)
+	}
 }
 
 impl CliConfiguration for ExportBlocksCmd {
@@ -0,0 +1,87 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{arg_enums::ExecutionStrategy, error, CliConfiguration, ImportParams, SharedParams};
+use log::info;
+use parity_scale_codec::Decode;
+use sc_client_api::RecordedCall;
+use sp_core::{hexdisplay::HexDisplay, traits::CodeExecutor};
+use sp_runtime::traits::{Block as BlockT, HashFor};
+use std::{fs, path::PathBuf};
+
+/// The `replay-call` command used to execute a recorded runtime call against its storage proof.
+///
+/// Runtime calls are recorded by running the node with `--record-runtime-calls`.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ReplayCallCmd {
+	/// Path of the recorded call.
+	#[clap(value_name = "FILE", parse(from_os_str))]
+	pub input: PathBuf,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl ReplayCallCmd {
+	/// Run the replay-call command
+	///
+	/// The call is executed with `--execution`, which defaults to WebAssembly, and prints the
+	/// hex encoded result of the call.
+	pub fn run<B, E>(&self, executor: E) -> error::Result<()>
+	where
+		B: BlockT,
+		E: CodeExecutor + Clone + 'static,
+	{
+		let data = fs::read(&self.input)?;
+		let recorded = RecordedCall::<B::Hash>::decode(&mut &data[..])?;
+
+		info!("Replaying runtime call `{}` on state {:?}", recorded.method, recorded.state_root);
+		if let Some(error) = &recorded.error {
+			info!("The call originally failed with: {}", error);
+		}
+
+		let strategy = self
+			.import_params
+			.execution_strategies
+			.execution
+			.unwrap_or(ExecutionStrategy::Wasm);
+		let result = recorded.replay::<HashFor<B>, _>(
+			&executor,
+			strategy.into(),
+			sp_core::testing::TaskExecutor::new(),
+		)?;
+		println!("0x{}", HexDisplay::from(&result));
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ReplayCallCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
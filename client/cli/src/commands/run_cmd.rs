@@ -50,7 +50,10 @@ pub struct RunCmd {
 	#[clap(long)]
 	pub no_grandpa: bool,
 
-	/// Experimental: Run in light client mode.
+	/// Experimental: Answer state queries with proofs fetched from full nodes.
+	///
+	/// This is not a complete light client: the node still imports and executes blocks and keeps
+	/// a state database. Syncing headers alone from warp proofs is not implemented.
 	#[clap(long)]
 	pub light: bool,
 
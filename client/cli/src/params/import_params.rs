@@ -26,7 +26,7 @@ use crate::{
 	params::{DatabaseParams, PruningParams},
 };
 use clap::Args;
-use sc_client_api::execution_extensions::ExecutionStrategies;
+use sc_client_api::{execution_extensions::ExecutionStrategies, CallRecordingConfig};
 use std::path::PathBuf;
 
 /// Parameters for block import.
@@ -68,6 +68,24 @@ pub struct ImportParams {
 	#[clap(long, value_name = "PATH", parse(from_os_str))]
 	pub wasm_runtime_overrides: Option<PathBuf>,
 
+	/// Record runtime calls that fail to files in the given directory.
+	///
+	/// The files can be executed again with the `replay-call` command, without access to the
+	/// database.
+	#[clap(long, value_name = "PATH", parse(from_os_str))]
+	pub record_runtime_calls: Option<PathBuf>,
+
+	/// Also record every call of the given runtime method, e.g. `Core_execute_block`.
+	///
+	/// Requires `--record-runtime-calls`.
+	#[clap(
+		long = "record-runtime-call-method",
+		value_name = "METHOD",
+		multiple_occurrences(true),
+		requires = "record-runtime-calls"
+	)]
+	pub record_runtime_call_methods: Vec<String>,
+
 	#[allow(missing_docs)]
 	#[clap(flatten)]
 	pub execution_strategies: ExecutionStrategiesParams,
@@ -94,6 +112,14 @@ impl ImportParams {
 		self.wasm_runtime_overrides.clone()
 	}
 
+	/// Get the runtime calls to record, if recording is enabled.
+	pub fn runtime_call_recording(&self) -> Option<CallRecordingConfig> {
+		self.record_runtime_calls.clone().map(|path| CallRecordingConfig {
+			path,
+			methods: self.record_runtime_call_methods.clone(),
+		})
+	}
+
 	/// Get execution strategies for the parameters
 	pub fn execution_strategies(&self, is_dev: bool, is_validator: bool) -> ExecutionStrategies {
 		let exec = &self.execution_strategies;
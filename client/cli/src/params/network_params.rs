@@ -20,7 +20,8 @@ use crate::{arg_enums::SyncMode, params::node_key_params::NodeKeyParams};
 use clap::Args;
 use sc_network::{
 	config::{
-		NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode, SetConfig, TransportConfig,
+		BandwidthLimit, NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode, QuotaConfig,
+		RequestQuotas, SetConfig, TransportConfig,
 	},
 	multiaddr::Protocol,
 };
@@ -28,7 +29,7 @@ use sc_service::{
 	config::{Multiaddr, MultiaddrWithPeerId},
 	ChainSpec, ChainType,
 };
-use std::{borrow::Cow, path::PathBuf};
+use std::{borrow::Cow, collections::HashMap, path::PathBuf, time::Duration};
 
 /// Parameters used to create the network configuration.
 #[derive(Debug, Clone, Args)]
@@ -130,6 +131,59 @@ pub struct NetworkParams {
 	#[clap(long)]
 	pub ipfs_server: bool,
 
+	/// Do not remember peer reputations and addresses across restarts.
+	///
+	/// By default they are kept in the network configuration directory.
+	#[clap(long)]
+	pub no_peer_store: bool,
+
+	/// Forget the peer reputations and addresses remembered from previous runs.
+	#[clap(long)]
+	pub wipe_peer_store: bool,
+
+	/// Limit the bandwidth sent on a protocol to all peers together, in bytes per second.
+	///
+	/// The limit applies to every notifications and request-response protocol whose name ends
+	/// with `PROTOCOL`, for example `--bandwidth-limit /sync/2=10000000`.
+	#[clap(
+		long,
+		value_name = "PROTOCOL=BYTES",
+		multiple_occurrences(true),
+		parse(try_from_str = parse_bandwidth_limit)
+	)]
+	pub bandwidth_limit: Vec<(String, u64)>,
+
+	/// Limit the bandwidth sent on a protocol to each peer, in bytes per second.
+	///
+	/// Accepts the same syntax as `--bandwidth-limit`.
+	#[clap(
+		long,
+		value_name = "PROTOCOL=BYTES",
+		multiple_occurrences(true),
+		parse(try_from_str = parse_bandwidth_limit)
+	)]
+	pub peer_bandwidth_limit: Vec<(String, u64)>,
+
+	/// Limit the number of block, state, warp sync and light client requests each peer may send
+	/// per second to each protocol.
+	///
+	/// Peers may send up to twice as many requests in a burst. Requests over quota are refused.
+	/// Unlimited by default.
+	#[clap(long, value_name = "COUNT")]
+	pub peer_request_rate: Option<u32>,
+
+	/// Limit the number of requests of each peer waiting to be handled by each protocol.
+	///
+	/// Unlimited by default.
+	#[clap(long, value_name = "COUNT")]
+	pub peer_pending_requests: Option<usize>,
+
+	/// Limit the time spent handling the requests of each peer, in milliseconds per second.
+	///
+	/// Unlimited by default.
+	#[clap(long, value_name = "MILLISECONDS")]
+	pub peer_request_time: Option<u64>,
+
 	/// Blockchain syncing mode.
 	///
 	/// - `full`: Download and validate full blockchain history.
@@ -239,9 +293,50 @@ impl NetworkParams {
 			kademlia_disjoint_query_paths: self.kademlia_disjoint_query_paths,
 			yamux_window_size: None,
 			ipfs_server: self.ipfs_server,
+			persist_peers: !self.no_peer_store,
+			wipe_peer_store: self.wipe_peer_store,
+			bandwidth_limits: self.bandwidth_limits(),
+			request_quotas: RequestQuotas::uniform(self.request_quota()),
 			sync_mode: self.sync.into(),
 		}
 	}
+
+	/// Returns the per-peer quota on inbound requests, unlimited unless requested otherwise.
+	fn request_quota(&self) -> QuotaConfig {
+		let mut quota = QuotaConfig::unlimited();
+		if let Some(rate) = self.peer_request_rate {
+			quota.requests_per_second = rate;
+			quota.burst = rate.saturating_mul(2);
+		}
+		if let Some(pending) = self.peer_pending_requests {
+			quota.max_pending_per_peer = pending;
+		}
+		if let Some(time) = self.peer_request_time {
+			quota.time_per_second = Duration::from_millis(time);
+		}
+		quota
+	}
+
+	/// Returns the bandwidth limits by protocol, merging the total and per-peer ones.
+	fn bandwidth_limits(&self) -> HashMap<String, BandwidthLimit> {
+		let mut limits = HashMap::<String, BandwidthLimit>::new();
+		for (protocol, rate) in &self.bandwidth_limit {
+			limits.entry(protocol.clone()).or_default().total = Some(*rate);
+		}
+		for (protocol, rate) in &self.peer_bandwidth_limit {
+			limits.entry(protocol.clone()).or_default().per_peer = Some(*rate);
+		}
+		limits
+	}
+}
+
+/// Parses a `PROTOCOL=BYTES` bandwidth limit.
+fn parse_bandwidth_limit(s: &str) -> Result<(String, u64), String> {
+	let (protocol, rate) = s
+		.rsplit_once('=')
+		.ok_or_else(|| format!("Expected `PROTOCOL=BYTES`, got `{}`", s))?;
+	let rate = rate.parse().map_err(|e| format!("Invalid bandwidth `{}`: {}", rate, e))?;
+	Ok((protocol.to_string(), rate))
 }
 
 #[cfg(test)]
@@ -288,6 +383,40 @@ mod tests {
 		assert_eq!(expected, params.network_params.reserved_nodes);
 	}
 
+	#[test]
+	fn bandwidth_limits_are_merged_by_protocol() {
+		let params = Cli::try_parse_from([
+			"",
+			"--bandwidth-limit",
+			"/sync/2=1000",
+			"--peer-bandwidth-limit",
+			"/sync/2=100",
+			"--peer-bandwidth-limit",
+			"/state/2=50",
+		])
+		.expect("Parses network params");
+
+		let limits = params.network_params.bandwidth_limits();
+		assert_eq!(limits["/sync/2"], BandwidthLimit { per_peer: Some(100), total: Some(1000) });
+		assert_eq!(limits["/state/2"], BandwidthLimit { per_peer: Some(50), total: None });
+		assert!(Cli::try_parse_from(["", "--bandwidth-limit", "/sync/2"]).is_err());
+	}
+
+	#[test]
+	fn request_quotas_are_opt_in() {
+		let params = Cli::try_parse_from([""]).expect("Parses network params");
+		assert!(params.network_params.request_quota().is_unlimited());
+
+		let params =
+			Cli::try_parse_from(["", "--peer-request-rate", "10", "--peer-request-time", "100"])
+				.expect("Parses network params");
+		let quota = params.network_params.request_quota();
+		assert_eq!(quota.requests_per_second, 10);
+		assert_eq!(quota.burst, 20);
+		assert_eq!(quota.max_pending_per_peer, usize::MAX);
+		assert_eq!(quota.time_per_second, Duration::from_millis(100));
+	}
+
 	#[test]
 	fn sync_ingores_case() {
 		let params = Cli::try_parse_from(["", "--sync", "wArP"]).expect("Parses network params");
@@ -23,9 +23,11 @@
 //! targeted at handling input parameter parsing providing
 //! a reasonable abstraction.
 
+use std::time::Duration;
+
 use clap::Args;
 use sc_network::config::Role;
-use sc_service::config::OffchainWorkerConfig;
+use sc_service::config::{OffchainHttpPolicy, OffchainSchedulingPolicy, OffchainWorkerConfig};
 
 use crate::{error, OffchainWorkerEnabled};
 
@@ -50,6 +52,40 @@ pub struct OffchainWorkerParams {
 	/// DB during block import.
 	#[clap(long = "enable-offchain-indexing", value_name = "ENABLE_OFFCHAIN_INDEXING")]
 	pub indexing_enabled: bool,
+
+	/// Only allow offchain workers to send HTTP requests to the given hosts.
+	///
+	/// Can be passed multiple times. By default requests to any host are allowed.
+	#[clap(long = "offchain-http-allowed-host", value_name = "HOST", multiple_occurrences(true))]
+	pub http_allowed_hosts: Vec<String>,
+
+	/// Maximum size in bytes of the body of each offchain worker HTTP request and response.
+	#[clap(long = "offchain-http-max-body-size", value_name = "BYTES")]
+	pub http_max_body_size: Option<usize>,
+
+	/// Maximum number of HTTP requests that offchain workers may have in progress at once.
+	#[clap(long = "offchain-http-max-concurrent-requests", value_name = "COUNT")]
+	pub http_max_concurrent_requests: Option<usize>,
+
+	/// Don't run offchain workers for blocks imported while the node is major syncing.
+	#[clap(long = "offchain-worker-skip-major-sync")]
+	pub skip_while_major_syncing: bool,
+
+	/// Maximum number of offchain workers running at the same time.
+	///
+	/// Blocks imported while the limit is reached don't get their offchain workers run. Defaults
+	/// to the number of CPUs when `--offchain-worker-max-run-time` is set, and to no limit
+	/// otherwise.
+	#[clap(long = "offchain-worker-max-concurrent", value_name = "COUNT")]
+	pub max_concurrent_workers: Option<usize>,
+
+	/// Maximum wall-clock time in seconds of a single offchain worker run.
+	///
+	/// Once it has passed, pending HTTP requests of the run are aborted and its blocking calls
+	/// return immediately. The run still counts against `--offchain-worker-max-concurrent` until
+	/// the runtime call has returned.
+	#[clap(long = "offchain-worker-max-run-time", value_name = "SECONDS")]
+	pub max_run_time: Option<u64>,
 }
 
 impl OffchainWorkerParams {
@@ -63,6 +99,23 @@ impl OffchainWorkerParams {
 		};
 
 		let indexing_enabled = self.indexing_enabled;
-		Ok(OffchainWorkerConfig { enabled, indexing_enabled })
+		let http_policy = OffchainHttpPolicy {
+			allowed_hosts: (!self.http_allowed_hosts.is_empty())
+				.then(|| self.http_allowed_hosts.clone()),
+			max_body_size: self.http_max_body_size,
+			max_concurrent_requests: self.http_max_concurrent_requests,
+		};
+		let scheduling = OffchainSchedulingPolicy {
+			skip_while_major_syncing: self.skip_while_major_syncing,
+			max_concurrent_workers: self.max_concurrent_workers,
+			max_run_time: self.max_run_time.map(Duration::from_secs),
+		};
+		Ok(OffchainWorkerConfig {
+			enabled,
+			indexing_enabled,
+			http_policy,
+			scheduling,
+			http_backend: None,
+		})
 	}
 }
@@ -19,7 +19,7 @@
 use crate::{error::Error as CliError, Result, SubstrateCli};
 use chrono::prelude::*;
 use futures::{future, future::FutureExt, pin_mut, select, Future};
-use log::info;
+use log::{info, warn};
 use sc_service::{Configuration, Error as ServiceError, TaskManager};
 use sc_utils::metrics::{TOKIO_THREADS_ALIVE, TOKIO_THREADS_TOTAL};
 use std::marker::PhantomData;
@@ -194,6 +194,12 @@ pub fn print_node_infos<C: SubstrateCli>(config: &Configuration) {
 	info!("📋 Chain specification: {}", config.chain_spec.name());
 	info!("🏷  Node name: {}", config.network.node_name);
 	info!("👤 Role: {}", config.display_role());
+	if config.role.is_light() {
+		warn!(
+			"Light client mode is incomplete: blocks are still imported and executed and the \
+			 state is kept locally, only state queries are answered with proofs from full nodes."
+		);
+	}
 	info!(
 		"💾 Database: {} at {}",
 		config.database,
@@ -96,6 +96,7 @@ pub use sp_database::Database;
 
 #[cfg(any(feature = "with-kvdb-rocksdb", test))]
 pub use bench::BenchmarkingState;
+pub use stats::ColumnStats;
 
 const CACHE_HEADERS: usize = 8;
 
@@ -408,6 +409,21 @@ pub(crate) mod columns {
 	/// Transactions
 	pub const TRANSACTION: u32 = 11;
 	pub const BODY_INDEX: u32 = 12;
+
+	/// All columns in use together with their names.
+	pub const NAMED: &[(u32, &str)] = &[
+		(META, "meta"),
+		(STATE, "state"),
+		(STATE_META, "state_meta"),
+		(KEY_LOOKUP, "key_lookup"),
+		(HEADER, "header"),
+		(BODY, "body"),
+		(JUSTIFICATIONS, "justifications"),
+		(AUX, "aux"),
+		(OFFCHAIN, "offchain"),
+		(TRANSACTION, "transaction"),
+		(BODY_INDEX, "body_index"),
+	];
 }
 
 struct PendingBlock<Block: BlockT> {
@@ -1073,6 +1089,20 @@ impl<Block: BlockT> Backend<Block> {
 		self.storage.clone()
 	}
 
+	/// Collect the number of entries and their sizes for every database column.
+	///
+	/// This iterates the whole database and can take a long time for big databases.
+	pub fn column_stats(&self) -> ClientResult<Vec<(&'static str, ColumnStats)>> {
+		columns::NAMED
+			.iter()
+			.map(|(col, name)| {
+				ColumnStats::collect(&*self.storage.db, *col)
+					.map(|stats| (*name, stats))
+					.map_err(|e| sp_blockchain::Error::Backend(e.to_string()))
+			})
+			.collect()
+	}
+
 	fn from_database(
 		db: Arc<dyn Database<DbHash>>,
 		canonicalization_delay: u64,
@@ -3456,4 +3486,17 @@ pub(crate) mod tests {
 		insert_header_no_head(&backend, 1, block0, [1; 32].into());
 		assert_eq!(backend.blockchain().leaves().unwrap(), vec![block2_a]);
 	}
+
+	#[test]
+	fn column_stats_count_stored_headers() {
+		let backend: Backend<Block> = Backend::new_test(10, 10);
+		let block0 = insert_header(&backend, 0, Default::default(), None, Default::default());
+		insert_header(&backend, 1, block0, None, Default::default());
+
+		let stats = backend.column_stats().unwrap();
+		let header = stats.iter().find(|(name, _)| *name == "header").unwrap().1;
+
+		assert_eq!(header.keys, 2);
+		assert!(header.value_bytes > 0);
+	}
 }
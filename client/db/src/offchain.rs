@@ -83,10 +83,7 @@ impl sp_core::offchain::OffchainStorage for LocalStorage {
 		new_value: &[u8],
 	) -> bool {
 		let key = concatenate_prefix_and_key(prefix, item_key);
-		let key_lock = {
-			let mut locks = self.locks.lock();
-			locks.entry(key.clone()).or_default().clone()
-		};
+		let key_lock = self.key_lock(&key);
 
 		let is_set;
 		{
@@ -99,16 +96,95 @@ impl sp_core::offchain::OffchainStorage for LocalStorage {
 			}
 		}
 
-		// clean the lock map if we're the only entry
+		self.release_key_lock(&key, key_lock);
+		is_set
+	}
+
+	fn keys_with_prefix(
+		&self,
+		prefix: &[u8],
+		key_prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Option<Vec<Vec<u8>>> {
+		let mut keys = self
+			.matching_keys(prefix, key_prefix)?
+			.into_iter()
+			.map(|key| key[prefix.len()..].to_vec())
+			.filter(|key| start_key.map_or(true, |start| key.as_slice() > start))
+			.collect::<Vec<_>>();
+		keys.sort();
+		keys.truncate(count);
+		Some(keys)
+	}
+
+	fn clear_prefix(&mut self, prefix: &[u8], key_prefix: &[u8]) -> Option<u32> {
+		let mut keys = self.matching_keys(prefix, key_prefix)?;
+		keys.sort();
+
+		// Wait for the `compare_and_set` calls in progress on the keys, so that they don't
+		// write back a value after it has been cleared. Key locks are taken in order and
+		// `compare_and_set` only ever holds a single one of them, so this can't deadlock.
+		let key_locks = keys.iter().map(|key| self.key_lock(key)).collect::<Vec<_>>();
+		let key_guards = key_locks.iter().map(|key_lock| key_lock.lock()).collect::<Vec<_>>();
+
+		let mut tx = Transaction::new();
+		for key in &keys {
+			tx.remove(columns::OFFCHAIN, key);
+		}
+		let result = self.db.commit(tx);
+
+		drop(key_guards);
+		for (key, key_lock) in keys.iter().zip(key_locks) {
+			self.release_key_lock(key, key_lock);
+		}
+
+		if let Err(err) = result {
+			error!("Error clearing prefix on local storage: {}", err);
+			return Some(0)
+		}
+		Some(keys.len() as u32)
+	}
+}
+
+impl LocalStorage {
+	/// Get the lock serializing the updates of the given full db key.
+	fn key_lock(&self, key: &[u8]) -> Arc<Mutex<()>> {
+		self.locks.lock().entry(key.to_vec()).or_default().clone()
+	}
+
+	/// Release a lock obtained from [`Self::key_lock`], removing it from the locks map if it
+	/// isn't used by anyone else.
+	fn release_key_lock(&self, key: &[u8], key_lock: Arc<Mutex<()>>) {
 		let mut locks = self.locks.lock();
-		{
-			drop(key_lock);
-			let key_lock = locks.get_mut(&key);
-			if key_lock.and_then(Arc::get_mut).is_some() {
-				locks.remove(&key);
+		drop(key_lock);
+		if locks.get_mut(key).and_then(Arc::get_mut).is_some() {
+			locks.remove(key);
+		}
+	}
+
+	/// Collect all full db keys that start with the given prefix and key prefix.
+	///
+	/// Returns `None` if the database doesn't report the original keys when iterating, as is
+	/// the case with parity-db which only keeps their hashes.
+	fn matching_keys(&self, prefix: &[u8], key_prefix: &[u8]) -> Option<Vec<Vec<u8>>> {
+		if !self.db.supports_key_iteration() {
+			return None
+		}
+
+		let full_prefix = concatenate_prefix_and_key(prefix, key_prefix);
+		let mut keys = Vec::new();
+		let result = self.db.iter_column(columns::OFFCHAIN, &mut |key, _| {
+			if key.starts_with(&full_prefix) {
+				keys.push(key.to_vec());
 			}
+		});
+
+		if let Err(err) = result {
+			error!("Error iterating local storage: {}", err);
+			return None
 		}
-		is_set
+		Some(keys)
 	}
 }
 
@@ -147,4 +223,61 @@ mod tests {
 		assert_eq!(storage.get(prefix, key), Some(b"asd".to_vec()));
 		assert!(storage.locks.lock().is_empty(), "Locks map should be empty!");
 	}
+
+	#[test]
+	fn should_page_and_clear_keys_by_prefix() {
+		let mut storage = LocalStorage::new_test();
+		let prefix = b"prefix";
+
+		storage.set(prefix, b"mmr:1", b"1");
+		storage.set(prefix, b"mmr:2", b"2");
+		storage.set(prefix, b"mmr:3", b"3");
+		storage.set(prefix, b"other", b"4");
+		storage.set(b"elsewhere", b"mmr:4", b"5");
+
+		assert_eq!(
+			storage.keys_with_prefix(prefix, b"mmr:", None, 2),
+			Some(vec![b"mmr:1".to_vec(), b"mmr:2".to_vec()]),
+		);
+		assert_eq!(
+			storage.keys_with_prefix(prefix, b"mmr:", Some(b"mmr:2"), 2),
+			Some(vec![b"mmr:3".to_vec()]),
+		);
+
+		assert_eq!(storage.clear_prefix(prefix, b"mmr:"), Some(3));
+		assert_eq!(storage.keys_with_prefix(prefix, b"mmr:", None, 10), Some(Vec::new()));
+		assert_eq!(storage.get(prefix, b"other"), Some(b"4".to_vec()));
+		assert_eq!(storage.get(b"elsewhere", b"mmr:4"), Some(b"5".to_vec()));
+		assert!(storage.locks.lock().is_empty(), "Locks map should be empty!");
+	}
+
+	#[test]
+	fn should_clear_prefix_after_compare_and_set_in_progress() {
+		let mut storage = LocalStorage::new_test();
+		let prefix = b"prefix";
+		storage.set(prefix, b"mmr:1", b"1");
+
+		// Hold the lock of the key as a `compare_and_set` in progress would.
+		let key = concatenate_prefix_and_key(prefix, b"mmr:1");
+		let key_lock = storage.key_lock(&key);
+		let key_guard = key_lock.lock();
+
+		let (tx, rx) = std::sync::mpsc::channel();
+		let mut clearing = storage.clone();
+		let handle = std::thread::spawn(move || {
+			let cleared = clearing.clear_prefix(prefix, b"mmr:");
+			tx.send(()).unwrap();
+			cleared
+		});
+
+		// The prefix isn't cleared while the key is locked.
+		assert!(rx.recv_timeout(std::time::Duration::from_millis(100)).is_err());
+		storage.set(prefix, b"mmr:1", b"2");
+		drop(key_guard);
+
+		assert_eq!(handle.join().unwrap(), Some(1));
+		assert_eq!(storage.get(prefix, b"mmr:1"), None);
+		storage.release_key_lock(&key, key_lock);
+		assert!(storage.locks.lock().is_empty(), "Locks map should be empty!");
+	}
 }
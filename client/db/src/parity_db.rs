@@ -155,6 +155,20 @@ impl<H: Clone + AsRef<[u8]>> Database<H> for DbAdapter {
 		true
 	}
 
+	fn iter_column(
+		&self,
+		col: ColumnId,
+		f: &mut dyn FnMut(&[u8], &[u8]),
+	) -> Result<(), DatabaseError> {
+		// Keys are stored hashed in parity-db, so the hash is what we report.
+		self.0
+			.iter_column_while(col as u8, |state| {
+				f(&state.key, &state.value);
+				true
+			})
+			.map_err(|e| DatabaseError(Box::new(e)))
+	}
+
 	fn sanitize_key(&self, key: &mut Vec<u8>) {
 		let _prefix = key.drain(0..key.len() - crate::DB_HASH_LEN);
 	}
@@ -18,6 +18,7 @@
 
 //! Database usage statistics
 
+use sp_database::{ColumnId, Database};
 use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 
 /// Accumulated usage statistics for state queries.
@@ -143,3 +144,38 @@ impl StateUsageStats {
 		}
 	}
 }
+
+/// Number and size of the entries stored in a database column.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnStats {
+	/// Number of entries.
+	pub keys: u64,
+	/// Total size of all keys in bytes.
+	pub key_bytes: u64,
+	/// Total size of all values in bytes.
+	pub value_bytes: u64,
+}
+
+impl ColumnStats {
+	/// Iterate over `col` and sum up its entries.
+	pub fn collect<H: Clone + AsRef<[u8]>>(
+		db: &dyn Database<H>,
+		col: ColumnId,
+	) -> sp_database::error::Result<Self> {
+		let mut stats = Self::default();
+		db.iter_column(col, &mut |key, value| stats.tally(key, value))?;
+		Ok(stats)
+	}
+
+	/// Tally one entry.
+	pub fn tally(&mut self, key: &[u8], value: &[u8]) {
+		self.keys += 1;
+		self.key_bytes += key.len() as u64;
+		self.value_bytes += value.len() as u64;
+	}
+
+	/// Total size of keys and values in bytes.
+	pub fn total_bytes(&self) -> u64 {
+		self.key_bytes + self.value_bytes
+	}
+}
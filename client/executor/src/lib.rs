@@ -46,7 +46,7 @@ pub use sp_core::traits::Externalities;
 pub use sp_version::{NativeVersion, RuntimeVersion};
 #[doc(hidden)]
 pub use sp_wasm_interface;
-pub use wasm_runtime::{read_embedded_version, WasmExecutionMethod};
+pub use wasm_runtime::{read_embedded_version, InstantiationTimeExt, WasmExecutionMethod};
 pub use wasmi;
 
 pub use sc_executor_common::{error, sandbox};
@@ -30,11 +30,13 @@ use sc_executor_common::{
 	wasm_runtime::{WasmInstance, WasmModule},
 };
 use sp_core::traits::{Externalities, FetchRuntimeCode, RuntimeCode};
+use sp_externalities::ExternalitiesExt as _;
 use sp_version::RuntimeVersion;
 use std::{
 	panic::AssertUnwindSafe,
 	path::{Path, PathBuf},
 	sync::Arc,
+	time::{Duration, Instant},
 };
 
 use sp_wasm_interface::HostFunctions;
@@ -49,6 +51,22 @@ pub enum WasmExecutionMethod {
 	Compiled,
 }
 
+sp_externalities::decl_extension! {
+	/// Accumulates the time spent preparing a Wasm instance for a call, including the
+	/// compilation of a runtime that is not cached yet.
+	///
+	/// Register it with the extensions of a call to measure instantiation separately from the
+	/// execution itself.
+	pub struct InstantiationTimeExt(Arc<Mutex<Duration>>);
+}
+
+/// Add the time elapsed since `started` to the registered [`InstantiationTimeExt`], if any.
+fn report_instantiation_time(mut ext: &mut dyn Externalities, started: Instant) {
+	if let Some(time) = ext.extension::<InstantiationTimeExt>() {
+		*time.lock() += started.elapsed();
+	}
+}
+
 impl Default for WasmExecutionMethod {
 	fn default() -> WasmExecutionMethod {
 		WasmExecutionMethod::Interpreted
@@ -77,7 +95,14 @@ struct VersionedRuntime {
 
 impl VersionedRuntime {
 	/// Run the given closure `f` with an instance of this runtime.
-	fn with_instance<R, F>(&self, ext: &mut dyn Externalities, f: F) -> Result<R, Error>
+	///
+	/// `started` is the moment the instance was requested, used to report the instantiation time.
+	fn with_instance<R, F>(
+		&self,
+		ext: &mut dyn Externalities,
+		started: Instant,
+		f: F,
+	) -> Result<R, Error>
 	where
 		F: FnOnce(
 			&Arc<dyn WasmModule>,
@@ -99,6 +124,7 @@ impl VersionedRuntime {
 					.take()
 					.map(|r| Ok((r, false)))
 					.unwrap_or_else(|| self.module.new_instance().map(|i| (i, true)))?;
+				report_instantiation_time(ext, started);
 
 				let result = f(&self.module, &mut *instance, self.version.as_ref(), ext);
 				if let Err(e) = &result {
@@ -135,6 +161,7 @@ impl VersionedRuntime {
 
 				// Allocate a new instance
 				let mut instance = self.module.new_instance()?;
+				report_instantiation_time(ext, started);
 
 				f(&self.module, &mut *instance, self.version.as_ref(), ext)
 			},
@@ -232,6 +259,7 @@ impl RuntimeCache {
 			&mut dyn Externalities,
 		) -> Result<R, Error>,
 	{
+		let started = Instant::now();
 		let code_hash = &runtime_code.hash;
 		let heap_pages = runtime_code.heap_pages.unwrap_or(default_heap_pages);
 
@@ -245,7 +273,7 @@ impl RuntimeCache {
 		} else {
 			let code = runtime_code.fetch_runtime_code().ok_or(WasmError::CodeNotFound)?;
 
-			let time = std::time::Instant::now();
+			let time = Instant::now();
 
 			let result = create_versioned_wasm_runtime::<H>(
 				&code,
@@ -282,7 +310,7 @@ impl RuntimeCache {
 		// Lock must be released prior to calling f
 		drop(runtimes);
 
-		Ok(versioned_runtime.with_instance(ext, f))
+		Ok(versioned_runtime.with_instance(ext, started, f))
 	}
 }
 
@@ -1504,6 +1504,26 @@ impl<Block: BlockT> sc_network_gossip::Validator<Block> for GossipValidator<Bloc
 		self.inner.write().peers.peer_disconnected(who);
 	}
 
+	fn message_topic(&self, _who: &PeerId, mut data: &[u8]) -> Option<Block::Hash> {
+		// Only decode the variant index of the `GossipMessage` and the round and set ids that
+		// follow it, without verifying the message.
+		match u8::decode(&mut data).ok()? {
+			// `GossipMessage::Vote`
+			0 => {
+				let (round, set_id) = <(Round, SetId)>::decode(&mut data).ok()?;
+				Some(super::round_topic::<Block>(round.0, set_id.0))
+			},
+			// `GossipMessage::Commit`
+			1 => {
+				let (_, set_id) = <(Round, SetId)>::decode(&mut data).ok()?;
+				Some(super::global_topic::<Block>(set_id.0))
+			},
+			// `GossipMessage::CatchUp`
+			4 => Some(super::global_topic::<Block>(SetId::decode(&mut data).ok()?.0)),
+			_ => None,
+		}
+	}
+
 	fn validate(
 		&self,
 		context: &mut dyn ValidatorContext<Block>,
@@ -1898,6 +1918,59 @@ mod tests {
 		assert_eq!(bad_sig, Action::Discard(cost::BAD_SIGNATURE));
 	}
 
+	#[test]
+	fn message_topic_is_read_from_the_message_prefix() {
+		let (val, _) = GossipValidator::<Block>::new(config(), voter_set_state(), None, None);
+		let peer = PeerId::random();
+		let topic = |message: GossipMessage<Block>| val.message_topic(&peer, &message.encode());
+
+		let vote = GossipMessage::<Block>::Vote(VoteMessage {
+			round: Round(3),
+			set_id: SetId(2),
+			message: SignedMessage::<Block> {
+				message: finality_grandpa::Message::Prevote(finality_grandpa::Prevote {
+					target_hash: Default::default(),
+					target_number: 10,
+				}),
+				signature: UncheckedFrom::unchecked_from([1; 64]),
+				id: UncheckedFrom::unchecked_from([2u8; 32]),
+			},
+		});
+		assert_eq!(topic(vote), Some(communication::round_topic::<Block>(3, 2)));
+
+		let commit = GossipMessage::<Block>::Commit(FullCommitMessage {
+			round: Round(3),
+			set_id: SetId(2),
+			message: finality_grandpa::CompactCommit {
+				target_hash: Default::default(),
+				target_number: 10,
+				precommits: Vec::new(),
+				auth_data: Vec::new(),
+			},
+		});
+		assert_eq!(topic(commit), Some(communication::global_topic::<Block>(2)));
+
+		let catch_up = GossipMessage::<Block>::CatchUp(FullCatchUpMessage {
+			set_id: SetId(2),
+			message: finality_grandpa::CatchUp {
+				round_number: 10,
+				prevotes: Default::default(),
+				precommits: Default::default(),
+				base_hash: Default::default(),
+				base_number: Default::default(),
+			},
+		});
+		assert_eq!(topic(catch_up), Some(communication::global_topic::<Block>(2)));
+
+		let neighbor = GossipMessage::<Block>::from(NeighborPacket {
+			round: Round(3),
+			set_id: SetId(2),
+			commit_finalized_height: 1,
+		});
+		assert_eq!(topic(neighbor), None);
+		assert_eq!(val.message_topic(&peer, &[0, 1]), None);
+	}
+
 	#[test]
 	fn unsolicited_catch_up_messages_discarded() {
 		let (val, _) = GossipValidator::<Block>::new(config(), voter_set_state(), None, None);
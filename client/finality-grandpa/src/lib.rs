@@ -64,8 +64,8 @@ use prometheus_endpoint::{PrometheusError, Registry};
 use sc_client_api::{
 	backend::{AuxStore, Backend},
 	utils::is_descendent_of,
-	BlockchainEvents, CallExecutor, ExecutionStrategy, ExecutorProvider, Finalizer, LockImportRun,
-	StorageProvider, TransactionFor,
+	BlockchainEvents, CallContext, CallExecutor, ExecutionStrategy, ExecutorProvider, Finalizer,
+	LockImportRun, StorageProvider, TransactionFor,
 };
 use sc_consensus::BlockImport;
 use sc_telemetry::{telemetry, TelemetryHandle, CONSENSUS_DEBUG, CONSENSUS_INFO};
@@ -514,6 +514,7 @@ where
 				&[],
 				ExecutionStrategy::NativeElseWasm,
 				None,
+				CallContext::Other,
 			)
 			.and_then(|call_result| {
 				Decode::decode(&mut &call_result[..]).map_err(|err| {
@@ -17,7 +17,9 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-	state_machine::{ConsensusGossip, TopicNotification, PERIODIC_MAINTENANCE_INTERVAL},
+	state_machine::{
+		ConsensusGossip, RateLimits, TopicNotification, PERIODIC_MAINTENANCE_INTERVAL,
+	},
 	Network, Validator,
 };
 
@@ -97,6 +99,12 @@ impl<B: BlockT> GossipEngine<B> {
 		}
 	}
 
+	/// Replace the limits on the gossip received from each peer. [`RateLimits::default`] is used
+	/// until this is called.
+	pub fn set_rate_limits(&mut self, rate_limits: RateLimits) {
+		self.state_machine.set_rate_limits(rate_limits);
+	}
+
 	pub fn report(&self, who: PeerId, reputation: ReputationChange) {
 		self.network.report_peer(who, reputation);
 	}
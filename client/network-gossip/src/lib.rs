@@ -63,7 +63,7 @@
 
 pub use self::{
 	bridge::GossipEngine,
-	state_machine::TopicNotification,
+	state_machine::{RateLimits, TopicNotification},
 	validator::{DiscardAll, MessageIntent, ValidationResult, Validator, ValidatorContext},
 };
 
@@ -21,7 +21,7 @@ use crate::{MessageIntent, Network, ValidationResult, Validator, ValidatorContex
 use ahash::AHashSet;
 use libp2p::PeerId;
 use lru::LruCache;
-use prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
+use prometheus_endpoint::{register, Counter, CounterVec, Opts, PrometheusError, Registry, U64};
 use sc_network::ObservedRole;
 use sp_runtime::traits::{Block as BlockT, Hash, HashFor};
 use std::{borrow::Cow, collections::HashMap, iter, sync::Arc, time, time::Instant};
@@ -47,10 +47,88 @@ mod rep {
 	pub const GOSSIP_SUCCESS: Rep = Rep::new(1 << 4, "Successfull gossip");
 	/// Reputation change when a peer sends us a gossip message that we already knew about.
 	pub const DUPLICATE_GOSSIP: Rep = Rep::new(-(1 << 2), "Duplicate gossip");
+	/// Reputation change when a peer sends us more gossip than its rate limits allow.
+	pub const RATE_LIMITED: Rep = Rep::new(-(1 << 8), "Gossip rate limit exceeded");
+	/// Reputation change when a peer sends us a gossip message over the maximum size.
+	pub const OVERSIZED: Rep = Rep::new(-(1 << 10), "Gossip message too large");
+}
+
+/// Limits on the gossip received from a single peer.
+///
+/// Every peer gets a budget of messages and bytes per `window`, and a smaller one for each topic.
+/// Both budgets are checked before a message is hashed or validated, the topic being given by
+/// [`Validator::message_topic`](crate::Validator::message_topic). Validators that can't tell the
+/// topic up front have the per-topic budget checked right after validation instead, before the
+/// message is kept or forwarded. Messages over budget or over `max_message_size` are dropped and
+/// the sender's reputation is lowered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RateLimits {
+	/// Length of the window the budgets apply to.
+	pub window: time::Duration,
+	/// Maximum number of messages a peer can send per window.
+	pub peer_messages: u32,
+	/// Maximum number of bytes a peer can send per window.
+	pub peer_bytes: u64,
+	/// Maximum number of messages a peer can send per topic and window.
+	pub topic_messages: u32,
+	/// Maximum number of bytes a peer can send per topic and window.
+	pub topic_bytes: u64,
+	/// Maximum size in bytes of a single message.
+	pub max_message_size: usize,
+}
+
+impl Default for RateLimits {
+	/// Limits that leave ample room for GRANDPA and BEEFY with ~1000 validators, where each round
+	/// produces a couple of messages per validator.
+	fn default() -> Self {
+		Self {
+			window: time::Duration::from_secs(10),
+			peer_messages: 20_000,
+			peer_bytes: 64 * 1024 * 1024,
+			topic_messages: 10_000,
+			topic_bytes: 32 * 1024 * 1024,
+			max_message_size: 1024 * 1024,
+		}
+	}
+}
+
+/// Messages and bytes received from a peer in the current rate limiting window.
+struct PeerBudget<H> {
+	window_start: Instant,
+	messages: u32,
+	bytes: u64,
+	topics: HashMap<H, (u32, u64)>,
+}
+
+impl<H: std::hash::Hash + Eq> PeerBudget<H> {
+	fn new(now: Instant) -> Self {
+		Self { window_start: now, messages: 0, bytes: 0, topics: HashMap::new() }
+	}
+
+	/// Account for a message of `size` bytes, returning `false` if it is over the peer budget.
+	fn charge_peer(&mut self, limits: &RateLimits, now: Instant, size: usize) -> bool {
+		if now.duration_since(self.window_start) >= limits.window {
+			*self = Self::new(now);
+		}
+
+		self.messages = self.messages.saturating_add(1);
+		self.bytes = self.bytes.saturating_add(size as u64);
+		self.messages <= limits.peer_messages && self.bytes <= limits.peer_bytes
+	}
+
+	/// Account for a message of `size` bytes under `topic`, returning `false` if it is over the
+	/// topic budget.
+	fn charge_topic(&mut self, limits: &RateLimits, topic: H, size: usize) -> bool {
+		let (messages, bytes) = self.topics.entry(topic).or_default();
+		*messages = messages.saturating_add(1);
+		*bytes = bytes.saturating_add(size as u64);
+		*messages <= limits.topic_messages && *bytes <= limits.topic_bytes
+	}
 }
 
 struct PeerConsensus<H> {
 	known_messages: AHashSet<H>,
+	budget: PeerBudget<H>,
 }
 
 /// Topic stream message with sender.
@@ -158,6 +236,7 @@ pub struct ConsensusGossip<B: BlockT> {
 	protocol: Cow<'static, str>,
 	validator: Arc<dyn Validator<B>>,
 	next_broadcast: Instant,
+	rate_limits: RateLimits,
 	metrics: Option<Metrics>,
 }
 
@@ -184,10 +263,16 @@ impl<B: BlockT> ConsensusGossip<B> {
 			protocol,
 			validator,
 			next_broadcast: Instant::now() + REBROADCAST_INTERVAL,
+			rate_limits: RateLimits::default(),
 			metrics,
 		}
 	}
 
+	/// Replace the limits on the gossip received from each peer.
+	pub fn set_rate_limits(&mut self, rate_limits: RateLimits) {
+		self.rate_limits = rate_limits;
+	}
+
 	/// Handle new connected peer.
 	pub fn new_peer(&mut self, network: &mut dyn Network<B>, who: PeerId, role: ObservedRole) {
 		tracing::trace!(
@@ -197,7 +282,13 @@ impl<B: BlockT> ConsensusGossip<B> {
 			?role,
 			"Registering peer",
 		);
-		self.peers.insert(who, PeerConsensus { known_messages: Default::default() });
+		self.peers.insert(
+			who,
+			PeerConsensus {
+				known_messages: Default::default(),
+				budget: PeerBudget::new(Instant::now()),
+			},
+		);
 
 		let validator = self.validator.clone();
 		let mut context = NetworkContext { gossip: self, network };
@@ -340,7 +431,38 @@ impl<B: BlockT> ConsensusGossip<B> {
 			);
 		}
 
+		if !self.peers.contains_key(&who) {
+			tracing::error!(
+				target: "gossip",
+				%who,
+				protocol = %self.protocol,
+				"Got message from unregistered peer",
+			);
+			return to_forward
+		}
+
+		let now = Instant::now();
 		for message in messages {
+			if message.len() > self.rate_limits.max_message_size {
+				self.oversized(network, who, message.len());
+				continue
+			}
+
+			if let Some(peer) = self.peers.get_mut(&who) {
+				if !peer.budget.charge_peer(&self.rate_limits, now, message.len()) {
+					self.rate_limited(network, who, "peer");
+					continue
+				}
+			}
+
+			let early_topic = self.validator.message_topic(&who, &message);
+			if let (Some(topic), Some(peer)) = (early_topic, self.peers.get_mut(&who)) {
+				if !peer.budget.charge_topic(&self.rate_limits, topic, message.len()) {
+					self.rate_limited(network, who, "topic");
+					continue
+				}
+			}
+
 			let message_hash = HashFor::<B>::hash(&message[..]);
 
 			if self.known_messages.contains(&message_hash) {
@@ -388,6 +510,14 @@ impl<B: BlockT> ConsensusGossip<B> {
 				},
 			};
 
+			// The topic couldn't be charged before validation.
+			if early_topic.is_none() &&
+				!peer.budget.charge_topic(&self.rate_limits, topic, message.len())
+			{
+				self.rate_limited(network, who, "topic");
+				continue
+			}
+
 			network.report_peer(who, rep::GOSSIP_SUCCESS);
 			peer.known_messages.insert(message_hash);
 			to_forward
@@ -401,6 +531,38 @@ impl<B: BlockT> ConsensusGossip<B> {
 		to_forward
 	}
 
+	/// Drop a message of `size` bytes from `who` that is over the maximum message size.
+	fn oversized(&self, network: &mut dyn Network<B>, who: PeerId, size: usize) {
+		tracing::debug!(
+			target: "gossip",
+			%who,
+			protocol = %self.protocol,
+			size,
+			"Dropped oversized message",
+		);
+		network.report_peer(who, rep::OVERSIZED);
+
+		if let Some(ref metrics) = self.metrics {
+			metrics.rate_limited_messages.with_label_values(&["size"]).inc();
+		}
+	}
+
+	/// Drop a message from `who` that is over the `budget` ("peer" or "topic") rate limit.
+	fn rate_limited(&self, network: &mut dyn Network<B>, who: PeerId, budget: &'static str) {
+		tracing::debug!(
+			target: "gossip",
+			%who,
+			protocol = %self.protocol,
+			budget,
+			"Dropped message over rate limit",
+		);
+		network.report_peer(who, rep::RATE_LIMITED);
+
+		if let Some(ref metrics) = self.metrics {
+			metrics.rate_limited_messages.with_label_values(&[budget]).inc();
+		}
+	}
+
 	/// Send all messages with given topic to a peer.
 	pub fn send_topic(
 		&mut self,
@@ -485,6 +647,7 @@ impl<B: BlockT> ConsensusGossip<B> {
 struct Metrics {
 	registered_messages: Counter<U64>,
 	expired_messages: Counter<U64>,
+	rate_limited_messages: CounterVec<U64>,
 }
 
 impl Metrics {
@@ -504,6 +667,17 @@ impl Metrics {
 				)?,
 				registry,
 			)?,
+			rate_limited_messages: register(
+				CounterVec::new(
+					Opts::new(
+						"substrate_network_gossip_rate_limited_messages_total",
+						"Number of incoming messages dropped by the gossip service for exceeding \
+						 the per-peer or per-topic rate limits or the maximum message size.",
+					),
+					&["budget"],
+				)?,
+				registry,
+			)?,
 		})
 	}
 }
@@ -718,4 +892,129 @@ mod tests {
 			to_forward,
 		);
 	}
+
+	#[test]
+	fn on_incoming_drops_messages_over_peer_budget() {
+		let mut consensus = ConsensusGossip::<Block>::new(Arc::new(AllowAll), "/foo".into(), None);
+		consensus.set_rate_limits(RateLimits {
+			peer_messages: 2,
+			topic_messages: 10,
+			..Default::default()
+		});
+
+		let mut network = NoOpNetwork::default();
+		let remote = PeerId::random();
+		consensus.new_peer(&mut network, remote, ObservedRole::Full);
+
+		let to_forward =
+			consensus.on_incoming(&mut network, remote, vec![vec![1], vec![2], vec![3]]);
+		assert_eq!(to_forward.len(), 2);
+		assert!(network
+			.inner
+			.lock()
+			.unwrap()
+			.peer_reports
+			.contains(&(remote, rep::RATE_LIMITED)));
+
+		// Other peers have their own budget.
+		let other = PeerId::random();
+		consensus.new_peer(&mut network, other, ObservedRole::Full);
+		assert_eq!(consensus.on_incoming(&mut network, other, vec![vec![4]]).len(), 1);
+	}
+
+	#[test]
+	fn on_incoming_drops_messages_over_topic_budget() {
+		struct TopicPerFirstByte;
+		impl Validator<Block> for TopicPerFirstByte {
+			fn validate(
+				&self,
+				_context: &mut dyn ValidatorContext<Block>,
+				_sender: &PeerId,
+				data: &[u8],
+			) -> ValidationResult<H256> {
+				ValidationResult::ProcessAndKeep(H256::repeat_byte(data[0]))
+			}
+		}
+
+		let mut consensus =
+			ConsensusGossip::<Block>::new(Arc::new(TopicPerFirstByte), "/foo".into(), None);
+		consensus.set_rate_limits(RateLimits { topic_bytes: 4, ..Default::default() });
+
+		let mut network = NoOpNetwork::default();
+		let remote = PeerId::random();
+		consensus.new_peer(&mut network, remote, ObservedRole::Full);
+
+		let to_forward = consensus.on_incoming(
+			&mut network,
+			remote,
+			vec![vec![1, 0, 0], vec![1, 1, 1], vec![2, 0, 0]],
+		);
+		let topics: Vec<_> = to_forward.into_iter().map(|(topic, _)| topic).collect();
+		assert_eq!(topics, vec![H256::repeat_byte(1), H256::repeat_byte(2)]);
+		assert_eq!(consensus.messages.len(), 2);
+	}
+
+	#[test]
+	fn on_incoming_charges_topic_budget_before_validation() {
+		#[derive(Default)]
+		struct TopicPerFirstByte(std::sync::atomic::AtomicUsize);
+		impl Validator<Block> for TopicPerFirstByte {
+			fn message_topic(&self, _sender: &PeerId, data: &[u8]) -> Option<H256> {
+				Some(H256::repeat_byte(data[0]))
+			}
+
+			fn validate(
+				&self,
+				_context: &mut dyn ValidatorContext<Block>,
+				_sender: &PeerId,
+				data: &[u8],
+			) -> ValidationResult<H256> {
+				self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+				ValidationResult::ProcessAndKeep(H256::repeat_byte(data[0]))
+			}
+		}
+
+		let validator = Arc::new(TopicPerFirstByte::default());
+		let mut consensus = ConsensusGossip::<Block>::new(validator.clone(), "/foo".into(), None);
+		consensus.set_rate_limits(RateLimits { topic_messages: 1, ..Default::default() });
+
+		let mut network = NoOpNetwork::default();
+		let remote = PeerId::random();
+		consensus.new_peer(&mut network, remote, ObservedRole::Full);
+
+		let to_forward =
+			consensus.on_incoming(&mut network, remote, vec![vec![1, 0], vec![1, 1], vec![2, 0]]);
+		assert_eq!(to_forward.len(), 2);
+		// The message over the topic budget was never validated.
+		assert_eq!(validator.0.load(std::sync::atomic::Ordering::SeqCst), 2);
+	}
+
+	#[test]
+	fn on_incoming_drops_oversized_messages() {
+		let mut consensus = ConsensusGossip::<Block>::new(Arc::new(AllowAll), "/foo".into(), None);
+		consensus.set_rate_limits(RateLimits { max_message_size: 2, ..Default::default() });
+
+		let mut network = NoOpNetwork::default();
+		let remote = PeerId::random();
+		consensus.new_peer(&mut network, remote, ObservedRole::Full);
+
+		let to_forward = consensus.on_incoming(&mut network, remote, vec![vec![1, 2, 3], vec![4]]);
+		assert_eq!(to_forward.len(), 1);
+		assert!(network.inner.lock().unwrap().peer_reports.contains(&(remote, rep::OVERSIZED)));
+	}
+
+	#[test]
+	fn peer_budget_is_reset_after_window() {
+		let limits = RateLimits {
+			window: time::Duration::from_secs(1),
+			peer_messages: 1,
+			..Default::default()
+		};
+		let start = Instant::now();
+		let mut budget = PeerBudget::<H256>::new(start);
+
+		assert!(budget.charge_peer(&limits, start, 1));
+		assert!(!budget.charge_peer(&limits, start, 1));
+		assert!(budget.charge_peer(&limits, start + limits.window, 1));
+	}
 }
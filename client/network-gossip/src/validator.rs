@@ -36,6 +36,15 @@ pub trait Validator<B: BlockT>: Send + Sync {
 		data: &[u8],
 	) -> ValidationResult<B::Hash>;
 
+	/// Cheaply determine the topic of a message before validating it, or `None` if it can't be
+	/// known without a full validation.
+	///
+	/// The topic is used to charge the message to the per-topic rate limits of the sender
+	/// before spending any effort on validating it.
+	fn message_topic(&self, _sender: &PeerId, _data: &[u8]) -> Option<B::Hash> {
+		None
+	}
+
 	/// Produce a closure for validating messages on a given topic.
 	fn message_expired<'a>(&'a self) -> Box<dyn FnMut(B::Hash, &[u8]) -> bool + 'a> {
 		Box::new(move |_topic, _data| false)
@@ -20,4 +20,5 @@
 
 pub mod config;
 pub mod message;
+pub mod request_quota;
 pub mod request_responses;
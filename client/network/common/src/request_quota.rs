@@ -0,0 +1,537 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Per-peer quotas on the inbound requests of a request-response protocol.
+//!
+//! [`InboundRequests`] sits between the [`IncomingRequest`] channel of a protocol and its
+//! handler. Requests are queued per peer and handed out to the handler in a round-robin fashion,
+//! so that a single peer can't monopolize the handler. On top of that, each peer is subject to a
+//! [`QuotaConfig`]: a rate limit on its requests, a maximum number of pending requests and a
+//! budget of time spent handling its requests. Requests over quota are refused. As honest peers may
+//! occasionally exceed a quota, for example while syncing from only a handful of nodes, a peer is
+//! only penalized with a [`ReputationChange`](sc_peerset::ReputationChange) when it keeps sending
+//! requests over quota.
+//!
+//! The total number of queued requests is bounded by the size of the inbound channel: once that
+//! many requests are queued, no more requests are taken from the channel until some have been
+//! handled, leaving the network to refuse the requests that don't fit.
+//!
+//! The default [`QuotaConfig`] doesn't limit anything, quotas are opt-in.
+
+use crate::request_responses::{IncomingRequest, OutgoingResponse};
+use futures::{channel::mpsc, prelude::*};
+use libp2p::PeerId;
+use log::debug;
+use std::{
+	collections::{HashMap, VecDeque},
+	time::{Duration, Instant},
+};
+
+const LOG_TARGET: &str = "sub-libp2p";
+
+mod rep {
+	use sc_peerset::ReputationChange as Rep;
+
+	/// Reputation change when a peer keeps sending requests faster than allowed.
+	pub const RATE_LIMITED: Rep = Rep::new(-(1 << 10), "Request rate limit exceeded");
+
+	/// Reputation change when a peer keeps having too many requests pending.
+	pub const TOO_MANY_PENDING: Rep = Rep::new(-(1 << 10), "Too many pending requests");
+
+	/// Reputation change when a peer keeps sending requests while out of time budget.
+	pub const BUDGET_EXHAUSTED: Rep = Rep::new(-(1 << 8), "Request time budget exhausted");
+}
+
+/// Reason for refusing a request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Refusal {
+	/// The peer sends requests faster than allowed.
+	RateLimited,
+	/// The peer has too many requests pending.
+	TooManyPending,
+	/// Handling the requests of the peer took too much time.
+	BudgetExhausted,
+}
+
+impl Refusal {
+	fn reputation_change(self) -> sc_peerset::ReputationChange {
+		match self {
+			Self::RateLimited => rep::RATE_LIMITED,
+			Self::TooManyPending => rep::TOO_MANY_PENDING,
+			Self::BudgetExhausted => rep::BUDGET_EXHAUSTED,
+		}
+	}
+}
+
+/// Quota on the inbound requests of a single peer.
+///
+/// The default quota never refuses any request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuotaConfig {
+	/// Number of requests per second a peer is allowed to send on average.
+	pub requests_per_second: u32,
+	/// Number of requests a peer is allowed to send in a burst.
+	pub burst: u32,
+	/// Maximum number of requests of a peer that are waiting to be handled.
+	pub max_pending_per_peer: usize,
+	/// Time per second that may be spent handling the requests of a peer on average.
+	///
+	/// Up to one second worth of budget can be used in a burst. A peer whose budget is exhausted
+	/// has its requests refused until the budget has been replenished.
+	pub time_per_second: Duration,
+}
+
+impl Default for QuotaConfig {
+	fn default() -> Self {
+		Self::unlimited()
+	}
+}
+
+impl QuotaConfig {
+	/// Quota that never refuses any request.
+	pub fn unlimited() -> Self {
+		Self {
+			requests_per_second: u32::MAX,
+			burst: u32::MAX,
+			max_pending_per_peer: usize::MAX,
+			time_per_second: Duration::MAX,
+		}
+	}
+
+	/// Returns whether this quota never refuses any request.
+	pub fn is_unlimited(&self) -> bool {
+		*self == Self::unlimited()
+	}
+}
+
+/// Queue of the inbound requests of a protocol, enforcing a [`QuotaConfig`] on every peer.
+pub struct InboundRequests {
+	receiver: mpsc::Receiver<IncomingRequest>,
+	config: QuotaConfig,
+	/// Maximum number of requests queued over all peers.
+	max_queued: usize,
+	/// Number of requests queued over all peers.
+	queued: usize,
+	peers: HashMap<PeerId, PeerQuota>,
+	/// Peers with queued requests, in the order they are served.
+	ready: VecDeque<PeerId>,
+	/// Peer whose request was last handed out, and when.
+	in_progress: Option<(PeerId, Instant)>,
+}
+
+/// Quota state and queued requests of a peer.
+struct PeerQuota {
+	requests: TokenBucket,
+	time: TokenBucket,
+	queue: VecDeque<IncomingRequest>,
+	/// Whether the last request of the peer was refused.
+	refused: bool,
+}
+
+impl InboundRequests {
+	/// Wraps the `receiver` of the inbound requests of a protocol.
+	///
+	/// At most `max_queued` requests are queued over all peers, which should be the size of the
+	/// channel of `receiver`, so that queueing requests doesn't defeat its backpressure.
+	pub fn new(
+		receiver: mpsc::Receiver<IncomingRequest>,
+		max_queued: usize,
+		config: QuotaConfig,
+	) -> Self {
+		Self {
+			receiver,
+			config,
+			max_queued: max_queued.max(1),
+			queued: 0,
+			peers: HashMap::new(),
+			ready: VecDeque::new(),
+			in_progress: None,
+		}
+	}
+
+	/// Returns the next request to handle, or `None` once the network has shut down.
+	///
+	/// Handlers are expected to handle requests one at a time: the time elapsed between a request
+	/// being returned and the next call to this method is charged to the peer that sent it.
+	pub async fn next(&mut self) -> Option<IncomingRequest> {
+		if let Some((peer, started)) = self.in_progress.take() {
+			self.charge(&peer, started.elapsed());
+		}
+
+		loop {
+			// Queue what has already arrived, so that peers get served fairly.
+			while self.queued < self.max_queued {
+				match self.receiver.try_next() {
+					Ok(Some(request)) => self.admit(request),
+					_ => break,
+				}
+			}
+
+			if let Some(request) = self.pop() {
+				self.in_progress = Some((request.peer, Instant::now()));
+				return Some(request)
+			}
+
+			match self.receiver.next().await {
+				Some(request) => self.admit(request),
+				None => return None,
+			}
+		}
+	}
+
+	/// Queues `request`, or refuses it if its peer is over quota.
+	fn admit(&mut self, request: IncomingRequest) {
+		let now = Instant::now();
+		let peer = request.peer;
+
+		if !self.peers.contains_key(&peer) {
+			// Forget about the peers that are back to their full allowance, in order to not keep
+			// track of every peer ever seen.
+			self.peers.retain(|_, quota| {
+				!quota.queue.is_empty() || !quota.requests.is_full(now) || !quota.time.is_full(now)
+			});
+		}
+
+		let config = &self.config;
+		let quota = self.peers.entry(peer).or_insert_with(|| PeerQuota {
+			requests: TokenBucket::new(
+				f64::from(config.requests_per_second),
+				f64::from(config.burst),
+				now,
+			),
+			time: TokenBucket::new(
+				config.time_per_second.as_secs_f64(),
+				config.time_per_second.as_secs_f64(),
+				now,
+			),
+			queue: VecDeque::new(),
+			refused: false,
+		});
+
+		let refusal = if quota.queue.len() >= config.max_pending_per_peer {
+			Some(Refusal::TooManyPending)
+		} else if quota.time.in_deficit(now) {
+			Some(Refusal::BudgetExhausted)
+		} else if !quota.requests.try_take(1.0, now) {
+			Some(Refusal::RateLimited)
+		} else {
+			None
+		};
+
+		match refusal {
+			Some(refusal) => {
+				// Only penalize peers that keep sending requests over quota.
+				let reputation_change = quota.refused.then(|| refusal.reputation_change());
+				quota.refused = true;
+				refuse(request, refusal, reputation_change);
+			},
+			None => {
+				quota.refused = false;
+				if quota.queue.is_empty() {
+					self.ready.push_back(peer);
+				}
+				quota.queue.push_back(request);
+				self.queued += 1;
+			},
+		}
+	}
+
+	/// Pops the next request to handle, moving on to the next peer.
+	fn pop(&mut self) -> Option<IncomingRequest> {
+		while let Some(peer) = self.ready.pop_front() {
+			let quota = match self.peers.get_mut(&peer) {
+				Some(quota) => quota,
+				None => continue,
+			};
+			if let Some(request) = quota.queue.pop_front() {
+				if !quota.queue.is_empty() {
+					self.ready.push_back(peer);
+				}
+				self.queued -= 1;
+				return Some(request)
+			}
+		}
+		None
+	}
+
+	/// Charges `elapsed` to the time budget of `peer`.
+	fn charge(&mut self, peer: &PeerId, elapsed: Duration) {
+		if let Some(quota) = self.peers.get_mut(peer) {
+			quota.time.take(elapsed.as_secs_f64(), Instant::now());
+		}
+	}
+}
+
+/// Refuses `request`, applying `reputation_change` to the peer that sent it, if any.
+fn refuse(
+	request: IncomingRequest,
+	refusal: Refusal,
+	reputation_change: Option<sc_peerset::ReputationChange>,
+) {
+	debug!(
+		target: LOG_TARGET,
+		"Refusing request from {}: {}",
+		request.peer,
+		refusal.reputation_change().reason,
+	);
+
+	let _ = request.pending_response.send(OutgoingResponse {
+		result: Err(()),
+		reputation_changes: reputation_change.into_iter().collect(),
+		sent_feedback: None,
+	});
+}
+
+/// Token bucket refilled at `rate` tokens per second, holding up to `capacity` tokens.
+///
+/// The number of tokens can go negative, in which case the bucket is in deficit until it has been
+/// refilled enough.
+struct TokenBucket {
+	rate: f64,
+	capacity: f64,
+	tokens: f64,
+	last_refill: Instant,
+}
+
+impl TokenBucket {
+	fn new(rate: f64, capacity: f64, now: Instant) -> Self {
+		Self { rate, capacity, tokens: capacity, last_refill: now }
+	}
+
+	fn refill(&mut self, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+		self.last_refill = now;
+	}
+
+	/// Takes `amount` tokens if there are enough of them.
+	fn try_take(&mut self, amount: f64, now: Instant) -> bool {
+		self.refill(now);
+		if self.tokens < amount {
+			return false
+		}
+		self.tokens -= amount;
+		true
+	}
+
+	/// Takes `amount` tokens, possibly putting the bucket in deficit.
+	fn take(&mut self, amount: f64, now: Instant) {
+		self.refill(now);
+		self.tokens -= amount;
+	}
+
+	fn in_deficit(&mut self, now: Instant) -> bool {
+		self.refill(now);
+		self.tokens < 0.0
+	}
+
+	fn is_full(&mut self, now: Instant) -> bool {
+		self.refill(now);
+		self.tokens >= self.capacity
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::{channel::oneshot, executor::block_on};
+
+	fn request(peer: PeerId, id: u8) -> (IncomingRequest, oneshot::Receiver<OutgoingResponse>) {
+		let (tx, rx) = oneshot::channel();
+		(IncomingRequest { peer, payload: vec![id], pending_response: tx }, rx)
+	}
+
+	fn config() -> QuotaConfig {
+		QuotaConfig {
+			requests_per_second: 1,
+			burst: 10,
+			max_pending_per_peer: 10,
+			time_per_second: Duration::from_secs(1),
+		}
+	}
+
+	#[test]
+	fn peers_are_served_in_turn() {
+		let (mut tx, rx) = mpsc::channel(16);
+		let mut requests = InboundRequests::new(rx, 16, config());
+		let (peer1, peer2) = (PeerId::random(), PeerId::random());
+
+		let mut responses = Vec::new();
+		for (peer, id) in [(peer1, 1), (peer1, 2), (peer1, 3), (peer2, 4)] {
+			let (request, response) = request(peer, id);
+			tx.try_send(request).unwrap();
+			responses.push(response);
+		}
+		drop(tx);
+
+		let order = block_on(async {
+			let mut order = Vec::new();
+			while let Some(request) = requests.next().await {
+				order.push(request.payload[0]);
+			}
+			order
+		});
+		assert_eq!(order, vec![1, 4, 2, 3]);
+	}
+
+	#[test]
+	fn requests_over_quota_are_refused() {
+		let (mut tx, rx) = mpsc::channel(16);
+		let mut requests = InboundRequests::new(
+			rx,
+			16,
+			QuotaConfig { burst: 2, max_pending_per_peer: 3, ..config() },
+		);
+		let (peer1, peer2) = (PeerId::random(), PeerId::random());
+
+		let (first, _first_response) = request(peer1, 1);
+		let (second, _second_response) = request(peer1, 2);
+		let (third, mut third_response) = request(peer1, 3);
+		let (other, _other_response) = request(peer2, 4);
+		for request in [first, second, third, other] {
+			tx.try_send(request).unwrap();
+		}
+
+		assert_eq!(block_on(requests.next()).unwrap().payload, vec![1]);
+		let refused = third_response.try_recv().unwrap().unwrap();
+		assert!(refused.result.is_err());
+		assert!(refused.reputation_changes.is_empty());
+
+		// Other peers are not affected.
+		assert_eq!(block_on(requests.next()).unwrap().payload, vec![4]);
+	}
+
+	#[test]
+	fn peers_repeatedly_over_quota_are_penalized() {
+		let (mut tx, rx) = mpsc::channel(16);
+		let mut requests = InboundRequests::new(rx, 16, QuotaConfig { burst: 1, ..config() });
+		let peer = PeerId::random();
+
+		let (first, _first_response) = request(peer, 1);
+		let (second, mut second_response) = request(peer, 2);
+		let (third, mut third_response) = request(peer, 3);
+		for request in [first, second, third] {
+			tx.try_send(request).unwrap();
+		}
+
+		assert_eq!(block_on(requests.next()).unwrap().payload, vec![1]);
+		let refused = second_response.try_recv().unwrap().unwrap();
+		assert!(refused.result.is_err());
+		assert!(refused.reputation_changes.is_empty());
+		let refused = third_response.try_recv().unwrap().unwrap();
+		assert!(refused.result.is_err());
+		assert_eq!(refused.reputation_changes, vec![rep::RATE_LIMITED]);
+	}
+
+	#[test]
+	fn queued_requests_are_bounded() {
+		let (mut tx, rx) = mpsc::channel(16);
+		let mut requests = InboundRequests::new(rx, 2, QuotaConfig::unlimited());
+
+		let mut responses = Vec::new();
+		for id in 0..4 {
+			let (request, response) = request(PeerId::random(), id);
+			tx.try_send(request).unwrap();
+			responses.push(response);
+		}
+		drop(tx);
+
+		assert_eq!(block_on(requests.next()).unwrap().payload, vec![0]);
+		// Only as many requests as allowed have been taken from the channel.
+		assert_eq!(requests.queued, 1);
+		assert_eq!(requests.peers.len(), 2);
+
+		let remaining = block_on(async {
+			let mut remaining = Vec::new();
+			while let Some(request) = requests.next().await {
+				remaining.push(request.payload[0]);
+			}
+			remaining
+		});
+		assert_eq!(remaining, vec![1, 2, 3]);
+	}
+
+	#[test]
+	fn too_many_pending_requests_are_refused() {
+		let (mut tx, rx) = mpsc::channel(16);
+		let mut requests =
+			InboundRequests::new(rx, 16, QuotaConfig { max_pending_per_peer: 2, ..config() });
+		let peer = PeerId::random();
+
+		let (first, _first_response) = request(peer, 1);
+		let (second, _second_response) = request(peer, 2);
+		let (third, mut third_response) = request(peer, 3);
+		for request in [first, second, third] {
+			tx.try_send(request).unwrap();
+		}
+
+		assert_eq!(block_on(requests.next()).unwrap().payload, vec![1]);
+		assert_eq!(block_on(requests.next()).unwrap().payload, vec![2]);
+		let refused = third_response.try_recv().unwrap().unwrap();
+		assert!(refused.result.is_err());
+		assert!(refused.reputation_changes.is_empty());
+	}
+
+	#[test]
+	fn exhausted_time_budget_refuses_requests() {
+		let (mut tx, rx) = mpsc::channel(16);
+		let mut requests = InboundRequests::new(rx, 16, config());
+		let peer = PeerId::random();
+
+		let (first, _first_response) = request(peer, 1);
+		tx.try_send(first).unwrap();
+		assert_eq!(block_on(requests.next()).unwrap().payload, vec![1]);
+		requests.charge(&peer, Duration::from_secs(5));
+
+		let (second, mut second_response) = request(peer, 2);
+		tx.try_send(second).unwrap();
+		drop(tx);
+		assert!(block_on(requests.next()).is_none());
+		let refused = second_response.try_recv().unwrap().unwrap();
+		assert!(refused.result.is_err());
+		assert!(refused.reputation_changes.is_empty());
+	}
+
+	#[test]
+	fn default_quota_is_unlimited() {
+		assert!(QuotaConfig::default().is_unlimited());
+
+		let (mut tx, rx) = mpsc::channel(64);
+		let mut requests = InboundRequests::new(rx, 64, QuotaConfig::default());
+		let peer = PeerId::random();
+
+		let mut responses = Vec::new();
+		for id in 0..64 {
+			let (request, response) = request(peer, id);
+			tx.try_send(request).unwrap();
+			responses.push(response);
+		}
+		drop(tx);
+
+		let handled = block_on(async {
+			let mut handled = 0;
+			while requests.next().await.is_some() {
+				handled += 1;
+			}
+			handled
+		});
+		assert_eq!(handled, 64);
+		// Handled requests are dropped without answer here, refused ones would have one.
+		assert!(responses.iter_mut().all(|response| response.try_recv().is_err()));
+	}
+}
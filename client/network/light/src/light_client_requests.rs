@@ -20,6 +20,8 @@
 
 /// For incoming light client requests.
 pub mod handler;
+/// For outgoing light client requests.
+pub mod sender;
 
 use sc_network_common::{config::ProtocolId, request_responses::ProtocolConfig};
 
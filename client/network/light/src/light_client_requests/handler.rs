@@ -24,13 +24,14 @@
 
 use crate::schema;
 use codec::{self, Decode, Encode};
-use futures::{channel::mpsc, prelude::*};
+use futures::channel::mpsc;
 use libp2p::PeerId;
 use log::{debug, trace};
 use prost::Message;
 use sc_client_api::{ProofProvider, StorageProof};
 use sc_network_common::{
 	config::ProtocolId,
+	request_quota::{InboundRequests, QuotaConfig},
 	request_responses::{IncomingRequest, OutgoingResponse, ProtocolConfig},
 };
 use sc_peerset::ReputationChange;
@@ -43,9 +44,12 @@ use std::{marker::PhantomData, sync::Arc};
 
 const LOG_TARGET: &str = "light-client-request-handler";
 
+/// Maximum number of inbound requests waiting to be handled.
+const MAX_QUEUED_REQUESTS: usize = 20;
+
 /// Handler for incoming light client requests from a remote peer.
 pub struct LightClientRequestHandler<B, Client> {
-	request_receiver: mpsc::Receiver<IncomingRequest>,
+	request_receiver: InboundRequests,
 	/// Blockchain client.
 	client: Arc<Client>,
 	_block: PhantomData<B>,
@@ -57,14 +61,20 @@ where
 	Client: ProofProvider<B> + Send + Sync + 'static,
 {
 	/// Create a new [`LightClientRequestHandler`].
-	pub fn new(protocol_id: &ProtocolId, client: Arc<Client>) -> (Self, ProtocolConfig) {
+	pub fn new(
+		protocol_id: &ProtocolId,
+		client: Arc<Client>,
+		quota: QuotaConfig,
+	) -> (Self, ProtocolConfig) {
 		// For now due to lack of data on light client request handling in production systems, this
 		// value is chosen to match the block request limit.
-		let (tx, request_receiver) = mpsc::channel(20);
+		let (tx, request_receiver) = mpsc::channel(MAX_QUEUED_REQUESTS);
 
 		let mut protocol_config = super::generate_protocol_config(protocol_id);
 		protocol_config.inbound_queue = Some(tx);
 
+		let request_receiver = InboundRequests::new(request_receiver, MAX_QUEUED_REQUESTS, quota);
+
 		(Self { client, request_receiver, _block: PhantomData::default() }, protocol_config)
 	}
 
@@ -0,0 +1,599 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Helper for outgoing light client requests.
+//!
+//! [`LightClientRequestSender`] implements [`RemoteFetcher`] on top of the light client request
+//! protocol. Requests are sent to connected full nodes, retrying with another peer when a
+//! request fails or its proof does not check out against the state root of the request header.
+
+use crate::schema;
+use codec::{Decode, Encode};
+use futures::future::{BoxFuture, FutureExt};
+use libp2p::PeerId;
+use log::{debug, trace};
+use parking_lot::Mutex;
+use prost::Message;
+use sc_client_api::{
+	light::{
+		RemoteCallRequest, RemoteFetcher, RemoteReadChildRequest, RemoteReadRequest,
+		RemoteReadResult,
+	},
+	StorageProof,
+};
+use sc_network_common::config::ProtocolId;
+use sp_blockchain::{Error as ClientError, HeaderBackend, Result as ClientResult};
+use sp_core::{
+	storage::{well_known_keys, ChildInfo, ChildType},
+	traits::{CodeExecutor, RuntimeCode, SpawnNamed, WrappedRuntimeCode},
+};
+use sp_runtime::{
+	generic::{BlockId, DigestItem},
+	traits::{Block as BlockT, Hash as HashT, HashFor, Header as HeaderT, NumberFor},
+};
+use sp_state_machine::OverlayedChanges;
+use std::{
+	borrow::Cow,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+};
+
+const LOG_TARGET: &str = "light-client-request-sender";
+
+/// Number of peers a request is sent to before giving up.
+const MAX_ATTEMPTS: usize = 3;
+
+/// Maximum number of headers walked back when checking whether the cached runtime code is still
+/// valid for a block. Past this distance the code is fetched again.
+const MAX_CODE_CACHE_DISTANCE: u32 = 256;
+
+/// Network side of the light client request protocol.
+pub trait RequestSender: Send + Sync {
+	/// Full nodes that light client requests can currently be sent to.
+	fn full_peers(&self) -> Vec<PeerId>;
+
+	/// Send `request` to `peer` using `protocol` and wait for the response.
+	fn send_request(
+		&self,
+		peer: PeerId,
+		protocol: Cow<'static, str>,
+		request: Vec<u8>,
+	) -> BoxFuture<'static, Result<Vec<u8>, String>>;
+}
+
+/// Runtime code fetched from the network, along with the block it was read at.
+struct CachedCode<B: BlockT> {
+	block: B::Hash,
+	number: NumberFor<B>,
+	code: Arc<Vec<u8>>,
+	hash: Vec<u8>,
+	heap_pages: Option<u64>,
+}
+
+/// Fetches and checks remote data on behalf of a light client.
+pub struct LightClientRequestSender<B: BlockT, Client, Exec> {
+	client: Arc<Client>,
+	network: Arc<dyn RequestSender>,
+	executor: Exec,
+	spawn_handle: Box<dyn SpawnNamed>,
+	protocol_name: Cow<'static, str>,
+	code_cache: Arc<Mutex<Option<CachedCode<B>>>>,
+	next_peer: Arc<AtomicUsize>,
+}
+
+impl<B: BlockT, Client, Exec: Clone> Clone for LightClientRequestSender<B, Client, Exec> {
+	fn clone(&self) -> Self {
+		Self {
+			client: self.client.clone(),
+			network: self.network.clone(),
+			executor: self.executor.clone(),
+			spawn_handle: self.spawn_handle.clone(),
+			protocol_name: self.protocol_name.clone(),
+			code_cache: self.code_cache.clone(),
+			next_peer: self.next_peer.clone(),
+		}
+	}
+}
+
+impl<B, Client, Exec> LightClientRequestSender<B, Client, Exec>
+where
+	B: BlockT,
+	Client: HeaderBackend<B> + 'static,
+	Exec: CodeExecutor + Clone + 'static,
+{
+	/// Create a new [`LightClientRequestSender`].
+	///
+	/// `client` is only used to look up headers; `executor` runs remote calls against the
+	/// fetched execution proofs.
+	pub fn new(
+		protocol_id: &ProtocolId,
+		client: Arc<Client>,
+		network: Arc<dyn RequestSender>,
+		executor: Exec,
+		spawn_handle: Box<dyn SpawnNamed>,
+	) -> Self {
+		Self {
+			client,
+			network,
+			executor,
+			spawn_handle,
+			protocol_name: super::generate_protocol_name(protocol_id).into(),
+			code_cache: Arc::new(Mutex::new(None)),
+			next_peer: Arc::new(AtomicUsize::new(0)),
+		}
+	}
+
+	/// Send `request` to up to [`MAX_ATTEMPTS`] full peers, returning the first response that
+	/// `check` accepts.
+	async fn fetch<T>(
+		&self,
+		request: schema::v1::light::request::Request,
+		check: impl Fn(schema::v1::light::response::Response) -> ClientResult<T>,
+	) -> ClientResult<T> {
+		let request = schema::v1::light::Request { request: Some(request) };
+		let mut payload = Vec::with_capacity(request.encoded_len());
+		request.encode(&mut payload).map_err(|_| ClientError::RemoteFetchFailed)?;
+
+		let peers = self.network.full_peers();
+		if peers.is_empty() {
+			debug!(target: LOG_TARGET, "No full peers to send light client request to.");
+			return Err(ClientError::RemoteFetchFailed)
+		}
+
+		// Rotate through the peers so that load is spread across all of them.
+		let offset = self.next_peer.fetch_add(1, Ordering::Relaxed);
+		for n in 0..peers.len().min(MAX_ATTEMPTS) {
+			let peer = peers[(offset + n) % peers.len()];
+			let response = match self
+				.network
+				.send_request(peer, self.protocol_name.clone(), payload.clone())
+				.await
+			{
+				Ok(response) => response,
+				Err(e) => {
+					debug!(target: LOG_TARGET, "Light client request to {} failed: {}", peer, e);
+					continue
+				},
+			};
+
+			let response = match schema::v1::light::Response::decode(&response[..]) {
+				Ok(schema::v1::light::Response { response: Some(response) }) => response,
+				Ok(_) => {
+					debug!(target: LOG_TARGET, "Empty light client response from {}.", peer);
+					continue
+				},
+				Err(e) => {
+					debug!(target: LOG_TARGET, "Invalid light client response from {}: {}", peer, e);
+					continue
+				},
+			};
+
+			match check(response) {
+				Ok(result) => return Ok(result),
+				Err(e) => debug!(
+					target: LOG_TARGET,
+					"Failed to check light client response from {}: {}",
+					peer,
+					e,
+				),
+			}
+		}
+
+		Err(ClientError::RemoteFetchFailed)
+	}
+
+	async fn read(&self, request: RemoteReadRequest<B::Header>) -> ClientResult<RemoteReadResult> {
+		trace!(target: LOG_TARGET, "Remote read of {} keys at {:?}.", request.keys.len(), request.block);
+
+		let root = *request.header.state_root();
+		let keys = request.keys;
+		let message = schema::v1::light::RemoteReadRequest {
+			block: request.block.encode(),
+			keys: keys.clone(),
+		};
+
+		self.fetch(schema::v1::light::request::Request::RemoteReadRequest(message), |response| {
+			let proof = read_response_proof(response)?;
+			sp_state_machine::read_proof_check::<HashFor<B>, _>(root, proof, &keys)
+				.map_err(ClientError::from_state)
+		})
+		.await
+	}
+
+	async fn read_child(
+		&self,
+		request: RemoteReadChildRequest<B::Header>,
+	) -> ClientResult<RemoteReadResult> {
+		let child_info = match ChildType::from_prefixed_key(&request.storage_key) {
+			Some((ChildType::ParentKeyId, storage_key)) => ChildInfo::new_default(storage_key),
+			None => return Err(ClientError::InvalidChildStorageKey),
+		};
+
+		let root = *request.header.state_root();
+		let keys = request.keys;
+		let message = schema::v1::light::RemoteReadChildRequest {
+			block: request.block.encode(),
+			storage_key: request.storage_key.into_inner(),
+			keys: keys.clone(),
+		};
+
+		self.fetch(
+			schema::v1::light::request::Request::RemoteReadChildRequest(message),
+			|response| {
+				let proof = read_response_proof(response)?;
+				sp_state_machine::read_child_proof_check::<HashFor<B>, _>(
+					root,
+					proof,
+					&child_info,
+					&keys,
+				)
+				.map_err(ClientError::from_state)
+			},
+		)
+		.await
+	}
+
+	async fn call(&self, request: RemoteCallRequest<B::Header>) -> ClientResult<Vec<u8>> {
+		trace!(target: LOG_TARGET, "Remote call of {} at {:?}.", request.method, request.block);
+
+		let (code, code_hash, heap_pages) = self.runtime_code(&request.header).await?;
+		let root = *request.header.state_root();
+		let method = request.method;
+		let call_data = request.call_data;
+		let message = schema::v1::light::RemoteCallRequest {
+			block: request.block.encode(),
+			method: method.clone(),
+			data: call_data.clone(),
+		};
+
+		self.fetch(schema::v1::light::request::Request::RemoteCallRequest(message), |response| {
+			let proof = match response {
+				schema::v1::light::response::Response::RemoteCallResponse(r) =>
+					StorageProof::decode(&mut &r.proof[..])
+						.map_err(|e| ClientError::Application(Box::new(e)))?,
+				_ => return Err(ClientError::RemoteFetchFailed),
+			};
+
+			let code_fetcher = WrappedRuntimeCode(code.as_slice().into());
+			let runtime_code =
+				RuntimeCode { code_fetcher: &code_fetcher, hash: code_hash.clone(), heap_pages };
+			sp_state_machine::execution_proof_check::<HashFor<B>, _, _>(
+				root,
+				proof,
+				&mut OverlayedChanges::default(),
+				&self.executor,
+				self.spawn_handle.clone(),
+				&method,
+				&call_data,
+				&runtime_code,
+			)
+			.map_err(ClientError::from_state)
+		})
+		.await
+	}
+
+	/// Runtime code and heap pages in effect at `header`.
+	///
+	/// Execution proofs do not contain the runtime code, so it is read separately and cached
+	/// until a block signals that the runtime environment was updated.
+	async fn runtime_code(
+		&self,
+		header: &B::Header,
+	) -> ClientResult<(Arc<Vec<u8>>, Vec<u8>, Option<u64>)> {
+		if let Some(cached) = self.code_cache.lock().as_ref() {
+			if self.is_code_unchanged_since(cached, header) {
+				return Ok((cached.code.clone(), cached.hash.clone(), cached.heap_pages))
+			}
+		}
+
+		let keys = vec![well_known_keys::CODE.to_vec(), well_known_keys::HEAP_PAGES.to_vec()];
+		let mut values = self
+			.read(RemoteReadRequest { block: header.hash(), header: header.clone(), keys })
+			.await?;
+
+		let code = values
+			.remove(well_known_keys::CODE)
+			.flatten()
+			.ok_or(ClientError::RuntimeCodeMissing)?;
+		let heap_pages = values
+			.remove(well_known_keys::HEAP_PAGES)
+			.flatten()
+			.and_then(|pages| u64::decode(&mut &pages[..]).ok());
+		let code = Arc::new(code);
+		let hash = <HashFor<B> as HashT>::hash(&code).encode();
+
+		*self.code_cache.lock() = Some(CachedCode {
+			block: header.hash(),
+			number: *header.number(),
+			code: code.clone(),
+			hash: hash.clone(),
+			heap_pages,
+		});
+
+		Ok((code, hash, heap_pages))
+	}
+
+	/// Whether no block between `cached` and `header` updated the runtime environment.
+	fn is_code_unchanged_since(&self, cached: &CachedCode<B>, header: &B::Header) -> bool {
+		if *header.number() < cached.number ||
+			*header.number() - cached.number > MAX_CODE_CACHE_DISTANCE.into()
+		{
+			return false
+		}
+
+		let mut current = header.clone();
+		loop {
+			if current.hash() == cached.block {
+				return true
+			}
+			if current.number() <= &cached.number ||
+				current
+					.digest()
+					.logs()
+					.iter()
+					.any(|log| matches!(log, DigestItem::RuntimeEnvironmentUpdated))
+			{
+				return false
+			}
+			current = match self.client.header(BlockId::Hash(*current.parent_hash())) {
+				Ok(Some(parent)) => parent,
+				_ => return false,
+			};
+		}
+	}
+}
+
+impl<B, Client, Exec> RemoteFetcher<B> for LightClientRequestSender<B, Client, Exec>
+where
+	B: BlockT,
+	Client: HeaderBackend<B> + 'static,
+	Exec: CodeExecutor + Clone + 'static,
+{
+	fn remote_read(
+		&self,
+		request: RemoteReadRequest<B::Header>,
+	) -> BoxFuture<'static, ClientResult<RemoteReadResult>> {
+		let this = self.clone();
+		async move { this.read(request).await }.boxed()
+	}
+
+	fn remote_read_child(
+		&self,
+		request: RemoteReadChildRequest<B::Header>,
+	) -> BoxFuture<'static, ClientResult<RemoteReadResult>> {
+		let this = self.clone();
+		async move { this.read_child(request).await }.boxed()
+	}
+
+	fn remote_call(
+		&self,
+		request: RemoteCallRequest<B::Header>,
+	) -> BoxFuture<'static, ClientResult<Vec<u8>>> {
+		let this = self.clone();
+		async move { this.call(request).await }.boxed()
+	}
+}
+
+fn read_response_proof(
+	response: schema::v1::light::response::Response,
+) -> ClientResult<StorageProof> {
+	match response {
+		schema::v1::light::response::Response::RemoteReadResponse(r) =>
+			StorageProof::decode(&mut &r.proof[..])
+				.map_err(|e| ClientError::Application(Box::new(e))),
+		_ => Err(ClientError::RemoteFetchFailed),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_client_api::ProofProvider;
+	use sp_core::storage::PrefixedStorageKey;
+	use substrate_test_runtime_client::{
+		runtime::{Block, Header},
+		DefaultTestClientBuilderExt, TestClient, TestClientBuilder, TestClientBuilderExt,
+	};
+
+	/// Answers requests from a local client, except for `bad_peer` which returns bogus proofs.
+	struct TestNetwork {
+		client: Arc<TestClient>,
+		peers: Vec<PeerId>,
+		bad_peer: Option<PeerId>,
+	}
+
+	impl TestNetwork {
+		fn respond(&self, request: Vec<u8>) -> Vec<u8> {
+			use schema::v1::light::{request::Request, response::Response};
+
+			let request = schema::v1::light::Request::decode(&request[..]).unwrap();
+			let response = match request.request.unwrap() {
+				Request::RemoteReadRequest(r) => {
+					let block = Decode::decode(&mut &r.block[..]).unwrap();
+					let proof = self
+						.client
+						.read_proof(&BlockId::Hash(block), &mut r.keys.iter().map(AsRef::as_ref))
+						.unwrap();
+					Response::RemoteReadResponse(schema::v1::light::RemoteReadResponse {
+						proof: proof.encode(),
+					})
+				},
+				Request::RemoteReadChildRequest(r) => {
+					let block = Decode::decode(&mut &r.block[..]).unwrap();
+					let storage_key = PrefixedStorageKey::new_ref(&r.storage_key);
+					let child_info = match ChildType::from_prefixed_key(storage_key) {
+						Some((ChildType::ParentKeyId, storage_key)) =>
+							ChildInfo::new_default(storage_key),
+						None => panic!("Light client sent an invalid child storage key"),
+					};
+					let proof = self
+						.client
+						.read_child_proof(
+							&BlockId::Hash(block),
+							&child_info,
+							&mut r.keys.iter().map(AsRef::as_ref),
+						)
+						.unwrap();
+					Response::RemoteReadResponse(schema::v1::light::RemoteReadResponse {
+						proof: proof.encode(),
+					})
+				},
+				Request::RemoteCallRequest(r) => {
+					let block = Decode::decode(&mut &r.block[..]).unwrap();
+					let (_, proof) = self
+						.client
+						.execution_proof(&BlockId::Hash(block), &r.method, &r.data)
+						.unwrap();
+					Response::RemoteCallResponse(schema::v1::light::RemoteCallResponse {
+						proof: proof.encode(),
+					})
+				},
+				request => panic!("Light client sent an unexpected request: {:?}", request),
+			};
+
+			let mut data = Vec::new();
+			schema::v1::light::Response { response: Some(response) }
+				.encode(&mut data)
+				.unwrap();
+			data
+		}
+	}
+
+	impl RequestSender for TestNetwork {
+		fn full_peers(&self) -> Vec<PeerId> {
+			self.peers.clone()
+		}
+
+		fn send_request(
+			&self,
+			peer: PeerId,
+			_protocol: Cow<'static, str>,
+			request: Vec<u8>,
+		) -> BoxFuture<'static, Result<Vec<u8>, String>> {
+			let response = if Some(peer) == self.bad_peer {
+				let response =
+					schema::v1::light::RemoteReadResponse { proof: StorageProof::empty().encode() };
+				let mut data = Vec::new();
+				schema::v1::light::Response {
+					response: Some(schema::v1::light::response::Response::RemoteReadResponse(
+						response,
+					)),
+				}
+				.encode(&mut data)
+				.unwrap();
+				data
+			} else {
+				self.respond(request)
+			};
+			futures::future::ready(Ok(response)).boxed()
+		}
+	}
+
+	fn sender(
+		peers: Vec<PeerId>,
+		bad_peer: Option<PeerId>,
+	) -> (
+		Arc<TestClient>,
+		LightClientRequestSender<Block, TestClient, impl CodeExecutor + Clone>,
+		Header,
+	) {
+		let client = Arc::new(
+			TestClientBuilder::new()
+				.add_extra_child_storage(
+					&ChildInfo::new_default(b"child"),
+					b"key".to_vec(),
+					b"value".to_vec(),
+				)
+				.build(),
+		);
+		let header = client.header(&BlockId::Number(0)).unwrap().unwrap();
+		let network = Arc::new(TestNetwork { client: client.clone(), peers, bad_peer });
+		let sender = LightClientRequestSender::new(
+			&ProtocolId::from("test"),
+			client.clone(),
+			network,
+			substrate_test_runtime_client::new_native_executor(),
+			Box::new(sp_core::testing::TaskExecutor::new()),
+		);
+		(client, sender, header)
+	}
+
+	#[test]
+	fn remote_read_retries_after_invalid_proof() {
+		let bad_peer = PeerId::random();
+		let (_, sender, header) = sender(vec![bad_peer, PeerId::random()], Some(bad_peer));
+
+		let request = RemoteReadRequest {
+			block: header.hash(),
+			header,
+			keys: vec![well_known_keys::HEAP_PAGES.to_vec(), b":unknown".to_vec()],
+		};
+		let values = futures::executor::block_on(sender.remote_read(request)).unwrap();
+
+		assert_eq!(values[well_known_keys::HEAP_PAGES], Some(16u64.encode()));
+		assert_eq!(values[&b":unknown"[..]], None);
+	}
+
+	#[test]
+	fn remote_read_child_checks_child_trie_proof() {
+		let (_, sender, header) = sender(vec![PeerId::random()], None);
+
+		let request = RemoteReadChildRequest {
+			block: header.hash(),
+			header,
+			storage_key: ChildInfo::new_default(b"child").prefixed_storage_key(),
+			keys: vec![b"key".to_vec(), b"other".to_vec()],
+		};
+		let values = futures::executor::block_on(sender.remote_read_child(request)).unwrap();
+
+		assert_eq!(values[&b"key"[..]], Some(b"value".to_vec()));
+		assert_eq!(values[&b"other"[..]], None);
+	}
+
+	#[test]
+	fn remote_read_fails_without_honest_peers() {
+		let bad_peer = PeerId::random();
+		let (_, sender, header) = sender(vec![bad_peer], Some(bad_peer));
+
+		let request =
+			RemoteReadRequest { block: header.hash(), header, keys: vec![b":code".to_vec()] };
+
+		assert!(matches!(
+			futures::executor::block_on(sender.remote_read(request)),
+			Err(ClientError::RemoteFetchFailed),
+		));
+	}
+
+	#[test]
+	fn remote_call_executes_on_execution_proof() {
+		let (client, sender, header) = sender(vec![PeerId::random()], None);
+
+		let request = RemoteCallRequest {
+			block: header.hash(),
+			header,
+			method: "Core_version".into(),
+			call_data: Vec::new(),
+		};
+		let version = futures::executor::block_on(sender.remote_call(request)).unwrap();
+
+		assert_eq!(version, client.runtime_version_at(&BlockId::Number(0)).unwrap().encode());
+		assert!(sender.code_cache.lock().is_some());
+	}
+}
@@ -0,0 +1,239 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Per-protocol bandwidth accounting and limiting.
+//!
+//! Every byte sent or received on a notifications or request-response protocol is accounted to
+//! that protocol. On top of that, the outbound traffic of a protocol can be limited, both per peer
+//! and in total, according to the [`BandwidthLimit`]s of the network configuration. Limits are
+//! enforced with token buckets that are refilled at the configured rate and hold up to one second
+//! worth of bytes.
+//!
+//! Sending is allowed as long as the buckets aren't in deficit, and the size of what is sent is
+//! then taken from them. A single message larger than the bucket is therefore never blocked, but
+//! delays whatever comes after it.
+
+use crate::{config::BandwidthLimit, network_state::ProtocolBandwidth};
+use libp2p::PeerId;
+use parking_lot::Mutex;
+use std::{
+	borrow::Cow,
+	collections::HashMap,
+	time::{Duration, Instant},
+};
+
+/// Bandwidth accounting of all the protocols, shared between the service and the behaviours.
+pub struct BandwidthAccounting {
+	/// Configured limits, by protocol name suffix.
+	limits: HashMap<String, BandwidthLimit>,
+	/// State of each protocol that has seen any traffic.
+	protocols: Mutex<HashMap<Cow<'static, str>, ProtocolState>>,
+}
+
+/// Traffic and limits of a single protocol.
+struct ProtocolState {
+	inbound: u64,
+	outbound: u64,
+	limit: BandwidthLimit,
+	total: Option<TokenBucket>,
+	peers: HashMap<PeerId, TokenBucket>,
+}
+
+impl BandwidthAccounting {
+	/// Creates a new accounting with the given limits, by protocol name suffix.
+	pub fn new(limits: HashMap<String, BandwidthLimit>) -> Self {
+		Self { limits, protocols: Mutex::new(HashMap::new()) }
+	}
+
+	/// Reports that `bytes` bytes were received on `protocol`.
+	pub fn report_inbound(&self, protocol: &Cow<'static, str>, bytes: usize) {
+		let mut protocols = self.protocols.lock();
+		let state = self.state(&mut protocols, protocol);
+		state.inbound = state.inbound.saturating_add(bytes as u64);
+	}
+
+	/// Reports that `bytes` bytes were sent to `peer` on `protocol`.
+	pub fn report_outbound(&self, protocol: &Cow<'static, str>, peer: &PeerId, bytes: usize) {
+		let now = Instant::now();
+		let mut protocols = self.protocols.lock();
+		let state = self.state(&mut protocols, protocol);
+		state.outbound = state.outbound.saturating_add(bytes as u64);
+
+		if let Some(total) = &mut state.total {
+			total.consume(bytes as u64, now);
+		}
+		if let Some(rate) = state.limit.per_peer {
+			if !state.peers.contains_key(peer) {
+				// Forget about the peers that are back to their full allowance, in order to not
+				// keep track of every peer ever seen.
+				state.peers.retain(|_, bucket| !bucket.is_full(now));
+			}
+			state
+				.peers
+				.entry(*peer)
+				.or_insert_with(|| TokenBucket::new(rate, now))
+				.consume(bytes as u64, now);
+		}
+	}
+
+	/// Returns how long to wait before sending to `peer` on `protocol` is allowed again, or `None`
+	/// if it is allowed right away.
+	pub fn outbound_delay(&self, protocol: &Cow<'static, str>, peer: &PeerId) -> Option<Duration> {
+		let now = Instant::now();
+		let mut protocols = self.protocols.lock();
+		let state = self.state(&mut protocols, protocol);
+
+		let total = state.total.as_mut().and_then(|bucket| bucket.delay(now));
+		let per_peer = state.peers.get_mut(peer).and_then(|bucket| bucket.delay(now));
+		total.max(per_peer)
+	}
+
+	/// Returns the number of bytes received and sent so far on each protocol.
+	pub fn stats(&self) -> HashMap<String, ProtocolBandwidth> {
+		self.protocols
+			.lock()
+			.iter()
+			.map(|(protocol, state)| {
+				(
+					protocol.to_string(),
+					ProtocolBandwidth { inbound: state.inbound, outbound: state.outbound },
+				)
+			})
+			.collect()
+	}
+
+	/// Returns the state of `protocol`, creating it if this is the first time it is seen.
+	fn state<'a>(
+		&self,
+		protocols: &'a mut HashMap<Cow<'static, str>, ProtocolState>,
+		protocol: &Cow<'static, str>,
+	) -> &'a mut ProtocolState {
+		protocols.entry(protocol.clone()).or_insert_with(|| {
+			let limit = self
+				.limits
+				.iter()
+				.find(|(suffix, _)| protocol.ends_with(suffix.as_str()))
+				.map(|(_, limit)| *limit)
+				.unwrap_or_default();
+			ProtocolState {
+				inbound: 0,
+				outbound: 0,
+				limit,
+				total: limit.total.map(|rate| TokenBucket::new(rate, Instant::now())),
+				peers: HashMap::new(),
+			}
+		})
+	}
+}
+
+/// Token bucket refilled at `rate` bytes per second, holding up to `rate` bytes.
+///
+/// The number of tokens can go negative, in which case the bucket is in deficit until it has been
+/// refilled enough.
+struct TokenBucket {
+	rate: u64,
+	tokens: f64,
+	last_refill: Instant,
+}
+
+impl TokenBucket {
+	fn new(rate: u64, now: Instant) -> Self {
+		Self { rate, tokens: rate as f64, last_refill: now }
+	}
+
+	fn refill(&mut self, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.rate as f64);
+		self.last_refill = now;
+	}
+
+	fn consume(&mut self, bytes: u64, now: Instant) {
+		self.refill(now);
+		self.tokens -= bytes as f64;
+	}
+
+	fn delay(&mut self, now: Instant) -> Option<Duration> {
+		self.refill(now);
+		if self.tokens >= 0.0 {
+			None
+		} else if self.rate == 0 {
+			// Nothing is ever allowed through, check back regularly all the same.
+			Some(Duration::from_secs(1))
+		} else {
+			Some(Duration::from_secs_f64(-self.tokens / self.rate as f64))
+		}
+	}
+
+	fn is_full(&mut self, now: Instant) -> bool {
+		self.refill(now);
+		self.tokens >= self.rate as f64
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const PROTOCOL: Cow<'static, str> = Cow::Borrowed("/dot/sync/2");
+
+	#[test]
+	fn token_bucket_refills_at_rate() {
+		let start = Instant::now();
+		let mut bucket = TokenBucket::new(1000, start);
+		assert_eq!(bucket.delay(start), None);
+
+		bucket.consume(3000, start);
+		assert_eq!(bucket.delay(start), Some(Duration::from_secs(2)));
+		assert_eq!(bucket.delay(start + Duration::from_secs(1)), Some(Duration::from_secs(1)));
+		assert_eq!(bucket.delay(start + Duration::from_secs(2)), None);
+		assert!(!bucket.is_full(start + Duration::from_secs(2)));
+		assert!(bucket.is_full(start + Duration::from_secs(10)));
+	}
+
+	#[test]
+	fn limits_apply_per_peer_and_in_total() {
+		let limit = BandwidthLimit { per_peer: Some(100), total: Some(250) };
+		let accounting = BandwidthAccounting::new([("/sync/2".to_string(), limit)].into());
+		let (peer1, peer2, peer3) = (PeerId::random(), PeerId::random(), PeerId::random());
+
+		accounting.report_outbound(&PROTOCOL, &peer1, 150);
+		assert!(accounting.outbound_delay(&PROTOCOL, &peer1).is_some());
+		assert!(accounting.outbound_delay(&PROTOCOL, &peer2).is_none());
+
+		accounting.report_outbound(&PROTOCOL, &peer2, 150);
+		assert!(accounting.outbound_delay(&PROTOCOL, &peer3).is_some());
+
+		// Other protocols are not limited.
+		let other = Cow::Borrowed("/dot/state/2");
+		accounting.report_outbound(&other, &peer1, 1000);
+		assert!(accounting.outbound_delay(&other, &peer1).is_none());
+	}
+
+	#[test]
+	fn traffic_is_accounted_per_protocol() {
+		let accounting = BandwidthAccounting::new(HashMap::new());
+		let peer = PeerId::random();
+		accounting.report_inbound(&PROTOCOL, 10);
+		accounting.report_outbound(&PROTOCOL, &peer, 20);
+		accounting.report_outbound(&PROTOCOL, &peer, 5);
+
+		let stats = accounting.stats();
+		assert_eq!(stats.len(), 1);
+		assert_eq!(stats[&*PROTOCOL], ProtocolBandwidth { inbound: 10, outbound: 25 });
+	}
+}
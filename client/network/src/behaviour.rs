@@ -17,7 +17,8 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-	bitswap::Bitswap,
+	bandwidth::BandwidthAccounting,
+	bitswap::{Bitswap, BitswapEvent, FetchResponseSender},
 	discovery::{DiscoveryBehaviour, DiscoveryConfig, DiscoveryOut},
 	peer_info,
 	protocol::{message::Roles, CustomMessageOutcome, NotificationsSink, Protocol},
@@ -25,6 +26,7 @@ use crate::{
 };
 
 use bytes::Bytes;
+use cid::Cid;
 use codec::Encode;
 use futures::channel::oneshot;
 use libp2p::{
@@ -32,8 +34,7 @@ use libp2p::{
 	identify::IdentifyInfo,
 	kad::record,
 	swarm::{
-		behaviour::toggle::Toggle, NetworkBehaviour, NetworkBehaviourAction,
-		NetworkBehaviourEventProcess, PollParameters,
+		NetworkBehaviour, NetworkBehaviourAction, NetworkBehaviourEventProcess, PollParameters,
 	},
 	NetworkBehaviour,
 };
@@ -51,8 +52,9 @@ use sp_runtime::{
 };
 use std::{
 	borrow::Cow,
-	collections::{HashSet, VecDeque},
+	collections::{HashMap, HashSet, VecDeque},
 	iter,
+	sync::Arc,
 	task::{Context, Poll},
 	time::Duration,
 };
@@ -82,8 +84,8 @@ where
 	peer_info: peer_info::PeerInfoBehaviour,
 	/// Discovers nodes of the network.
 	discovery: DiscoveryBehaviour,
-	/// Bitswap server for blockchain data.
-	bitswap: Toggle<Bitswap<B, Client>>,
+	/// Bitswap server and client for blockchain data.
+	bitswap: Bitswap<B, Client>,
 	/// Generic request-response protocols.
 	request_responses: request_responses::RequestResponsesBehaviour,
 
@@ -223,11 +225,12 @@ where
 		block_request_protocol_config: ProtocolConfig,
 		state_request_protocol_config: ProtocolConfig,
 		warp_sync_protocol_config: Option<ProtocolConfig>,
-		bitswap: Option<Bitswap<B, Client>>,
+		bitswap: Bitswap<B, Client>,
 		light_client_request_protocol_config: ProtocolConfig,
 		// All remaining request protocol configs.
 		mut request_response_protocols: Vec<ProtocolConfig>,
 		peerset: PeersetHandle,
+		protocol_bandwidth: Arc<BandwidthAccounting>,
 	) -> Result<Self, request_responses::RegisterError> {
 		// Extract protocol name and add to `request_response_protocols`.
 		let block_request_protocol_name = block_request_protocol_config.name.to_string();
@@ -248,10 +251,11 @@ where
 			substrate,
 			peer_info: peer_info::PeerInfoBehaviour::new(user_agent, local_public_key),
 			discovery: disco_config.finish(),
-			bitswap: bitswap.into(),
+			bitswap,
 			request_responses: request_responses::RequestResponsesBehaviour::new(
 				request_response_protocols.into_iter(),
 				peerset,
+				protocol_bandwidth,
 			)?,
 			events: VecDeque::new(),
 			block_request_protocol_name,
@@ -265,11 +269,21 @@ where
 		self.discovery.known_peers()
 	}
 
+	/// Returns the addresses of the nodes that we know exist in the network.
+	pub fn known_addresses(&mut self) -> HashMap<PeerId, Vec<Multiaddr>> {
+		self.discovery.known_addresses()
+	}
+
 	/// Adds a hard-coded address for the given peer, that never expires.
 	pub fn add_known_address(&mut self, peer_id: PeerId, addr: Multiaddr) {
 		self.discovery.add_known_address(peer_id, addr)
 	}
 
+	/// Adds an address of a peer that was remembered from a previous run.
+	pub fn add_stored_address(&mut self, peer_id: PeerId, addr: Multiaddr) {
+		self.discovery.add_stored_address(peer_id, addr)
+	}
+
 	/// Returns the number of nodes in each Kademlia kbucket for each Kademlia instance.
 	///
 	/// Identifies Kademlia instances by their [`ProtocolId`] and kbuckets by the base 2 logarithm
@@ -335,6 +349,12 @@ where
 	pub fn put_value(&mut self, key: record::Key, value: Vec<u8>) {
 		self.discovery.put_value(key, value);
 	}
+
+	/// Start fetching the content of `cid` over bitswap. The result is sent on
+	/// `pending_response`.
+	pub fn bitswap_fetch(&mut self, cid: Cid, pending_response: FetchResponseSender) {
+		self.bitswap.fetch(cid, pending_response);
+	}
 }
 
 fn reported_roles_to_observed_role(roles: Roles) -> ObservedRole {
@@ -347,7 +367,7 @@ fn reported_roles_to_observed_role(roles: Roles) -> ObservedRole {
 	}
 }
 
-impl<B, Client> NetworkBehaviourEventProcess<void::Void> for Behaviour<B, Client>
+impl<B, Client> NetworkBehaviourEventProcess<BitswapEvent> for Behaviour<B, Client>
 where
 	B: BlockT,
 	Client: HeaderBackend<B>
@@ -358,8 +378,10 @@ where
 		+ Sync
 		+ 'static,
 {
-	fn inject_event(&mut self, event: void::Void) {
-		void::unreachable(event)
+	fn inject_event(&mut self, event: BitswapEvent) {
+		match event {
+			BitswapEvent::FindProviders(key) => self.discovery.get_providers(key),
+		}
 	}
 }
 
@@ -534,6 +556,7 @@ where
 			listen_addrs.truncate(30);
 		}
 
+		self.bitswap.on_peer_identified(&peer_id, &protocols);
 		for addr in listen_addrs {
 			self.discovery.add_self_reported_address(&peer_id, protocols.iter(), addr);
 		}
@@ -577,6 +600,9 @@ where
 				self.events
 					.push_back(BehaviourOut::Dht(DhtEvent::ValuePutFailed(key), duration));
 			},
+			DiscoveryOut::ProvidersFound(key, providers, _duration) => {
+				self.bitswap.add_providers(&key, providers);
+			},
 			DiscoveryOut::RandomKademliaStarted(protocols) =>
 				for protocol in protocols {
 					self.events.push_back(BehaviourOut::RandomKademliaStarted(protocol));
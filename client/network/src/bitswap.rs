@@ -14,29 +14,45 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-//! Bitswap server for substrate.
+//! Bitswap server and client for substrate.
 //!
 //! Allows querying transactions by hash over standard bitswap protocol
 //! Only supports bitswap 1.2.0.
 //! CID is expected to reference 256-bit Blake2b transaction hash.
+//!
+//! The client side asks connected peers, as well as peers providing the CID in the DHT, for the
+//! content of a CID. Received content is only accepted if it matches the hash of the CID.
+//! Since a failed protocol negotiation closes the whole connection, wantlists are only sent to
+//! peers that reported supporting bitswap through identify.
 
 use crate::schema::bitswap::{
-	message::{wantlist::WantType, Block as MessageBlock, BlockPresence, BlockPresenceType},
+	message::{
+		wantlist::{Entry as WantlistEntry, WantType},
+		Block as MessageBlock, BlockPresence, BlockPresenceType, Wantlist,
+	},
 	Message as BitswapMessage,
 };
-use cid::Version;
+use cid::{
+	multihash::{Code, MultihashDigest},
+	Cid, Version,
+};
 use core::pin::Pin;
 use futures::{
+	channel::oneshot,
 	io::{AsyncRead, AsyncWrite},
-	Future,
+	Future, FutureExt,
 };
+use futures_timer::Delay;
 use libp2p::{
 	core::{
-		connection::ConnectionId, upgrade, InboundUpgrade, Multiaddr, OutboundUpgrade, PeerId,
-		UpgradeInfo,
+		connection::ConnectionId, upgrade, ConnectedPoint, InboundUpgrade, Multiaddr,
+		OutboundUpgrade, PeerId, UpgradeInfo,
 	},
+	kad::record,
 	swarm::{
-		NetworkBehaviour, NetworkBehaviourAction, NotifyHandler, OneShotHandler, PollParameters,
+		dial_opts::{DialOpts, PeerCondition},
+		DialError, IntoConnectionHandler, NetworkBehaviour, NetworkBehaviourAction, NotifyHandler,
+		OneShotHandler, PollParameters,
 	},
 };
 use log::{debug, error, trace};
@@ -44,11 +60,12 @@ use prost::Message;
 use sc_client_api::BlockBackend;
 use sp_runtime::traits::Block as BlockT;
 use std::{
-	collections::VecDeque,
+	collections::{HashMap, HashSet, VecDeque},
 	io,
 	marker::PhantomData,
 	sync::Arc,
 	task::{Context, Poll},
+	time::{Duration, Instant},
 };
 use unsigned_varint::encode as varint_encode;
 
@@ -65,6 +82,15 @@ const MAX_RESPONSE_QUEUE: usize = 20;
 // Max number of blocks per wantlist
 const MAX_WANTED_BLOCKS: usize = 16;
 
+// Max number of connected peers asked for a CID.
+const MAX_FETCH_PEERS: usize = 16;
+// Max number of CIDs fetched at the same time.
+const MAX_PENDING_FETCHES: usize = 64;
+// Time after which a fetch is given up.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+// Interval at which fetches are checked for timeouts.
+const FETCH_TIMEOUT_CHECK: Duration = Duration::from_secs(1);
+
 const PROTOCOL_NAME: &[u8] = b"/ipfs/bitswap/1.2.0";
 
 type FutureResult<T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + Send>>;
@@ -179,42 +205,295 @@ impl Prefix {
 	}
 }
 
+/// Event generated by [`Bitswap`].
+#[derive(Debug)]
+pub enum BitswapEvent {
+	/// The peers providing the given key in the DHT should be searched for, and reported back
+	/// with [`Bitswap::add_providers`].
+	FindProviders(record::Key),
+}
+
+/// Sender of the result of [`Bitswap::fetch`].
+pub type FetchResponseSender = oneshot::Sender<Result<Vec<u8>, BitswapError>>;
+
+/// Ongoing fetch of a CID.
+struct Fetch {
+	/// When the fetch started.
+	started: Instant,
+	/// Peers asked for the CID.
+	asked: HashSet<PeerId>,
+	/// Peers that answered they don't have the CID.
+	dont_have: HashSet<PeerId>,
+	/// Whether the DHT providers search has finished.
+	providers_searched: bool,
+	/// Senders waiting for the content.
+	pending_responses: Vec<FetchResponseSender>,
+}
+
+impl Fetch {
+	/// Returns `true` if every peer that could have been asked answered with `DontHave`.
+	fn is_exhausted(&self) -> bool {
+		self.providers_searched && self.asked.len() == self.dont_have.len()
+	}
+}
+
 /// Network behaviour that handles sending and receiving IPFS blocks.
 pub struct Bitswap<B, Client> {
 	client: Arc<Client>,
+	/// Whether the content asked for by remotes is served.
+	serve: bool,
+	/// Responses to the wantlists of remotes.
 	ready_blocks: VecDeque<(PeerId, BitswapMessage)>,
+	/// Wantlists of our own fetches, to be sent to connected peers.
+	ready_wants: VecDeque<(PeerId, BitswapMessage)>,
+	/// Peers we are connected to, and whether they support bitswap once they are identified.
+	connected: HashMap<PeerId, Option<bool>>,
+	/// CIDs we are fetching.
+	fetches: HashMap<Cid, Fetch>,
+	/// Messages waiting for the peer to be connected and identified as supporting bitswap.
+	pending_wants: HashMap<PeerId, Vec<BitswapMessage>>,
+	/// Peers that need to be dialed.
+	pending_dials: VecDeque<PeerId>,
+	/// Events to report to the outside.
+	events: VecDeque<BitswapEvent>,
+	/// Fires when the fetches should be checked for timeouts.
+	timeout_check: Delay,
 	_block: PhantomData<B>,
 }
 
 impl<B, Client> Bitswap<B, Client> {
 	/// Create a new instance of the bitswap protocol handler.
-	pub fn new(client: Arc<Client>) -> Self {
-		Self { client, ready_blocks: Default::default(), _block: PhantomData::default() }
+	///
+	/// Content asked for by remotes is only served if `serve` is `true`, otherwise every request
+	/// is answered with `DontHave`. Fetching content with [`Bitswap::fetch`] is always possible.
+	pub fn new(client: Arc<Client>, serve: bool) -> Self {
+		Self {
+			client,
+			serve,
+			ready_blocks: Default::default(),
+			ready_wants: Default::default(),
+			connected: Default::default(),
+			fetches: Default::default(),
+			pending_wants: Default::default(),
+			pending_dials: Default::default(),
+			events: Default::default(),
+			timeout_check: Delay::new(FETCH_TIMEOUT_CHECK),
+			_block: PhantomData::default(),
+		}
 	}
-}
 
-impl<B, Client> NetworkBehaviour for Bitswap<B, Client>
-where
-	B: BlockT,
-	Client: BlockBackend<B> + Send + Sync + 'static,
-{
-	type ConnectionHandler = OneShotHandler<BitswapConfig, BitswapMessage, HandlerEvent>;
-	type OutEvent = void::Void;
+	/// Start fetching the content of `cid`.
+	///
+	/// The content is requested from a limited number of connected peers supporting bitswap and
+	/// from the providers found in the DHT. The result is sent on `pending_response` once the
+	/// content was received and verified, all peers answered that they don't have it, or the fetch
+	/// timed out.
+	pub fn fetch(&mut self, cid: Cid, pending_response: FetchResponseSender) {
+		if let Err(err) = Code::try_from(cid.hash().code()) {
+			let _ = pending_response.send(Err(BitswapError::UnsupportedCid(err.to_string())));
+			return
+		}
 
-	fn new_handler(&mut self) -> Self::ConnectionHandler {
-		Default::default()
+		if let Some(fetch) = self.fetches.get_mut(&cid) {
+			fetch.pending_responses.push(pending_response);
+			return
+		}
+
+		if self.fetches.len() >= MAX_PENDING_FETCHES {
+			let _ = pending_response.send(Err(BitswapError::TooManyFetches));
+			return
+		}
+
+		debug!(target: LOG_TARGET, "Fetching CID {}", cid);
+		let mut fetch = Fetch {
+			started: Instant::now(),
+			asked: HashSet::new(),
+			dont_have: HashSet::new(),
+			providers_searched: false,
+			pending_responses: vec![pending_response],
+		};
+
+		let bitswap_peers = self
+			.connected
+			.iter()
+			.filter_map(|(peer, supported)| (*supported == Some(true)).then(|| *peer));
+		for peer in bitswap_peers.take(MAX_FETCH_PEERS) {
+			fetch.asked.insert(peer);
+			self.ready_wants.push_back((peer, want_message(&cid)));
+		}
+
+		self.events
+			.push_back(BitswapEvent::FindProviders(record::Key::new(&cid.hash().to_bytes())));
+		self.fetches.insert(cid, fetch);
 	}
 
-	fn addresses_of_peer(&mut self, _peer: &PeerId) -> Vec<Multiaddr> {
-		Vec::new()
+	/// Report the `providers` found in the DHT for `key`, see [`BitswapEvent::FindProviders`].
+	pub fn add_providers(&mut self, key: &record::Key, providers: HashSet<PeerId>) {
+		let key = key.to_vec();
+		let mut exhausted = Vec::new();
+
+		for (cid, fetch) in self.fetches.iter_mut() {
+			if cid.hash().to_bytes() != key {
+				continue
+			}
+			fetch.providers_searched = true;
+
+			for peer in providers.iter() {
+				let supported = self.connected.get(peer).copied();
+				if supported == Some(Some(false)) || !fetch.asked.insert(*peer) {
+					continue
+				}
+
+				trace!(target: LOG_TARGET, "Asking provider {} for CID {}", peer, cid);
+				match supported {
+					Some(Some(_)) => self.ready_wants.push_back((*peer, want_message(cid))),
+					// Connected, but not identified yet.
+					Some(None) =>
+						self.pending_wants.entry(*peer).or_default().push(want_message(cid)),
+					None => {
+						let queued = self.pending_wants.entry(*peer).or_default();
+						if queued.is_empty() {
+							self.pending_dials.push_back(*peer);
+						}
+						queued.push(want_message(cid));
+					},
+				}
+			}
+
+			if fetch.is_exhausted() {
+				exhausted.push(*cid);
+			}
+		}
+
+		for cid in exhausted {
+			self.finish_fetch(&cid, || Err(BitswapError::NotFound));
+		}
 	}
 
-	fn inject_event(&mut self, peer: PeerId, _connection: ConnectionId, message: HandlerEvent) {
-		let request = match message {
-			HandlerEvent::ResponseSent => return,
-			HandlerEvent::Request(msg) => msg,
-		};
-		trace!(target: LOG_TARGET, "Received request: {:?} from {}", request, peer);
+	/// Report the `protocols` that `peer` reported supporting through identify.
+	///
+	/// Wantlists are only sent to peers supporting bitswap, as a failed protocol negotiation
+	/// closes the whole connection. Peers that don't support it are treated as not having any of
+	/// the content they were going to be asked for.
+	pub fn on_peer_identified(&mut self, peer: &PeerId, protocols: &[String]) {
+		let supported = protocols.iter().any(|protocol| protocol.as_bytes() == PROTOCOL_NAME);
+		match self.connected.get_mut(peer) {
+			Some(known) => *known = Some(supported),
+			None => return,
+		}
+
+		let messages = self.pending_wants.remove(peer).unwrap_or_default();
+		if supported {
+			self.ready_wants.extend(messages.into_iter().map(|message| (*peer, message)));
+		} else {
+			trace!(target: LOG_TARGET, "Peer {} doesn't support bitswap", peer);
+			self.on_peer_unavailable(peer);
+		}
+	}
+
+	/// Complete the fetch of `cid`, sending the result created by `result` to all waiting senders.
+	fn finish_fetch(&mut self, cid: &Cid, result: impl Fn() -> Result<Vec<u8>, BitswapError>) {
+		if let Some(fetch) = self.fetches.remove(cid) {
+			debug!(
+				target: LOG_TARGET,
+				"Fetch of CID {} finished after {:?}",
+				cid,
+				fetch.started.elapsed(),
+			);
+			for pending_response in fetch.pending_responses {
+				let _ = pending_response.send(result());
+			}
+		}
+	}
+
+	/// Handle the blocks and block presences in a message received from `peer`.
+	fn on_response(&mut self, peer: &PeerId, message: &BitswapMessage) {
+		for block in &message.payload {
+			// Only accept the data if it matches one of the wanted CIDs.
+			let cid = self.fetches.keys().find(|cid| {
+				Code::try_from(cid.hash().code())
+					.map_or(false, |code| code.digest(&block.data) == *cid.hash())
+			});
+
+			match cid.copied() {
+				Some(cid) => {
+					trace!(target: LOG_TARGET, "Received CID {} from {}", cid, peer);
+					self.finish_fetch(&cid, || Ok(block.data.clone()));
+				},
+				None => debug!(target: LOG_TARGET, "Received unexpected block from {}", peer),
+			}
+		}
+
+		for presence in &message.block_presences {
+			if presence.r#type != BlockPresenceType::DontHave as i32 {
+				continue
+			}
+			let cid = match Cid::read_bytes(presence.cid.as_slice()) {
+				Ok(cid) => cid,
+				Err(e) => {
+					trace!(target: LOG_TARGET, "Bad CID {:?}: {:?}", presence.cid, e);
+					continue
+				},
+			};
+			let exhausted = match self.fetches.get_mut(&cid) {
+				Some(fetch) if fetch.asked.contains(peer) => {
+					fetch.dont_have.insert(*peer);
+					fetch.is_exhausted()
+				},
+				_ => false,
+			};
+			if exhausted {
+				self.finish_fetch(&cid, || Err(BitswapError::NotFound));
+			}
+		}
+	}
+
+	/// Treat all fetches waiting for an answer of `peer` as if it doesn't have the CID.
+	fn on_peer_unavailable(&mut self, peer: &PeerId) {
+		let exhausted = self
+			.fetches
+			.iter_mut()
+			.filter_map(|(cid, fetch)| {
+				(fetch.asked.contains(peer) &&
+					fetch.dont_have.insert(*peer) &&
+					fetch.is_exhausted())
+				.then(|| *cid)
+			})
+			.collect::<Vec<_>>();
+
+		for cid in exhausted {
+			self.finish_fetch(&cid, || Err(BitswapError::NotFound));
+		}
+	}
+}
+
+/// Build a message asking for the content of `cid`.
+fn want_message(cid: &Cid) -> BitswapMessage {
+	BitswapMessage {
+		wantlist: Some(Wantlist {
+			entries: vec![WantlistEntry {
+				block: cid.to_bytes(),
+				priority: 1,
+				cancel: false,
+				want_type: WantType::Block as i32,
+				send_dont_have: true,
+			}],
+			full: false,
+		}),
+		blocks: Default::default(),
+		payload: Default::default(),
+		block_presences: Default::default(),
+		pending_bytes: 0,
+	}
+}
+
+impl<B, Client> Bitswap<B, Client>
+where
+	B: BlockT,
+	Client: BlockBackend<B> + Send + Sync + 'static,
+{
+	/// Answer the `wantlist` received from `peer`.
+	fn serve_wantlist(&mut self, peer: PeerId, wantlist: Wantlist) {
 		if self.ready_blocks.len() > MAX_RESPONSE_QUEUE {
 			debug!(target: LOG_TARGET, "Ignored request: queue is full");
 			return
@@ -226,13 +505,6 @@ where
 			block_presences: Default::default(),
 			pending_bytes: 0,
 		};
-		let wantlist = match request.wantlist {
-			Some(wantlist) => wantlist,
-			None => {
-				debug!(target: LOG_TARGET, "Unexpected bitswap message from {}", peer);
-				return
-			},
-		};
 		if wantlist.entries.len() > MAX_WANTED_BLOCKS {
 			trace!(target: LOG_TARGET, "Ignored request: too many entries");
 			return
@@ -245,6 +517,16 @@ where
 					continue
 				},
 			};
+			if !self.serve {
+				trace!(target: LOG_TARGET, "Not serving CID {} to {}: serving is disabled", cid, peer);
+				if entry.send_dont_have {
+					response.block_presences.push(BlockPresence {
+						r#type: BlockPresenceType::DontHave as i32,
+						cid: cid.to_bytes(),
+					});
+				}
+				continue
+			}
 			if cid.version() != cid::Version::V1 ||
 				cid.hash().code() != u64::from(cid::multihash::Code::Blake2b256) ||
 				cid.hash().size() != 32
@@ -292,22 +574,133 @@ where
 				},
 			}
 		}
+		if !self.serve && response.block_presences.is_empty() {
+			return
+		}
 		trace!(target: LOG_TARGET, "Response: {:?}", response);
 		self.ready_blocks.push_back((peer, response));
 	}
+}
+
+impl<B, Client> NetworkBehaviour for Bitswap<B, Client>
+where
+	B: BlockT,
+	Client: BlockBackend<B> + Send + Sync + 'static,
+{
+	type ConnectionHandler = OneShotHandler<BitswapConfig, BitswapMessage, HandlerEvent>;
+	type OutEvent = BitswapEvent;
+
+	fn new_handler(&mut self) -> Self::ConnectionHandler {
+		Default::default()
+	}
+
+	fn addresses_of_peer(&mut self, _peer: &PeerId) -> Vec<Multiaddr> {
+		Vec::new()
+	}
+
+	fn inject_connection_established(
+		&mut self,
+		peer_id: &PeerId,
+		_: &ConnectionId,
+		_: &ConnectedPoint,
+		_: Option<&Vec<Multiaddr>>,
+		_: usize,
+	) {
+		// Wantlists waiting for the peer are sent once it is identified.
+		self.connected.entry(*peer_id).or_insert(None);
+	}
+
+	fn inject_connection_closed(
+		&mut self,
+		peer_id: &PeerId,
+		_: &ConnectionId,
+		_: &ConnectedPoint,
+		_: <Self::ConnectionHandler as IntoConnectionHandler>::Handler,
+		remaining_established: usize,
+	) {
+		if remaining_established == 0 {
+			self.connected.remove(peer_id);
+			self.pending_wants.remove(peer_id);
+			self.on_peer_unavailable(peer_id);
+		}
+	}
+
+	fn inject_dial_failure(
+		&mut self,
+		peer_id: Option<PeerId>,
+		_: Self::ConnectionHandler,
+		_: &DialError,
+	) {
+		if let Some(peer_id) = peer_id {
+			// The dial is refused if a connection got established in the meantime.
+			if !self.connected.contains_key(&peer_id) &&
+				self.pending_wants.remove(&peer_id).is_some()
+			{
+				self.on_peer_unavailable(&peer_id);
+			}
+		}
+	}
+
+	fn inject_event(&mut self, peer: PeerId, _connection: ConnectionId, message: HandlerEvent) {
+		let message = match message {
+			HandlerEvent::ResponseSent => return,
+			HandlerEvent::Request(msg) => msg,
+		};
+		trace!(target: LOG_TARGET, "Received message: {:?} from {}", message, peer);
+
+		if !message.payload.is_empty() || !message.block_presences.is_empty() {
+			self.on_response(&peer, &message);
+		}
+
+		match message.wantlist {
+			Some(wantlist) => self.serve_wantlist(peer, wantlist),
+			None if message.payload.is_empty() && message.block_presences.is_empty() => {
+				debug!(target: LOG_TARGET, "Unexpected bitswap message from {}", peer);
+			},
+			None => {},
+		}
+	}
 
 	fn poll(
 		&mut self,
-		_ctx: &mut Context,
+		cx: &mut Context,
 		_: &mut impl PollParameters,
 	) -> Poll<NetworkBehaviourAction<Self::OutEvent, Self::ConnectionHandler>> {
-		if let Some((peer_id, message)) = self.ready_blocks.pop_front() {
+		if let Some(event) = self.events.pop_front() {
+			return Poll::Ready(NetworkBehaviourAction::GenerateEvent(event))
+		}
+
+		if let Some(peer_id) = self.pending_dials.pop_front() {
+			return Poll::Ready(NetworkBehaviourAction::Dial {
+				opts: DialOpts::peer_id(peer_id).condition(PeerCondition::Disconnected).build(),
+				handler: self.new_handler(),
+			})
+		}
+
+		if let Some((peer_id, message)) =
+			self.ready_blocks.pop_front().or_else(|| self.ready_wants.pop_front())
+		{
 			return Poll::Ready(NetworkBehaviourAction::NotifyHandler {
 				peer_id,
 				handler: NotifyHandler::Any,
 				event: message,
 			})
 		}
+
+		while self.timeout_check.poll_unpin(cx).is_ready() {
+			self.timeout_check.reset(FETCH_TIMEOUT_CHECK);
+
+			let timed_out = self
+				.fetches
+				.iter()
+				.filter(|(_, fetch)| fetch.started.elapsed() >= FETCH_TIMEOUT)
+				.map(|(cid, _)| *cid)
+				.collect::<Vec<_>>();
+			for cid in timed_out {
+				self.finish_fetch(&cid, || Err(BitswapError::Timeout));
+			}
+		}
+
 		Poll::Pending
 	}
 }
@@ -338,4 +731,181 @@ pub enum BitswapError {
 	/// Error sending response.
 	#[error("Failed to send response.")]
 	SendResponse,
+
+	/// The CID uses an unsupported hash function.
+	#[error("Unsupported CID: {0}.")]
+	UnsupportedCid(String),
+
+	/// None of the peers asked has the content.
+	#[error("Content not found.")]
+	NotFound,
+
+	/// Fetching the content took too long.
+	#[error("Fetching the content timed out.")]
+	Timeout,
+
+	/// Too many fetches are running at the same time.
+	#[error("Too many pending fetches.")]
+	TooManyFetches,
+
+	/// The network worker is no longer running.
+	#[error("Network worker is not running.")]
+	NetworkClosed,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::executor::block_on;
+	use substrate_test_runtime_client::{runtime::Block, TestClient};
+
+	fn block_message(data: &[u8]) -> BitswapMessage {
+		BitswapMessage {
+			wantlist: None,
+			blocks: Default::default(),
+			payload: vec![MessageBlock { prefix: Vec::new(), data: data.to_vec() }],
+			block_presences: Default::default(),
+			pending_bytes: 0,
+		}
+	}
+
+	#[test]
+	fn fetch_only_accepts_matching_content() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let mut bitswap = Bitswap::<Block, TestClient>::new(client, false);
+		let peer = PeerId::random();
+
+		let cid = Cid::new_v1(0x55, Code::Blake2b256.digest(b"hello"));
+		let (tx, mut rx) = oneshot::channel();
+		bitswap.fetch(cid, tx);
+		assert!(matches!(
+			bitswap.events.pop_front(),
+			Some(BitswapEvent::FindProviders(key)) if key.to_vec() == cid.hash().to_bytes()
+		));
+
+		bitswap.on_response(&peer, &block_message(b"world"));
+		assert!(rx.try_recv().unwrap().is_none());
+
+		bitswap.on_response(&peer, &block_message(b"hello"));
+		assert_eq!(block_on(rx).unwrap().unwrap(), b"hello".to_vec());
+		assert!(bitswap.fetches.is_empty());
+	}
+
+	fn wantlist(cid: &Cid) -> Wantlist {
+		want_message(cid).wantlist.unwrap()
+	}
+
+	#[test]
+	fn wantlist_is_answered_with_dont_have_if_not_serving() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let mut bitswap = Bitswap::<Block, TestClient>::new(client, false);
+		let peer = PeerId::random();
+
+		let cid = Cid::new_v1(0x55, Code::Blake2b256.digest(b"hello"));
+		bitswap.serve_wantlist(peer, wantlist(&cid));
+
+		let (to, response) = bitswap.ready_blocks.pop_front().unwrap();
+		assert_eq!(to, peer);
+		assert!(response.payload.is_empty());
+		assert_eq!(
+			response.block_presences,
+			vec![BlockPresence { r#type: BlockPresenceType::DontHave as i32, cid: cid.to_bytes() }],
+		);
+	}
+
+	#[test]
+	fn fetches_do_not_fill_the_response_queue() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let mut bitswap = Bitswap::<Block, TestClient>::new(client, false);
+		bitswap
+			.connected
+			.extend((0..MAX_FETCH_PEERS).map(|_| (PeerId::random(), Some(true))));
+
+		for i in 0..MAX_PENDING_FETCHES {
+			let (tx, _rx) = oneshot::channel();
+			bitswap.fetch(Cid::new_v1(0x55, Code::Blake2b256.digest(&i.to_le_bytes())), tx);
+		}
+		assert!(bitswap.ready_wants.len() > MAX_RESPONSE_QUEUE);
+
+		let cid = Cid::new_v1(0x55, Code::Blake2b256.digest(b"hello"));
+		bitswap.serve_wantlist(PeerId::random(), wantlist(&cid));
+		assert_eq!(bitswap.ready_blocks.len(), 1);
+	}
+
+	#[test]
+	fn fetch_fails_if_no_peer_has_content() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let mut bitswap = Bitswap::<Block, TestClient>::new(client, false);
+		let peer = PeerId::random();
+		bitswap.connected.insert(peer, Some(true));
+
+		let cid = Cid::new_v1(0x55, Code::Blake2b256.digest(b"hello"));
+		let (tx, rx) = oneshot::channel();
+		bitswap.fetch(cid, tx);
+		bitswap.add_providers(&record::Key::new(&cid.hash().to_bytes()), HashSet::new());
+
+		bitswap.on_response(
+			&peer,
+			&BitswapMessage {
+				wantlist: None,
+				blocks: Default::default(),
+				payload: Default::default(),
+				block_presences: vec![BlockPresence {
+					r#type: BlockPresenceType::DontHave as i32,
+					cid: cid.to_bytes(),
+				}],
+				pending_bytes: 0,
+			},
+		);
+		assert!(matches!(block_on(rx).unwrap(), Err(BitswapError::NotFound)));
+	}
+
+	fn protocols(bitswap: bool) -> Vec<String> {
+		let mut protocols = vec!["/ipfs/id/1.0.0".to_string()];
+		if bitswap {
+			protocols.push(String::from_utf8(PROTOCOL_NAME.to_vec()).unwrap());
+		}
+		protocols
+	}
+
+	#[test]
+	fn wantlists_are_only_sent_to_peers_supporting_bitswap() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let mut bitswap = Bitswap::<Block, TestClient>::new(client, false);
+		let (with, without, unidentified) = (PeerId::random(), PeerId::random(), PeerId::random());
+		for peer in [with, without, unidentified] {
+			bitswap.connected.insert(peer, None);
+		}
+		bitswap.on_peer_identified(&with, &protocols(true));
+		bitswap.on_peer_identified(&without, &protocols(false));
+
+		let cid = Cid::new_v1(0x55, Code::Blake2b256.digest(b"hello"));
+		let (tx, _rx) = oneshot::channel();
+		bitswap.fetch(cid, tx);
+
+		let asked = bitswap.ready_wants.iter().map(|(peer, _)| *peer).collect::<Vec<_>>();
+		assert_eq!(asked, vec![with]);
+	}
+
+	#[test]
+	fn provider_not_supporting_bitswap_is_not_asked() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let mut bitswap = Bitswap::<Block, TestClient>::new(client, false);
+		let provider = PeerId::random();
+
+		let cid = Cid::new_v1(0x55, Code::Blake2b256.digest(b"hello"));
+		let (tx, rx) = oneshot::channel();
+		bitswap.fetch(cid, tx);
+		bitswap.add_providers(&record::Key::new(&cid.hash().to_bytes()), HashSet::from([provider]));
+		assert_eq!(bitswap.pending_dials.pop_front(), Some(provider));
+
+		// The provider is connected, but turns out not to speak bitswap.
+		bitswap.connected.insert(provider, None);
+		assert!(bitswap.ready_wants.is_empty());
+		bitswap.on_peer_identified(&provider, &protocols(false));
+
+		assert!(bitswap.ready_wants.is_empty());
+		assert!(bitswap.pending_wants.is_empty());
+		assert!(matches!(block_on(rx).unwrap(), Err(BitswapError::NotFound)));
+	}
 }
@@ -23,6 +23,7 @@
 
 pub use sc_network_common::{
 	config::ProtocolId,
+	request_quota::QuotaConfig,
 	request_responses::{
 		IncomingRequest, OutgoingResponse, ProtocolConfig as RequestResponseConfig,
 	},
@@ -441,6 +442,25 @@ pub struct NetworkConfiguration {
 	/// Enable serving block data over IPFS bitswap.
 	pub ipfs_server: bool,
 
+	/// Persist peer reputations and addresses in `net_config_path` across restarts.
+	///
+	/// Has no effect if `net_config_path` is `None`.
+	pub persist_peers: bool,
+
+	/// Discard any previously persisted peer reputations and addresses on startup.
+	pub wipe_peer_store: bool,
+
+	/// Limits on the outbound bandwidth of notifications and request-response protocols.
+	///
+	/// A limit applies to every protocol whose name ends with the key, so that for example
+	/// `/sync/2` limits the block requests protocol regardless of the chain.
+	pub bandwidth_limits: HashMap<String, BandwidthLimit>,
+
+	/// Per-peer quotas on the inbound requests of the protocols served by the node.
+	///
+	/// The default quotas don't limit anything.
+	pub request_quotas: RequestQuotas,
+
 	/// Size of Yamux receive window of all substreams. `None` for the default (256kiB).
 	/// Any value less than 256kiB is invalid.
 	///
@@ -492,6 +512,10 @@ impl NetworkConfiguration {
 			kademlia_disjoint_query_paths: false,
 			yamux_window_size: None,
 			ipfs_server: false,
+			persist_peers: true,
+			wipe_peer_store: false,
+			bandwidth_limits: HashMap::new(),
+			request_quotas: RequestQuotas::default(),
 		}
 	}
 
@@ -526,6 +550,43 @@ impl NetworkConfiguration {
 	}
 }
 
+/// Limit on the outbound bandwidth of a protocol, in bytes per second.
+///
+/// Traffic is allowed to burst up to one second worth of bytes. Requests arriving while the limit
+/// is exceeded are refused, and notifications are delayed or dropped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BandwidthLimit {
+	/// Limit applied to the traffic sent to each peer individually.
+	pub per_peer: Option<u64>,
+	/// Limit applied to the traffic sent to all peers together.
+	pub total: Option<u64>,
+}
+
+/// Per-peer quotas on the inbound requests of each protocol served by the node.
+#[derive(Clone, Debug, Default)]
+pub struct RequestQuotas {
+	/// Quota on block requests.
+	pub block_requests: QuotaConfig,
+	/// Quota on state requests.
+	pub state_requests: QuotaConfig,
+	/// Quota on warp sync requests.
+	pub warp_sync_requests: QuotaConfig,
+	/// Quota on light client requests.
+	pub light_client_requests: QuotaConfig,
+}
+
+impl RequestQuotas {
+	/// Applies the same `quota` to every protocol.
+	pub fn uniform(quota: QuotaConfig) -> Self {
+		Self {
+			block_requests: quota.clone(),
+			state_requests: quota.clone(),
+			warp_sync_requests: quota.clone(),
+			light_client_requests: quota,
+		}
+	}
+}
+
 /// Configuration for a set of nodes.
 #[derive(Clone, Debug)]
 pub struct SetConfig {
@@ -61,8 +61,8 @@ use libp2p::{
 			self,
 			store::{MemoryStore, RecordStore},
 		},
-		GetClosestPeersError, Kademlia, KademliaBucketInserts, KademliaConfig, KademliaEvent,
-		QueryId, QueryResult, Quorum, Record,
+		GetClosestPeersError, GetProvidersError, Kademlia, KademliaBucketInserts, KademliaConfig,
+		KademliaEvent, QueryId, QueryResult, Quorum, Record,
 	},
 	mdns::{Mdns, MdnsConfig, MdnsEvent},
 	multiaddr::Protocol,
@@ -296,6 +296,24 @@ impl DiscoveryBehaviour {
 		peers
 	}
 
+	/// Returns the addresses of the nodes present in the Kademlia routing tables.
+	pub fn known_addresses(&mut self) -> HashMap<PeerId, Vec<Multiaddr>> {
+		let mut addresses = HashMap::<PeerId, Vec<Multiaddr>>::new();
+		for k in self.kademlias.values_mut() {
+			for b in k.kbuckets() {
+				for e in b.iter() {
+					let list = addresses.entry(*e.node.key.preimage()).or_default();
+					for addr in e.node.value.iter() {
+						if !list.contains(addr) {
+							list.push(addr.clone());
+						}
+					}
+				}
+			}
+		}
+		addresses
+	}
+
 	/// Adds a hard-coded address for the given peer, that never expires.
 	///
 	/// This adds an entry to the parameter that was passed to `new`.
@@ -313,6 +331,19 @@ impl DiscoveryBehaviour {
 		}
 	}
 
+	/// Adds an address of a peer that was remembered from a previous run.
+	///
+	/// Like self-reported addresses, non-global addresses are ignored unless they are allowed in
+	/// the DHT.
+	pub fn add_stored_address(&mut self, peer_id: PeerId, addr: Multiaddr) {
+		if !self.allow_non_globals_in_dht && !self.can_add_to_dht(&addr) {
+			trace!(target: "sub-libp2p", "Ignoring stored non-global address {} of {}.", addr, peer_id);
+			return
+		}
+
+		self.add_known_address(peer_id, addr)
+	}
+
 	/// Add a self-reported address of a remote peer to the k-buckets of the supported
 	/// DHTs (`supported_protocols`).
 	///
@@ -376,6 +407,15 @@ impl DiscoveryBehaviour {
 		}
 	}
 
+	/// Start searching the DHT for peers that provide the given key.
+	///
+	/// A corresponding `ProvidersFound` event will later be generated.
+	pub fn get_providers(&mut self, key: record::Key) {
+		for k in self.kademlias.values_mut() {
+			k.get_providers(key.clone());
+		}
+	}
+
 	/// Returns the number of nodes in each Kademlia kbucket for each Kademlia instance.
 	///
 	/// Identifies Kademlia instances by their [`ProtocolId`] and kbuckets by the base 2 logarithm
@@ -492,6 +532,12 @@ pub enum DiscoveryOut {
 	/// Returning the corresponding key as well as the request duration.
 	ValuePutFailed(record::Key, Duration),
 
+	/// The providers search for the given key has finished.
+	///
+	/// Returning the providers that were found, which may be none, as well as the request
+	/// duration.
+	ProvidersFound(record::Key, HashSet<PeerId>, Duration),
+
 	/// Started a random Kademlia query for each DHT identified by the given `ProtocolId`s.
 	///
 	/// Only happens if [`DiscoveryConfig::with_dht_random_walk`] has been configured to `true`.
@@ -868,6 +914,32 @@ impl NetworkBehaviour for DiscoveryBehaviour {
 							};
 							return Poll::Ready(NetworkBehaviourAction::GenerateEvent(ev))
 						},
+						KademliaEvent::OutboundQueryCompleted {
+							result: QueryResult::GetProviders(res),
+							stats,
+							..
+						} => {
+							let ev = match res {
+								Ok(ok) => DiscoveryOut::ProvidersFound(
+									ok.key,
+									ok.providers,
+									stats.duration().unwrap_or_default(),
+								),
+								Err(GetProvidersError::Timeout { key, providers, .. }) => {
+									debug!(
+										target: "sub-libp2p",
+										"Libp2p => Providers query for {:?} timed out with {} results",
+										HexDisplay::from(&key), providers.len(),
+									);
+									DiscoveryOut::ProvidersFound(
+										key,
+										providers,
+										stats.duration().unwrap_or_default(),
+									)
+								},
+							};
+							return Poll::Ready(NetworkBehaviourAction::GenerateEvent(ev))
+						},
 						KademliaEvent::OutboundQueryCompleted {
 							result: QueryResult::RepublishRecord(res),
 							..
@@ -1135,6 +1207,24 @@ mod tests {
 		futures::executor::block_on(fut);
 	}
 
+	#[test]
+	fn stored_non_global_addresses_are_ignored() {
+		let mut discovery = {
+			let keypair = Keypair::generate_ed25519();
+			let mut config = DiscoveryConfig::new(keypair.public());
+			config.add_protocol(ProtocolId::from("dot"));
+			config.finish()
+		};
+
+		let remote_peer_id = PeerId::random();
+		discovery.add_stored_address(remote_peer_id, "/ip4/10.0.0.1/tcp/30333".parse().unwrap());
+		assert!(discovery.ephemeral_addresses.get(&remote_peer_id).is_none());
+
+		let global_addr: Multiaddr = "/ip4/1.1.1.1/tcp/30333".parse().unwrap();
+		discovery.add_stored_address(remote_peer_id, global_addr.clone());
+		assert_eq!(discovery.ephemeral_addresses.get(&remote_peer_id), Some(&vec![global_addr]));
+	}
+
 	#[test]
 	fn discovery_ignores_peers_with_unknown_protocols() {
 		let supported_protocol_id = ProtocolId::from("a");
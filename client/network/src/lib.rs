@@ -244,6 +244,7 @@
 //!
 //! More precise usage details are still being worked on and will likely change in the future.
 
+mod bandwidth;
 mod behaviour;
 mod discovery;
 mod peer_info;
@@ -261,6 +262,7 @@ pub mod network_state;
 pub mod transactions;
 
 #[doc(inline)]
+pub use cid::Cid;
 pub use libp2p::{multiaddr, Multiaddr, PeerId};
 pub use protocol::{
 	event::{DhtEvent, Event, ObservedRole},
@@ -50,6 +50,19 @@ pub struct NetworkState {
 	pub not_connected_peers: HashMap<String, NotConnectedPeer>,
 	/// State of the peerset manager.
 	pub peerset: serde_json::Value,
+	/// Bandwidth used by each notifications and request-response protocol.
+	#[serde(default)]
+	pub protocol_bandwidth: HashMap<String, ProtocolBandwidth>,
+}
+
+/// Part of the `NetworkState` struct. Unstable.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolBandwidth {
+	/// Total number of bytes received on the protocol.
+	pub inbound: u64,
+	/// Total number of bytes sent on the protocol.
+	pub outbound: u64,
 }
 
 /// Part of the `NetworkState` struct. Unstable.
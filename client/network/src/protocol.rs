@@ -50,6 +50,8 @@ use sc_client_api::{BlockBackend, HeaderBackend, ProofProvider};
 use sc_consensus::import_queue::{BlockImportError, BlockImportStatus, IncomingBlock, Origin};
 use sc_network_common::config::ProtocolId;
 use sc_network_sync::{
+	compression,
+	journal::SyncJournal,
 	message::{
 		BlockAnnounce, BlockAttributes, BlockData, BlockRequest, BlockResponse, BlockState,
 		FromBlock,
@@ -107,6 +109,10 @@ const NUM_HARDCODED_PEERSETS: usize = 1;
 /// and disconnect to free connection slot.
 const LIGHT_MAXIMAL_BLOCKS_DIFFERENCE: u64 = 8192;
 
+/// Directory, relative to the network configuration path, where warp and state sync progress is
+/// recorded so that it can be resumed after a restart.
+const SYNC_JOURNAL_DIR: &str = "sync";
+
 mod rep {
 	use sc_peerset::ReputationChange as Rep;
 	/// Reputation change when a peer doesn't respond in time to our messages.
@@ -311,6 +317,7 @@ where
 		block_announce_validator: Box<dyn BlockAnnounceValidator<B> + Send>,
 		metrics_registry: Option<&Registry>,
 		warp_sync_provider: Option<Arc<dyn WarpSyncProvider<B>>>,
+		peer_records: HashMap<PeerId, sc_peerset::PeerRecord>,
 	) -> error::Result<(Protocol<B, Client>, sc_peerset::PeersetHandle, Vec<(PeerId, Multiaddr)>)> {
 		let info = chain.info();
 		let sync = ChainSync::new(
@@ -319,6 +326,10 @@ where
 			block_announce_validator,
 			config.max_parallel_downloads,
 			warp_sync_provider,
+			network_config
+				.net_config_path
+				.as_ref()
+				.map(|path| SyncJournal::new(path.join(SYNC_JOURNAL_DIR), info.genesis_hash)),
 		)
 		.map_err(Box::new)?;
 
@@ -396,7 +407,7 @@ where
 				});
 			}
 
-			sc_peerset::Peerset::from_config(sc_peerset::PeersetConfig { sets })
+			sc_peerset::Peerset::from_config(sc_peerset::PeersetConfig { sets, peer_records })
 		};
 
 		let block_announces_protocol: Cow<'static, str> =
@@ -501,6 +512,11 @@ where
 		self.behaviour.peerset_debug_info()
 	}
 
+	/// Returns a snapshot of the peerset reputations, suitable for persisting to disk.
+	pub fn peer_records(&mut self) -> HashMap<PeerId, sc_peerset::PeerRecord> {
+		self.behaviour.peer_records()
+	}
+
 	/// Returns the number of peers we're connected to.
 	pub fn num_connected_peers(&self) -> usize {
 		self.peers.len()
@@ -1284,6 +1300,7 @@ fn prepare_block_request<B: BlockT>(
 		direction: request.direction as i32,
 		max_blocks: request.max.unwrap_or(0),
 		support_multiple_justifications: true,
+		accept_compression: sc_network_sync::schema::v1::Compression::Zstd as i32,
 	};
 
 	CustomMessageOutcome::BlockRequest { target: who, request, pending_response: tx }
@@ -1461,7 +1478,11 @@ where
 								let protobuf_response =
 									match sc_network_sync::schema::v1::BlockResponse::decode(
 										&resp[..],
-									) {
+									)
+									.map_err(|e| e.to_string())
+									.and_then(|proto| {
+										compression::decompress(proto).map_err(|e| e.to_string())
+									}) {
 										Ok(proto) => proto,
 										Err(e) => {
 											debug!(
@@ -1483,7 +1504,11 @@ where
 								let protobuf_response =
 									match sc_network_sync::schema::v1::StateResponse::decode(
 										&resp[..],
-									) {
+									)
+									.map_err(|e| e.to_string())
+									.and_then(|proto| {
+										compression::decompress(proto).map_err(|e| e.to_string())
+									}) {
 										Ok(proto) => proto,
 										Err(e) => {
 											debug!(
@@ -1579,7 +1604,7 @@ where
 			let event = prepare_block_request(&mut self.peers, *id, request);
 			self.pending_messages.push_back(event);
 		}
-		if let Some((id, request)) = self.sync.state_request() {
+		for (id, request) in self.sync.state_requests() {
 			let event = prepare_state_request(&mut self.peers, id, request);
 			self.pending_messages.push_back(event);
 		}
@@ -38,7 +38,7 @@ use smallvec::SmallVec;
 use std::{
 	borrow::Cow,
 	cmp,
-	collections::{hash_map::Entry, VecDeque},
+	collections::{hash_map::Entry, HashMap, VecDeque},
 	mem,
 	pin::Pin,
 	str,
@@ -602,6 +602,11 @@ impl Notifications {
 		self.peerset.debug_info()
 	}
 
+	/// Returns a snapshot of the peerset reputations, suitable for persisting to disk.
+	pub fn peer_records(&mut self) -> HashMap<PeerId, sc_peerset::PeerRecord> {
+		self.peerset.peer_records()
+	}
+
 	/// Function that is called when the peerset wants us to connect to a peer.
 	fn peerset_report_connect(&mut self, peer_id: PeerId, set_id: sc_peerset::SetId) {
 		// If `PeerId` is unknown to us, insert an entry, start dialing, and return early.
@@ -34,7 +34,7 @@
 //! - If provided, a ["requests processing"](ProtocolConfig::inbound_queue) channel
 //! is used to handle incoming requests.
 
-use crate::ReputationChange;
+use crate::{bandwidth::BandwidthAccounting, ReputationChange};
 use futures::{
 	channel::{mpsc, oneshot},
 	prelude::*,
@@ -59,6 +59,7 @@ use std::{
 	collections::{hash_map::Entry, HashMap},
 	io, iter,
 	pin::Pin,
+	sync::Arc,
 	task::{Context, Poll},
 	time::{Duration, Instant},
 };
@@ -174,6 +175,10 @@ pub struct RequestResponsesBehaviour {
 	/// Pending message request, holds `MessageRequest` as a Future state to poll it
 	/// until we get a response from `Peerset`
 	message_request: Option<MessageRequest>,
+
+	/// Bandwidth accounting the traffic is reported to, and that incoming requests are refused
+	/// by when the limit of their protocol is reached.
+	protocol_bandwidth: Arc<BandwidthAccounting>,
 }
 
 // This is a state of processing incoming request Message.
@@ -205,6 +210,7 @@ impl RequestResponsesBehaviour {
 	pub fn new(
 		list: impl Iterator<Item = ProtocolConfig>,
 		peerset: PeersetHandle,
+		protocol_bandwidth: Arc<BandwidthAccounting>,
 	) -> Result<Self, RegisterError> {
 		let mut protocols = HashMap::new();
 		for protocol in list {
@@ -241,6 +247,7 @@ impl RequestResponsesBehaviour {
 			send_feedback: Default::default(),
 			peerset,
 			message_request: None,
+			protocol_bandwidth,
 		})
 	}
 
@@ -260,11 +267,12 @@ impl RequestResponsesBehaviour {
 	) {
 		if let Some((protocol, _)) = self.protocols.get_mut(protocol_name) {
 			if protocol.is_connected(target) || connect.should_connect() {
+				let protocol_name = Cow::from(protocol_name.to_string());
+				self.protocol_bandwidth.report_outbound(&protocol_name, target, request.len());
 				let request_id = protocol.send_request(target, request);
-				let prev_req_id = self.pending_requests.insert(
-					(protocol_name.to_string().into(), request_id).into(),
-					(Instant::now(), pending_response),
-				);
+				let prev_req_id = self
+					.pending_requests
+					.insert((protocol_name, request_id).into(), (Instant::now(), pending_response));
 				debug_assert!(prev_req_id.is_none(), "Expect request id to be unique.");
 			} else if pending_response.send(Err(RequestFailure::NotConnected)).is_err() {
 				log::debug!(
@@ -497,6 +505,21 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 							continue 'poll_all
 						}
 
+						let protocol = Cow::from(protocol);
+						if let Some(delay) =
+							self.protocol_bandwidth.outbound_delay(&protocol, &peer)
+						{
+							// Dropping `channel` refuses the request.
+							log::debug!(
+								target: "sub-libp2p",
+								"Refusing request from {} on {}: bandwidth limit reached for {:?}",
+								peer,
+								protocol,
+								delay,
+							);
+							continue 'poll_all
+						}
+
 						let (tx, rx) = oneshot::channel();
 
 						// Submit the request to the "response builder" passed by the user at
@@ -514,7 +537,6 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 							debug_assert!(false, "Received message on outbound-only protocol.");
 						}
 
-						let protocol = Cow::from(protocol);
 						self.pending_responses.push(Box::pin(async move {
 							// The `tx` created above can be dropped if we are not capable of
 							// processing this request, which is reflected as a
@@ -555,6 +577,11 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 
 				if let Ok(payload) = result {
 					if let Some((protocol, _)) = self.protocols.get_mut(&*protocol_name) {
+						self.protocol_bandwidth.report_outbound(
+							&protocol_name,
+							&peer,
+							payload.len(),
+						);
 						if protocol.send_response(inner_channel, Ok(payload)).is_err() {
 							// Note: Failure is handled further below when receiving
 							// `InboundFailure` event from `RequestResponse` behaviour.
@@ -623,6 +650,7 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 							message:
 								RequestResponseMessage::Request { request_id, request, channel, .. },
 						} => {
+							self.protocol_bandwidth.report_inbound(protocol, request.len());
 							self.pending_responses_arrival_time
 								.insert((protocol.clone(), request_id).into(), Instant::now());
 
@@ -653,6 +681,9 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 							message: RequestResponseMessage::Response { request_id, response },
 							..
 						} => {
+							if let Ok(response) = &response {
+								self.protocol_bandwidth.report_inbound(protocol, response.len());
+							}
 							let (started, delivered) = match self
 								.pending_requests
 								.remove(&(protocol.clone(), request_id).into())
@@ -982,11 +1013,17 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			peer_records: Default::default(),
 		};
 
 		let (peerset, handle) = Peerset::from_config(config);
 
-		let behaviour = RequestResponsesBehaviour::new(list, handle).unwrap();
+		let behaviour = RequestResponsesBehaviour::new(
+			list,
+			handle,
+			Arc::new(BandwidthAccounting::new(Default::default())),
+		)
+		.unwrap();
 
 		let mut swarm = Swarm::new(transport, behaviour, keypair.public().to_peer_id());
 		let listen_addr: Multiaddr = format!("/memory/{}", rand::random::<u64>()).parse().unwrap();
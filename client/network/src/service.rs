@@ -28,8 +28,9 @@
 //! which is then processed by [`NetworkWorker::poll`].
 
 use crate::{
+	bandwidth::BandwidthAccounting,
 	behaviour::{self, Behaviour, BehaviourOut},
-	bitswap::Bitswap,
+	bitswap::{Bitswap, BitswapError, FetchResponseSender},
 	config::{parse_str_addr, Params, TransportConfig},
 	discovery::DiscoveryConfig,
 	error::Error,
@@ -43,8 +44,10 @@ use crate::{
 	transactions, transport, DhtEvent, ExHashT, NetworkStateInfo, NetworkStatus, ReputationChange,
 };
 
+use cid::Cid;
 use codec::Encode as _;
 use futures::{channel::oneshot, prelude::*};
+use futures_timer::Delay;
 use libp2p::{
 	core::{either::EitherError, upgrade, ConnectedPoint, Executor},
 	multiaddr,
@@ -79,6 +82,7 @@ use std::{
 		Arc,
 	},
 	task::Poll,
+	time::Duration,
 };
 
 pub use behaviour::{
@@ -100,6 +104,9 @@ pub use libp2p::{
 };
 pub use signature::Signature;
 
+/// Interval at which the known peers are written to the peer store.
+const PEER_STORE_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Substrate network service. Handles network IO and manages connectivity.
 pub struct NetworkService<B: BlockT + 'static, H: ExHashT> {
 	/// Number of peers we're connected to.
@@ -114,6 +121,8 @@ pub struct NetworkService<B: BlockT + 'static, H: ExHashT> {
 	local_identity: Keypair,
 	/// Bandwidth logging system. Can be queried to know the average bandwidth consumed.
 	bandwidth: Arc<transport::BandwidthSinks>,
+	/// Bandwidth used by each protocol, and limits on it.
+	protocol_bandwidth: Arc<BandwidthAccounting>,
 	/// Peerset manager (PSM); manages the reputation of nodes and indicates the network which
 	/// nodes it should be connected to or not.
 	peerset: PeersetHandle,
@@ -125,6 +134,8 @@ pub struct NetworkService<B: BlockT + 'static, H: ExHashT> {
 	/// Field extracted from the [`Metrics`] struct and necessary to report the
 	/// notifications-related metrics.
 	notifications_sizes_metric: Option<HistogramVec>,
+	/// Full nodes we have an open sync substream with. Updated by the [`NetworkWorker`].
+	full_sync_peers: Arc<Mutex<HashSet<PeerId>>>,
 	/// Marker to pin the `H` generic. Serves no purpose except to not break backwards
 	/// compatibility.
 	_marker: PhantomData<H>,
@@ -183,6 +194,34 @@ where
 			fs::create_dir_all(path)?;
 		}
 
+		let peer_store =
+			params.network_config.net_config_path.as_deref().map(sc_peerset::PeerStore::new);
+		if params.network_config.wipe_peer_store {
+			if let Some(peer_store) = &peer_store {
+				peer_store.wipe()?;
+			}
+		}
+		let peer_store = peer_store.filter(|_| params.network_config.persist_peers);
+		let peer_records = match &peer_store {
+			Some(peer_store) => peer_store.load().unwrap_or_else(|e| {
+				warn!(
+					target: "sub-libp2p",
+					"Failed to load peers from {}: {}", peer_store.path().display(), e,
+				);
+				HashMap::new()
+			}),
+			None => HashMap::new(),
+		};
+		let stored_addresses = peer_records
+			.iter()
+			.flat_map(|(peer_id, record)| {
+				record
+					.addresses
+					.iter()
+					.filter_map(move |addr| Some((*peer_id, addr.parse().ok()?)))
+			})
+			.collect::<Vec<(PeerId, Multiaddr)>>();
+
 		let transactions_handler_proto =
 			transactions::TransactionsHandlerPrototype::new(params.protocol_id.clone());
 		params
@@ -225,6 +264,7 @@ where
 			params.block_announce_validator,
 			params.metrics_registry.as_ref(),
 			warp_sync_provider,
+			peer_records,
 		)?;
 
 		// List of multiaddresses that we know in the network.
@@ -259,6 +299,8 @@ where
 
 		let num_connected = Arc::new(AtomicUsize::new(0));
 		let is_major_syncing = Arc::new(AtomicBool::new(false));
+		let protocol_bandwidth =
+			Arc::new(BandwidthAccounting::new(params.network_config.bandwidth_limits.clone()));
 
 		// Build the swarm.
 		let client = params.chain.clone();
@@ -347,7 +389,7 @@ where
 			};
 
 			let behaviour = {
-				let bitswap = params.network_config.ipfs_server.then(|| Bitswap::new(client));
+				let bitswap = Bitswap::new(client, params.network_config.ipfs_server);
 				let result = Behaviour::new(
 					protocol,
 					user_agent,
@@ -360,6 +402,7 @@ where
 					params.light_client_request_protocol_config,
 					params.network_config.request_response_protocols,
 					peerset_handle.clone(),
+					protocol_bandwidth.clone(),
 				);
 
 				match result {
@@ -398,6 +441,7 @@ where
 				registry,
 				MetricSources {
 					bandwidth: bandwidth.clone(),
+					protocol_bandwidth: protocol_bandwidth.clone(),
 					major_syncing: is_major_syncing.clone(),
 					connected_peers: num_connected.clone(),
 				},
@@ -421,11 +465,18 @@ where
 			);
 		}
 
+		// Add the addresses of the peers remembered from previous runs.
+		for (peer_id, addr) in stored_addresses {
+			swarm.behaviour_mut().add_stored_address(peer_id, addr);
+		}
+
 		let external_addresses = Arc::new(Mutex::new(Vec::new()));
 		let peers_notifications_sinks = Arc::new(Mutex::new(HashMap::new()));
+		let full_sync_peers = Arc::new(Mutex::new(HashSet::new()));
 
 		let service = Arc::new(NetworkService {
 			bandwidth,
+			protocol_bandwidth,
 			external_addresses: external_addresses.clone(),
 			num_connected: num_connected.clone(),
 			is_major_syncing: is_major_syncing.clone(),
@@ -437,6 +488,7 @@ where
 			notifications_sizes_metric: metrics
 				.as_ref()
 				.map(|metrics| metrics.notifications_sizes.clone()),
+			full_sync_peers: full_sync_peers.clone(),
 			_marker: PhantomData,
 		});
 
@@ -458,9 +510,12 @@ where
 			from_service,
 			event_streams: out_events::OutChannels::new(params.metrics_registry.as_ref())?,
 			peers_notifications_sinks,
+			full_sync_peers,
 			tx_handler_controller,
 			metrics,
 			boot_node_ids,
+			peer_store,
+			peer_store_flush: Delay::new(PEER_STORE_FLUSH_INTERVAL),
 		})
 	}
 
@@ -530,6 +585,31 @@ where
 		self.network_service.behaviour().user_protocol().num_sync_requests()
 	}
 
+	/// Writes the reputations and addresses of the known peers to the peer store, if any.
+	fn flush_peer_store(&mut self) {
+		let peer_store = match &self.peer_store {
+			Some(peer_store) => peer_store,
+			None => return,
+		};
+
+		let behaviour = self.network_service.behaviour_mut();
+		let mut records = behaviour.user_protocol_mut().peer_records();
+		let now = sc_peerset::unix_time();
+		for (peer_id, addresses) in behaviour.known_addresses() {
+			records
+				.entry(peer_id)
+				.or_insert_with(|| sc_peerset::PeerRecord::new(0, now))
+				.addresses = addresses.iter().map(ToString::to_string).collect();
+		}
+
+		if let Err(e) = peer_store.store(&records) {
+			warn!(
+				target: "sub-libp2p",
+				"Failed to store peers in {}: {}", peer_store.path().display(), e,
+			);
+		}
+	}
+
 	/// Adds an address for a node.
 	pub fn add_known_address(&mut self, peer_id: PeerId, addr: Multiaddr) {
 		self.network_service.behaviour_mut().add_known_address(peer_id, addr);
@@ -657,6 +737,7 @@ where
 			connected_peers,
 			not_connected_peers,
 			peerset: swarm.behaviour_mut().user_protocol_mut().peerset_debug_info(),
+			protocol_bandwidth: self.service.protocol_bandwidth.stats(),
 		}
 	}
 
@@ -768,6 +849,17 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
 			}
 		};
 
+		if let Some(delay) = self.protocol_bandwidth.outbound_delay(&protocol, &target) {
+			// Notification silently discarded, as the bandwidth limit of the protocol is reached.
+			debug!(
+				target: "sub-libp2p",
+				"Dropping notification to {} on {:?}: bandwidth limit reached for {:?}",
+				target, protocol, delay,
+			);
+			return
+		}
+		self.protocol_bandwidth.report_outbound(&protocol, &target, message.len());
+
 		if let Some(notifications_sizes_metric) = self.notifications_sizes_metric.as_ref() {
 			notifications_sizes_metric
 				.with_label_values(&["out", &protocol])
@@ -871,7 +963,12 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
 			.as_ref()
 			.map(|histogram| histogram.with_label_values(&["out", &protocol]));
 
-		Ok(NotificationSender { sink, protocol_name: protocol, notification_size_metric })
+		Ok(NotificationSender {
+			sink,
+			protocol_name: protocol,
+			notification_size_metric,
+			protocol_bandwidth: self.protocol_bandwidth.clone(),
+		})
 	}
 
 	/// Returns a stream containing the events that happen on the network.
@@ -954,6 +1051,24 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
 		});
 	}
 
+	/// Fetch the content of `cid` from other peers over bitswap.
+	///
+	/// The content is requested from connected peers and from peers announcing it in the DHT.
+	/// It is only returned if it matches the hash of the CID.
+	pub async fn bitswap_fetch(&self, cid: Cid) -> Result<Vec<u8>, BitswapError> {
+		let (tx, rx) = oneshot::channel();
+
+		let _ = self
+			.to_worker
+			.unbounded_send(ServiceToWorkerMsg::BitswapFetch { cid, pending_response: tx });
+
+		match rx.await {
+			Ok(v) => v,
+			// The channel can only be closed if the network worker no longer exists.
+			Err(_) => Err(BitswapError::NetworkClosed),
+		}
+	}
+
 	/// High-level network status information.
 	///
 	/// Returns an error if the `NetworkWorker` is no longer running.
@@ -1337,6 +1452,35 @@ where
 	}
 }
 
+impl<B, H> sc_network_light::light_client_requests::sender::RequestSender for NetworkService<B, H>
+where
+	B: BlockT + 'static,
+	H: ExHashT,
+{
+	fn full_peers(&self) -> Vec<PeerId> {
+		self.full_sync_peers.lock().iter().cloned().collect()
+	}
+
+	fn send_request(
+		&self,
+		peer: PeerId,
+		protocol: Cow<'static, str>,
+		request: Vec<u8>,
+	) -> future::BoxFuture<'static, Result<Vec<u8>, String>> {
+		let (tx, rx) = oneshot::channel();
+		self.start_request(peer, protocol, request, tx, IfDisconnected::ImmediateError);
+
+		async move {
+			match rx.await {
+				Ok(result) => result.map_err(|e| e.to_string()),
+				Err(_) =>
+					Err(RequestFailure::Network(OutboundFailure::ConnectionClosed).to_string()),
+			}
+		}
+		.boxed()
+	}
+}
+
 /// A `NotificationSender` allows for sending notifications to a peer with a chosen protocol.
 #[must_use]
 pub struct NotificationSender {
@@ -1348,12 +1492,24 @@ pub struct NotificationSender {
 	/// Field extracted from the [`Metrics`] struct and necessary to report the
 	/// notifications-related metrics.
 	notification_size_metric: Option<Histogram>,
+
+	/// Bandwidth accounting the sent notifications are reported to.
+	protocol_bandwidth: Arc<BandwidthAccounting>,
 }
 
 impl NotificationSender {
 	/// Returns a future that resolves when the `NotificationSender` is ready to send a
 	/// notification.
+	///
+	/// If the bandwidth limit of the protocol is reached, the future only resolves once the
+	/// limit allows sending again.
 	pub async fn ready(&self) -> Result<NotificationSenderReady<'_>, NotificationSenderError> {
+		while let Some(delay) =
+			self.protocol_bandwidth.outbound_delay(&self.protocol_name, self.sink.peer_id())
+		{
+			Delay::new(delay).await;
+		}
+
 		Ok(NotificationSenderReady {
 			ready: match self.sink.reserve_notification().await {
 				Ok(r) => r,
@@ -1362,6 +1518,7 @@ impl NotificationSender {
 			peer_id: self.sink.peer_id(),
 			protocol_name: &self.protocol_name,
 			notification_size_metric: self.notification_size_metric.clone(),
+			protocol_bandwidth: &self.protocol_bandwidth,
 		})
 	}
 }
@@ -1380,6 +1537,9 @@ pub struct NotificationSenderReady<'a> {
 	/// Field extracted from the [`Metrics`] struct and necessary to report the
 	/// notifications-related metrics.
 	notification_size_metric: Option<Histogram>,
+
+	/// Bandwidth accounting the notification is reported to.
+	protocol_bandwidth: &'a BandwidthAccounting,
 }
 
 impl<'a> NotificationSenderReady<'a> {
@@ -1390,6 +1550,11 @@ impl<'a> NotificationSenderReady<'a> {
 		if let Some(notification_size_metric) = &self.notification_size_metric {
 			notification_size_metric.observe(notification.len() as f64);
 		}
+		self.protocol_bandwidth.report_outbound(
+			self.protocol_name,
+			self.peer_id,
+			notification.len(),
+		);
 
 		trace!(
 			target: "sub-libp2p",
@@ -1448,6 +1613,10 @@ enum ServiceToWorkerMsg<B: BlockT, H: ExHashT> {
 		pending_response: oneshot::Sender<Result<Vec<u8>, RequestFailure>>,
 		connect: IfDisconnected,
 	},
+	BitswapFetch {
+		cid: Cid,
+		pending_response: FetchResponseSender,
+	},
 	NetworkStatus {
 		pending_response: oneshot::Sender<Result<NetworkStatus<B>, RequestFailure>>,
 	},
@@ -1499,6 +1668,12 @@ where
 	peers_notifications_sinks: Arc<Mutex<HashMap<(PeerId, Cow<'static, str>), NotificationsSink>>>,
 	/// Controller for the handler of incoming and outgoing transactions.
 	tx_handler_controller: transactions::TransactionsHandlerController<H>,
+	/// Full nodes we have an open sync substream with. Shared with the [`NetworkService`].
+	full_sync_peers: Arc<Mutex<HashSet<PeerId>>>,
+	/// Store the known peers are periodically written to, if persisting them is enabled.
+	peer_store: Option<sc_peerset::PeerStore>,
+	/// Fires when the known peers must next be written to the peer store.
+	peer_store_flush: Delay,
 }
 
 impl<B, H, Client> Future for NetworkWorker<B, H, Client>
@@ -1637,6 +1812,8 @@ where
 						connect,
 					);
 				},
+				ServiceToWorkerMsg::BitswapFetch { cid, pending_response } =>
+					this.network_service.behaviour_mut().bitswap_fetch(cid, pending_response),
 				ServiceToWorkerMsg::NetworkStatus { pending_response } => {
 					let _ = pending_response.send(Ok(this.status()));
 				},
@@ -1861,6 +2038,9 @@ where
 					remote,
 					messages,
 				})) => {
+					for (protocol, message) in &messages {
+						this.service.protocol_bandwidth.report_inbound(protocol, message.len());
+					}
 					if let Some(metrics) = this.metrics.as_ref() {
 						for (protocol, message) in &messages {
 							metrics
@@ -1872,9 +2052,19 @@ where
 					this.event_streams.send(Event::NotificationsReceived { remote, messages });
 				},
 				Poll::Ready(SwarmEvent::Behaviour(BehaviourOut::SyncConnected(remote))) => {
+					let is_full = this
+						.network_service
+						.behaviour_mut()
+						.user_protocol_mut()
+						.peers_info()
+						.any(|(id, info)| *id == remote && info.roles.is_full());
+					if is_full {
+						this.full_sync_peers.lock().insert(remote);
+					}
 					this.event_streams.send(Event::SyncConnected { remote });
 				},
 				Poll::Ready(SwarmEvent::Behaviour(BehaviourOut::SyncDisconnected(remote))) => {
+					this.full_sync_peers.lock().remove(&remote);
 					this.event_streams.send(Event::SyncDisconnected { remote });
 				},
 				Poll::Ready(SwarmEvent::Behaviour(BehaviourOut::Dht(event, duration))) => {
@@ -2111,6 +2301,11 @@ where
 
 		this.is_major_syncing.store(is_major_syncing, Ordering::Relaxed);
 
+		if this.peer_store_flush.poll_unpin(cx).is_ready() {
+			this.peer_store_flush = Delay::new(PEER_STORE_FLUSH_INTERVAL);
+			this.flush_peer_store();
+		}
+
 		if let Some(metrics) = this.metrics.as_ref() {
 			for (proto, buckets) in this.network_service.behaviour_mut().num_entries_per_kbucket() {
 				for (lower_ilog2_bucket_bound, num_entries) in buckets {
@@ -2149,6 +2344,23 @@ where
 	}
 }
 
+impl<B, H, Client> Drop for NetworkWorker<B, H, Client>
+where
+	B: BlockT + 'static,
+	H: ExHashT,
+	Client: HeaderBackend<B>
+		+ BlockBackend<B>
+		+ HeaderMetadata<B, Error = sp_blockchain::Error>
+		+ ProofProvider<B>
+		+ Send
+		+ Sync
+		+ 'static,
+{
+	fn drop(&mut self) {
+		self.flush_peer_store();
+	}
+}
+
 impl<B, H, Client> Unpin for NetworkWorker<B, H, Client>
 where
 	B: BlockT + 'static,
@@ -16,7 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::transport::BandwidthSinks;
+use crate::{bandwidth::BandwidthAccounting, transport::BandwidthSinks};
 use prometheus_endpoint::{
 	self as prometheus, Counter, CounterVec, Gauge, GaugeVec, HistogramOpts, MetricSource, Opts,
 	PrometheusError, Registry, SourcedCounter, SourcedGauge, U64,
@@ -34,6 +34,7 @@ pub use prometheus_endpoint::{Histogram, HistogramVec};
 /// Registers all networking metrics with the given registry.
 pub fn register(registry: &Registry, sources: MetricSources) -> Result<Metrics, PrometheusError> {
 	BandwidthCounters::register(registry, sources.bandwidth)?;
+	ProtocolBandwidthCounters::register(registry, sources.protocol_bandwidth)?;
 	MajorSyncingGauge::register(registry, sources.major_syncing)?;
 	NumConnectedGauge::register(registry, sources.connected_peers)?;
 	Metrics::register(registry)
@@ -42,6 +43,7 @@ pub fn register(registry: &Registry, sources: MetricSources) -> Result<Metrics,
 /// Predefined metric sources that are fed directly into prometheus.
 pub struct MetricSources {
 	pub bandwidth: Arc<BandwidthSinks>,
+	pub protocol_bandwidth: Arc<BandwidthAccounting>,
 	pub major_syncing: Arc<AtomicBool>,
 	pub connected_peers: Arc<AtomicUsize>,
 }
@@ -285,6 +287,45 @@ impl MetricSource for BandwidthCounters {
 	}
 }
 
+/// The per-protocol bandwidth counter metric.
+#[derive(Clone)]
+pub struct ProtocolBandwidthCounters(Arc<BandwidthAccounting>);
+
+impl ProtocolBandwidthCounters {
+	/// Registers the `ProtocolBandwidthCounters` metric whose values are
+	/// obtained from the given accounting.
+	fn register(
+		registry: &Registry,
+		accounting: Arc<BandwidthAccounting>,
+	) -> Result<(), PrometheusError> {
+		prometheus::register(
+			SourcedCounter::new(
+				&Opts::new(
+					"substrate_sub_libp2p_protocol_bytes_total",
+					"Bandwidth usage of each notifications and request-response protocol",
+				)
+				.variable_label("direction")
+				.variable_label("protocol"),
+				ProtocolBandwidthCounters(accounting),
+			)?,
+			registry,
+		)?;
+
+		Ok(())
+	}
+}
+
+impl MetricSource for ProtocolBandwidthCounters {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		for (protocol, bandwidth) in self.0.stats() {
+			set(&["in", &protocol], bandwidth.inbound);
+			set(&["out", &protocol], bandwidth.outbound);
+		}
+	}
+}
+
 /// The "major syncing" metric.
 #[derive(Clone)]
 pub struct MajorSyncingGauge(Arc<AtomicBool>);
@@ -22,7 +22,7 @@ use crate::{
 
 use futures::prelude::*;
 use libp2p::PeerId;
-use sc_network_common::config::ProtocolId;
+use sc_network_common::{config::ProtocolId, request_quota::QuotaConfig};
 use sc_network_light::light_client_requests::handler::LightClientRequestHandler;
 use sc_network_sync::block_request_handler::BlockRequestHandler;
 use sp_runtime::traits::{Block as BlockT, Header as _};
@@ -91,20 +91,22 @@ fn build_test_full_node(
 	let protocol_id = ProtocolId::from("/test-protocol-name");
 
 	let block_request_protocol_config = {
-		let (handler, protocol_config) = BlockRequestHandler::new(&protocol_id, client.clone(), 50);
+		let (handler, protocol_config) =
+			BlockRequestHandler::new(&protocol_id, client.clone(), 50, QuotaConfig::unlimited());
 		async_std::task::spawn(handler.run().boxed());
 		protocol_config
 	};
 
 	let state_request_protocol_config = {
-		let (handler, protocol_config) = StateRequestHandler::new(&protocol_id, client.clone(), 50);
+		let (handler, protocol_config) =
+			StateRequestHandler::new(&protocol_id, client.clone(), 50, QuotaConfig::unlimited());
 		async_std::task::spawn(handler.run().boxed());
 		protocol_config
 	};
 
 	let light_client_request_protocol_config = {
 		let (handler, protocol_config) =
-			LightClientRequestHandler::new(&protocol_id, client.clone());
+			LightClientRequestHandler::new(&protocol_id, client.clone(), QuotaConfig::unlimited());
 		async_std::task::spawn(handler.run().boxed());
 		protocol_config
 	};
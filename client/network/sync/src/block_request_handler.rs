@@ -18,14 +18,12 @@
 //! `crate::request_responses::RequestResponsesBehaviour`.
 
 use crate::{
+	compression,
 	message::BlockAttributes,
 	schema::v1::{block_request::FromBlock, BlockResponse, Direction},
 };
 use codec::{Decode, Encode};
-use futures::{
-	channel::{mpsc, oneshot},
-	stream::StreamExt,
-};
+use futures::channel::{mpsc, oneshot};
 use libp2p::PeerId;
 use log::debug;
 use lru::LruCache;
@@ -33,6 +31,7 @@ use prost::Message;
 use sc_client_api::BlockBackend;
 use sc_network_common::{
 	config::ProtocolId,
+	request_quota::{InboundRequests, QuotaConfig},
 	request_responses::{IncomingRequest, OutgoingResponse, ProtocolConfig},
 };
 use sp_blockchain::HeaderBackend;
@@ -116,7 +115,7 @@ enum SeenRequestsValue {
 /// Handler for incoming block requests from a remote peer.
 pub struct BlockRequestHandler<B: BlockT, Client> {
 	client: Arc<Client>,
-	request_receiver: mpsc::Receiver<IncomingRequest>,
+	request_receiver: InboundRequests,
 	/// Maps from request to number of times we have seen this request.
 	///
 	/// This is used to check if a peer is spamming us with the same request.
@@ -133,6 +132,7 @@ where
 		protocol_id: &ProtocolId,
 		client: Arc<Client>,
 		num_peer_hint: usize,
+		quota: QuotaConfig,
 	) -> (Self, ProtocolConfig) {
 		// Reserve enough request slots for one request per peer when we are at the maximum
 		// number of peers.
@@ -142,6 +142,7 @@ where
 		protocol_config.inbound_queue = Some(tx);
 
 		let seen_requests = LruCache::new(num_peer_hint * 2);
+		let request_receiver = InboundRequests::new(request_receiver, num_peer_hint, quota);
 
 		(Self { client, request_receiver, seen_requests }, protocol_config)
 	}
@@ -261,6 +262,7 @@ where
 				}
 			}
 
+			let block_response = compression::compress(block_response, request.accept_compression);
 			let mut data = Vec::with_capacity(block_response.encoded_len());
 			block_response.encode(&mut data)?;
 
@@ -392,7 +394,7 @@ where
 			}
 		}
 
-		Ok(BlockResponse { blocks })
+		Ok(BlockResponse { blocks, compressed: Vec::new() })
 	}
 }
 
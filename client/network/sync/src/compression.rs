@@ -0,0 +1,189 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Compression of block and state responses.
+//!
+//! Requests list the compression they accept in `accept_compression`. A responder that supports
+//! it encodes the response as usual and returns it compressed in the `compressed` field, leaving
+//! all other fields empty. Peers unaware of compression ignore the request field and answer with
+//! plain responses, which [`decompress`] passes through unchanged.
+
+use crate::schema::v1::{BlockResponse, Compression, StateResponse};
+use prost::Message;
+
+/// Maximum size of a decompressed response.
+///
+/// This matches the maximum size of a response on the wire, so a compressed response never
+/// carries more data than an uncompressed one could.
+pub const MAX_DECOMPRESSED_SIZE: usize = 16 * 1024 * 1024;
+
+/// Responses smaller than this are not worth compressing.
+const MIN_COMPRESSION_SIZE: usize = 1024;
+
+/// Error decompressing a response.
+#[derive(Debug, thiserror::Error)]
+pub enum DecompressError {
+	/// The compressed data is invalid or inflates past [`MAX_DECOMPRESSED_SIZE`].
+	#[error("Failed to decompress response: {0}.")]
+	Decompress(#[from] sp_maybe_compressed_blob::Error),
+	/// The decompressed data is not a valid response.
+	#[error("Failed to decode decompressed response: {0}.")]
+	DecodeProto(#[from] prost::DecodeError),
+	/// The decompressed response is itself compressed.
+	#[error("Compressed response contains another compressed response.")]
+	Nested,
+}
+
+/// A response that can be sent compressed.
+pub trait CompressibleResponse: Message + Default {
+	/// The compressed form of the response, empty if it isn't compressed.
+	fn compressed(&self) -> &[u8];
+
+	/// Build a response carrying only its compressed form.
+	fn from_compressed(compressed: Vec<u8>) -> Self;
+}
+
+impl CompressibleResponse for BlockResponse {
+	fn compressed(&self) -> &[u8] {
+		&self.compressed
+	}
+
+	fn from_compressed(compressed: Vec<u8>) -> Self {
+		Self { compressed, ..Default::default() }
+	}
+}
+
+impl CompressibleResponse for StateResponse {
+	fn compressed(&self) -> &[u8] {
+		&self.compressed
+	}
+
+	fn from_compressed(compressed: Vec<u8>) -> Self {
+		Self { compressed, ..Default::default() }
+	}
+}
+
+/// Compress `response` if the request accepted compression and it makes the response smaller.
+pub fn compress<R: CompressibleResponse>(response: R, accept_compression: i32) -> R {
+	if Compression::from_i32(accept_compression) != Some(Compression::Zstd) ||
+		response.encoded_len() < MIN_COMPRESSION_SIZE
+	{
+		return response
+	}
+
+	let encoded = response.encode_to_vec();
+	match sp_maybe_compressed_blob::compress(&encoded, MAX_DECOMPRESSED_SIZE) {
+		Some(compressed) if compressed.len() < encoded.len() => R::from_compressed(compressed),
+		_ => response,
+	}
+}
+
+/// Decompress `response` if it was sent compressed, refusing to inflate it past
+/// [`MAX_DECOMPRESSED_SIZE`].
+pub fn decompress<R: CompressibleResponse>(response: R) -> Result<R, DecompressError> {
+	if response.compressed().is_empty() {
+		return Ok(response)
+	}
+
+	let encoded =
+		sp_maybe_compressed_blob::decompress(response.compressed(), MAX_DECOMPRESSED_SIZE)?;
+	let response = R::decode(&encoded[..])?;
+	if response.compressed().is_empty() {
+		Ok(response)
+	} else {
+		Err(DecompressError::Nested)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::schema::v1::{KeyValueStateEntry, StateEntry};
+
+	fn state_response(entries: usize) -> StateResponse {
+		StateResponse {
+			entries: vec![KeyValueStateEntry {
+				state_root: Vec::new(),
+				entries: (0..entries)
+					.map(|i| StateEntry {
+						key: (i as u32).to_be_bytes().to_vec(),
+						value: vec![0; 32],
+					})
+					.collect(),
+				complete: true,
+			}],
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn compresses_only_when_accepted() {
+		let response = state_response(100);
+
+		let plain = compress(response.clone(), Compression::None as i32);
+		assert_eq!(plain, response);
+
+		let compressed = compress(response.clone(), Compression::Zstd as i32);
+		assert!(compressed.entries.is_empty());
+		assert!(compressed.encoded_len() < response.encoded_len());
+		assert_eq!(decompress(compressed).unwrap(), response);
+	}
+
+	#[test]
+	fn small_responses_are_not_compressed() {
+		let response = state_response(1);
+		assert_eq!(compress(response.clone(), Compression::Zstd as i32), response);
+	}
+
+	#[test]
+	fn plain_responses_pass_through() {
+		let response = state_response(100);
+		assert_eq!(decompress(response.clone()).unwrap(), response);
+	}
+
+	#[test]
+	fn refuses_compression_bombs() {
+		let bomb = BlockResponse {
+			blocks: vec![crate::schema::v1::BlockData {
+				body: vec![vec![0; MAX_DECOMPRESSED_SIZE]],
+				..Default::default()
+			}],
+			..Default::default()
+		};
+		let compressed =
+			sp_maybe_compressed_blob::compress(&bomb.encode_to_vec(), usize::MAX).unwrap();
+
+		assert!(matches!(
+			decompress(BlockResponse::from_compressed(compressed)),
+			Err(DecompressError::Decompress(sp_maybe_compressed_blob::Error::PossibleBomb)),
+		));
+	}
+
+	#[test]
+	fn refuses_nested_compression() {
+		let inner = compress(state_response(100), Compression::Zstd as i32);
+		let outer =
+			sp_maybe_compressed_blob::compress(&inner.encode_to_vec(), MAX_DECOMPRESSED_SIZE)
+				.unwrap();
+
+		assert!(matches!(
+			decompress(StateResponse::from_compressed(outer)),
+			Err(DecompressError::Nested),
+		));
+	}
+}
@@ -0,0 +1,368 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Persistence of warp and state sync progress.
+//!
+//! Downloading the state of a large chain can take hours, so the progress of an ongoing warp
+//! or state sync is recorded on disk and picked up again after a restart. Two files are kept:
+//!
+//! - the warp sync progress, i.e. the last verified authority set and the block it was proven up
+//!   to, rewritten after each verified warp proof fragment;
+//! - the state journal, made of the target header followed by every accepted state response, in
+//!   order, along with the index of the key range it was requested for. Responses are verified
+//!   again when replayed, so a corrupted or stale journal is detected and discarded.
+//!
+//! Failing to write the journal is never fatal: sync carries on, it just won't be able to resume.
+
+use crate::schema::v1::StateResponse;
+use codec::{Decode, Encode};
+use log::{debug, warn};
+use prost::Message;
+use sp_finality_grandpa::{AuthorityList, SetId};
+use sp_runtime::traits::Block as BlockT;
+use std::{
+	fs::{self, File, OpenOptions},
+	io::{self, BufReader, Read, Write},
+	path::PathBuf,
+};
+
+const WARP_PROGRESS_FILE: &str = "warp_progress";
+const STATE_JOURNAL_FILE: &str = "state_journal";
+
+/// Upper bound on the size of a single journal record.
+const MAX_RECORD_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Encode, Decode)]
+struct WarpProgressRecord<Hash> {
+	genesis_hash: Hash,
+	set_id: SetId,
+	authorities: AuthorityList,
+	last_hash: Hash,
+}
+
+#[derive(Encode, Decode)]
+struct StateTargetRecord<Hash, Header> {
+	genesis_hash: Hash,
+	target: Header,
+	skip_proof: bool,
+}
+
+/// On-disk record of the progress of warp and state sync.
+#[derive(Clone, Debug)]
+pub struct SyncJournal<B: BlockT> {
+	dir: PathBuf,
+	genesis_hash: B::Hash,
+}
+
+impl<B: BlockT> SyncJournal<B> {
+	/// Create a journal stored in `dir`, for the chain with the given genesis hash.
+	///
+	/// Anything recorded for another chain is ignored.
+	pub fn new(dir: impl Into<PathBuf>, genesis_hash: B::Hash) -> Self {
+		Self { dir: dir.into(), genesis_hash }
+	}
+
+	/// Returns the recorded warp sync progress, as the last verified authority set and the hash of
+	/// the block it was proven up to.
+	pub fn warp_progress(&self) -> Option<(SetId, AuthorityList, B::Hash)> {
+		let data = fs::read(self.dir.join(WARP_PROGRESS_FILE)).ok()?;
+		match WarpProgressRecord::<B::Hash>::decode(&mut data.as_slice()) {
+			Ok(record) if record.genesis_hash == self.genesis_hash =>
+				Some((record.set_id, record.authorities, record.last_hash)),
+			Ok(_) => {
+				debug!(target: "sync", "Ignoring warp sync progress recorded for another chain");
+				None
+			},
+			Err(e) => {
+				debug!(target: "sync", "Ignoring corrupted warp sync progress: {}", e);
+				None
+			},
+		}
+	}
+
+	/// Record the warp sync progress, replacing any previous record.
+	pub fn store_warp_progress(
+		&self,
+		set_id: SetId,
+		authorities: &AuthorityList,
+		last_hash: B::Hash,
+	) {
+		let record = WarpProgressRecord {
+			genesis_hash: self.genesis_hash,
+			set_id,
+			authorities: authorities.clone(),
+			last_hash,
+		};
+		let result = self.write_atomically(WARP_PROGRESS_FILE, &record.encode());
+		self.report("warp sync progress", result);
+	}
+
+	/// Returns the target header of the recorded state sync and whether proofs were skipped.
+	pub fn state_target(&self) -> Option<(B::Header, bool)> {
+		let file = File::open(self.dir.join(STATE_JOURNAL_FILE)).ok()?;
+		let record = read_record(&mut BufReader::new(file)).ok().flatten()?;
+		match StateTargetRecord::<B::Hash, B::Header>::decode(&mut record.as_slice()) {
+			Ok(target) if target.genesis_hash == self.genesis_hash =>
+				Some((target.target, target.skip_proof)),
+			Ok(_) => {
+				debug!(target: "sync", "Ignoring state sync journal recorded for another chain");
+				None
+			},
+			Err(e) => {
+				debug!(target: "sync", "Ignoring corrupted state sync journal: {}", e);
+				None
+			},
+		}
+	}
+
+	/// Start a new state journal for the given target, discarding the previous one.
+	pub fn start_state(&self, target: &B::Header, skip_proof: bool) {
+		let record = StateTargetRecord {
+			genesis_hash: self.genesis_hash,
+			target: target.clone(),
+			skip_proof,
+		};
+		let result = self.write_atomically(STATE_JOURNAL_FILE, &frame(&record.encode()));
+		self.report("state sync journal", result);
+	}
+
+	/// Append a state response accepted for the given key range to the state journal.
+	pub fn append_state(&self, range: u32, response: &[u8]) {
+		let mut record = range.to_le_bytes().to_vec();
+		record.extend_from_slice(response);
+		let result = OpenOptions::new()
+			.append(true)
+			.open(self.dir.join(STATE_JOURNAL_FILE))
+			.and_then(|mut file| file.write_all(&frame(&record)));
+		self.report("state sync journal", result);
+	}
+
+	/// Feed the recorded state responses and their key range to `import`, in order, until it
+	/// returns `false`.
+	///
+	/// Returns `true` if all responses were accepted. A record left incomplete by an interrupted
+	/// write is dropped from the journal, so that appending can continue after it.
+	pub fn replay_state(&self, mut import: impl FnMut(u32, StateResponse) -> bool) -> bool {
+		let path = self.dir.join(STATE_JOURNAL_FILE);
+		let mut reader = match File::open(&path) {
+			Ok(file) => BufReader::new(file),
+			Err(_) => return false,
+		};
+		// Skip the target record.
+		let mut valid_len = match read_record(&mut reader) {
+			Ok(Some(record)) => frame_len(&record),
+			_ => return false,
+		};
+		loop {
+			match read_record(&mut reader) {
+				Ok(Some(record)) => {
+					let (range, response) = match decode_state_record(&record) {
+						Some(decoded) => decoded,
+						None => {
+							debug!(target: "sync", "Corrupted state sync journal record");
+							return false
+						},
+					};
+					if !import(range, response) {
+						return false
+					}
+					valid_len += frame_len(&record);
+				},
+				Ok(None) => return true,
+				Err(e) => {
+					debug!(target: "sync", "Truncating state sync journal: {}", e);
+					let result = OpenOptions::new()
+						.write(true)
+						.open(&path)
+						.and_then(|file| file.set_len(valid_len));
+					self.report("state sync journal", result);
+					return true
+				},
+			}
+		}
+	}
+
+	/// Discard all recorded progress.
+	pub fn clear(&self) {
+		for file in [WARP_PROGRESS_FILE, STATE_JOURNAL_FILE] {
+			match fs::remove_file(self.dir.join(file)) {
+				Err(e) if e.kind() != io::ErrorKind::NotFound =>
+					warn!(target: "sync", "Failed to remove {}: {}", file, e),
+				_ => {},
+			}
+		}
+	}
+
+	fn write_atomically(&self, file: &str, data: &[u8]) -> io::Result<()> {
+		fs::create_dir_all(&self.dir)?;
+		let tmp = self.dir.join(format!("{}.tmp", file));
+		fs::write(&tmp, data)?;
+		fs::rename(&tmp, self.dir.join(file))
+	}
+
+	fn report(&self, what: &str, result: io::Result<()>) {
+		if let Err(e) = result {
+			warn!(
+				target: "sync",
+				"Failed to record {} in {}, sync won't resume after a restart: {}",
+				what,
+				self.dir.display(),
+				e,
+			);
+		}
+	}
+}
+
+/// Length-prefix a record.
+fn frame(record: &[u8]) -> Vec<u8> {
+	let mut framed = Vec::with_capacity(record.len() + 4);
+	framed.extend_from_slice(&(record.len() as u32).to_le_bytes());
+	framed.extend_from_slice(record);
+	framed
+}
+
+fn decode_state_record(record: &[u8]) -> Option<(u32, StateResponse)> {
+	let range = u32::from_le_bytes(record.get(..4)?.try_into().ok()?);
+	let response = StateResponse::decode(&record[4..]).ok()?;
+	Some((range, response))
+}
+
+fn frame_len(record: &[u8]) -> u64 {
+	record.len() as u64 + 4
+}
+
+/// Read a length-prefixed record, returning `None` at the end of the file.
+fn read_record(reader: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+	let mut len = [0u8; 4];
+	let mut read = 0;
+	while read < len.len() {
+		match reader.read(&mut len[read..]) {
+			Ok(0) if read == 0 => return Ok(None),
+			Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+			Ok(n) => read += n,
+			Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+			Err(e) => return Err(e),
+		}
+	}
+	let len = u32::from_le_bytes(len) as usize;
+	if len > MAX_RECORD_SIZE {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "record too large"))
+	}
+	let mut record = vec![0; len];
+	reader.read_exact(&mut record)?;
+	Ok(Some(record))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+	use sp_runtime::traits::Header as _;
+	use substrate_test_runtime_client::runtime::{Block, Header};
+
+	fn header(number: u64) -> Header {
+		Header::new(
+			number,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		)
+	}
+
+	fn response(marker: u8) -> StateResponse {
+		StateResponse { entries: Vec::new(), proof: vec![marker], compressed: Vec::new() }
+	}
+
+	fn replayed(journal: &SyncJournal<Block>) -> (bool, Vec<u8>) {
+		let mut markers = Vec::new();
+		let complete = journal.replay_state(|range, response| {
+			assert_eq!(range as u8, response.proof[0] % 2);
+			markers.push(response.proof[0]);
+			true
+		});
+		(complete, markers)
+	}
+
+	#[test]
+	fn warp_progress_is_scoped_to_the_chain() {
+		let dir = tempfile::tempdir().unwrap();
+		let journal = SyncJournal::<Block>::new(dir.path(), H256::repeat_byte(1));
+		assert!(journal.warp_progress().is_none());
+
+		journal.store_warp_progress(3, &Vec::new(), H256::repeat_byte(7));
+		assert_eq!(journal.warp_progress(), Some((3, Vec::new(), H256::repeat_byte(7))));
+
+		let other_chain = SyncJournal::<Block>::new(dir.path(), H256::repeat_byte(2));
+		assert!(other_chain.warp_progress().is_none());
+
+		journal.clear();
+		assert!(journal.warp_progress().is_none());
+	}
+
+	#[test]
+	fn state_responses_are_replayed_in_order() {
+		let dir = tempfile::tempdir().unwrap();
+		let journal = SyncJournal::<Block>::new(dir.path(), H256::repeat_byte(1));
+		assert!(!journal.replay_state(|_, _| true));
+
+		journal.start_state(&header(10), false);
+		for marker in 1..=3 {
+			journal.append_state(marker as u32 % 2, &response(marker).encode_to_vec());
+		}
+		assert_eq!(journal.state_target().map(|(h, skip)| (*h.number(), skip)), Some((10, false)));
+		assert_eq!(replayed(&journal), (true, vec![1, 2, 3]));
+
+		// A rejected response stops the replay.
+		let mut count = 0;
+		assert!(!journal.replay_state(|_, _| {
+			count += 1;
+			count < 2
+		}));
+		assert_eq!(count, 2);
+
+		// Starting over discards the recorded responses.
+		journal.start_state(&header(20), true);
+		assert_eq!(journal.state_target().map(|(h, skip)| (*h.number(), skip)), Some((20, true)));
+		assert_eq!(replayed(&journal), (true, Vec::new()));
+	}
+
+	#[test]
+	fn interrupted_append_is_dropped() {
+		let dir = tempfile::tempdir().unwrap();
+		let journal = SyncJournal::<Block>::new(dir.path(), H256::repeat_byte(1));
+		journal.start_state(&header(10), false);
+		journal.append_state(1, &response(1).encode_to_vec());
+
+		// Simulate a crash in the middle of writing a record.
+		let mut record = 0u32.to_le_bytes().to_vec();
+		record.extend_from_slice(&response(2).encode_to_vec());
+		let mut partial = frame(&record);
+		partial.truncate(partial.len() - 1);
+		OpenOptions::new()
+			.append(true)
+			.open(dir.path().join(STATE_JOURNAL_FILE))
+			.unwrap()
+			.write_all(&partial)
+			.unwrap();
+
+		assert_eq!(replayed(&journal), (true, vec![1]));
+		journal.append_state(1, &response(3).encode_to_vec());
+		assert_eq!(replayed(&journal), (true, vec![1, 3]));
+	}
+}
@@ -30,6 +30,8 @@
 
 pub mod block_request_handler;
 pub mod blocks;
+pub mod compression;
+pub mod journal;
 pub mod message;
 pub mod schema;
 pub mod state;
@@ -39,6 +41,7 @@ pub mod warp_request_handler;
 
 use crate::{
 	blocks::BlockCollection,
+	journal::SyncJournal,
 	message::{BlockAnnounce, BlockAttributes, BlockRequest, BlockResponse},
 	schema::v1::{StateRequest, StateResponse},
 	state::{StateDownloadProgress, StateSync},
@@ -243,6 +246,8 @@ pub struct ChainSync<B: BlockT, Client> {
 	warp_sync: Option<WarpSync<B, Client>>,
 	/// Warp sync provider.
 	warp_sync_provider: Option<Arc<dyn WarpSyncProvider<B>>>,
+	/// Record of warp and state sync progress, if it is persisted.
+	journal: Option<SyncJournal<B>>,
 	/// Enable importing existing blocks. This is used used after the state download to
 	/// catch up to the latest state while re-importing blocks.
 	import_existing: bool,
@@ -517,6 +522,7 @@ where
 		block_announce_validator: Box<dyn BlockAnnounceValidator<B> + Send>,
 		max_parallel_downloads: u32,
 		warp_sync_provider: Option<Arc<dyn WarpSyncProvider<B>>>,
+		journal: Option<SyncJournal<B>>,
 	) -> Result<Self, ClientError> {
 		let mut sync = Self {
 			client,
@@ -537,6 +543,7 @@ where
 			state_sync: None,
 			warp_sync: None,
 			warp_sync_provider,
+			journal,
 			import_existing: false,
 			gap_sync: None,
 		};
@@ -608,7 +615,11 @@ where
 			best_seen_block: best_seen,
 			num_peers: self.peers.len() as u32,
 			queued_blocks: self.queue_blocks.len() as u32,
-			state_sync: self.state_sync.as_ref().map(|s| s.progress()),
+			state_sync: self
+				.state_sync
+				.as_ref()
+				.map(|s| s.progress())
+				.or_else(|| self.warp_sync.as_ref().and_then(|s| s.state_progress())),
 			warp_sync: warp_sync_progress,
 		}
 	}
@@ -726,8 +737,12 @@ where
 					{
 						log::debug!(target: "sync", "Starting warp state sync.");
 						if let Some(provider) = &self.warp_sync_provider {
-							self.warp_sync =
-								Some(WarpSync::new(self.client.clone(), provider.clone()));
+							self.warp_sync = Some(WarpSync::new(
+								self.client.clone(),
+								provider.clone(),
+								self.journal.clone(),
+								self.median_seen().unwrap_or_else(Zero::zero),
+							));
 						}
 					}
 				}
@@ -971,50 +986,53 @@ where
 		Either::Right(iter)
 	}
 
-	/// Get a state request, if any.
-	pub fn state_request(&mut self) -> Option<(PeerId, StateRequest)> {
+	/// Get the state requests to send, if any.
+	///
+	/// The state is split in key ranges downloaded in parallel, with one request per peer.
+	pub fn state_requests(&mut self) -> Vec<(PeerId, StateRequest)> {
+		let mut requests = Vec::new();
 		if self.allowed_requests.is_empty() {
-			return None
+			return requests
 		}
-		if (self.state_sync.is_some() || self.warp_sync.is_some()) &&
-			self.peers.iter().any(|(_, peer)| peer.state == PeerSyncState::DownloadingState)
-		{
-			// Only one pending state request is allowed.
-			return None
-		}
-		if let Some(sync) = &self.state_sync {
+		if let Some(sync) = &mut self.state_sync {
 			if sync.is_complete() {
-				return None
+				return requests
 			}
 
+			let target = sync.target_block_num();
 			for (id, peer) in self.peers.iter_mut() {
-				if peer.state.is_available() && peer.common_number >= sync.target_block_num() {
-					peer.state = PeerSyncState::DownloadingState;
-					let request = sync.next_request();
+				if peer.state.is_available() && peer.common_number >= target {
+					let request = match sync.next_request(*id) {
+						Some(request) => request,
+						None => break,
+					};
 					trace!(target: "sync", "New StateRequest for {}: {:?}", id, request);
-					self.allowed_requests.clear();
-					return Some((*id, request))
+					peer.state = PeerSyncState::DownloadingState;
+					requests.push((*id, request));
 				}
 			}
-		}
-		if let Some(sync) = &self.warp_sync {
+		} else if let Some(sync) = &mut self.warp_sync {
 			if sync.is_complete() {
-				return None
+				return requests
 			}
-			if let (Some(request), Some(target)) =
-				(sync.next_state_request(), sync.target_block_number())
-			{
+			if let Some(target) = sync.target_block_number() {
 				for (id, peer) in self.peers.iter_mut() {
 					if peer.state.is_available() && peer.best_number >= target {
+						let request = match sync.next_state_request(*id) {
+							Some(request) => request,
+							None => break,
+						};
 						trace!(target: "sync", "New StateRequest for {}: {:?}", id, request);
 						peer.state = PeerSyncState::DownloadingState;
-						self.allowed_requests.clear();
-						return Some((*id, request))
+						requests.push((*id, request));
 					}
 				}
 			}
 		}
-		None
+		if !requests.is_empty() {
+			self.allowed_requests.clear();
+		}
+		requests
 	}
 
 	/// Get a warp sync request, if any.
@@ -1305,7 +1323,7 @@ where
 				response.entries.len(),
 				response.proof.len(),
 			);
-			sync.import(response)
+			sync.import(who, response)
 		} else if let Some(sync) = &mut self.warp_sync {
 			debug!(
 				target: "sync",
@@ -1314,7 +1332,7 @@ where
 				response.entries.len(),
 				response.proof.len(),
 			);
-			sync.import_state(response)
+			sync.import_state(who, response)
 		} else {
 			debug!(target: "sync", "Ignored obsolete state response from {}", who);
 			return Err(BadPeer(*who, rep::NOT_REQUESTED))
@@ -1556,6 +1574,7 @@ where
 							self.state_sync.as_ref().map_or(0, |s| s.progress().size / (1024 * 1024)),
 						);
 						self.state_sync = None;
+						self.clear_journal();
 						self.mode = SyncMode::Full;
 						output.extend(self.restart());
 					}
@@ -1570,6 +1589,7 @@ where
 							self.warp_sync.as_ref().map_or(0, |s| s.progress().total_bytes / (1024 * 1024)),
 						);
 						self.warp_sync = None;
+						self.clear_journal();
 						self.mode = SyncMode::Full;
 						output.extend(self.restart());
 					}
@@ -1624,6 +1644,7 @@ where
 					warn!(target: "sync", "💔 Error importing block {:?}: {}", hash, e.unwrap_err());
 					self.state_sync = None;
 					self.warp_sync = None;
+					self.clear_journal();
 					output.extend(self.restart());
 				},
 				Err(BlockImportError::Cancelled) => {},
@@ -1653,20 +1674,27 @@ where
 		if let SyncMode::LightState { skip_proofs, .. } = &self.mode {
 			if self.state_sync.is_none() && !self.peers.is_empty() && self.queue_blocks.is_empty() {
 				// Finalized a recent block.
-				let mut heads: Vec<_> =
-					self.peers.iter().map(|(_, peer)| peer.best_number).collect();
-				heads.sort();
-				let median = heads[heads.len() / 2];
+				let median = self.median_seen().unwrap_or_else(Zero::zero);
 				if number + STATE_SYNC_FINALITY_THRESHOLD.saturated_into() >= median {
 					if let Ok(Some(header)) = self.client.header(BlockId::hash(*hash)) {
-						log::debug!(
-							target: "sync",
-							"Starting state sync for #{} ({})",
-							number,
-							hash,
-						);
-						self.state_sync =
-							Some(StateSync::new(self.client.clone(), header, *skip_proofs));
+						let skip_proofs = *skip_proofs;
+						let state_sync = self
+							.resume_state_sync(skip_proofs, number, median)
+							.unwrap_or_else(|| {
+								log::debug!(
+									target: "sync",
+									"Starting state sync for #{} ({})",
+									number,
+									hash,
+								);
+								StateSync::new(
+									self.client.clone(),
+									header,
+									skip_proofs,
+									self.journal.clone(),
+								)
+							});
+						self.state_sync = Some(state_sync);
 						self.allowed_requests.set_all();
 					}
 				}
@@ -2051,6 +2079,12 @@ where
 			gap_sync.blocks.clear_peer_download(who)
 		}
 		self.peers.remove(who);
+		if let Some(state_sync) = &mut self.state_sync {
+			state_sync.peer_disconnected(who);
+		}
+		if let Some(warp_sync) = &mut self.warp_sync {
+			warp_sync.peer_disconnected(who);
+		}
 		self.extra_justifications.peer_disconnected(who);
 		self.allowed_requests.set_all();
 		self.fork_targets.retain(|_, target| {
@@ -2065,6 +2099,39 @@ where
 		}
 	}
 
+	/// Returns the median of the best block numbers reported by peers.
+	fn median_seen(&self) -> Option<NumberFor<B>> {
+		let mut heads: Vec<_> = self.peers.values().map(|peer| peer.best_number).collect();
+		heads.sort();
+		heads.get(heads.len() / 2).copied()
+	}
+
+	/// Resume the state sync recorded in the journal, if its target is still part of the
+	/// finalized chain and recent enough for peers to have its state.
+	fn resume_state_sync(
+		&self,
+		skip_proofs: bool,
+		finalized: NumberFor<B>,
+		median: NumberFor<B>,
+	) -> Option<StateSync<B, Client>> {
+		let journal = self.journal.clone()?;
+		let client = &self.client;
+		StateSync::resume(client.clone(), journal, |target, skip| {
+			let number = *target.number();
+			skip == skip_proofs &&
+				number <= finalized &&
+				number.saturating_add(warp::MAX_RESUMED_STATE_AGE.saturated_into()) >= median &&
+				client.hash(number).ok().flatten() == Some(target.hash())
+		})
+	}
+
+	/// Discard the recorded warp and state sync progress.
+	fn clear_journal(&self) {
+		if let Some(journal) = &self.journal {
+			journal.clear();
+		}
+	}
+
 	/// Restart the sync process. This will reset all pending block requests and return an iterator
 	/// of new block requests to make to peers. Peers that were downloading finality data (i.e.
 	/// their state was `DownloadingJustification`) are unaffected and will stay in the same state.
@@ -2595,7 +2662,7 @@ mod test {
 		let peer_id = PeerId::random();
 
 		let mut sync =
-			ChainSync::new(SyncMode::Full, client.clone(), block_announce_validator, 1, None)
+			ChainSync::new(SyncMode::Full, client.clone(), block_announce_validator, 1, None, None)
 				.unwrap();
 
 		let (a1_hash, a1_number) = {
@@ -2648,6 +2715,7 @@ mod test {
 			Box::new(DefaultBlockAnnounceValidator),
 			1,
 			None,
+			None,
 		)
 		.unwrap();
 
@@ -2816,6 +2884,7 @@ mod test {
 			Box::new(DefaultBlockAnnounceValidator),
 			5,
 			None,
+			None,
 		)
 		.unwrap();
 
@@ -2931,6 +3000,7 @@ mod test {
 			Box::new(DefaultBlockAnnounceValidator),
 			5,
 			None,
+			None,
 		)
 		.unwrap();
 
@@ -3055,6 +3125,7 @@ mod test {
 			Box::new(DefaultBlockAnnounceValidator),
 			5,
 			None,
+			None,
 		)
 		.unwrap();
 
@@ -3163,6 +3234,7 @@ mod test {
 			Box::new(DefaultBlockAnnounceValidator),
 			1,
 			None,
+			None,
 		)
 		.unwrap();
 
@@ -3196,6 +3268,7 @@ mod test {
 			Box::new(DefaultBlockAnnounceValidator),
 			1,
 			None,
+			None,
 		)
 		.unwrap();
 
@@ -18,28 +18,91 @@
 
 //! State sync support.
 
-use crate::schema::v1::{StateEntry, StateRequest, StateResponse};
+use crate::{
+	journal::SyncJournal,
+	schema::v1::{Compression, StateEntry, StateRequest, StateResponse},
+};
 use codec::{Decode, Encode};
-use log::debug;
+use libp2p::PeerId;
+use log::{debug, info};
+use prost::Message;
 use sc_client_api::{CompactProof, ProofProvider};
 use sc_consensus::ImportedState;
-use smallvec::SmallVec;
+use smallvec::{smallvec, SmallVec};
 use sp_core::storage::well_known_keys;
 use sp_runtime::traits::{Block as BlockT, Header, NumberFor};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+	collections::{HashMap, HashSet},
+	sync::Arc,
+};
+
+/// Number of key ranges the state is split into, each downloaded independently.
+///
+/// Ranges are delimited by the first byte of top trie keys. Child trie roots are all stored under
+/// the same prefix, so each child trie is downloaded as part of a single range.
+const STATE_RANGES: usize = 16;
+
+/// Number of distinct leading key bytes in each range.
+const RANGE_SPAN: usize = 256 / STATE_RANGES;
+
+/// A range of the top trie key space, downloaded from one peer at a time.
+struct KeyRange {
+	/// Position of the download, keys up to it have been downloaded.
+	last_key: SmallVec<[Vec<u8>; 2]>,
+	/// Last top trie key within the range, `None` for the last range.
+	end: Option<Vec<u8>>,
+	/// Whether all keys of the range have been downloaded.
+	complete: bool,
+	/// Peer the range is currently requested from.
+	peer: Option<PeerId>,
+}
+
+impl KeyRange {
+	/// Split the key space in `STATE_RANGES` ranges.
+	///
+	/// The start of a request is exclusive, so each range ends with the key the next one starts
+	/// after.
+	fn split_key_space() -> Vec<Self> {
+		(0..STATE_RANGES)
+			.map(|i| KeyRange {
+				last_key: if i == 0 {
+					SmallVec::new()
+				} else {
+					smallvec![vec![(i * RANGE_SPAN) as u8]]
+				},
+				end: (i + 1 < STATE_RANGES).then(|| vec![((i + 1) * RANGE_SPAN) as u8]),
+				complete: false,
+				peer: None,
+			})
+			.collect()
+	}
+
+	/// Number of leading key bytes of the range that have been downloaded.
+	fn downloaded_span(&self, index: usize) -> usize {
+		if self.complete {
+			return RANGE_SPAN
+		}
+		let start = index * RANGE_SPAN;
+		let cursor = self.last_key.get(0).and_then(|key| key.get(0)).map_or(start, |b| *b as usize);
+		cursor.saturating_sub(start).min(RANGE_SPAN)
+	}
+}
 
 /// State sync state machine. Accumulates partial state data until it
 /// is ready to be imported.
+///
+/// The key space is split in ranges which are downloaded in parallel from different peers.
 pub struct StateSync<B: BlockT, Client> {
 	target_block: B::Hash,
 	target_header: B::Header,
 	target_root: B::Hash,
-	last_key: SmallVec<[Vec<u8>; 2]>,
+	ranges: Vec<KeyRange>,
 	state: HashMap<Vec<u8>, (Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>)>,
 	complete: bool,
 	client: Arc<Client>,
 	imported_bytes: u64,
 	skip_proof: bool,
+	journal: Option<SyncJournal<B>>,
 }
 
 /// Reported state download progress.
@@ -49,6 +112,12 @@ pub struct StateDownloadProgress {
 	pub percentage: u32,
 	/// Total state size in bytes downloaded so far.
 	pub size: u64,
+	/// Number of key ranges the state is split into.
+	pub ranges: u32,
+	/// Number of key ranges fully downloaded.
+	pub completed_ranges: u32,
+	/// Number of key ranges being downloaded from a peer.
+	pub active_ranges: u32,
 }
 
 /// Import state chunk result.
@@ -67,22 +136,92 @@ where
 	Client: ProofProvider<B> + Send + Sync + 'static,
 {
 	///  Create a new instance.
-	pub fn new(client: Arc<Client>, target: B::Header, skip_proof: bool) -> Self {
+	///
+	/// If a journal is given, it is restarted for this target and records the downloaded state.
+	pub fn new(
+		client: Arc<Client>,
+		target: B::Header,
+		skip_proof: bool,
+		journal: Option<SyncJournal<B>>,
+	) -> Self {
+		if let Some(journal) = &journal {
+			journal.start_state(&target, skip_proof);
+		}
+		let mut sync = Self::new_unrecorded(client, target, skip_proof);
+		sync.journal = journal;
+		sync
+	}
+
+	/// Resume the state sync recorded in `journal`, replaying the state downloaded so far.
+	///
+	/// `is_valid_target` is given the recorded target header and whether proofs were skipped, and
+	/// decides whether the recorded sync is still worth resuming. Returns `None` if there is
+	/// nothing to resume or the recorded state doesn't verify anymore.
+	pub fn resume(
+		client: Arc<Client>,
+		journal: SyncJournal<B>,
+		is_valid_target: impl FnOnce(&B::Header, bool) -> bool,
+	) -> Option<Self> {
+		let (target, skip_proof) = journal.state_target()?;
+		if !is_valid_target(&target, skip_proof) {
+			debug!(
+				target: "sync",
+				"Not resuming state sync of #{} ({}), target is not valid anymore",
+				target.number(),
+				target.hash(),
+			);
+			return None
+		}
+		let mut sync = Self::new_unrecorded(client, target, skip_proof);
+		// Only responses that didn't complete the state are recorded.
+		let replayed = journal.replay_state(|range, response| {
+			(range as usize) < sync.ranges.len() &&
+				matches!(sync.import_range(range as usize, response), ImportResult::Continue)
+		});
+		if !replayed {
+			debug!(target: "sync", "Discarding state sync journal that failed to replay");
+			return None
+		}
+		info!(
+			target: "sync",
+			"Resuming state sync of #{} ({} MiB already downloaded)",
+			sync.target_block_num(),
+			sync.imported_bytes / (1024 * 1024),
+		);
+		sync.journal = Some(journal);
+		Some(sync)
+	}
+
+	fn new_unrecorded(client: Arc<Client>, target: B::Header, skip_proof: bool) -> Self {
 		Self {
 			client,
 			target_block: target.hash(),
 			target_root: *target.state_root(),
 			target_header: target,
-			last_key: SmallVec::default(),
+			ranges: KeyRange::split_key_space(),
 			state: HashMap::default(),
 			complete: false,
 			imported_bytes: 0,
 			skip_proof,
+			journal: None,
 		}
 	}
 
-	///  Validate and import a state response.
-	pub fn import(&mut self, response: StateResponse) -> ImportResult<B> {
+	///  Validate and import a state response from `who`.
+	pub fn import(&mut self, who: &PeerId, response: StateResponse) -> ImportResult<B> {
+		let index = match self.ranges.iter().position(|range| range.peer.as_ref() == Some(who)) {
+			Some(index) => index,
+			None => {
+				debug!(target: "sync", "Unexpected state response from {}", who);
+				return ImportResult::BadResponse
+			},
+		};
+		self.ranges[index].peer = None;
+		self.import_range(index, response)
+	}
+
+	fn import_range(&mut self, index: usize, mut response: StateResponse) -> ImportResult<B> {
+		let recorded = self.journal.as_ref().map(|_| response.encode_to_vec());
 		if response.entries.is_empty() && response.proof.is_empty() {
 			debug!(target: "sync", "Bad state response");
 			return ImportResult::BadResponse
@@ -91,6 +230,8 @@ where
 			debug!(target: "sync", "Missing proof");
 			return ImportResult::BadResponse
 		}
+		let mut last_key = self.ranges[index].last_key.clone();
+		let end = self.ranges[index].end.clone();
 		let complete = if !self.skip_proof {
 			debug!(target: "sync", "Importing state from {} trie nodes", response.proof.len());
 			let proof_size = response.proof.len() as u64;
@@ -101,10 +242,10 @@ where
 					return ImportResult::BadResponse
 				},
 			};
-			let (values, completed) = match self.client.verify_range_proof(
+			let (mut values, completed) = match self.client.verify_range_proof(
 				self.target_root,
 				proof,
-				self.last_key.as_slice(),
+				last_key.as_slice(),
 			) {
 				Err(e) => {
 					debug!(
@@ -118,8 +259,18 @@ where
 			};
 			debug!(target: "sync", "Imported with {} keys", values.len());
 
-			let complete = completed == 0;
-			if !complete && !values.update_last_key(completed, &mut self.last_key) {
+			// The peer doesn't know where the range ends and may have sent keys past it.
+			let past_end = match (&end, values.0.first_mut()) {
+				(Some(end), Some(top)) if top.state_root.is_empty() =>
+					split_past_end(end, &mut top.key_values, key_value_pair),
+				_ => None,
+			};
+			if let Some(dropped_roots) = &past_end {
+				values.0.retain(|level| !dropped_roots.contains(&level.state_root));
+			}
+
+			let complete = completed == 0 || past_end.is_some();
+			if !complete && !values.update_last_key(completed, &mut last_key) {
 				debug!(target: "sync", "Error updating key cursor, depth: {}", completed);
 			};
 
@@ -148,7 +299,8 @@ where
 				let mut entry = self.state.entry(values.state_root).or_default();
 				if entry.0.len() > 0 && entry.1.len() > 1 {
 					// Already imported child_trie with same root.
-					// Warning this will not work with parallel download.
+					// Child tries are downloaded within a single range, so this holds with
+					// parallel download.
 				} else if entry.0.is_empty() {
 					for (key, _value) in key_values.iter() {
 						self.imported_bytes += key.len() as u64;
@@ -165,16 +317,26 @@ where
 			self.imported_bytes += proof_size;
 			complete
 		} else {
+			// The peer doesn't know where the range ends and may have sent keys past it.
+			let past_end = match (&end, response.entries.first_mut()) {
+				(Some(end), Some(top)) if top.state_root.is_empty() =>
+					split_past_end(end, &mut top.entries, state_entry),
+				_ => None,
+			};
+			if let Some(dropped_roots) = &past_end {
+				response.entries.retain(|state| !dropped_roots.contains(&state.state_root));
+			}
+
 			let mut complete = true;
 			// if the trie is a child trie and one of its parent trie is empty,
 			// the parent cursor stays valid.
 			// Empty parent trie content only happens when all the response content
 			// is part of a single child trie.
-			if self.last_key.len() == 2 && response.entries[0].entries.is_empty() {
+			if last_key.len() == 2 && response.entries[0].entries.is_empty() {
 				// Do not remove the parent trie position.
-				self.last_key.pop();
+				last_key.pop();
 			} else {
-				self.last_key.clear();
+				last_key.clear();
 			}
 			for state in response.entries {
 				debug!(
@@ -186,7 +348,7 @@ where
 
 				if !state.complete {
 					if let Some(e) = state.entries.last() {
-						self.last_key.push(e.key.clone());
+						last_key.push(e.key.clone());
 					}
 					complete = false;
 				}
@@ -210,9 +372,12 @@ where
 					}
 				}
 			}
-			complete
+			complete || past_end.is_some()
 		};
-		if complete {
+		let range = &mut self.ranges[index];
+		range.last_key = last_key;
+		range.complete = complete;
+		if self.ranges.iter().all(|range| range.complete) {
 			self.complete = true;
 			ImportResult::Import(
 				self.target_block,
@@ -223,16 +388,35 @@ where
 				},
 			)
 		} else {
+			if let (Some(journal), Some(recorded)) = (&self.journal, recorded) {
+				journal.append_state(index as u32, &recorded);
+			}
 			ImportResult::Continue
 		}
 	}
 
-	/// Produce next state request.
-	pub fn next_request(&self) -> StateRequest {
-		StateRequest {
+	/// Produce the next state request to send to `who`, if a range is left to download.
+	///
+	/// The requested range is reserved for `who` until its response is imported or the peer
+	/// disconnects.
+	pub fn next_request(&mut self, who: PeerId) -> Option<StateRequest> {
+		if self.ranges.iter().any(|range| range.peer == Some(who)) {
+			return None
+		}
+		let range = self.ranges.iter_mut().find(|range| !range.complete && range.peer.is_none())?;
+		range.peer = Some(who);
+		Some(StateRequest {
 			block: self.target_block.encode(),
-			start: self.last_key.clone().into_vec(),
+			start: range.last_key.clone().into_vec(),
 			no_proof: self.skip_proof,
+			accept_compression: Compression::Zstd as i32,
+		})
+	}
+
+	/// Release the range requested from a peer, so that it is requested from another one.
+	pub fn peer_disconnected(&mut self, who: &PeerId) {
+		for range in self.ranges.iter_mut().filter(|range| range.peer.as_ref() == Some(who)) {
+			range.peer = None;
 		}
 	}
 
@@ -253,8 +437,153 @@ where
 
 	/// Returns state sync estimated progress.
 	pub fn progress(&self) -> StateDownloadProgress {
-		let cursor = *self.last_key.get(0).and_then(|last| last.get(0)).unwrap_or(&0u8);
-		let percent_done = cursor as u32 * 100 / 256;
-		StateDownloadProgress { percentage: percent_done, size: self.imported_bytes }
+		let downloaded: usize = self
+			.ranges
+			.iter()
+			.enumerate()
+			.map(|(index, range)| range.downloaded_span(index))
+			.sum();
+		let count = |f: fn(&KeyRange) -> bool| self.ranges.iter().filter(|r| f(r)).count() as u32;
+		StateDownloadProgress {
+			percentage: (downloaded * 100 / 256) as u32,
+			size: self.imported_bytes,
+			ranges: self.ranges.len() as u32,
+			completed_ranges: count(|range| range.complete),
+			active_ranges: count(|range| range.peer.is_some()),
+		}
+	}
+}
+
+/// Split off the top trie entries of a response that are past the end of the requested range.
+///
+/// Returns `None` if all entries are within the range. Otherwise returns the roots of the child
+/// tries that are only referenced by the entries split off, which must be dropped as well.
+fn split_past_end<T>(
+	end: &[u8],
+	top: &mut Vec<T>,
+	key_value: fn(&T) -> (&[u8], &[u8]),
+) -> Option<HashSet<Vec<u8>>> {
+	let split = top.iter().position(|entry| key_value(entry).0 > end)?;
+	let dropped = top.split_off(split);
+	let child_root = |entry: &T| {
+		let (key, value) = key_value(entry);
+		well_known_keys::is_child_storage_key(key).then(|| value.to_vec())
+	};
+	let kept: HashSet<_> = top.iter().filter_map(child_root).collect();
+	Some(
+		dropped
+			.iter()
+			.filter_map(child_root)
+			.filter(|root| !kept.contains(root))
+			.collect(),
+	)
+}
+
+fn key_value_pair(entry: &(Vec<u8>, Vec<u8>)) -> (&[u8], &[u8]) {
+	(&entry.0, &entry.1)
+}
+
+fn state_entry(entry: &StateEntry) -> (&[u8], &[u8]) {
+	(&entry.key, &entry.value)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::schema::v1::KeyValueStateEntry;
+	use sp_blockchain::HeaderBackend;
+	use sp_runtime::generic::BlockId;
+	use substrate_test_runtime_client::{
+		runtime::Block, DefaultTestClientBuilderExt, TestClient, TestClientBuilder,
+		TestClientBuilderExt,
+	};
+
+	fn state_sync() -> StateSync<Block, TestClient> {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let genesis = client.header(&BlockId::Number(0)).unwrap().unwrap();
+		StateSync::new(client, genesis, true, None)
+	}
+
+	fn response(keys: &[&[u8]], complete: bool) -> StateResponse {
+		StateResponse {
+			entries: vec![KeyValueStateEntry {
+				state_root: Vec::new(),
+				entries: keys
+					.iter()
+					.map(|key| StateEntry { key: key.to_vec(), value: vec![1] })
+					.collect(),
+				complete,
+			}],
+			proof: Vec::new(),
+			compressed: Vec::new(),
+		}
+	}
+
+	#[test]
+	fn ranges_are_requested_from_different_peers() {
+		let mut sync = state_sync();
+		let peers: Vec<_> = (0..=STATE_RANGES).map(|_| PeerId::random()).collect();
+		let starts: Vec<_> = peers
+			.iter()
+			.filter_map(|peer| sync.next_request(*peer))
+			.map(|r| r.start)
+			.collect();
+		assert_eq!(starts.len(), STATE_RANGES);
+		assert!(starts[0].is_empty());
+		assert_eq!(starts[1], vec![vec![0x10]]);
+		assert_eq!(sync.progress().active_ranges, STATE_RANGES as u32);
+
+		// A peer downloads a single range at a time.
+		assert!(sync.next_request(peers[0]).is_none());
+
+		// The range of a disconnected peer is requested from another one.
+		sync.peer_disconnected(&peers[1]);
+		assert_eq!(sync.next_request(peers[STATE_RANGES]).unwrap().start, vec![vec![0x10]]);
+	}
+
+	#[test]
+	fn keys_past_the_range_end_are_dropped() {
+		let mut sync = state_sync();
+		let peer = PeerId::random();
+		assert!(matches!(
+			sync.import(&peer, response(&[&[0x01]], false)),
+			ImportResult::BadResponse
+		));
+
+		sync.next_request(peer).unwrap();
+		let result = sync.import(&peer, response(&[&[0x01], &[0x05]], false));
+		assert!(matches!(result, ImportResult::Continue));
+		assert_eq!(sync.next_request(peer).unwrap().start, vec![vec![0x05]]);
+
+		let result = sync.import(&peer, response(&[&[0x10], &[0x10, 0x01], &[0x11]], false));
+		assert!(matches!(result, ImportResult::Continue));
+		let progress = sync.progress();
+		assert_eq!(progress.completed_ranges, 1);
+		assert_eq!(progress.percentage, 6);
+		let top: Vec<_> = sync.state[&Vec::new()].0.iter().map(|(key, _)| key.clone()).collect();
+		assert_eq!(top, vec![vec![0x01], vec![0x05], vec![0x10]]);
+
+		// The next range starts after the end of the first one.
+		assert_eq!(sync.next_request(peer).unwrap().start, vec![vec![0x10]]);
+	}
+
+	#[test]
+	fn state_is_imported_once_all_ranges_are_complete() {
+		let mut sync = state_sync();
+		let peer = PeerId::random();
+		for range in 0..STATE_RANGES {
+			assert!(sync.next_request(peer).is_some());
+			let key = [(range * RANGE_SPAN) as u8 + 1];
+			match sync.import(&peer, response(&[&key], true)) {
+				ImportResult::Continue => assert!(range + 1 < STATE_RANGES),
+				ImportResult::Import(_, _, state) => {
+					assert_eq!(range + 1, STATE_RANGES);
+					assert_eq!(state.state.len(), STATE_RANGES);
+				},
+				ImportResult::BadResponse => panic!("Unexpected bad response"),
+			}
+		}
+		assert!(sync.is_complete());
+		assert_eq!(sync.progress().percentage, 100);
 	}
 }
@@ -17,12 +17,12 @@
 //! Helper for handling (i.e. answering) state requests from a remote peer via the
 //! `crate::request_responses::RequestResponsesBehaviour`.
 
-use crate::schema::v1::{KeyValueStateEntry, StateEntry, StateRequest, StateResponse};
-use codec::{Decode, Encode};
-use futures::{
-	channel::{mpsc, oneshot},
-	stream::StreamExt,
+use crate::{
+	compression,
+	schema::v1::{KeyValueStateEntry, StateEntry, StateRequest, StateResponse},
 };
+use codec::{Decode, Encode};
+use futures::channel::{mpsc, oneshot};
 use libp2p::PeerId;
 use log::{debug, trace};
 use lru::LruCache;
@@ -30,6 +30,7 @@ use prost::Message;
 use sc_client_api::ProofProvider;
 use sc_network_common::{
 	config::ProtocolId,
+	request_quota::{InboundRequests, QuotaConfig},
 	request_responses::{IncomingRequest, OutgoingResponse, ProtocolConfig},
 };
 use sp_runtime::{generic::BlockId, traits::Block as BlockT};
@@ -94,7 +95,7 @@ enum SeenRequestsValue {
 /// Handler for incoming block requests from a remote peer.
 pub struct StateRequestHandler<B: BlockT, Client> {
 	client: Arc<Client>,
-	request_receiver: mpsc::Receiver<IncomingRequest>,
+	request_receiver: InboundRequests,
 	/// Maps from request to number of times we have seen this request.
 	///
 	/// This is used to check if a peer is spamming us with the same request.
@@ -111,6 +112,7 @@ where
 		protocol_id: &ProtocolId,
 		client: Arc<Client>,
 		num_peer_hint: usize,
+		quota: QuotaConfig,
 	) -> (Self, ProtocolConfig) {
 		// Reserve enough request slots for one request per peer when we are at the maximum
 		// number of peers.
@@ -120,6 +122,7 @@ where
 		protocol_config.inbound_queue = Some(tx);
 
 		let seen_requests = LruCache::new(num_peer_hint * 2);
+		let request_receiver = InboundRequests::new(request_receiver, num_peer_hint, quota);
 
 		(Self { client, request_receiver, seen_requests }, protocol_config)
 	}
@@ -227,6 +230,7 @@ where
 				}
 			}
 
+			let response = compression::compress(response, request.accept_compression);
 			let mut data = Vec::with_capacity(response.encoded_len());
 			response.encode(&mut data)?;
 			Ok(data)
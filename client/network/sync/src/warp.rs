@@ -22,15 +22,21 @@ pub use crate::warp_request_handler::{
 	EncodedProof, Request as WarpProofRequest, VerificationResult, WarpSyncProvider,
 };
 use crate::{
+	journal::SyncJournal,
 	schema::v1::{StateRequest, StateResponse},
-	state::{ImportResult, StateSync},
+	state::{ImportResult, StateDownloadProgress, StateSync},
 };
+use libp2p::PeerId;
 use sc_client_api::ProofProvider;
 use sp_blockchain::HeaderBackend;
 use sp_finality_grandpa::{AuthorityList, SetId};
-use sp_runtime::traits::{Block as BlockT, NumberFor, Zero};
+use sp_runtime::traits::{Block as BlockT, Header, NumberFor, Saturating, Zero};
 use std::{fmt, sync::Arc};
 
+/// Maximum number of blocks the target of a recorded state download may be behind the best
+/// block seen for the download to be resumed. Older state is likely pruned by peers.
+pub(crate) const MAX_RESUMED_STATE_AGE: u32 = 256;
+
 enum Phase<B: BlockT, Client> {
 	WarpProof { set_id: SetId, authorities: AuthorityList, last_hash: B::Hash },
 	State(StateSync<B, Client>),
@@ -86,6 +92,7 @@ pub struct WarpSync<B: BlockT, Client> {
 	client: Arc<Client>,
 	warp_sync_provider: Arc<dyn WarpSyncProvider<B>>,
 	total_proof_bytes: u64,
+	journal: Option<SyncJournal<B>>,
 }
 
 impl<B, Client> WarpSync<B, Client>
@@ -94,24 +101,64 @@ where
 	Client: HeaderBackend<B> + ProofProvider<B> + 'static,
 {
 	///  Create a new instance.
-	pub fn new(client: Arc<Client>, warp_sync_provider: Arc<dyn WarpSyncProvider<B>>) -> Self {
-		let last_hash = client.hash(Zero::zero()).unwrap().expect("Genesis header always exists");
-		let phase = Phase::WarpProof {
-			set_id: 0,
-			authorities: warp_sync_provider.current_authorities(),
+	///
+	/// If a journal is given, progress is resumed from it when possible and recorded to it.
+	/// `best_seen` is the best block number reported by peers: a state download is only resumed
+	/// if its target is recent enough for peers to still have the state.
+	pub fn new(
+		client: Arc<Client>,
+		warp_sync_provider: Arc<dyn WarpSyncProvider<B>>,
+		journal: Option<SyncJournal<B>>,
+		best_seen: NumberFor<B>,
+	) -> Self {
+		let phase = journal
+			.as_ref()
+			.and_then(|journal| Self::resume(&client, journal, best_seen))
+			.unwrap_or_else(|| {
+				let last_hash =
+					client.hash(Zero::zero()).unwrap().expect("Genesis header always exists");
+				Phase::WarpProof {
+					set_id: 0,
+					authorities: warp_sync_provider.current_authorities(),
+					last_hash,
+				}
+			});
+		Self { client, warp_sync_provider, phase, total_proof_bytes: 0, journal }
+	}
+
+	fn resume(
+		client: &Arc<Client>,
+		journal: &SyncJournal<B>,
+		best_seen: NumberFor<B>,
+	) -> Option<Phase<B, Client>> {
+		let finalized = client.info().finalized_number;
+		let state_sync =
+			StateSync::resume(client.clone(), journal.clone(), |target, skip_proof| {
+				!skip_proof &&
+					*target.number() > finalized &&
+					target.number().saturating_add(MAX_RESUMED_STATE_AGE.into()) >= best_seen
+			});
+		if let Some(state_sync) = state_sync {
+			return Some(Phase::State(state_sync))
+		}
+		let (set_id, authorities, last_hash) = journal.warp_progress()?;
+		log::info!(
+			target: "sync",
+			"Resuming warp sync from authority set {} (block {})",
+			set_id,
 			last_hash,
-		};
-		Self { client, warp_sync_provider, phase, total_proof_bytes: 0 }
+		);
+		Some(Phase::WarpProof { set_id, authorities, last_hash })
 	}
 
-	///  Validate and import a state response.
-	pub fn import_state(&mut self, response: StateResponse) -> ImportResult<B> {
+	///  Validate and import a state response from `who`.
+	pub fn import_state(&mut self, who: &PeerId, response: StateResponse) -> ImportResult<B> {
 		match &mut self.phase {
 			Phase::WarpProof { .. } => {
 				log::debug!(target: "sync", "Unexpected state response");
 				ImportResult::BadResponse
 			},
-			Phase::State(sync) => sync.import(response),
+			Phase::State(sync) => sync.import(who, response),
 		}
 	}
 
@@ -134,12 +181,20 @@ where
 						*authorities = new_authorities;
 						*last_hash = new_last_hash;
 						self.total_proof_bytes += response.0.len() as u64;
+						if let Some(journal) = &self.journal {
+							journal.store_warp_progress(*set_id, authorities, *last_hash);
+						}
 						WarpProofImportResult::Success
 					},
 					Ok(VerificationResult::Complete(new_set_id, _, header)) => {
 						log::debug!(target: "sync", "Verified complete proof, set_id={:?}", new_set_id);
 						self.total_proof_bytes += response.0.len() as u64;
-						let state_sync = StateSync::new(self.client.clone(), header, false);
+						let state_sync = StateSync::new(
+							self.client.clone(),
+							header,
+							false,
+							self.journal.clone(),
+						);
 						self.phase = Phase::State(state_sync);
 						WarpProofImportResult::Success
 					},
@@ -148,11 +203,26 @@ where
 		}
 	}
 
-	/// Produce next state request.
-	pub fn next_state_request(&self) -> Option<StateRequest> {
+	/// Produce the next state request to send to `who`, if any.
+	pub fn next_state_request(&mut self, who: PeerId) -> Option<StateRequest> {
+		match &mut self.phase {
+			Phase::WarpProof { .. } => None,
+			Phase::State(sync) => sync.next_request(who),
+		}
+	}
+
+	/// Release the state range requested from a disconnected peer.
+	pub fn peer_disconnected(&mut self, who: &PeerId) {
+		if let Phase::State(sync) = &mut self.phase {
+			sync.peer_disconnected(who);
+		}
+	}
+
+	/// Returns the state download progress, once the state is being downloaded.
+	pub fn state_progress(&self) -> Option<StateDownloadProgress> {
 		match &self.phase {
 			Phase::WarpProof { .. } => None,
-			Phase::State(sync) => Some(sync.next_request()),
+			Phase::State(sync) => Some(sync.progress()),
 		}
 	}
 
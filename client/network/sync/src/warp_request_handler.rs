@@ -17,13 +17,11 @@
 //! Helper for handling (i.e. answering) grandpa warp sync requests from a remote peer.
 
 use codec::{Decode, Encode};
-use futures::{
-	channel::{mpsc, oneshot},
-	stream::StreamExt,
-};
+use futures::channel::{mpsc, oneshot};
 use log::debug;
 use sc_network_common::{
 	config::ProtocolId,
+	request_quota::{InboundRequests, QuotaConfig},
 	request_responses::{
 		IncomingRequest, OutgoingResponse, ProtocolConfig as RequestResponseConfig,
 	},
@@ -45,6 +43,9 @@ pub struct Request<B: BlockT> {
 
 const MAX_RESPONSE_SIZE: u64 = 16 * 1024 * 1024;
 
+/// Maximum number of inbound requests waiting to be handled.
+const MAX_QUEUED_REQUESTS: usize = 20;
+
 /// Proof verification result.
 pub enum VerificationResult<Block: BlockT> {
 	/// Proof is valid, but the target was not reached.
@@ -93,7 +94,7 @@ fn generate_protocol_name(protocol_id: ProtocolId) -> String {
 /// Handler for incoming grandpa warp sync requests from a remote peer.
 pub struct RequestHandler<TBlock: BlockT> {
 	backend: Arc<dyn WarpSyncProvider<TBlock>>,
-	request_receiver: mpsc::Receiver<IncomingRequest>,
+	request_receiver: InboundRequests,
 }
 
 impl<TBlock: BlockT> RequestHandler<TBlock> {
@@ -101,12 +102,15 @@ impl<TBlock: BlockT> RequestHandler<TBlock> {
 	pub fn new(
 		protocol_id: ProtocolId,
 		backend: Arc<dyn WarpSyncProvider<TBlock>>,
+		quota: QuotaConfig,
 	) -> (Self, RequestResponseConfig) {
-		let (tx, request_receiver) = mpsc::channel(20);
+		let (tx, request_receiver) = mpsc::channel(MAX_QUEUED_REQUESTS);
 
 		let mut request_response_config = generate_request_response_config(protocol_id);
 		request_response_config.inbound_queue = Some(tx);
 
+		let request_receiver = InboundRequests::new(request_receiver, MAX_QUEUED_REQUESTS, quota);
+
 		(Self { backend, request_receiver }, request_response_config)
 	}
 
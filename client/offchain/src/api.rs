@@ -21,7 +21,9 @@ use std::{collections::HashSet, str::FromStr, sync::Arc, thread::sleep};
 use crate::NetworkProvider;
 use codec::{Decode, Encode};
 use futures::Future;
-pub use http::SharedClient;
+pub use http::{HttpBackend, HttpBackendError, HttpContext, HttpPolicy, SharedClient};
+#[cfg(any(test, feature = "test-helpers"))]
+pub use http_mock::{MockHttpBackend, MockHttpRequest};
 use sc_network::{Multiaddr, PeerId};
 use sp_core::{
 	offchain::{
@@ -33,6 +35,8 @@ use sp_core::{
 pub use sp_offchain::STORAGE_PREFIX;
 
 mod http;
+#[cfg(any(test, feature = "test-helpers"))]
+mod http_mock;
 
 mod timestamp;
 
@@ -151,6 +155,23 @@ pub(crate) struct Api {
 	is_validator: bool,
 	/// Everything HTTP-related is handled by a different struct.
 	http: http::HttpApi,
+	/// Time at which this offchain worker run must end, if any.
+	deadline: Option<Timestamp>,
+}
+
+impl Api {
+	/// The earlier of `deadline` and the deadline of this run.
+	fn run_deadline(&self, deadline: Option<Timestamp>) -> Option<Timestamp> {
+		match (deadline, self.deadline) {
+			(Some(deadline), Some(run_deadline)) => Some(deadline.min(run_deadline)),
+			(deadline, run_deadline) => deadline.or(run_deadline),
+		}
+	}
+
+	/// Returns `true` if the deadline of this run has passed.
+	fn is_expired(&self) -> bool {
+		self.deadline.map_or(false, |deadline| deadline <= timestamp::now())
+	}
 }
 
 impl offchain::Externalities for Api {
@@ -170,6 +191,7 @@ impl offchain::Externalities for Api {
 	}
 
 	fn sleep_until(&mut self, deadline: Timestamp) {
+		let deadline = self.run_deadline(Some(deadline)).unwrap_or(deadline);
 		sleep(timestamp::timestamp_from_now(deadline));
 	}
 
@@ -183,6 +205,9 @@ impl offchain::Externalities for Api {
 		uri: &str,
 		_meta: &[u8],
 	) -> Result<HttpRequestId, ()> {
+		if self.is_expired() {
+			return Err(())
+		}
 		self.http.request_start(method, uri)
 	}
 
@@ -201,6 +226,7 @@ impl offchain::Externalities for Api {
 		chunk: &[u8],
 		deadline: Option<Timestamp>,
 	) -> Result<(), HttpError> {
+		let deadline = self.run_deadline(deadline);
 		self.http.request_write_body(request_id, chunk, deadline)
 	}
 
@@ -209,6 +235,7 @@ impl offchain::Externalities for Api {
 		ids: &[HttpRequestId],
 		deadline: Option<Timestamp>,
 	) -> Vec<HttpRequestStatus> {
+		let deadline = self.run_deadline(deadline);
 		self.http.response_wait(ids, deadline)
 	}
 
@@ -222,6 +249,7 @@ impl offchain::Externalities for Api {
 		buffer: &mut [u8],
 		deadline: Option<Timestamp>,
 	) -> Result<usize, HttpError> {
+		let deadline = self.run_deadline(deadline);
 		self.http.response_read_body(request_id, buffer, deadline)
 	}
 
@@ -301,14 +329,21 @@ pub(crate) struct AsyncApi {
 
 impl AsyncApi {
 	/// Creates new Offchain extensions API implementation an the asynchronous processing part.
+	///
+	/// Once `max_run_time` has passed, all blocking calls of the API return immediately and no
+	/// new HTTP requests can be started.
 	pub fn new(
 		network_provider: Arc<dyn NetworkProvider + Send + Sync>,
 		is_validator: bool,
-		shared_http_client: SharedClient,
+		http_context: HttpContext,
+		max_run_time: Option<std::time::Duration>,
 	) -> (Api, Self) {
-		let (http_api, http_worker) = http::http(shared_http_client);
+		let (http_api, http_worker) = http::http(http_context);
+		let deadline = max_run_time.map(|max_run_time| {
+			timestamp::now().add(offchain::Duration::from_millis(max_run_time.as_millis() as u64))
+		});
 
-		let api = Api { network_provider, is_validator, http: http_api };
+		let api = Api { network_provider, is_validator, http: http_api, deadline };
 
 		let async_api = Self { http: Some(http_worker) };
 
@@ -339,6 +374,10 @@ mod tests {
 		fn set_authorized_only(&self, _reserved_only: bool) {
 			unimplemented!()
 		}
+
+		fn is_major_syncing(&self) -> bool {
+			false
+		}
 	}
 
 	impl NetworkStateInfo for TestNetwork {
@@ -354,9 +393,9 @@ mod tests {
 	fn offchain_api() -> (Api, AsyncApi) {
 		sp_tracing::try_init_simple();
 		let mock = Arc::new(TestNetwork());
-		let shared_client = SharedClient::new();
+		let context = HttpContext::new(Arc::new(SharedClient::new()), Default::default());
 
-		AsyncApi::new(mock, false, shared_client)
+		AsyncApi::new(mock, false, context, None)
 	}
 
 	fn offchain_db() -> Db<LocalStorage> {
@@ -384,6 +423,21 @@ mod tests {
 		assert!(timestamp.unix_millis() >= d);
 	}
 
+	#[test]
+	fn should_stop_blocking_once_run_time_is_over() {
+		let mock = Arc::new(TestNetwork());
+		let context = HttpContext::new(Arc::new(SharedClient::new()), Default::default());
+		let mut api =
+			AsyncApi::new(mock, false, context, Some(std::time::Duration::from_millis(100))).0;
+
+		let now = api.timestamp();
+		api.sleep_until(now.add(sp_core::offchain::Duration::from_millis(60_000)));
+		let new_now = api.timestamp();
+
+		assert!(new_now.unix_millis() - now.unix_millis() < 60_000);
+		assert!(api.http_request_start("GET", "http://localhost", &[]).is_err());
+	}
+
 	#[test]
 	fn should_sleep() {
 		let mut api = offchain_api().0;
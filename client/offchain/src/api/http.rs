@@ -26,11 +26,14 @@
 //! The reason for this design is driven by the fact that HTTP requests should continue running
 //! (i.e.: the socket should continue being processed) in the background even if the runtime isn't
 //! actively calling any function.
+//!
+//! Requests are performed by an [`HttpBackend`], which is the shared hyper client by default, and
+//! are subject to the node's [`HttpPolicy`].
 
 use crate::api::timestamp;
 use bytes::buf::{Buf, Reader};
 use fnv::FnvHashMap;
-use futures::{channel::mpsc, future, prelude::*};
+use futures::{channel::mpsc, future, future::BoxFuture, prelude::*};
 use hyper::{client, Body, Client as HyperClient};
 use hyper_rustls::HttpsConnector;
 use once_cell::sync::Lazy;
@@ -40,7 +43,10 @@ use std::{
 	fmt,
 	io::Read as _,
 	pin::Pin,
-	sync::Arc,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
 	task::{Context, Poll},
 };
 
@@ -58,8 +64,106 @@ impl SharedClient {
 	}
 }
 
+/// Error reported by an [`HttpBackend`].
+pub type HttpBackendError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Something that performs the HTTP requests started by offchain workers.
+pub trait HttpBackend: Send + Sync {
+	/// Send `request` and resolve once the head of the response has been received.
+	///
+	/// The body of `request` is streamed while it is being written by the offchain worker.
+	fn request(
+		&self,
+		request: hyper::Request<Body>,
+	) -> BoxFuture<'static, Result<hyper::Response<Body>, HttpBackendError>>;
+}
+
+impl std::fmt::Debug for dyn HttpBackend {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "HttpBackend")
+	}
+}
+
+impl HttpBackend for SharedClient {
+	fn request(
+		&self,
+		request: hyper::Request<Body>,
+	) -> BoxFuture<'static, Result<hyper::Response<Body>, HttpBackendError>> {
+		self.0
+			.request(request)
+			.map_err(|error| Box::new(error) as HttpBackendError)
+			.boxed()
+	}
+}
+
+/// Restrictions on the HTTP requests of offchain workers.
+///
+/// Hosts and concurrency are checked when a request is started, body sizes while the bodies are
+/// transferred.
+#[derive(Debug, Clone, Default)]
+pub struct HttpPolicy {
+	/// Hosts that requests may be sent to, or `None` to allow any host.
+	pub allowed_hosts: Option<Vec<String>>,
+	/// Maximum size in bytes of each request and response body.
+	pub max_body_size: Option<usize>,
+	/// Maximum number of requests in progress at the same time, across all offchain workers.
+	pub max_concurrent_requests: Option<usize>,
+}
+
+impl HttpPolicy {
+	/// Returns `true` if requests may be sent to `uri`.
+	fn allows(&self, uri: &hyper::Uri) -> bool {
+		match (&self.allowed_hosts, uri.host()) {
+			(None, _) => true,
+			(Some(hosts), Some(host)) =>
+				hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host)),
+			(Some(_), None) => false,
+		}
+	}
+}
+
+/// State shared by the HTTP requests of all offchain workers.
+#[derive(Clone)]
+pub struct HttpContext {
+	backend: Arc<dyn HttpBackend>,
+	policy: Arc<HttpPolicy>,
+	in_flight: Arc<AtomicUsize>,
+}
+
+impl HttpContext {
+	/// Creates a new context sending requests through `backend` under the given `policy`.
+	pub fn new(backend: Arc<dyn HttpBackend>, policy: HttpPolicy) -> Self {
+		Self { backend, policy: Arc::new(policy), in_flight: Default::default() }
+	}
+
+	/// Count a new request against `max_concurrent_requests`, if allowed.
+	fn acquire_slot(&self) -> Option<RequestSlot> {
+		let max = self.policy.max_concurrent_requests.unwrap_or(usize::MAX);
+		self.in_flight
+			.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < max).then(|| n + 1))
+			.ok()
+			.map(|_| RequestSlot(self.in_flight.clone()))
+	}
+}
+
+/// A request counted against `max_concurrent_requests` until dropped.
+struct RequestSlot(Arc<AtomicUsize>);
+
+impl Drop for RequestSlot {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::SeqCst);
+	}
+}
+
+/// Policy bookkeeping of one request within `HttpApi`.
+struct TrackedRequest {
+	_slot: RequestSlot,
+	/// Number of request body bytes written so far.
+	body_written: usize,
+}
+
 /// Creates a pair of [`HttpApi`] and [`HttpWorker`].
-pub fn http(shared_client: SharedClient) -> (HttpApi, HttpWorker) {
+pub fn http(context: HttpContext) -> (HttpApi, HttpWorker) {
 	let (to_worker, from_api) = tracing_unbounded("mpsc_ocw_to_worker");
 	let (to_api, from_worker) = tracing_unbounded("mpsc_ocw_to_api");
 
@@ -70,10 +174,17 @@ pub fn http(shared_client: SharedClient) -> (HttpApi, HttpWorker) {
 		// writing runtime code with hardcoded IDs.
 		next_id: HttpRequestId(rand::random::<u16>() % 2000),
 		requests: FnvHashMap::default(),
+		tracked: FnvHashMap::default(),
+		context: context.clone(),
 	};
 
-	let engine =
-		HttpWorker { to_api, from_api, http_client: shared_client.0, requests: Vec::new() };
+	let engine = HttpWorker {
+		to_api,
+		from_api,
+		backend: context.backend,
+		max_body_size: context.policy.max_body_size,
+		requests: Vec::new(),
+	};
 
 	(api, engine)
 }
@@ -92,6 +203,11 @@ pub struct HttpApi {
 	next_id: HttpRequestId,
 	/// List of HTTP requests in preparation or in progress.
 	requests: FnvHashMap<HttpRequestId, HttpApiRequest>,
+	/// Policy bookkeeping of the requests started by this API. Entries of requests that are no
+	/// longer in `requests` are released lazily when starting a new request.
+	tracked: FnvHashMap<HttpRequestId, TrackedRequest>,
+	/// Backend and policy shared with the other offchain workers.
+	context: HttpContext,
 }
 
 /// One active request within `HttpApi`.
@@ -106,7 +222,7 @@ enum HttpApiRequest {
 	/// A request has been dispatched but the worker notified us of an error. We report this
 	/// failure to the user as an `IoError` and remove the request from the list as soon as
 	/// possible.
-	Fail(hyper::Error),
+	Fail(HttpBackendError),
 }
 
 /// A request within `HttpApi` that has received a response.
@@ -124,7 +240,7 @@ struct HttpApiRequestRp {
 	/// Elements extracted from the channel are first put into `current_read_chunk`.
 	/// If the channel produces an error, then that is translated into an `IoError` and the request
 	/// is removed from the list.
-	body: stream::Fuse<mpsc::Receiver<Result<hyper::body::Bytes, hyper::Error>>>,
+	body: stream::Fuse<mpsc::Receiver<Result<hyper::body::Bytes, HttpBackendError>>>,
 	/// Chunk that has been extracted from the channel and that is currently being read.
 	/// Reading data from the response should read from this field in priority.
 	current_read_chunk: Option<Reader<hyper::body::Bytes>>,
@@ -141,6 +257,21 @@ impl HttpApi {
 		*request.method_mut() = hyper::Method::from_bytes(method.as_bytes()).map_err(|_| ())?;
 		*request.uri_mut() = hyper::Uri::from_maybe_shared(uri.to_owned()).map_err(|_| ())?;
 
+		if !self.context.policy.allows(request.uri()) {
+			tracing::debug!(target: LOG_TARGET, %uri, "Request to host denied by policy");
+			return Err(())
+		}
+
+		let requests = &self.requests;
+		self.tracked.retain(|id, _| requests.contains_key(id));
+		let slot = match self.context.acquire_slot() {
+			Some(slot) => slot,
+			None => {
+				tracing::debug!(target: LOG_TARGET, %uri, "Too many concurrent requests");
+				return Err(())
+			},
+		};
+
 		let new_id = self.next_id;
 		debug_assert!(!self.requests.contains_key(&new_id));
 		match self.next_id.0.checked_add(1) {
@@ -155,6 +286,7 @@ impl HttpApi {
 		};
 		self.requests
 			.insert(new_id, HttpApiRequest::NotDispatched(request, body_sender));
+		self.tracked.insert(new_id, TrackedRequest { _slot: slot, body_written: 0 });
 
 		tracing::error!(
 			target: LOG_TARGET,
@@ -201,6 +333,17 @@ impl HttpApi {
 		// Don't forget to add it back if necessary when returning.
 		let mut request = self.requests.remove(&request_id).ok_or(HttpError::Invalid)?;
 
+		if let (Some(max), Some(tracked)) =
+			(self.context.policy.max_body_size, self.tracked.get_mut(&request_id))
+		{
+			tracked.body_written = tracked.body_written.saturating_add(chunk.len());
+			if tracked.body_written > max {
+				tracing::debug!(target: LOG_TARGET, id = %request_id.0, "Request body too large");
+				// The request isn't put back, which fails it.
+				return Err(HttpError::IoError)
+			}
+		}
+
 		let mut deadline = timestamp::deadline_to_future(deadline);
 		// Closure that writes data to a sender, taking the deadline into account. Can return `Ok`
 		// (if the body has been written), or `DeadlineReached`, or `IoError`.
@@ -600,14 +743,14 @@ enum WorkerToApi {
 		/// the next item.
 		/// Can also be used to send an error, in case an error happend on the HTTP socket. After
 		/// an error is sent, the channel will close.
-		body: mpsc::Receiver<Result<hyper::body::Bytes, hyper::Error>>,
+		body: mpsc::Receiver<Result<hyper::body::Bytes, HttpBackendError>>,
 	},
 	/// A request has failed because of an error. The request is then no longer valid.
 	Fail {
 		/// The ID that was passed to the worker.
 		id: HttpRequestId,
 		/// Error that happened.
-		error: hyper::Error,
+		error: HttpBackendError,
 	},
 }
 
@@ -618,7 +761,9 @@ pub struct HttpWorker {
 	/// Used to receive messages from the `HttpApi`.
 	from_api: TracingUnboundedReceiver<ApiToWorker>,
 	/// The engine that runs HTTP requests.
-	http_client: Arc<Lazy<HyperClient<HttpsConnector<client::HttpConnector>, Body>>>,
+	backend: Arc<dyn HttpBackend>,
+	/// Maximum size of a response body, as per the [`HttpPolicy`].
+	max_body_size: Option<usize>,
 	/// HTTP requests that are being worked on by the engine.
 	requests: Vec<(HttpRequestId, HttpWorkerRequest)>,
 }
@@ -626,13 +771,15 @@ pub struct HttpWorker {
 /// HTTP request being processed by the worker.
 enum HttpWorkerRequest {
 	/// Request has been dispatched and is waiting for a response from the Internet.
-	Dispatched(hyper::client::ResponseFuture),
+	Dispatched(BoxFuture<'static, Result<hyper::Response<Body>, HttpBackendError>>),
 	/// Progressively reading the body of the response and sending it to the channel.
 	ReadBody {
 		/// Body to read `Chunk`s from. Only used if the channel is ready to accept data.
 		body: hyper::Body,
 		/// Channel to the [`HttpApi`] where we send the chunks to.
-		tx: mpsc::Sender<Result<hyper::body::Bytes, hyper::Error>>,
+		tx: mpsc::Sender<Result<hyper::body::Bytes, HttpBackendError>>,
+		/// Number of body bytes read so far.
+		read: usize,
 	},
 }
 
@@ -676,19 +823,20 @@ impl Future for HttpWorker {
 						body: body_rx,
 					});
 
-					me.requests.push((id, HttpWorkerRequest::ReadBody { body, tx: body_tx }));
+					me.requests
+						.push((id, HttpWorkerRequest::ReadBody { body, tx: body_tx, read: 0 }));
 					cx.waker().wake_by_ref(); // reschedule in order to poll the new future
 					continue
 				},
 
-				HttpWorkerRequest::ReadBody { mut body, mut tx } => {
+				HttpWorkerRequest::ReadBody { mut body, mut tx, mut read } => {
 					// Before reading from the HTTP response, check that `tx` is ready to accept
 					// a new chunk.
 					match tx.poll_ready(cx) {
 						Poll::Ready(Ok(())) => {},
 						Poll::Ready(Err(_)) => continue, // don't insert the request back
 						Poll::Pending => {
-							me.requests.push((id, HttpWorkerRequest::ReadBody { body, tx }));
+							me.requests.push((id, HttpWorkerRequest::ReadBody { body, tx, read }));
 							continue
 						},
 					}
@@ -696,17 +844,23 @@ impl Future for HttpWorker {
 					// `tx` is ready. Read a chunk from the socket and send it to the channel.
 					match Stream::poll_next(Pin::new(&mut body), cx) {
 						Poll::Ready(Some(Ok(chunk))) => {
+							read = read.saturating_add(chunk.len());
+							if me.max_body_size.map_or(false, |max| read > max) {
+								let error = "Response body exceeds the maximum allowed size";
+								let _ = tx.start_send(Err(error.into()));
+								continue // don't insert the request back
+							}
 							let _ = tx.start_send(Ok(chunk));
-							me.requests.push((id, HttpWorkerRequest::ReadBody { body, tx }));
+							me.requests.push((id, HttpWorkerRequest::ReadBody { body, tx, read }));
 							cx.waker().wake_by_ref(); // reschedule in order to continue reading
 						},
 						Poll::Ready(Some(Err(err))) => {
-							let _ = tx.start_send(Err(err));
+							let _ = tx.start_send(Err(Box::new(err)));
 							// don't insert the request back
 						},
 						Poll::Ready(None) => {}, // EOF; don't insert the request back
 						Poll::Pending => {
-							me.requests.push((id, HttpWorkerRequest::ReadBody { body, tx }));
+							me.requests.push((id, HttpWorkerRequest::ReadBody { body, tx, read }));
 						},
 					}
 				},
@@ -718,7 +872,7 @@ impl Future for HttpWorker {
 			Poll::Pending => {},
 			Poll::Ready(None) => return Poll::Ready(()), // stops the worker
 			Poll::Ready(Some(ApiToWorker::Dispatch { id, request })) => {
-				let future = me.http_client.request(request);
+				let future = me.backend.request(request);
 				debug_assert!(me.requests.iter().all(|(i, _)| *i != id));
 				me.requests.push((id, HttpWorkerRequest::Dispatched(future)));
 				cx.waker().wake_by_ref(); // reschedule the task to poll the request
@@ -749,7 +903,7 @@ impl fmt::Debug for HttpWorkerRequest {
 #[cfg(test)]
 mod tests {
 	use super::{
-		super::{tests::TestNetwork, AsyncApi},
+		super::{tests::TestNetwork, AsyncApi, MockHttpBackend, MockHttpRequest},
 		*,
 	};
 	use crate::api::timestamp;
@@ -772,7 +926,7 @@ mod tests {
 		};
 		( $response:expr ) => {{
 			let hyper_client = SHARED_CLIENT.clone();
-			let (api, worker) = http(hyper_client.clone());
+			let (api, worker) = http(HttpContext::new(Arc::new(hyper_client), Default::default()));
 
 			let (addr_tx, addr_rx) = std::sync::mpsc::channel();
 			std::thread::spawn(move || {
@@ -801,6 +955,13 @@ mod tests {
 		}};
 	}
 
+	// Returns an `HttpApi` whose worker is ran in the background and answers from `mock`.
+	fn build_api_mock(mock: &MockHttpBackend, policy: HttpPolicy) -> HttpApi {
+		let (api, worker) = http(HttpContext::new(Arc::new(mock.clone()), policy));
+		std::thread::spawn(move || futures::executor::block_on(worker));
+		api
+	}
+
 	#[test]
 	fn basic_localhost() {
 		let deadline = timestamp::now().add(Duration::from_millis(10_000));
@@ -1095,7 +1256,8 @@ mod tests {
 
 		{
 			let mock = Arc::new(TestNetwork());
-			let (mut api, async_api) = AsyncApi::new(mock, false, shared_client.clone());
+			let context = HttpContext::new(Arc::new(shared_client.clone()), Default::default());
+			let (mut api, async_api) = AsyncApi::new(mock, false, context, None);
 			api.timestamp();
 
 			futures::executor::block_on(async move {
@@ -1110,7 +1272,8 @@ mod tests {
 
 		{
 			let mock = Arc::new(TestNetwork());
-			let (mut api, async_api) = AsyncApi::new(mock, false, shared_client.clone());
+			let context = HttpContext::new(Arc::new(shared_client.clone()), Default::default());
+			let (mut api, async_api) = AsyncApi::new(mock, false, context, None);
 			let id = api.http_request_start("lol", "nope", &[]).unwrap();
 			api.http_request_write_body(id, &[], None).unwrap();
 			futures::executor::block_on(async move {
@@ -1121,4 +1284,88 @@ mod tests {
 		// Check that the http client initialized, because it was used.
 		assert!(Lazy::into_value(Arc::try_unwrap(shared_client.0).unwrap()).is_ok());
 	}
+
+	#[test]
+	fn mock_backend_answers_scripted_requests() {
+		let deadline = timestamp::now().add(Duration::from_millis(10_000));
+		let mock = MockHttpBackend::new();
+		mock.expect_request(MockHttpRequest {
+			method: "POST".into(),
+			uri: "http://example.com/price".into(),
+			headers: vec![("X-Foo".into(), "bar".into())],
+			body: b"1234".to_vec(),
+			response_headers: vec![("Content-Type".into(), "text/plain".into())],
+			response: b"42".to_vec(),
+			..Default::default()
+		});
+		let mut api = build_api_mock(&mock, Default::default());
+
+		let id = api.request_start("POST", "http://example.com/price").unwrap();
+		api.request_add_header(id, "X-Foo", "bar").unwrap();
+		api.request_write_body(id, b"1234", Some(deadline)).unwrap();
+		api.request_write_body(id, &[], Some(deadline)).unwrap();
+
+		assert_eq!(
+			api.response_wait(&[id], Some(deadline)),
+			vec![HttpRequestStatus::Finished(200)]
+		);
+		assert_eq!(
+			api.response_headers(id),
+			vec![(b"content-type".to_vec(), b"text/plain".to_vec())],
+		);
+		let mut buf = [0; 16];
+		let n = api.response_read_body(id, &mut buf, Some(deadline)).unwrap();
+		assert_eq!(&buf[..n], b"42");
+		assert_eq!(mock.remaining(), 0);
+
+		// Requests that weren't scripted fail.
+		let id = api.request_start("GET", "http://example.com/other").unwrap();
+		assert_eq!(api.response_wait(&[id], Some(deadline)), vec![HttpRequestStatus::IoError]);
+	}
+
+	#[test]
+	fn policy_is_enforced_before_requests_start() {
+		let mock = MockHttpBackend::new();
+		let policy = HttpPolicy {
+			allowed_hosts: Some(vec!["example.com".into()]),
+			max_concurrent_requests: Some(2),
+			..Default::default()
+		};
+		let mut api = build_api_mock(&mock, policy.clone());
+
+		assert!(api.request_start("GET", "http://example.org/").is_err());
+		let first = api.request_start("GET", "http://EXAMPLE.com/").unwrap();
+		let _second = api.request_start("GET", "http://example.com/").unwrap();
+		assert!(api.request_start("GET", "http://example.com/").is_err());
+
+		// Finishing a request frees its slot.
+		assert_eq!(api.response_wait(&[first], None), vec![HttpRequestStatus::IoError]);
+		assert!(api.request_start("GET", "http://example.com/").is_ok());
+	}
+
+	#[test]
+	fn policy_limits_body_sizes() {
+		let deadline = timestamp::now().add(Duration::from_millis(10_000));
+		let mock = MockHttpBackend::new();
+		mock.expect_request(MockHttpRequest {
+			method: "GET".into(),
+			uri: "http://example.com/".into(),
+			response: vec![0; 16],
+			..Default::default()
+		});
+		let policy = HttpPolicy { max_body_size: Some(8), ..Default::default() };
+		let mut api = build_api_mock(&mock, policy);
+
+		let id = api.request_start("POST", "http://example.com/").unwrap();
+		assert_eq!(api.request_write_body(id, &[0; 9], Some(deadline)), Err(HttpError::IoError));
+		assert_eq!(api.request_write_body(id, &[], Some(deadline)), Err(HttpError::Invalid));
+
+		let id = api.request_start("GET", "http://example.com/").unwrap();
+		assert_eq!(
+			api.response_wait(&[id], Some(deadline)),
+			vec![HttpRequestStatus::Finished(200)]
+		);
+		let mut buf = [0; 32];
+		assert_eq!(api.response_read_body(id, &mut buf, Some(deadline)), Err(HttpError::IoError));
+	}
 }
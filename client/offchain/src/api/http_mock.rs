@@ -0,0 +1,125 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! An [`HttpBackend`] that answers offchain worker requests from a script.
+//!
+//! This allows testing offchain workers on a full node without any network access. Only
+//! available with the `test-helpers` feature.
+
+use super::http::{HttpBackend, HttpBackendError};
+use futures::{future::BoxFuture, FutureExt};
+use hyper::Body;
+use parking_lot::Mutex;
+use std::{collections::VecDeque, sync::Arc};
+
+/// A request expected by a [`MockHttpBackend`] and the response to answer it with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockHttpRequest {
+	/// Expected HTTP method.
+	pub method: String,
+	/// Expected URI.
+	pub uri: String,
+	/// Headers the request must contain. Other headers are ignored.
+	pub headers: Vec<(String, String)>,
+	/// Expected request body.
+	pub body: Vec<u8>,
+	/// Status code of the response.
+	pub response_status: u16,
+	/// Headers of the response.
+	pub response_headers: Vec<(String, String)>,
+	/// Body of the response.
+	pub response: Vec<u8>,
+}
+
+impl Default for MockHttpRequest {
+	fn default() -> Self {
+		Self {
+			method: "GET".into(),
+			uri: String::new(),
+			headers: Vec::new(),
+			body: Vec::new(),
+			response_status: 200,
+			response_headers: Vec::new(),
+			response: Vec::new(),
+		}
+	}
+}
+
+/// An [`HttpBackend`] that answers requests from a script instead of the network.
+///
+/// Requests must arrive in the order they are expected in. A request that doesn't match the next
+/// expected one, or that arrives when none is expected, fails with an I/O error.
+#[derive(Debug, Clone, Default)]
+pub struct MockHttpBackend {
+	expected: Arc<Mutex<VecDeque<MockHttpRequest>>>,
+}
+
+impl MockHttpBackend {
+	/// Creates a new backend that doesn't expect any request.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Expect `request` after all the previously expected ones.
+	pub fn expect_request(&self, request: MockHttpRequest) {
+		self.expected.lock().push_back(request);
+	}
+
+	/// Number of expected requests that haven't been received yet.
+	pub fn remaining(&self) -> usize {
+		self.expected.lock().len()
+	}
+}
+
+impl HttpBackend for MockHttpBackend {
+	fn request(
+		&self,
+		request: hyper::Request<Body>,
+	) -> BoxFuture<'static, Result<hyper::Response<Body>, HttpBackendError>> {
+		let expected = self.expected.lock().pop_front();
+		async move {
+			let (parts, body) = request.into_parts();
+			let expected = expected
+				.ok_or_else(|| format!("Unexpected request: {} {}", parts.method, parts.uri))?;
+
+			if parts.method.as_str() != expected.method || parts.uri.to_string() != expected.uri {
+				return Err(format!(
+					"Expected request {} {}, got {} {}",
+					expected.method, expected.uri, parts.method, parts.uri,
+				)
+				.into())
+			}
+			for (name, value) in &expected.headers {
+				if !parts.headers.get_all(name.as_str()).iter().any(|v| v == value.as_str()) {
+					return Err(format!("Missing request header {}: {}", name, value).into())
+				}
+			}
+			let body = hyper::body::to_bytes(body).await?;
+			if body[..] != expected.body[..] {
+				return Err(format!("Unexpected request body: {:?}", body).into())
+			}
+
+			let mut response = hyper::Response::builder().status(expected.response_status);
+			for (name, value) in &expected.response_headers {
+				response = response.header(name.as_str(), value.as_str());
+			}
+			Ok(response.body(Body::from(expected.response))?)
+		}
+		.boxed()
+	}
+}
@@ -35,13 +35,23 @@
 
 #![warn(missing_docs)]
 
-use std::{collections::HashSet, fmt, marker::PhantomData, sync::Arc};
+use std::{
+	collections::HashSet,
+	fmt,
+	marker::PhantomData,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
 
 use futures::{
-	future::{ready, Future},
+	future::{self, ready, Either, Future},
 	prelude::*,
 };
 use parking_lot::Mutex;
+use prometheus_endpoint::{register, Counter, CounterVec, Opts, PrometheusError, Registry, U64};
 use sc_network::{ExHashT, NetworkService, NetworkStateInfo, PeerId};
 use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_core::{offchain, traits::SpawnNamed, ExecutionContext};
@@ -53,7 +63,9 @@ use threadpool::ThreadPool;
 
 mod api;
 
-pub use api::Db as OffchainDb;
+pub use api::{Db as OffchainDb, HttpBackend, HttpBackendError, HttpPolicy};
+#[cfg(any(test, feature = "test-helpers"))]
+pub use api::{MockHttpBackend, MockHttpRequest};
 pub use sp_offchain::{OffchainWorkerApi, STORAGE_PREFIX};
 
 const LOG_TARGET: &str = "offchain-worker";
@@ -66,6 +78,9 @@ pub trait NetworkProvider: NetworkStateInfo {
 
 	/// Set the authorized only flag.
 	fn set_authorized_only(&self, reserved_only: bool);
+
+	/// Returns `true` while the node is doing a major sync.
+	fn is_major_syncing(&self) -> bool;
 }
 
 impl<B, H> NetworkProvider for NetworkService<B, H>
@@ -80,6 +95,30 @@ where
 	fn set_authorized_only(&self, reserved_only: bool) {
 		NetworkService::set_authorized_only(self, reserved_only)
 	}
+
+	fn is_major_syncing(&self) -> bool {
+		NetworkService::is_major_syncing(self)
+	}
+}
+
+/// Restrictions on when and for how long offchain workers run.
+#[derive(Debug, Clone, Default)]
+pub struct SchedulingPolicy {
+	/// Don't run offchain workers for blocks imported while the node is major syncing.
+	pub skip_while_major_syncing: bool,
+	/// Maximum number of offchain workers running at the same time, or `None` for no limit.
+	///
+	/// Blocks imported while the limit is reached are skipped. When `max_run_time` is set, the
+	/// limit defaults to the number of CPUs.
+	pub max_concurrent_workers: Option<usize>,
+	/// Maximum wall-clock time of a single offchain worker run, or `None` for no limit.
+	///
+	/// Once it has passed, the HTTP requests of the run are aborted and all further blocking
+	/// calls of the run into the offchain API return immediately. The runtime call itself can't
+	/// be interrupted, so when a limit is set every run gets a thread of its own, which is left
+	/// to finish the call in the background instead of holding up the shared worker threads.
+	/// The run counts against `max_concurrent_workers` until its thread exits.
+	pub max_run_time: Option<Duration>,
 }
 
 /// Options for [`OffchainWorkers`]
@@ -88,6 +127,14 @@ pub struct OffchainWorkerOptions {
 	///
 	/// If not enabled, any http request will panic.
 	pub enable_http_requests: bool,
+	/// Restrictions on the http requests of offchain workers.
+	pub http_policy: HttpPolicy,
+	/// Backend performing http requests, or `None` to send them over the network.
+	pub http_backend: Option<Arc<dyn HttpBackend>>,
+	/// Restrictions on when and for how long offchain workers run.
+	pub scheduling: SchedulingPolicy,
+	/// Registry to report the metrics of offchain workers to.
+	pub prometheus_registry: Option<Registry>,
 }
 
 /// An offchain workers manager.
@@ -95,18 +142,38 @@ pub struct OffchainWorkers<Client, Block: traits::Block> {
 	client: Arc<Client>,
 	_block: PhantomData<Block>,
 	thread_pool: Mutex<ThreadPool>,
-	shared_http_client: api::SharedClient,
+	http_context: api::HttpContext,
 	enable_http: bool,
+	scheduling: SchedulingPolicy,
+	running: Arc<AtomicUsize>,
+	metrics: Option<Metrics>,
 }
 
 impl<Client, Block: traits::Block> OffchainWorkers<Client, Block> {
 	/// Creates new [`OffchainWorkers`].
 	pub fn new(client: Arc<Client>) -> Self {
-		Self::new_with_options(client, OffchainWorkerOptions { enable_http_requests: true })
+		Self::new_with_options(
+			client,
+			OffchainWorkerOptions {
+				enable_http_requests: true,
+				http_policy: Default::default(),
+				http_backend: None,
+				scheduling: Default::default(),
+				prometheus_registry: None,
+			},
+		)
 	}
 
 	/// Creates new [`OffchainWorkers`] using the given `options`.
 	pub fn new_with_options(client: Arc<Client>, options: OffchainWorkerOptions) -> Self {
+		let metrics = options.prometheus_registry.as_ref().and_then(|registry| {
+			Metrics::register(registry)
+				.map_err(
+					|err| tracing::warn!(target: LOG_TARGET, "Failed to register metrics: {}", err),
+				)
+				.ok()
+		});
+
 		Self {
 			client,
 			_block: PhantomData,
@@ -114,12 +181,94 @@ impl<Client, Block: traits::Block> OffchainWorkers<Client, Block> {
 				"offchain-worker".into(),
 				num_cpus::get(),
 			)),
-			shared_http_client: api::SharedClient::new(),
+			http_context: api::HttpContext::new(
+				options.http_backend.unwrap_or_else(|| Arc::new(api::SharedClient::new())),
+				options.http_policy,
+			),
 			enable_http: options.enable_http_requests,
+			scheduling: options.scheduling,
+			running: Default::default(),
+			metrics,
+		}
+	}
+
+	/// Maximum number of offchain workers running at the same time.
+	///
+	/// Runs with a maximum run time get threads of their own, whose number must be bounded.
+	fn max_concurrent_workers(&self) -> usize {
+		match (self.scheduling.max_concurrent_workers, self.scheduling.max_run_time) {
+			(Some(max), _) => max,
+			(None, Some(_)) => num_cpus::get(),
+			(None, None) => usize::MAX,
+		}
+	}
+
+	/// Count a new run against `max_concurrent_workers`, if allowed.
+	fn acquire_worker_slot(&self) -> Option<WorkerSlot> {
+		let max = self.max_concurrent_workers();
+		self.running
+			.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < max).then(|| n + 1))
+			.ok()
+			.map(|_| WorkerSlot(self.running.clone()))
+	}
+
+	/// Note that the offchain workers of a block weren't run for the given `reason`.
+	fn note_skipped(&self, header: &Block::Header, reason: &'static str) {
+		tracing::debug!(
+			target: LOG_TARGET,
+			"Skipping offchain workers at {:?}: {}",
+			header.hash(),
+			reason.replace('_', " "),
+		);
+		if let Some(metrics) = &self.metrics {
+			metrics.skipped.with_label_values(&[reason]).inc();
 		}
 	}
 }
 
+/// A running offchain worker counted against `max_concurrent_workers` until dropped.
+///
+/// It is owned by the thread running the worker, so that runs exceeding their maximum run time
+/// are counted until the runtime call has returned.
+struct WorkerSlot(Arc<AtomicUsize>);
+
+impl Drop for WorkerSlot {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::SeqCst);
+	}
+}
+
+/// Offchain worker Prometheus metrics.
+#[derive(Clone)]
+struct Metrics {
+	skipped: CounterVec<U64>,
+	aborted: Counter<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			skipped: register(
+				CounterVec::new(
+					Opts::new(
+						"substrate_offchain_workers_skipped_total",
+						"Number of blocks whose offchain workers were not run",
+					),
+					&["reason"],
+				)?,
+				registry,
+			)?,
+			aborted: register(
+				Counter::new(
+					"substrate_offchain_workers_aborted_total",
+					"Number of offchain worker runs aborted after exceeding their maximum run time",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
 impl<Client, Block: traits::Block> fmt::Debug for OffchainWorkers<Client, Block> {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		f.debug_tuple("OffchainWorkers").finish()
@@ -140,6 +289,11 @@ where
 		network_provider: Arc<dyn NetworkProvider + Send + Sync>,
 		is_validator: bool,
 	) -> impl Future<Output = ()> {
+		if self.scheduling.skip_while_major_syncing && network_provider.is_major_syncing() {
+			self.note_skipped(header, "major_sync");
+			return Either::Left(ready(()))
+		}
+
 		let runtime = self.client.runtime_api();
 		let at = BlockId::hash(header.hash());
 		let has_api_v1 = runtime.has_api_with::<dyn OffchainWorkerApi<Block>, _>(&at, |v| v == 1);
@@ -165,9 +319,23 @@ where
 			at,
 			version
 		);
-		let process = (version > 0).then(|| {
-			let (api, runner) =
-				api::AsyncApi::new(network_provider, is_validator, self.shared_http_client.clone());
+		let slot = if version > 0 {
+			let slot = self.acquire_worker_slot();
+			if slot.is_none() {
+				self.note_skipped(header, "too_many_workers");
+			}
+			slot
+		} else {
+			None
+		};
+		let process = slot.map(|slot| {
+			let max_run_time = self.scheduling.max_run_time;
+			let (api, runner) = api::AsyncApi::new(
+				network_provider,
+				is_validator,
+				self.http_context.clone(),
+				max_run_time,
+			);
 			tracing::debug!(target: LOG_TARGET, "Spawning offchain workers at {:?}", at);
 			let header = header.clone();
 			let client = self.client.clone();
@@ -175,7 +343,8 @@ where
 			let mut capabilities = offchain::Capabilities::all();
 
 			capabilities.set(offchain::Capabilities::HTTP, self.enable_http);
-			self.spawn_worker(move || {
+			self.spawn_worker(max_run_time.is_some(), move || {
+				let _slot = slot;
 				let runtime = client.runtime_api();
 				let api = Box::new(api);
 				tracing::debug!(target: LOG_TARGET, "Running offchain workers at {:?}", at);
@@ -201,12 +370,32 @@ where
 				}
 			});
 
-			runner.process()
+			let metrics = self.metrics.clone();
+			let process = runner.process();
+			async move {
+				let max_run_time = match max_run_time {
+					Some(max_run_time) => max_run_time,
+					None => return process.await,
+				};
+				let timeout = futures_timer::Delay::new(max_run_time);
+				futures::pin_mut!(process);
+				if let Either::Right(_) = future::select(process, timeout).await {
+					tracing::warn!(
+						target: LOG_TARGET,
+						"Offchain workers at {:?} exceeded their maximum run time of {:?}",
+						at,
+						max_run_time,
+					);
+					if let Some(metrics) = metrics {
+						metrics.aborted.inc();
+					}
+				}
+			}
 		});
 
-		async move {
+		Either::Right(async move {
 			futures::future::OptionFuture::from(process).await;
-		}
+		})
 	}
 
 	/// Spawns a new offchain worker.
@@ -214,11 +403,19 @@ where
 	/// We spawn offchain workers for each block in a separate thread,
 	/// since they can run for a significant amount of time
 	/// in a blocking fashion and we don't want to block the runtime.
+	/// Runs that may be abandoned once their maximum run time is exceeded get a `dedicated`
+	/// thread, so that they can't exhaust the thread pool. The number of such threads is bounded
+	/// by [`Self::max_concurrent_workers`].
 	///
 	/// Note that we should avoid that if we switch to future-based runtime in the future,
 	/// alternatively:
-	fn spawn_worker(&self, f: impl FnOnce() -> () + Send + 'static) {
-		self.thread_pool.lock().execute(f);
+	fn spawn_worker(&self, dedicated: bool, f: impl FnOnce() -> () + Send + 'static) {
+		if !dedicated {
+			return self.thread_pool.lock().execute(f)
+		}
+		if let Err(e) = std::thread::Builder::new().name("offchain-worker".into()).spawn(f) {
+			tracing::error!(target: LOG_TARGET, "Failed to spawn offchain worker thread: {}", e);
+		}
 	}
 }
 
@@ -296,6 +493,10 @@ mod tests {
 		fn set_authorized_only(&self, _reserved_only: bool) {
 			unimplemented!()
 		}
+
+		fn is_major_syncing(&self) -> bool {
+			false
+		}
 	}
 
 	struct TestPool(Arc<BasicPool<FullChainApi<TestClient, Block>, Block>>);
@@ -338,6 +539,92 @@ mod tests {
 		assert_eq!(pool.0.ready().next().unwrap().is_propagable(), false);
 	}
 
+	#[test]
+	fn should_skip_offchain_workers_when_too_many_are_running() {
+		sp_tracing::try_init_simple();
+
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let network = Arc::new(TestNetwork());
+		let header = client.header(&BlockId::number(0)).unwrap().unwrap();
+
+		// when
+		let offchain = OffchainWorkers::new_with_options(
+			client,
+			OffchainWorkerOptions {
+				enable_http_requests: true,
+				http_policy: Default::default(),
+				http_backend: None,
+				scheduling: SchedulingPolicy {
+					max_concurrent_workers: Some(0),
+					..Default::default()
+				},
+				prometheus_registry: Some(Registry::new()),
+			},
+		);
+		futures::executor::block_on(offchain.on_block_imported(&header, network, false));
+
+		// then
+		assert_eq!(offchain.running.load(Ordering::SeqCst), 0);
+		let skipped = &offchain.metrics.as_ref().unwrap().skipped;
+		assert_eq!(skipped.with_label_values(&["too_many_workers"]).get(), 1);
+	}
+
+	#[test]
+	fn should_hold_worker_slot_until_timed_out_run_exits() {
+		sp_tracing::try_init_simple();
+
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let network = Arc::new(TestNetwork());
+		let header = client.header(&BlockId::number(0)).unwrap().unwrap();
+
+		// when
+		let offchain = OffchainWorkers::new_with_options(
+			client,
+			OffchainWorkerOptions {
+				enable_http_requests: true,
+				http_policy: Default::default(),
+				http_backend: None,
+				scheduling: SchedulingPolicy {
+					max_concurrent_workers: Some(1),
+					max_run_time: Some(Duration::from_millis(0)),
+					..Default::default()
+				},
+				prometheus_registry: None,
+			},
+		);
+		let run = offchain.on_block_imported(&header, network, false);
+		assert_eq!(offchain.running.load(Ordering::SeqCst), 1);
+		futures::executor::block_on(run);
+
+		// then
+		let started = std::time::Instant::now();
+		while offchain.running.load(Ordering::SeqCst) != 0 {
+			assert!(started.elapsed() < Duration::from_secs(10), "worker thread never exited");
+			std::thread::sleep(Duration::from_millis(10));
+		}
+		assert!(offchain.acquire_worker_slot().is_some());
+	}
+
+	#[test]
+	fn should_bound_dedicated_worker_threads() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let offchain = OffchainWorkers::new_with_options(
+			client,
+			OffchainWorkerOptions {
+				enable_http_requests: true,
+				http_policy: Default::default(),
+				http_backend: None,
+				scheduling: SchedulingPolicy {
+					max_run_time: Some(Duration::from_secs(1)),
+					..Default::default()
+				},
+				prometheus_registry: None,
+			},
+		);
+
+		assert_eq!(offchain.max_concurrent_workers(), num_cpus::get());
+	}
+
 	#[test]
 	fn offchain_index_set_and_clear_works() {
 		use sp_core::offchain::OffchainStorage;
@@ -33,6 +33,7 @@
 //! will at all time try to maintain a connection with.
 
 mod peersstate;
+mod store;
 
 use futures::{channel::oneshot, prelude::*};
 use log::{debug, error, trace};
@@ -47,6 +48,7 @@ use std::{
 use wasm_timer::Delay;
 
 pub use libp2p::PeerId;
+pub use store::{unix_time, PeerRecord, PeerStore, PEER_STORE_FILE};
 
 /// We don't accept nodes whose reputation is under this value.
 pub const BANNED_THRESHOLD: i32 = 82 * (i32::MIN / 100);
@@ -218,6 +220,11 @@ impl From<u64> for IncomingIndex {
 pub struct PeersetConfig {
 	/// List of sets of nodes the peerset manages.
 	pub sets: Vec<SetConfig>,
+
+	/// Peers remembered from a previous run, see [`PeerStore`].
+	///
+	/// Their reputations are restored, which in particular keeps banned peers banned.
+	pub peer_records: HashMap<PeerId, PeerRecord>,
 }
 
 /// Configuration for a single set of nodes.
@@ -314,6 +321,16 @@ impl Peerset {
 			}
 		}
 
+		if !config.peer_records.is_empty() {
+			let now = store::unix_time();
+			for (peer_id, record) in config.peer_records {
+				let reputation = record.reputation_at(now);
+				if reputation != 0 {
+					peerset.data.peer_reputation(peer_id).set_reputation(reputation);
+				}
+			}
+		}
+
 		for set_index in 0..peerset.data.num_sets() {
 			peerset.alloc_slots(SetId(set_index));
 		}
@@ -484,23 +501,8 @@ impl Peerset {
 		};
 
 		// For each elapsed second, move the node reputation towards zero.
-		// If we multiply each second the reputation by `k` (where `k` is between 0 and 1), it
-		// takes `ln(0.5) / ln(k)` seconds to reduce the reputation by half. Use this formula to
-		// empirically determine a value of `k` that looks correct.
 		for _ in 0..secs_diff {
 			for peer_id in self.data.peers().cloned().collect::<Vec<_>>() {
-				// We use `k = 0.98`, so we divide by `50`. With that value, it takes 34.3 seconds
-				// to reduce the reputation by half.
-				fn reput_tick(reput: i32) -> i32 {
-					let mut diff = reput / 50;
-					if diff == 0 && reput < 0 {
-						diff = -1;
-					} else if diff == 0 && reput > 0 {
-						diff = 1;
-					}
-					reput.saturating_sub(diff)
-				}
-
 				let mut peer_reputation = self.data.peer_reputation(peer_id);
 
 				let before = peer_reputation.reputation();
@@ -717,6 +719,24 @@ impl Peerset {
 	pub fn num_discovered_peers(&self) -> usize {
 		self.data.peers().len()
 	}
+
+	/// Returns the records of all peers with a non-zero reputation, to be kept in a
+	/// [`PeerStore`].
+	pub fn peer_records(&mut self) -> HashMap<PeerId, PeerRecord> {
+		self.update_time();
+
+		let now = store::unix_time();
+		self.data
+			.peers()
+			.cloned()
+			.collect::<Vec<_>>()
+			.into_iter()
+			.filter_map(|peer_id| {
+				let reputation = self.data.peer_reputation(peer_id).reputation();
+				(reputation != 0).then(|| (peer_id, PeerRecord::new(reputation, now)))
+			})
+			.collect()
+	}
 }
 
 impl Stream for Peerset {
@@ -763,6 +783,23 @@ impl Stream for Peerset {
 	}
 }
 
+/// Moves the reputation `reput` one second of decay towards zero.
+///
+/// If we multiply each second the reputation by `k` (where `k` is between 0 and 1), it takes
+/// `ln(0.5) / ln(k)` seconds to reduce the reputation by half. Use this formula to empirically
+/// determine a value of `k` that looks correct.
+fn reput_tick(reput: i32) -> i32 {
+	// We use `k = 0.98`, so we divide by `50`. With that value, it takes 34.3 seconds to reduce
+	// the reputation by half.
+	let mut diff = reput / 50;
+	if diff == 0 && reput < 0 {
+		diff = -1;
+	} else if diff == 0 && reput > 0 {
+		diff = 1;
+	}
+	reput.saturating_sub(diff)
+}
+
 /// Reason for calling [`Peerset::dropped`].
 pub enum DropReason {
 	/// Substream or connection has been closed for an unknown reason.
@@ -777,8 +814,8 @@ pub enum DropReason {
 #[cfg(test)]
 mod tests {
 	use super::{
-		IncomingIndex, Message, Peerset, PeersetConfig, ReputationChange, SetConfig, SetId,
-		BANNED_THRESHOLD,
+		store, IncomingIndex, Message, PeerRecord, Peerset, PeersetConfig, ReputationChange,
+		SetConfig, SetId, BANNED_THRESHOLD,
 	};
 	use futures::prelude::*;
 	use libp2p::PeerId;
@@ -812,6 +849,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: true,
 			}],
+			peer_records: Default::default(),
 		};
 
 		let (peerset, handle) = Peerset::from_config(config);
@@ -845,6 +883,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			peer_records: Default::default(),
 		};
 
 		let (mut peerset, _handle) = Peerset::from_config(config);
@@ -876,6 +915,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: true,
 			}],
+			peer_records: Default::default(),
 		};
 
 		let (mut peerset, _) = Peerset::from_config(config);
@@ -897,6 +937,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			peer_records: Default::default(),
 		};
 
 		let (mut peerset, _handle) = Peerset::from_config(config);
@@ -913,6 +954,28 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn test_peerset_restores_banned_peers() {
+		let bootnode = PeerId::random();
+		let ii = IncomingIndex(1);
+		let config = PeersetConfig {
+			sets: vec![SetConfig {
+				in_peers: 25,
+				out_peers: 25,
+				bootnodes: vec![bootnode],
+				reserved_nodes: Default::default(),
+				reserved_only: false,
+			}],
+			peer_records: [(bootnode, PeerRecord::new(i32::MIN, store::unix_time()))].into(),
+		};
+
+		// The banned bootnode is neither connected to nor accepted.
+		let (mut peerset, _handle) = Peerset::from_config(config);
+		peerset.incoming(SetId::from(0), bootnode, ii);
+
+		assert_messages(peerset, vec![Message::Reject(ii)]);
+	}
+
 	#[test]
 	fn test_peerset_banned() {
 		let (mut peerset, handle) = Peerset::from_config(PeersetConfig {
@@ -923,6 +986,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			peer_records: Default::default(),
 		});
 
 		// We ban a node by setting its reputation under the threshold.
@@ -966,6 +1030,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			peer_records: Default::default(),
 		});
 
 		// We ban a node by setting its reputation under the threshold.
@@ -0,0 +1,215 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! On-disk store of peer information that is kept across restarts.
+//!
+//! The store holds, for every peer worth remembering, its reputation together with the time it
+//! was recorded, until when it is banned and the addresses it was last seen at. Reputations are
+//! decayed by the time elapsed since they were recorded when the store is loaded, exactly as if
+//! the node had kept running.
+
+use crate::{reput_tick, BANNED_THRESHOLD};
+use libp2p::PeerId;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	fs, io,
+	path::{Path, PathBuf},
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Name of the file the peers are stored in.
+pub const PEER_STORE_FILE: &str = "peers.json";
+
+/// Information about a peer kept in the [`PeerStore`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerRecord {
+	/// Reputation of the peer at `updated_at`.
+	pub reputation: i32,
+	/// Unix time in seconds at which `reputation` was recorded.
+	pub updated_at: u64,
+	/// Unix time in seconds until which the peer is banned, if it is.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub banned_until: Option<u64>,
+	/// Addresses the peer was last seen at.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub addresses: Vec<String>,
+}
+
+impl PeerRecord {
+	/// Create a record of the given `reputation` at `now`, in unix seconds.
+	pub fn new(reputation: i32, now: u64) -> Self {
+		let banned_until =
+			(reputation < BANNED_THRESHOLD).then(|| now + secs_until_unbanned(reputation));
+		Self { reputation, updated_at: now, banned_until, addresses: Vec::new() }
+	}
+
+	/// Returns the reputation at `now`, in unix seconds.
+	///
+	/// The reputation is decayed by the time elapsed since it was recorded. A peer is kept under
+	/// the ban threshold until `banned_until`, even if the clock went backwards in the meantime.
+	pub fn reputation_at(&self, now: u64) -> i32 {
+		let mut reputation = self.reputation;
+		for _ in 0..now.saturating_sub(self.updated_at) {
+			if reputation == 0 {
+				break
+			}
+			reputation = reput_tick(reputation);
+		}
+
+		match self.banned_until {
+			Some(until) if until > now => reputation.min(BANNED_THRESHOLD - 1),
+			_ => reputation,
+		}
+	}
+}
+
+/// Number of seconds until the decay brings `reputation` back over the ban threshold.
+fn secs_until_unbanned(mut reputation: i32) -> u64 {
+	let mut secs = 0;
+	while reputation < BANNED_THRESHOLD {
+		reputation = reput_tick(reputation);
+		secs += 1;
+	}
+	secs
+}
+
+/// Returns the current unix time in seconds.
+pub fn unix_time() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|d| d.as_secs())
+		.unwrap_or_default()
+}
+
+/// Store of [`PeerRecord`]s in a JSON file.
+#[derive(Debug, Clone)]
+pub struct PeerStore {
+	path: PathBuf,
+}
+
+impl PeerStore {
+	/// Create a store that keeps the peers in [`PEER_STORE_FILE`] within `dir`.
+	pub fn new(dir: &Path) -> Self {
+		Self { path: dir.join(PEER_STORE_FILE) }
+	}
+
+	/// Path of the file the peers are stored in.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Load the stored peers.
+	///
+	/// Returns an empty list if nothing was stored yet. A corrupted store is ignored, so that it
+	/// can never prevent the node from starting.
+	pub fn load(&self) -> io::Result<HashMap<PeerId, PeerRecord>> {
+		let data = match fs::read(&self.path) {
+			Ok(data) => data,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+			Err(e) => return Err(e),
+		};
+
+		let records: HashMap<String, PeerRecord> = match serde_json::from_slice(&data) {
+			Ok(records) => records,
+			Err(e) => {
+				warn!(
+					target: "peerset",
+					"Ignoring corrupted peer store {}: {}", self.path.display(), e,
+				);
+				return Ok(HashMap::new())
+			},
+		};
+
+		Ok(records
+			.into_iter()
+			.filter_map(|(peer_id, record)| Some((peer_id.parse().ok()?, record)))
+			.collect())
+	}
+
+	/// Replace the stored peers with `records`.
+	pub fn store(&self, records: &HashMap<PeerId, PeerRecord>) -> io::Result<()> {
+		let records = records
+			.iter()
+			.map(|(peer_id, record)| (peer_id.to_base58(), record))
+			.collect::<HashMap<_, _>>();
+		let data = serde_json::to_vec(&records)?;
+
+		// Write to a temporary file first, so that an interrupted write never corrupts the store.
+		let tmp = self.path.with_extension("json.tmp");
+		fs::write(&tmp, data)?;
+		fs::rename(tmp, &self.path)
+	}
+
+	/// Remove all stored peers.
+	pub fn wipe(&self) -> io::Result<()> {
+		match fs::remove_file(&self.path) {
+			Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+			_ => Ok(()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reputation_decays_while_stored() {
+		let record = PeerRecord::new(-1000, 100);
+		assert_eq!(record.banned_until, None);
+		assert_eq!(record.reputation_at(100), -1000);
+		assert_eq!(record.reputation_at(101), -980);
+		assert_eq!(record.reputation_at(100_000), 0);
+		// The clock going backwards doesn't change the reputation.
+		assert_eq!(record.reputation_at(50), -1000);
+	}
+
+	#[test]
+	fn banned_peers_stay_banned_until_the_ban_expires() {
+		let record = PeerRecord::new(i32::MIN, 100);
+		let until = record.banned_until.unwrap();
+		assert!(until > 100);
+		assert!(record.reputation_at(until - 1) < BANNED_THRESHOLD);
+		assert!(record.reputation_at(until) >= BANNED_THRESHOLD);
+		assert!(record.reputation_at(50) < BANNED_THRESHOLD);
+	}
+
+	#[test]
+	fn store_round_trip() {
+		let dir = tempfile::tempdir().unwrap();
+		let store = PeerStore::new(dir.path());
+		assert!(store.load().unwrap().is_empty());
+
+		let mut records = HashMap::new();
+		let mut record = PeerRecord::new(-5, 10);
+		record.addresses.push("/ip4/127.0.0.1/tcp/30333".into());
+		records.insert(PeerId::random(), record);
+		store.store(&records).unwrap();
+		assert_eq!(store.load().unwrap(), records);
+
+		fs::write(store.path(), b"garbage").unwrap();
+		assert!(store.load().unwrap().is_empty());
+
+		store.wipe().unwrap();
+		store.wipe().unwrap();
+		assert!(store.load().unwrap().is_empty());
+	}
+}
@@ -33,4 +33,24 @@ pub trait OffchainApi {
 	/// Get offchain local storage under given key and prefix.
 	#[method(name = "offchain_localStorageGet")]
 	fn get_local_storage(&self, kind: StorageKind, key: Bytes) -> RpcResult<Option<Bytes>>;
+
+	/// Get up to `count` offchain local storage keys starting with `prefix`.
+	///
+	/// Keys are returned in lexicographic order, beginning after `start_key` if given. Fails on
+	/// database backends that only keep the hashes of the keys, such as parity-db.
+	#[method(name = "offchain_localStorageKeysPaged")]
+	fn local_storage_keys_paged(
+		&self,
+		kind: StorageKind,
+		prefix: Option<Bytes>,
+		count: u32,
+		start_key: Option<Bytes>,
+	) -> RpcResult<Vec<Bytes>>;
+
+	/// Remove all offchain local storage entries whose key starts with `prefix`.
+	///
+	/// Returns the number of removed entries. Fails on the same database backends as
+	/// `offchain_localStorageKeysPaged`.
+	#[method(name = "offchain_localStorageClearPrefix")]
+	fn clear_local_storage_prefix(&self, kind: StorageKind, prefix: Bytes) -> RpcResult<u32>;
 }
@@ -36,6 +36,12 @@ pub enum Error {
 	/// Peer argument is malformatted.
 	#[error("{0}")]
 	MalformattedPeerArg(String),
+	/// CID argument is malformatted.
+	#[error("{0}")]
+	MalformattedCid(String),
+	/// Fetching content over bitswap failed.
+	#[error("Bitswap fetch failed: {0}")]
+	BitswapFetchFailed(String),
 }
 
 // Base code for all system errors.
@@ -44,6 +50,10 @@ const BASE_ERROR: i32 = 2000;
 const NOT_HEALTHY_ERROR: i32 = BASE_ERROR + 1;
 // Peer argument is malformatted.
 const MALFORMATTED_PEER_ARG_ERROR: i32 = BASE_ERROR + 2;
+// CID argument is malformatted.
+const MALFORMATTED_CID_ERROR: i32 = BASE_ERROR + 3;
+// Fetching content over bitswap failed.
+const BITSWAP_FETCH_FAILED_ERROR: i32 = BASE_ERROR + 4;
 
 impl From<Error> for JsonRpseeError {
 	fn from(e: Error) -> Self {
@@ -55,6 +65,13 @@ impl From<Error> for JsonRpseeError {
 				e,
 				None::<()>,
 			)),
+			Error::MalformattedCid(e) =>
+				CallError::Custom(ErrorObject::owned(MALFORMATTED_CID_ERROR, e, None::<()>)),
+			Error::BitswapFetchFailed(_) => CallError::Custom(ErrorObject::owned(
+				BITSWAP_FETCH_FAILED_ERROR,
+				e.to_string(),
+				None::<()>,
+			)),
 		}
 		.into()
 	}
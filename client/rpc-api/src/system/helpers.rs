@@ -93,6 +93,25 @@ pub struct SyncState<Number> {
 	/// Height of the highest block learned from the network. Missing if no block is known yet.
 	#[serde(default = "Default::default", skip_serializing_if = "Option::is_none")]
 	pub highest_block: Option<Number>,
+	/// Progress of the state download, if the state is being downloaded.
+	#[serde(default = "Default::default", skip_serializing_if = "Option::is_none")]
+	pub state_download: Option<StateDownload>,
+}
+
+/// Progress of a state download.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateDownload {
+	/// Estimated download percentage.
+	pub percentage: u32,
+	/// Total state size in bytes downloaded so far.
+	pub downloaded_bytes: u64,
+	/// Number of key ranges the state is split into.
+	pub ranges: u32,
+	/// Number of key ranges fully downloaded.
+	pub completed_ranges: u32,
+	/// Number of key ranges being downloaded from a peer.
+	pub active_ranges: u32,
 }
 #[cfg(test)]
 mod tests {
@@ -132,6 +151,7 @@ mod tests {
 				starting_block: 12u32,
 				current_block: 50u32,
 				highest_block: Some(128u32),
+				state_download: None,
 			})
 			.unwrap(),
 			r#"{"startingBlock":12,"currentBlock":50,"highestBlock":128}"#,
@@ -142,9 +162,27 @@ mod tests {
 				starting_block: 12u32,
 				current_block: 50u32,
 				highest_block: None,
+				state_download: None,
 			})
 			.unwrap(),
 			r#"{"startingBlock":12,"currentBlock":50}"#,
 		);
+
+		assert_eq!(
+			::serde_json::to_string(&SyncState {
+				starting_block: 12u32,
+				current_block: 12u32,
+				highest_block: Some(128u32),
+				state_download: Some(StateDownload {
+					percentage: 25,
+					downloaded_bytes: 1024,
+					ranges: 16,
+					completed_ranges: 4,
+					active_ranges: 3,
+				}),
+			})
+			.unwrap(),
+			r#"{"startingBlock":12,"currentBlock":12,"highestBlock":128,"stateDownload":{"percentage":25,"downloadedBytes":1024,"ranges":16,"completedRanges":4,"activeRanges":3}}"#,
+		);
 	}
 }
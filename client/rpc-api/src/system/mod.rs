@@ -22,8 +22,9 @@ use jsonrpsee::{
 	core::{JsonValue, RpcResult},
 	proc_macros::rpc,
 };
+use sp_core::Bytes;
 
-pub use self::helpers::{Health, NodeRole, PeerInfo, SyncState, SystemInfo};
+pub use self::helpers::{Health, NodeRole, PeerInfo, StateDownload, SyncState, SystemInfo};
 
 pub mod error;
 pub mod helpers;
@@ -100,6 +101,13 @@ pub trait SystemApi<Hash, Number> {
 	#[method(name = "system_reservedPeers")]
 	async fn system_reserved_peers(&self) -> RpcResult<Vec<String>>;
 
+	/// Fetch the content of the given CID from other peers over bitswap.
+	///
+	/// Typically used to retrieve data stored with indexed transactions. The content is only
+	/// returned if it matches the hash of the CID.
+	#[method(name = "system_bitswapFetch")]
+	async fn system_bitswap_fetch(&self, cid: String) -> RpcResult<Bytes>;
+
 	/// Returns the roles the node is running as.
 	#[method(name = "system_nodeRoles")]
 	async fn system_node_roles(&self) -> RpcResult<Vec<NodeRole>>;
@@ -33,6 +33,24 @@ use sp_core::{
 };
 use std::sync::Arc;
 
+/// Maximum number of keys returned by a single `offchain_localStorageKeysPaged` call.
+const KEYS_PAGED_MAX_COUNT: u32 = 1000;
+
+/// Prefix of the keys of [`StorageKind::LOCAL`] storage in the offchain database.
+///
+/// Offchain workers don't have access to fork-aware local storage yet, so this storage is only
+/// reachable over RPC. It is kept apart from the persistent storage of offchain workers, which
+/// lives under [`sp_offchain::STORAGE_PREFIX`].
+const LOCAL_STORAGE_PREFIX: &[u8] = b"local-storage";
+
+/// The prefix of the keys of the given kind of storage in the offchain database.
+fn storage_prefix(kind: StorageKind) -> &'static [u8] {
+	match kind {
+		StorageKind::PERSISTENT => sp_offchain::STORAGE_PREFIX,
+		StorageKind::LOCAL => LOCAL_STORAGE_PREFIX,
+	}
+}
+
 /// Offchain API
 #[derive(Debug)]
 pub struct Offchain<T: OffchainStorage> {
@@ -53,10 +71,7 @@ impl<T: OffchainStorage + 'static> OffchainApiServer for Offchain<T> {
 	fn set_local_storage(&self, kind: StorageKind, key: Bytes, value: Bytes) -> RpcResult<()> {
 		self.deny_unsafe.check_if_safe()?;
 
-		let prefix = match kind {
-			StorageKind::PERSISTENT => sp_offchain::STORAGE_PREFIX,
-			StorageKind::LOCAL => return Err(JsonRpseeError::from(Error::UnavailableStorageKind)),
-		};
+		let prefix = storage_prefix(kind);
 		self.storage.write().set(prefix, &*key, &*value);
 		Ok(())
 	}
@@ -64,11 +79,50 @@ impl<T: OffchainStorage + 'static> OffchainApiServer for Offchain<T> {
 	fn get_local_storage(&self, kind: StorageKind, key: Bytes) -> RpcResult<Option<Bytes>> {
 		self.deny_unsafe.check_if_safe()?;
 
-		let prefix = match kind {
-			StorageKind::PERSISTENT => sp_offchain::STORAGE_PREFIX,
-			StorageKind::LOCAL => return Err(JsonRpseeError::from(Error::UnavailableStorageKind)),
-		};
+		let prefix = storage_prefix(kind);
 
 		Ok(self.storage.read().get(prefix, &*key).map(Into::into))
 	}
+
+	fn local_storage_keys_paged(
+		&self,
+		kind: StorageKind,
+		prefix: Option<Bytes>,
+		count: u32,
+		start_key: Option<Bytes>,
+	) -> RpcResult<Vec<Bytes>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		if count > KEYS_PAGED_MAX_COUNT {
+			return Err(JsonRpseeError::from(Error::InvalidCount {
+				value: count,
+				max: KEYS_PAGED_MAX_COUNT,
+			}))
+		}
+
+		let storage_prefix = storage_prefix(kind);
+
+		let keys = self
+			.storage
+			.read()
+			.keys_with_prefix(
+				storage_prefix,
+				prefix.as_deref().unwrap_or_default(),
+				start_key.as_deref(),
+				count as usize,
+			)
+			.ok_or(Error::KeyIterationUnsupported)?;
+		Ok(keys.into_iter().map(Into::into).collect())
+	}
+
+	fn clear_local_storage_prefix(&self, kind: StorageKind, prefix: Bytes) -> RpcResult<u32> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let storage_prefix = storage_prefix(kind);
+
+		self.storage
+			.write()
+			.clear_prefix(storage_prefix, &*prefix)
+			.ok_or_else(|| Error::KeyIterationUnsupported.into())
+	}
 }
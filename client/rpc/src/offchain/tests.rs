@@ -18,6 +18,7 @@
 
 use super::*;
 use assert_matches::assert_matches;
+use jsonrpsee::types::error::CallError;
 use sp_core::{offchain::storage::InMemOffchainStorage, Bytes};
 
 #[test]
@@ -37,9 +38,134 @@ fn local_storage_should_work() {
 	);
 }
 
+#[test]
+fn local_storage_keys_should_be_paged_and_cleared_by_prefix() {
+	let storage = InMemOffchainStorage::default();
+	let offchain = Offchain::new(storage, DenyUnsafe::No);
+	let value = Bytes(b"offchain_value".to_vec());
+	for key in [&b"mmr:1"[..], b"mmr:2", b"mmr:3", b"other"] {
+		offchain
+			.set_local_storage(StorageKind::PERSISTENT, Bytes(key.to_vec()), value.clone())
+			.unwrap();
+	}
+	let prefix = Bytes(b"mmr:".to_vec());
+
+	assert_matches!(
+		offchain.local_storage_keys_paged(StorageKind::PERSISTENT, Some(prefix.clone()), 2, None),
+		Ok(ref keys) if *keys == vec![Bytes(b"mmr:1".to_vec()), Bytes(b"mmr:2".to_vec())]
+	);
+	assert_matches!(
+		offchain.local_storage_keys_paged(
+			StorageKind::PERSISTENT,
+			Some(prefix.clone()),
+			2,
+			Some(Bytes(b"mmr:2".to_vec())),
+		),
+		Ok(ref keys) if *keys == vec![Bytes(b"mmr:3".to_vec())]
+	);
+	assert_matches!(
+		offchain.local_storage_keys_paged(StorageKind::PERSISTENT, None, 1001, None),
+		Err(JsonRpseeError::Call(CallError::Custom(err))) => {
+			assert_eq!(err.message(), "count exceeds maximum value. value: 1001, max: 1000")
+		}
+	);
+
+	assert_matches!(
+		offchain.clear_local_storage_prefix(StorageKind::PERSISTENT, prefix.clone()),
+		Ok(3)
+	);
+	assert_matches!(
+		offchain.local_storage_keys_paged(StorageKind::PERSISTENT, None, 10, None),
+		Ok(ref keys) if *keys == vec![Bytes(b"other".to_vec())]
+	);
+}
+
+#[test]
+fn local_storage_kinds_are_kept_apart() {
+	let storage = InMemOffchainStorage::default();
+	let offchain = Offchain::new(storage, DenyUnsafe::No);
+	let persistent = Bytes(b"persistent_value".to_vec());
+	let local = Bytes(b"local_value".to_vec());
+	for key in [&b"mmr:1"[..], b"mmr:2"] {
+		offchain
+			.set_local_storage(StorageKind::PERSISTENT, Bytes(key.to_vec()), persistent.clone())
+			.unwrap();
+	}
+	offchain
+		.set_local_storage(StorageKind::LOCAL, Bytes(b"mmr:1".to_vec()), local.clone())
+		.unwrap();
+	let prefix = Bytes(b"mmr:".to_vec());
+
+	assert_matches!(
+		offchain.get_local_storage(StorageKind::LOCAL, Bytes(b"mmr:1".to_vec())),
+		Ok(Some(ref v)) if *v == local
+	);
+	assert_matches!(
+		offchain.local_storage_keys_paged(StorageKind::LOCAL, Some(prefix.clone()), 10, None),
+		Ok(ref keys) if *keys == vec![Bytes(b"mmr:1".to_vec())]
+	);
+	assert_matches!(offchain.clear_local_storage_prefix(StorageKind::LOCAL, prefix.clone()), Ok(1));
+	assert_matches!(
+		offchain.get_local_storage(StorageKind::LOCAL, Bytes(b"mmr:1".to_vec())),
+		Ok(None)
+	);
+	assert_matches!(
+		offchain.local_storage_keys_paged(StorageKind::PERSISTENT, Some(prefix), 10, None),
+		Ok(ref keys) if keys.len() == 2
+	);
+}
+
+/// Offchain storage that can't enumerate its keys, like the one backed by parity-db.
+#[derive(Clone, Default)]
+struct UnorderedStorage(InMemOffchainStorage);
+
+impl OffchainStorage for UnorderedStorage {
+	fn set(&mut self, prefix: &[u8], key: &[u8], value: &[u8]) {
+		self.0.set(prefix, key, value)
+	}
+
+	fn remove(&mut self, prefix: &[u8], key: &[u8]) {
+		self.0.remove(prefix, key)
+	}
+
+	fn get(&self, prefix: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+		self.0.get(prefix, key)
+	}
+
+	fn compare_and_set(
+		&mut self,
+		prefix: &[u8],
+		key: &[u8],
+		old_value: Option<&[u8]>,
+		new_value: &[u8],
+	) -> bool {
+		self.0.compare_and_set(prefix, key, old_value, new_value)
+	}
+}
+
+#[test]
+fn local_storage_keys_report_unsupported_backend() {
+	let offchain = Offchain::new(UnorderedStorage::default(), DenyUnsafe::No);
+	let prefix = Bytes(b"mmr:".to_vec());
+
+	assert_matches!(
+		offchain.local_storage_keys_paged(StorageKind::PERSISTENT, Some(prefix.clone()), 10, None),
+		Err(JsonRpseeError::Call(CallError::Custom(err))) => {
+			assert_eq!(err.code(), 5003);
+			assert_eq!(
+				err.message(),
+				"Listing offchain storage keys is not supported on this database backend."
+			);
+		}
+	);
+	assert_matches!(
+		offchain.clear_local_storage_prefix(StorageKind::PERSISTENT, prefix),
+		Err(JsonRpseeError::Call(CallError::Custom(err))) if err.code() == 5003
+	);
+}
+
 #[test]
 fn offchain_calls_considered_unsafe() {
-	use jsonrpsee::types::error::CallError;
 	let storage = InMemOffchainStorage::default();
 	let offchain = Offchain::new(storage, DenyUnsafe::Yes);
 	let key = Bytes(b"offchain_storage".to_vec());
@@ -19,6 +19,7 @@
 //! Substrate state API.
 
 mod state_full;
+mod state_light;
 
 #[cfg(test)]
 mod tests;
@@ -45,7 +46,8 @@ use sp_api::{CallApiAt, Metadata, ProvideRuntimeApi};
 use self::error::Error;
 
 use sc_client_api::{
-	Backend, BlockBackend, BlockchainEvents, ExecutorProvider, ProofProvider, StorageProvider,
+	light::RemoteFetcher, Backend, BlockBackend, BlockchainEvents, ExecutorProvider, ProofProvider,
+	StorageProvider,
 };
 pub use sc_rpc_api::{child_state::*, state::*};
 use sp_blockchain::{HeaderBackend, HeaderMetadata};
@@ -196,6 +198,25 @@ where
 	(StateApi { backend, deny_unsafe }, ChildState { backend: child_backend })
 }
 
+/// Create new state API that works on light node.
+///
+/// Storage and runtime calls are answered through `fetcher`; methods that need local state
+/// return an error.
+pub fn new_light<Block, Client>(
+	client: Arc<Client>,
+	fetcher: Arc<dyn RemoteFetcher<Block>>,
+	deny_unsafe: DenyUnsafe,
+) -> (StateApi<Block, Client>, ChildState<Block, Client>)
+where
+	Block: BlockT + 'static,
+	Client: HeaderBackend<Block> + Send + Sync + 'static,
+{
+	let child_backend =
+		Box::new(self::state_light::LightState::new(client.clone(), fetcher.clone()));
+	let backend = Box::new(self::state_light::LightState::new(client, fetcher));
+	(StateApi { backend, deny_unsafe }, ChildState { backend: child_backend })
+}
+
 /// State API with subscriptions support.
 pub struct StateApi<Block, Client> {
 	backend: Box<dyn StateBackend<Block, Client>>,
@@ -33,8 +33,8 @@ use jsonrpsee::{
 	PendingSubscription,
 };
 use sc_client_api::{
-	Backend, BlockBackend, BlockchainEvents, CallExecutor, ExecutorProvider, ProofProvider,
-	StorageProvider,
+	Backend, BlockBackend, BlockchainEvents, CallContext, CallExecutor, ExecutorProvider,
+	ProofProvider, StorageProvider,
 };
 use sc_rpc_api::state::ReadProof;
 use sp_api::{CallApiAt, Metadata, ProvideRuntimeApi};
@@ -206,6 +206,7 @@ where
 						&*call_data,
 						self.client.execution_extensions().strategies().other,
 						None,
+						CallContext::Rpc,
 					)
 					.map(Into::into)
 			})
@@ -0,0 +1,322 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! State API backend for light nodes.
+//!
+//! Values are fetched from full nodes on demand and checked against the state root of a locally
+//! verified header, see [`RemoteFetcher`], rather than read from the local state database.
+//! Methods that would need to enumerate storage or follow it over time return
+//! [`sp_blockchain::Error::NotAvailableOnLightClient`].
+
+use std::{marker::PhantomData, sync::Arc};
+
+use super::{client_err, error::Error, ChildStateBackend, StateBackend};
+
+use codec::Decode;
+use jsonrpsee::{
+	core::{async_trait, Error as JsonRpseeError},
+	PendingSubscription,
+};
+use sc_client_api::light::{
+	RemoteCallRequest, RemoteFetcher, RemoteReadChildRequest, RemoteReadRequest,
+};
+use sc_rpc_api::state::ReadProof;
+use sp_blockchain::{Error as ClientError, HeaderBackend};
+use sp_core::{
+	storage::{PrefixedStorageKey, StorageChangeSet, StorageData, StorageKey},
+	Bytes, OpaqueMetadata,
+};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Hash as HashT, HashFor},
+};
+use sp_version::RuntimeVersion;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// State API backend for light nodes.
+pub struct LightState<Block: BlockT, Client> {
+	client: Arc<Client>,
+	fetcher: Arc<dyn RemoteFetcher<Block>>,
+	_phantom: PhantomData<Block>,
+}
+
+impl<Block, Client> LightState<Block, Client>
+where
+	Block: BlockT + 'static,
+	Client: HeaderBackend<Block> + Send + Sync + 'static,
+{
+	/// Create new state API backend for light nodes.
+	pub fn new(client: Arc<Client>, fetcher: Arc<dyn RemoteFetcher<Block>>) -> Self {
+		Self { client, fetcher, _phantom: PhantomData }
+	}
+
+	/// Returns the header of the given block, or of the best block if `None` is passed.
+	fn header(&self, block: Option<Block::Hash>) -> Result<Block::Header> {
+		let hash = block.unwrap_or_else(|| self.client.info().best_hash);
+		self.client
+			.header(BlockId::Hash(hash))
+			.map_err(client_err)?
+			.ok_or_else(|| client_err(ClientError::UnknownBlock(format!("{}", hash))))
+	}
+
+	async fn read(
+		&self,
+		block: Option<Block::Hash>,
+		keys: Vec<StorageKey>,
+	) -> Result<Vec<Option<StorageData>>> {
+		let header = self.header(block)?;
+		let request = RemoteReadRequest {
+			block: header.hash(),
+			header,
+			keys: keys.iter().map(|key| key.0.clone()).collect(),
+		};
+		let mut values = self.fetcher.remote_read(request).await.map_err(client_err)?;
+		Ok(keys
+			.iter()
+			.map(|key| values.remove(&key.0).flatten().map(StorageData))
+			.collect())
+	}
+
+	async fn read_child(
+		&self,
+		block: Option<Block::Hash>,
+		storage_key: PrefixedStorageKey,
+		keys: Vec<StorageKey>,
+	) -> Result<Vec<Option<StorageData>>> {
+		let header = self.header(block)?;
+		let request = RemoteReadChildRequest {
+			block: header.hash(),
+			header,
+			storage_key,
+			keys: keys.iter().map(|key| key.0.clone()).collect(),
+		};
+		let mut values = self.fetcher.remote_read_child(request).await.map_err(client_err)?;
+		Ok(keys
+			.iter()
+			.map(|key| values.remove(&key.0).flatten().map(StorageData))
+			.collect())
+	}
+
+	async fn remote_call(
+		&self,
+		block: Option<Block::Hash>,
+		method: String,
+		call_data: Vec<u8>,
+	) -> Result<Vec<u8>> {
+		let header = self.header(block)?;
+		let request = RemoteCallRequest { block: header.hash(), header, method, call_data };
+		self.fetcher.remote_call(request).await.map_err(client_err)
+	}
+}
+
+#[async_trait]
+impl<Block, Client> StateBackend<Block, Client> for LightState<Block, Client>
+where
+	Block: BlockT + 'static,
+	Client: HeaderBackend<Block> + Send + Sync + 'static,
+{
+	async fn call(
+		&self,
+		block: Option<Block::Hash>,
+		method: String,
+		call_data: Bytes,
+	) -> Result<Bytes> {
+		self.remote_call(block, method, call_data.0).await.map(Into::into)
+	}
+
+	async fn storage_keys(
+		&self,
+		_block: Option<Block::Hash>,
+		_prefix: StorageKey,
+	) -> Result<Vec<StorageKey>> {
+		Err(not_available())
+	}
+
+	async fn storage_pairs(
+		&self,
+		_block: Option<Block::Hash>,
+		_prefix: StorageKey,
+	) -> Result<Vec<(StorageKey, StorageData)>> {
+		Err(not_available())
+	}
+
+	async fn storage_keys_paged(
+		&self,
+		_block: Option<Block::Hash>,
+		_prefix: Option<StorageKey>,
+		_count: u32,
+		_start_key: Option<StorageKey>,
+	) -> Result<Vec<StorageKey>> {
+		Err(not_available())
+	}
+
+	async fn storage(
+		&self,
+		block: Option<Block::Hash>,
+		key: StorageKey,
+	) -> Result<Option<StorageData>> {
+		Ok(self.read(block, vec![key]).await?.pop().flatten())
+	}
+
+	async fn storage_hash(
+		&self,
+		block: Option<Block::Hash>,
+		key: StorageKey,
+	) -> Result<Option<Block::Hash>> {
+		Ok(StateBackend::storage(self, block, key)
+			.await?
+			.map(|data| <HashFor<Block> as HashT>::hash(&data.0)))
+	}
+
+	/// Unlike on full nodes, the size of storage entries under `key` as a prefix is not summed
+	/// up, as that would require enumerating the keys.
+	async fn storage_size(
+		&self,
+		block: Option<Block::Hash>,
+		key: StorageKey,
+	) -> Result<Option<u64>> {
+		Ok(StateBackend::storage(self, block, key).await?.map(|data| data.0.len() as u64))
+	}
+
+	async fn metadata(&self, block: Option<Block::Hash>) -> Result<Bytes> {
+		let encoded = self.remote_call(block, "Metadata_metadata".into(), Vec::new()).await?;
+		OpaqueMetadata::decode(&mut &encoded[..])
+			.map(Into::into)
+			.map_err(|e| Error::Client(Box::new(e)))
+	}
+
+	async fn runtime_version(&self, block: Option<Block::Hash>) -> Result<RuntimeVersion> {
+		let encoded = self.remote_call(block, "Core_version".into(), Vec::new()).await?;
+		RuntimeVersion::decode(&mut &encoded[..]).map_err(|e| Error::Client(Box::new(e)))
+	}
+
+	async fn query_storage(
+		&self,
+		_from: Block::Hash,
+		_to: Option<Block::Hash>,
+		_keys: Vec<StorageKey>,
+	) -> Result<Vec<StorageChangeSet<Block::Hash>>> {
+		Err(not_available())
+	}
+
+	async fn query_storage_at(
+		&self,
+		keys: Vec<StorageKey>,
+		at: Option<Block::Hash>,
+	) -> Result<Vec<StorageChangeSet<Block::Hash>>> {
+		let block = self.header(at)?.hash();
+		let values = self.read(Some(block), keys.clone()).await?;
+		Ok(vec![StorageChangeSet { block, changes: keys.into_iter().zip(values).collect() }])
+	}
+
+	async fn read_proof(
+		&self,
+		_block: Option<Block::Hash>,
+		_keys: Vec<StorageKey>,
+	) -> Result<ReadProof<Block::Hash>> {
+		Err(not_available())
+	}
+
+	async fn trace_block(
+		&self,
+		_block: Block::Hash,
+		_targets: Option<String>,
+		_storage_keys: Option<String>,
+		_methods: Option<String>,
+	) -> Result<sp_rpc::tracing::TraceBlockResponse> {
+		Err(not_available())
+	}
+
+	fn subscribe_runtime_version(&self, pending: PendingSubscription) {
+		pending.reject(JsonRpseeError::from(not_available()));
+	}
+
+	fn subscribe_storage(&self, pending: PendingSubscription, _keys: Option<Vec<StorageKey>>) {
+		pending.reject(JsonRpseeError::from(not_available()));
+	}
+}
+
+#[async_trait]
+impl<Block, Client> ChildStateBackend<Block, Client> for LightState<Block, Client>
+where
+	Block: BlockT + 'static,
+	Client: HeaderBackend<Block> + Send + Sync + 'static,
+{
+	async fn read_child_proof(
+		&self,
+		_block: Option<Block::Hash>,
+		_storage_key: PrefixedStorageKey,
+		_keys: Vec<StorageKey>,
+	) -> Result<ReadProof<Block::Hash>> {
+		Err(not_available())
+	}
+
+	async fn storage_keys(
+		&self,
+		_block: Option<Block::Hash>,
+		_storage_key: PrefixedStorageKey,
+		_prefix: StorageKey,
+	) -> Result<Vec<StorageKey>> {
+		Err(not_available())
+	}
+
+	async fn storage_keys_paged(
+		&self,
+		_block: Option<Block::Hash>,
+		_storage_key: PrefixedStorageKey,
+		_prefix: Option<StorageKey>,
+		_count: u32,
+		_start_key: Option<StorageKey>,
+	) -> Result<Vec<StorageKey>> {
+		Err(not_available())
+	}
+
+	async fn storage(
+		&self,
+		block: Option<Block::Hash>,
+		storage_key: PrefixedStorageKey,
+		key: StorageKey,
+	) -> Result<Option<StorageData>> {
+		Ok(self.read_child(block, storage_key, vec![key]).await?.pop().flatten())
+	}
+
+	async fn storage_entries(
+		&self,
+		block: Option<Block::Hash>,
+		storage_key: PrefixedStorageKey,
+		keys: Vec<StorageKey>,
+	) -> Result<Vec<Option<StorageData>>> {
+		self.read_child(block, storage_key, keys).await
+	}
+
+	async fn storage_hash(
+		&self,
+		block: Option<Block::Hash>,
+		storage_key: PrefixedStorageKey,
+		key: StorageKey,
+	) -> Result<Option<Block::Hash>> {
+		Ok(ChildStateBackend::storage(self, block, storage_key, key)
+			.await?
+			.map(|data| <HashFor<Block> as HashT>::hash(&data.0)))
+	}
+}
+
+fn not_available() -> Error {
+	client_err(ClientError::NotAvailableOnLightClient)
+}
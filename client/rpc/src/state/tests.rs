@@ -20,16 +20,28 @@ use self::error::Error;
 use super::*;
 use crate::testing::{test_executor, timeout_secs};
 use assert_matches::assert_matches;
-use futures::executor;
+use futures::{executor, future::BoxFuture, FutureExt};
 use jsonrpsee::{
 	core::Error as RpcError,
 	types::{error::CallError as RpcCallError, EmptyParams, ErrorObject},
 };
 use sc_block_builder::BlockBuilderProvider;
+use sc_client_api::{
+	light::{RemoteCallRequest, RemoteReadChildRequest, RemoteReadRequest, RemoteReadResult},
+	CallContext, CallExecutor,
+};
 use sc_rpc_api::DenyUnsafe;
 use sp_consensus::BlockOrigin;
-use sp_core::{hash::H256, storage::ChildInfo};
+use sp_core::{
+	hash::H256,
+	storage::{ChildInfo, ChildType},
+	ExecutionStrategy,
+};
 use sp_io::hashing::blake2_256;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{BlakeTwo256, Hash as _},
+};
 use std::sync::Arc;
 use substrate_test_runtime_client::{prelude::*, runtime};
 
@@ -503,7 +515,8 @@ async fn should_return_runtime_version() {
 		\"specVersion\":2,\"implVersion\":2,\"apis\":[[\"0xdf6acb689907609b\",4],\
 		[\"0x37e397fc7c91f5e4\",1],[\"0xd2bc9897eed08f15\",3],[\"0x40fe3ad401f8959a\",6],\
 		[\"0xc6e9a76309f39b09\",1],[\"0xdd718d5cc53262d4\",1],[\"0xcbca25e39f142387\",2],\
-		[\"0xf78b278be53f454c\",2],[\"0xab3c0572291feb8b\",1],[\"0xbc9d89904f5b923f\",1]],\
+		[\"0xf78b278be53f454c\",2],[\"0xab3c0572291feb8b\",1],[\"0xbc9d89904f5b923f\",1],\
+		[\"0x91b1c8b16328eb92\",1]],\
 		\"transactionVersion\":1,\"stateVersion\":1}";
 
 	let runtime_version = api.runtime_version(None.into()).await.unwrap();
@@ -565,3 +578,147 @@ async fn concrete_storage_subscriptions_are_rpc_safe() {
 
 	assert!(sub.is_ok());
 }
+
+/// Answers the fetches of a light client from a local full client, without proofs.
+struct LocalFetcher(Arc<TestClient>);
+
+impl RemoteFetcher<runtime::Block> for LocalFetcher {
+	fn remote_read(
+		&self,
+		request: RemoteReadRequest<runtime::Header>,
+	) -> BoxFuture<'static, sp_blockchain::Result<RemoteReadResult>> {
+		let at = BlockId::Hash(request.block);
+		let values = request
+			.keys
+			.into_iter()
+			.map(|key| {
+				let value = self.0.storage(&at, &StorageKey(key.clone()))?;
+				Ok((key, value.map(|data| data.0)))
+			})
+			.collect();
+		futures::future::ready(values).boxed()
+	}
+
+	fn remote_read_child(
+		&self,
+		request: RemoteReadChildRequest<runtime::Header>,
+	) -> BoxFuture<'static, sp_blockchain::Result<RemoteReadResult>> {
+		let at = BlockId::Hash(request.block);
+		let child_info = match ChildType::from_prefixed_key(&request.storage_key) {
+			Some((ChildType::ParentKeyId, storage_key)) => ChildInfo::new_default(storage_key),
+			None => panic!("Invalid child storage key"),
+		};
+		let values = request
+			.keys
+			.into_iter()
+			.map(|key| {
+				let value = self.0.child_storage(&at, &child_info, &StorageKey(key.clone()))?;
+				Ok((key, value.map(|data| data.0)))
+			})
+			.collect();
+		futures::future::ready(values).boxed()
+	}
+
+	fn remote_call(
+		&self,
+		request: RemoteCallRequest<runtime::Header>,
+	) -> BoxFuture<'static, sp_blockchain::Result<Vec<u8>>> {
+		let result = self.0.executor().call(
+			&BlockId::Hash(request.block),
+			&request.method,
+			&request.call_data,
+			ExecutionStrategy::NativeElseWasm,
+			None,
+			CallContext::Rpc,
+		);
+		futures::future::ready(result).boxed()
+	}
+}
+
+fn new_light_test(
+	client: TestClient,
+) -> (Arc<TestClient>, StateApi<runtime::Block, TestClient>, ChildState<runtime::Block, TestClient>)
+{
+	let client = Arc::new(client);
+	let fetcher = Arc::new(LocalFetcher(client.clone()));
+	let (api, child) = new_light(client.clone(), fetcher, DenyUnsafe::No);
+	(client, api, child)
+}
+
+#[tokio::test]
+async fn light_should_return_storage_through_fetcher() {
+	const KEY: &[u8] = b":mock";
+	const VALUE: &[u8] = b"hello world";
+	const CHILD_VALUE: &[u8] = b"hello world !";
+
+	let child_info = ChildInfo::new_default(STORAGE_KEY);
+	let (client, api, child) = new_light_test(
+		TestClientBuilder::new()
+			.add_extra_storage(KEY.to_vec(), VALUE.to_vec())
+			.add_extra_child_storage(&child_info, KEY.to_vec(), CHILD_VALUE.to_vec())
+			.build(),
+	);
+	let genesis_hash = client.genesis_hash();
+	let key = StorageKey(KEY.to_vec());
+
+	assert_eq!(
+		api.storage(key.clone(), Some(genesis_hash)).await.unwrap(),
+		Some(StorageData(VALUE.to_vec())),
+	);
+	assert_eq!(api.storage_hash(key.clone(), None).await.unwrap(), Some(BlakeTwo256::hash(VALUE)),);
+	assert_eq!(api.storage_size(key.clone(), None).await.unwrap(), Some(VALUE.len() as u64));
+	assert_eq!(api.storage(StorageKey(b":unknown".to_vec()), None).await.unwrap(), None);
+	assert_eq!(
+		api.query_storage_at(vec![key.clone()], Some(genesis_hash)).await.unwrap(),
+		vec![StorageChangeSet {
+			block: genesis_hash,
+			changes: vec![(key.clone(), Some(StorageData(VALUE.to_vec())))],
+		}],
+	);
+	assert_eq!(
+		child.storage(prefixed_storage_key(), key.clone(), None).await.unwrap(),
+		Some(StorageData(CHILD_VALUE.to_vec())),
+	);
+	assert_eq!(
+		child
+			.storage_entries(
+				prefixed_storage_key(),
+				vec![key, StorageKey(b":unknown".to_vec())],
+				Some(genesis_hash),
+			)
+			.await
+			.unwrap(),
+		vec![Some(StorageData(CHILD_VALUE.to_vec())), None],
+	);
+}
+
+#[tokio::test]
+async fn light_should_call_runtime_through_fetcher() {
+	let (client, api, _child) = new_light_test(TestClientBuilder::new().build());
+
+	assert_eq!(
+		api.runtime_version(None).await.unwrap(),
+		client.runtime_version_at(&BlockId::Number(0)).unwrap(),
+	);
+	assert_matches!(api.call("balanceOf".into(), Bytes(vec![1, 2, 3]), None).await, Err(_));
+}
+
+#[tokio::test]
+async fn light_should_reject_methods_needing_local_state() {
+	let (_client, api, child) = new_light_test(TestClientBuilder::new().build());
+
+	assert_matches!(api.storage_keys_paged(None, 10, None, None).await, Err(_));
+	assert_matches!(api.read_proof(vec![StorageKey(b":code".to_vec())], None).await, Err(_));
+	assert_matches!(
+		child.storage_keys_paged(prefixed_storage_key(), None, 10, None, None).await,
+		Err(_)
+	);
+	assert_matches!(
+		api.storage(StorageKey(b":code".to_vec()), Some(H256::repeat_byte(1))).await,
+		Err(_)
+	);
+
+	let api_rpc = api.into_rpc();
+	let key = StorageKey(STORAGE_KEY.to_vec());
+	assert_matches!(api_rpc.subscribe("state_subscribeStorage", [[key]]).await, Err(_));
+}
@@ -29,11 +29,12 @@ use jsonrpsee::{
 use sc_rpc_api::DenyUnsafe;
 use sc_tracing::logging;
 use sc_utils::mpsc::TracingUnboundedSender;
+use sp_core::Bytes;
 use sp_runtime::traits::{self, Header as HeaderT};
 
 use self::error::Result;
 
-pub use self::helpers::{Health, NodeRole, PeerInfo, SyncState, SystemInfo};
+pub use self::helpers::{Health, NodeRole, PeerInfo, StateDownload, SyncState, SystemInfo};
 pub use sc_rpc_api::system::*;
 
 /// System API implementation
@@ -62,6 +63,8 @@ pub enum Request<B: traits::Block> {
 	NetworkRemoveReservedPeer(String, oneshot::Sender<Result<()>>),
 	/// Must return the list of reserved peers
 	NetworkReservedPeers(oneshot::Sender<Vec<String>>),
+	/// Must return the content of the CID, fetched over bitswap.
+	BitswapFetch(String, oneshot::Sender<Result<Vec<u8>>>),
 	/// Must return the node role.
 	NodeRoles(oneshot::Sender<Vec<NodeRole>>),
 	/// Must return the state of the node syncing.
@@ -166,6 +169,17 @@ impl<B: traits::Block> SystemApiServer<B::Hash, <B::Header as HeaderT>::Number>
 		rx.await.map_err(|e| JsonRpseeError::to_call_error(e))
 	}
 
+	async fn system_bitswap_fetch(&self, cid: String) -> RpcResult<Bytes> {
+		self.deny_unsafe.check_if_safe()?;
+		let (tx, rx) = oneshot::channel();
+		let _ = self.send_back.unbounded_send(Request::BitswapFetch(cid, tx));
+		match rx.await {
+			Ok(Ok(data)) => Ok(data.into()),
+			Ok(Err(e)) => Err(JsonRpseeError::from(e)),
+			Err(e) => Err(JsonRpseeError::to_call_error(e)),
+		}
+	}
+
 	async fn system_node_roles(&self) -> RpcResult<Vec<NodeRole>> {
 		let (tx, rx) = oneshot::channel();
 		let _ = self.send_back.unbounded_send(Request::NodeRoles(tx));
@@ -94,6 +94,7 @@ fn api<T: Into<Option<Status>>>(sync: T) -> RpcModule<System<Block>> {
 							connected_peers: Default::default(),
 							not_connected_peers: Default::default(),
 							peerset: serde_json::Value::Null,
+							protocol_bandwidth: Default::default(),
 						})
 						.unwrap(),
 					);
@@ -116,6 +117,12 @@ fn api<T: Into<Option<Status>>>(sync: T) -> RpcModule<System<Block>> {
 					let _ = sender
 						.send(vec!["QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV".to_string()]);
 				},
+				Request::BitswapFetch(cid, sender) => {
+					let _ = match sc_network::Cid::try_from(cid.as_str()) {
+						Ok(_) => sender.send(Ok(b"hello".to_vec())),
+						Err(e) => sender.send(Err(error::Error::MalformattedCid(e.to_string()))),
+					};
+				},
 				Request::NodeRoles(sender) => {
 					let _ = sender.send(vec![NodeRole::Authority]);
 				},
@@ -124,6 +131,7 @@ fn api<T: Into<Option<Status>>>(sync: T) -> RpcModule<System<Block>> {
 						starting_block: 1,
 						current_block: 2,
 						highest_block: Some(3),
+						state_download: None,
 					});
 				},
 			};
@@ -283,6 +291,7 @@ async fn system_network_state() {
 			connected_peers: Default::default(),
 			not_connected_peers: Default::default(),
 			peerset: serde_json::Value::Null,
+			protocol_bandwidth: Default::default(),
 		}
 	);
 }
@@ -299,7 +308,12 @@ async fn system_sync_state() {
 		api(None).call("system_syncState", EmptyParams::new()).await.unwrap();
 	assert_eq!(
 		sync_state,
-		SyncState { starting_block: 1, current_block: 2, highest_block: Some(3) }
+		SyncState {
+			starting_block: 1,
+			current_block: 2,
+			highest_block: Some(3),
+			state_download: None
+		}
 	);
 }
 
@@ -334,6 +348,24 @@ async fn system_network_remove_reserved() {
 		Err(RpcError::Call(CallError::Custom(err))) if err.message().contains("base-58 decode error: provided string contained invalid character '/' at byte 0")
 	);
 }
+
+#[tokio::test]
+async fn system_bitswap_fetch() {
+	let data: sp_core::Bytes = api(None)
+		.call(
+			"system_bitswapFetch",
+			["bafkreigh2akiscaildcqabsyg3dfr6chu3fgpregiymsck7e7aqa4s52zy"],
+		)
+		.await
+		.expect("call with good CID works");
+	assert_eq!(data.0, b"hello".to_vec());
+
+	assert_matches!(
+		api(None).call::<_, sp_core::Bytes>("system_bitswapFetch", ["not a cid"]).await,
+		Err(RpcError::Call(CallError::Custom(_)))
+	);
+}
+
 #[tokio::test]
 async fn system_network_reserved_peers() {
 	let reserved_peers: Vec<String> =
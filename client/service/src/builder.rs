@@ -30,8 +30,9 @@ use log::info;
 use prometheus_endpoint::Registry;
 use sc_chain_spec::get_extension;
 use sc_client_api::{
-	execution_extensions::ExecutionExtensions, proof_provider::ProofProvider, BadBlocks,
-	BlockBackend, BlockchainEvents, ExecutorProvider, ForkBlocks, StorageProvider, UsageProvider,
+	execution_extensions::ExecutionExtensions, light::RemoteFetcher, proof_provider::ProofProvider,
+	BadBlocks, BlockBackend, BlockchainEvents, ExecutorProvider, ForkBlocks, StorageProvider,
+	UsageProvider,
 };
 use sc_client_db::{Backend, DatabaseSettings};
 use sc_consensus::import_queue::ImportQueue;
@@ -40,7 +41,9 @@ use sc_keystore::LocalKeystore;
 use sc_network::{
 	block_request_handler::{self, BlockRequestHandler},
 	config::{Role, SyncMode},
-	light_client_requests::{self, handler::LightClientRequestHandler},
+	light_client_requests::{
+		self, handler::LightClientRequestHandler, sender::LightClientRequestSender,
+	},
 	state_request_handler::{self, StateRequestHandler},
 	warp_request_handler::{self, RequestHandler as WarpSyncRequestHandler, WarpSyncProvider},
 	NetworkService,
@@ -250,6 +253,7 @@ where
 				offchain_worker_enabled: config.offchain_worker.enabled,
 				offchain_indexing_api: config.offchain_worker.indexing_enabled,
 				wasm_runtime_overrides: config.wasm_runtime_overrides.clone(),
+				call_recording: config.runtime_call_recording.clone(),
 				no_genesis: matches!(
 					config.network.sync_mode,
 					SyncMode::Fast { .. } | SyncMode::Warp { .. }
@@ -306,7 +310,8 @@ where
 		executor,
 		spawn_handle,
 		config.clone(),
-	)?;
+	)?
+	.with_prometheus_registry(prometheus_registry.as_ref());
 	crate::client::Client::new(
 		backend,
 		executor,
@@ -343,6 +348,40 @@ pub struct SpawnTasksParams<'a, TBl: BlockT, TCl, TExPool, TRpc, Backend> {
 	pub system_rpc_tx: TracingUnboundedSender<sc_rpc::system::Request<TBl>>,
 	/// Telemetry instance for this node.
 	pub telemetry: Option<&'a mut Telemetry>,
+	/// Fetcher serving the state RPCs of a light client, see [`build_light_client_fetcher`].
+	///
+	/// The state RPCs read from the local database when this is `None`.
+	pub light_client_fetcher: Option<Arc<dyn RemoteFetcher<TBl>>>,
+}
+
+/// Build the fetcher a light client uses to answer state queries.
+///
+/// Returns `None` unless the node runs as a light client. Storage reads and runtime calls are
+/// requested from full peers over the light client request protocol, and the returned proofs are
+/// checked against locally imported headers. `executor` runs runtime calls on execution proofs.
+pub fn build_light_client_fetcher<TBl, TCl, TExec>(
+	config: &Configuration,
+	client: Arc<TCl>,
+	network: Arc<NetworkService<TBl, <TBl as BlockT>::Hash>>,
+	executor: TExec,
+	spawn_handle: SpawnTaskHandle,
+) -> Option<Arc<dyn RemoteFetcher<TBl>>>
+where
+	TBl: BlockT,
+	TCl: HeaderBackend<TBl> + 'static,
+	TExec: CodeExecutor + Clone + 'static,
+{
+	if !config.role.is_light() {
+		return None
+	}
+
+	Some(Arc::new(LightClientRequestSender::new(
+		&config.protocol_id(),
+		client,
+		network,
+		executor,
+		Box::new(spawn_handle),
+	)))
 }
 
 /// Build a shared offchain workers instance.
@@ -357,7 +396,16 @@ where
 	TCl: Send + Sync + ProvideRuntimeApi<TBl> + BlockchainEvents<TBl> + 'static,
 	<TCl as ProvideRuntimeApi<TBl>>::Api: sc_offchain::OffchainWorkerApi<TBl>,
 {
-	let offchain_workers = Some(Arc::new(sc_offchain::OffchainWorkers::new(client.clone())));
+	let offchain_workers = Some(Arc::new(sc_offchain::OffchainWorkers::new_with_options(
+		client.clone(),
+		sc_offchain::OffchainWorkerOptions {
+			enable_http_requests: true,
+			http_policy: config.offchain_worker.http_policy.clone(),
+			http_backend: config.offchain_worker.http_backend.clone(),
+			scheduling: config.offchain_worker.scheduling.clone(),
+			prometheus_registry: config.prometheus_registry().cloned(),
+		},
+	)));
 
 	// Inform the offchain worker about new imported blocks
 	if let Some(offchain) = offchain_workers.clone() {
@@ -420,6 +468,7 @@ where
 		network,
 		system_rpc_tx,
 		telemetry,
+		light_client_fetcher,
 	} = params;
 
 	let chain_info = client.usage_info().chain;
@@ -493,6 +542,7 @@ where
 			system_rpc_tx.clone(),
 			&config,
 			backend.offchain_storage(),
+			light_client_fetcher.clone(),
 			&*rpc_builder,
 		)
 	};
@@ -585,6 +635,7 @@ fn gen_rpc_module<TBl, TBackend, TCl, TRpc, TExPool>(
 	system_rpc_tx: TracingUnboundedSender<sc_rpc::system::Request<TBl>>,
 	config: &Configuration,
 	offchain_storage: Option<<TBackend as sc_client_api::backend::Backend<TBl>>::OffchainStorage>,
+	light_client_fetcher: Option<Arc<dyn RemoteFetcher<TBl>>>,
 	rpc_builder: &(dyn Fn(DenyUnsafe, SubscriptionTaskExecutor) -> Result<RpcModule<TRpc>, Error>),
 ) -> Result<RpcModule<()>, Error>
 where
@@ -620,12 +671,15 @@ where
 
 	let (chain, state, child_state) = {
 		let chain = sc_rpc::chain::new_full(client.clone(), task_executor.clone()).into_rpc();
-		let (state, child_state) = sc_rpc::state::new_full(
-			client.clone(),
-			task_executor.clone(),
-			deny_unsafe,
-			config.rpc_max_payload,
-		);
+		let (state, child_state) = match light_client_fetcher {
+			Some(fetcher) => sc_rpc::state::new_light(client.clone(), fetcher, deny_unsafe),
+			None => sc_rpc::state::new_full(
+				client.clone(),
+				task_executor.clone(),
+				deny_unsafe,
+				config.rpc_max_payload,
+			),
+		};
 		let state = state.into_rpc();
 		let child_state = child_state.into_rpc();
 
@@ -752,6 +806,7 @@ where
 				client.clone(),
 				config.network.default_peers_set.in_peers as usize +
 					config.network.default_peers_set.out_peers as usize,
+				config.network.request_quotas.block_requests.clone(),
 			);
 			spawn_handle.spawn("block-request-handler", Some("networking"), handler.run());
 			protocol_config
@@ -768,6 +823,7 @@ where
 				&protocol_id,
 				client.clone(),
 				config.network.default_peers_set_num_full as usize,
+				config.network.request_quotas.state_requests.clone(),
 			);
 			spawn_handle.spawn("state-request-handler", Some("networking"), handler.run());
 			protocol_config
@@ -780,8 +836,11 @@ where
 			warp_request_handler::generate_request_response_config(protocol_id.clone())
 		} else {
 			// Allow both outgoing and incoming requests.
-			let (handler, protocol_config) =
-				WarpSyncRequestHandler::new(protocol_id.clone(), provider.clone());
+			let (handler, protocol_config) = WarpSyncRequestHandler::new(
+				protocol_id.clone(),
+				provider.clone(),
+				config.network.request_quotas.warp_sync_requests.clone(),
+			);
 			spawn_handle.spawn("warp-sync-request-handler", Some("networking"), handler.run());
 			protocol_config
 		};
@@ -794,8 +853,11 @@ where
 			light_client_requests::generate_protocol_config(&protocol_id)
 		} else {
 			// Allow both outgoing and incoming requests.
-			let (handler, protocol_config) =
-				LightClientRequestHandler::new(&protocol_id, client.clone());
+			let (handler, protocol_config) = LightClientRequestHandler::new(
+				&protocol_id,
+				client.clone(),
+				config.network.request_quotas.light_client_requests.clone(),
+			);
 			spawn_handle.spawn("light-client-request-handler", Some("networking"), handler.run());
 			protocol_config
 		}
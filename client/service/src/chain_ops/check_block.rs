@@ -19,12 +19,25 @@
 use crate::error::Error;
 use codec::Encode;
 use futures::{future, prelude::*};
-use sc_client_api::{BlockBackend, HeaderBackend};
+use sc_block_builder::{BlockBuilderApi, BlockBuilderProvider};
+use sc_client_api::{backend, BlockBackend, HeaderBackend};
 use sc_consensus::import_queue::ImportQueue;
-use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_runtime::{
+	generic::{BlockId, Digest},
+	traits::{Block as BlockT, Header as HeaderT, NumberFor},
+	SaturatedConversion,
+};
 
 use crate::chain_ops::import_blocks;
-use std::{pin::Pin, sync::Arc};
+use std::{
+	pin::Pin,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		mpsc, Arc,
+	},
+	time::{Duration, Instant},
+};
 
 /// Re-validate known block.
 pub fn check_block<B, IQ, C>(
@@ -49,3 +62,222 @@ where
 		Err(e) => Box::pin(future::err(format!("Error reading block: {}", e).into())),
 	}
 }
+
+/// Outcome of re-executing a block, see [`check_block_range`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockCheckOutcome<Hash> {
+	/// The computed state root matches the one in the header.
+	Ok,
+	/// The block executed, but resulted in a different state root.
+	StateRootMismatch {
+		/// State root found in the header.
+		expected: Hash,
+		/// State root computed by executing the block.
+		computed: Hash,
+	},
+	/// The block could not be executed.
+	Failed(String),
+}
+
+/// Result of re-executing a single block, see [`check_block_range`].
+#[derive(Debug, Clone)]
+pub struct BlockCheck<B: BlockT> {
+	/// Number of the block.
+	pub number: NumberFor<B>,
+	/// Hash of the block.
+	pub hash: B::Hash,
+	/// Whether the execution produced the expected state root.
+	pub outcome: BlockCheckOutcome<B::Hash>,
+	/// Time spent executing the block.
+	pub duration: Duration,
+}
+
+/// Re-execute all blocks from `from` to `to` (inclusive) on top of their parent state.
+///
+/// Blocks are executed on `jobs` threads in parallel and `on_checked` is called for every block
+/// as soon as it was checked, so blocks are not necessarily reported in order. The state of
+/// every parent block has to be available, which usually requires an archive node.
+pub fn check_block_range<B, BA, C>(
+	client: Arc<C>,
+	from: NumberFor<B>,
+	to: NumberFor<B>,
+	jobs: usize,
+	mut on_checked: impl FnMut(BlockCheck<B>) -> Result<(), Error>,
+) -> Result<(), Error>
+where
+	B: BlockT,
+	BA: backend::Backend<B> + 'static,
+	C: BlockBackend<B>
+		+ HeaderBackend<B>
+		+ BlockBuilderProvider<BA, B, C>
+		+ ProvideRuntimeApi<B>
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: ApiExt<B, StateBackend = backend::StateBackendFor<BA, B>> + BlockBuilderApi<B>,
+{
+	let (tx, rx) = mpsc::channel();
+	let next = Arc::new(AtomicU64::new(from.saturated_into()));
+	let workers = (0..jobs.max(1))
+		.map(|_| spawn_check_worker(client.clone(), next.clone(), to, tx.clone()))
+		.collect::<Vec<_>>();
+	drop(tx);
+
+	for check in rx {
+		on_checked(check?)?;
+	}
+
+	for worker in workers {
+		worker.join().map_err(|_| Error::Other("Block check worker panicked".into()))?;
+	}
+
+	Ok(())
+}
+
+fn spawn_check_worker<B, BA, C>(
+	client: Arc<C>,
+	next: Arc<AtomicU64>,
+	to: NumberFor<B>,
+	tx: mpsc::Sender<Result<BlockCheck<B>, Error>>,
+) -> std::thread::JoinHandle<()>
+where
+	B: BlockT,
+	BA: backend::Backend<B> + 'static,
+	C: BlockBackend<B>
+		+ HeaderBackend<B>
+		+ BlockBuilderProvider<BA, B, C>
+		+ ProvideRuntimeApi<B>
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: ApiExt<B, StateBackend = backend::StateBackendFor<BA, B>> + BlockBuilderApi<B>,
+{
+	let to: u64 = to.saturated_into();
+
+	std::thread::spawn(move || loop {
+		let number = next.fetch_add(1, Ordering::Relaxed);
+		// Stop once the range is done or the receiver gave up.
+		if number > to || tx.send(check_single_block(&*client, number.saturated_into())).is_err() {
+			break
+		}
+	})
+}
+
+fn check_single_block<B, BA, C>(client: &C, number: NumberFor<B>) -> Result<BlockCheck<B>, Error>
+where
+	B: BlockT,
+	BA: backend::Backend<B>,
+	C: BlockBackend<B> + HeaderBackend<B> + BlockBuilderProvider<BA, B, C> + ProvideRuntimeApi<B>,
+	C::Api: ApiExt<B, StateBackend = backend::StateBackendFor<BA, B>> + BlockBuilderApi<B>,
+{
+	let hash = client.hash(number)?.ok_or_else(|| format!("Unknown block #{}", number))?;
+	let block = client
+		.block(&BlockId::Hash(hash))?
+		.ok_or_else(|| format!("Missing body of block #{}", number))?
+		.block;
+	let (header, extrinsics) = block.deconstruct();
+
+	// The seal is added after the block was executed, everything else is known to the runtime.
+	let digest = Digest {
+		logs: header
+			.digest()
+			.logs()
+			.iter()
+			.filter(|d| d.as_seal().is_none())
+			.cloned()
+			.collect(),
+	};
+
+	let started = Instant::now();
+	let built = client
+		.new_block_at(&BlockId::Hash(*header.parent_hash()), digest, false)
+		.and_then(|mut builder| {
+			extrinsics.into_iter().try_for_each(|xt| builder.push(xt))?;
+			builder.build()
+		});
+	let duration = started.elapsed();
+
+	let outcome = match built {
+		Ok(built) if built.block.header().state_root() == header.state_root() =>
+			BlockCheckOutcome::Ok,
+		Ok(built) => BlockCheckOutcome::StateRootMismatch {
+			expected: *header.state_root(),
+			computed: *built.block.header().state_root(),
+		},
+		Err(e) => BlockCheckOutcome::Failed(e.to_string()),
+	};
+
+	Ok(BlockCheck { number, hash, outcome, duration })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::executor::block_on;
+	use sp_consensus::BlockOrigin;
+	use substrate_test_runtime_client::{
+		prelude::*,
+		runtime::{Block, Transfer},
+	};
+
+	fn client_with_blocks(count: u64) -> Arc<substrate_test_runtime_client::TestClient> {
+		let mut client = Arc::new(substrate_test_runtime_client::new());
+
+		for nonce in 0..count {
+			let mut builder = client.new_block(Default::default()).unwrap();
+			builder
+				.push_transfer(Transfer {
+					from: AccountKeyring::Alice.into(),
+					to: AccountKeyring::Bob.into(),
+					amount: 1,
+					nonce,
+				})
+				.unwrap();
+			let block = builder.build().unwrap().block;
+			block_on(client.import(BlockOrigin::Own, block)).unwrap();
+		}
+
+		client
+	}
+
+	#[test]
+	fn check_block_range_reexecutes_every_block() {
+		let client = client_with_blocks(4);
+
+		let mut checks = Vec::new();
+		check_block_range(client, 1, 4, 2, |check: BlockCheck<Block>| {
+			checks.push(check);
+			Ok(())
+		})
+		.unwrap();
+
+		checks.sort_by_key(|check| check.number);
+		assert_eq!(checks.iter().map(|check| check.number).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+		assert!(checks.iter().all(|check| check.outcome == BlockCheckOutcome::Ok));
+	}
+
+	#[test]
+	fn check_block_range_fails_on_unknown_blocks() {
+		let client = client_with_blocks(1);
+
+		let mut checked = 0;
+		let result = check_block_range(client, 1, 2, 1, |_: BlockCheck<Block>| {
+			checked += 1;
+			Ok(())
+		});
+
+		assert!(result.unwrap_err().to_string().contains("Unknown block #2"));
+		assert_eq!(checked, 1);
+	}
+
+	#[test]
+	fn check_single_block_reexecutes_block_on_parent_state() {
+		let client = client_with_blocks(2);
+
+		let check = check_single_block(&*client, 2).unwrap();
+
+		assert_eq!(check.hash, client.hash(2).unwrap().unwrap());
+		assert_eq!(check.outcome, BlockCheckOutcome::Ok);
+		assert!(check_single_block(&*client, 3).is_err());
+	}
+}
@@ -0,0 +1,393 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::error::Error;
+use codec::Encode;
+use futures::{future, prelude::*};
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sp_core::hexdisplay::HexDisplay;
+use sp_runtime::{
+	generic::{BlockId, SignedBlock},
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, One, SaturatedConversion, Zero},
+};
+
+use sc_client_api::{BlockBackend, UsageProvider};
+use std::{
+	fs,
+	io::Write,
+	path::{Path, PathBuf},
+	pin::Pin,
+	sync::Arc,
+	task::Poll,
+};
+
+/// Number of blocks after which the export cursor is persisted.
+const CURSOR_INTERVAL: u64 = 100;
+
+/// A decoded extrinsic, see [`BlockDecoder`].
+#[derive(Debug, Clone)]
+pub struct DecodedExtrinsic {
+	/// Name of the pallet the call belongs to, if known.
+	pub pallet: Option<String>,
+	/// Name of the call, if known.
+	pub call: Option<String>,
+	/// JSON representation of the extrinsic.
+	pub value: Value,
+}
+
+impl DecodedExtrinsic {
+	/// An extrinsic that isn't decoded, represented as hex encoded bytes.
+	pub fn opaque(xt: &impl Encode) -> Self {
+		Self {
+			pallet: None,
+			call: None,
+			value: format!("0x{}", HexDisplay::from(&xt.encode())).into(),
+		}
+	}
+}
+
+/// A decoded event, see [`BlockDecoder`].
+#[derive(Debug, Clone)]
+pub struct DecodedEvent {
+	/// Index of the extrinsic that emitted the event, if any.
+	pub extrinsic_index: Option<u32>,
+	/// JSON representation of the event.
+	pub value: Value,
+}
+
+/// Decodes the contents of blocks for [`export_blocks_ndjson`].
+pub trait BlockDecoder<B: BlockT> {
+	/// Decode the extrinsics of `block`, returns one result per extrinsic.
+	///
+	/// Extrinsics that fail to decode are exported as hex together with the error.
+	fn extrinsics(&self, block: &B) -> Vec<Result<DecodedExtrinsic, String>>;
+
+	/// Decode the events emitted while executing the block with the given `hash`.
+	fn events(&self, hash: B::Hash) -> Result<Vec<DecodedEvent>, String>;
+}
+
+/// A [`BlockDecoder`] that doesn't know about the runtime.
+///
+/// Extrinsics are exported as hex encoded bytes and events are not exported.
+pub struct OpaqueBlockDecoder;
+
+impl<B: BlockT> BlockDecoder<B> for OpaqueBlockDecoder {
+	fn extrinsics(&self, block: &B) -> Vec<Result<DecodedExtrinsic, String>> {
+		block.extrinsics().iter().map(|xt| Ok(DecodedExtrinsic::opaque(xt))).collect()
+	}
+
+	fn events(&self, _: B::Hash) -> Result<Vec<DecodedEvent>, String> {
+		Ok(Vec::new())
+	}
+}
+
+/// Selects the extrinsics that are exported by [`export_blocks_ndjson`].
+///
+/// An empty list matches everything. Blocks without any matching extrinsic are skipped, unless
+/// no filter is set at all.
+#[derive(Debug, Clone, Default)]
+pub struct ExtrinsicFilter {
+	/// Names of the pallets whose calls are exported.
+	pub pallets: Vec<String>,
+	/// Names of the calls that are exported.
+	pub calls: Vec<String>,
+}
+
+impl ExtrinsicFilter {
+	/// Returns `true` if no filter is set.
+	pub fn is_empty(&self) -> bool {
+		self.pallets.is_empty() && self.calls.is_empty()
+	}
+
+	fn matches(&self, xt: &DecodedExtrinsic) -> bool {
+		let matches = |names: &[String], name: &Option<String>| {
+			names.is_empty() || name.as_ref().map_or(false, |name| names.contains(name))
+		};
+
+		matches(&self.pallets, &xt.pallet) && matches(&self.calls, &xt.call)
+	}
+}
+
+/// Position of a NDJSON export, used to resume an interrupted export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportCursor {
+	/// Number of the next block to export.
+	pub next: u64,
+	/// Length of the output written up to the block before `next`.
+	pub offset: u64,
+}
+
+impl ExportCursor {
+	/// Read the cursor stored at `path`, returns `None` if there is no cursor yet.
+	pub fn load(path: &Path) -> Result<Option<Self>, Error> {
+		if !path.exists() {
+			return Ok(None)
+		}
+
+		let data = fs::read(path)?;
+		serde_json::from_slice(&data)
+			.map(Some)
+			.map_err(|e| format!("Error parsing export cursor: {}", e).into())
+	}
+
+	fn store(&self, path: &Path) -> Result<(), Error> {
+		let data = serde_json::to_vec(self).map_err(|e| format!("Error writing cursor: {}", e))?;
+		// Write to a temporary file first, so that an interrupted write never corrupts the cursor.
+		let tmp = path.with_extension("tmp");
+		fs::write(&tmp, data)?;
+		fs::rename(tmp, path)?;
+		Ok(())
+	}
+}
+
+/// Performs the blocks export as newline delimited JSON, one block per line.
+///
+/// Every line contains the header, the extrinsics and events decoded by `decoder` and the
+/// justifications of a block. Extrinsics not matching `filter` are left out. Extrinsics that
+/// can't be decoded are exported as hex with an `error`, if the events can't be read they are
+/// exported as `null` with an `eventsError`. If `cursor` is
+/// given, the position of the export is regularly stored at that path. `offset` is the length of
+/// the output already written by a previous run.
+pub fn export_blocks_ndjson<B, C, D>(
+	client: Arc<C>,
+	mut output: impl Write + 'static,
+	from: NumberFor<B>,
+	to: Option<NumberFor<B>>,
+	decoder: D,
+	filter: ExtrinsicFilter,
+	cursor: Option<(PathBuf, u64)>,
+) -> Pin<Box<dyn Future<Output = Result<(), Error>>>>
+where
+	C: BlockBackend<B> + UsageProvider<B> + 'static,
+	B: BlockT,
+	D: BlockDecoder<B> + 'static,
+{
+	let mut block = from;
+	let last = to.unwrap_or_else(|| client.usage_info().chain.best_number);
+	let (cursor, mut offset) = match cursor {
+		Some((path, offset)) => (Some(path), offset),
+		None => (None, 0),
+	};
+
+	info!("Exporting blocks from #{} to #{}", block, last);
+
+	// See `export_blocks` on why this is implemented as a future.
+	let export = future::poll_fn(move |cx| {
+		if last < block {
+			return Poll::Ready(Ok(()))
+		}
+
+		let signed_block = match client.block(&BlockId::number(block))? {
+			Some(signed_block) => signed_block,
+			// Reached end of the chain.
+			None => return Poll::Ready(Ok(())),
+		};
+
+		if let Some(line) = block_line(&signed_block, &decoder, &filter)? {
+			let mut line =
+				serde_json::to_vec(&line).map_err(|e| format!("Error writing JSON: {}", e))?;
+			line.push(b'\n');
+			output.write_all(&line)?;
+			offset += line.len() as u64;
+		}
+
+		if (block % 10000u32.into()).is_zero() {
+			info!("#{}", block);
+		}
+
+		let next = block.saturated_into::<u64>() + 1;
+		if let Some(path) = &cursor {
+			if next % CURSOR_INTERVAL == 0 || block == last {
+				output.flush()?;
+				ExportCursor { next, offset }.store(path)?;
+			}
+		}
+
+		if block == last {
+			output.flush()?;
+			return Poll::Ready(Ok(()))
+		}
+		block += One::one();
+
+		// Re-schedule the task in order to continue the operation.
+		cx.waker().wake_by_ref();
+		Poll::Pending
+	});
+
+	Box::pin(export)
+}
+
+/// Build the JSON line of a block, returns `None` if the block is filtered out.
+fn block_line<B: BlockT>(
+	signed_block: &SignedBlock<B>,
+	decoder: &impl BlockDecoder<B>,
+	filter: &ExtrinsicFilter,
+) -> Result<Option<Value>, Error> {
+	let header = signed_block.block.header();
+	let hash = header.hash();
+
+	let extrinsics = decoder
+		.extrinsics(&signed_block.block)
+		.into_iter()
+		.zip(signed_block.block.extrinsics())
+		.map(|(decoded, xt)| match decoded {
+			Ok(decoded) => (decoded, None),
+			Err(error) => (DecodedExtrinsic::opaque(xt), Some(error)),
+		})
+		.enumerate()
+		.filter(|(_, (xt, _))| filter.matches(xt))
+		.collect::<Vec<_>>();
+
+	if !filter.is_empty() && extrinsics.is_empty() {
+		return Ok(None)
+	}
+
+	let (events, events_error) = match decoder.events(hash) {
+		Ok(events) => (
+			events
+				.into_iter()
+				.filter(|event| {
+					filter.is_empty() ||
+						event.extrinsic_index.map_or(false, |index| {
+							extrinsics.iter().any(|(i, _)| *i as u32 == index)
+						})
+				})
+				.map(|event| event.value)
+				.collect::<Vec<_>>()
+				.into(),
+			None,
+		),
+		Err(error) => (Value::Null, Some(error)),
+	};
+
+	let justifications = signed_block
+		.justifications
+		.iter()
+		.flat_map(|justifications| justifications.iter())
+		.map(|(engine, data)| {
+			json!({
+				"engine": String::from_utf8_lossy(engine),
+				"data": format!("0x{}", HexDisplay::from(data)),
+			})
+		})
+		.collect::<Vec<_>>();
+
+	let mut line = json!({
+		"number": header.number().to_string(),
+		"hash": format!("{:?}", hash),
+		"header": serde_json::to_value(header).map_err(|e| format!("Error encoding header: {}", e))?,
+		"extrinsics": extrinsics
+			.into_iter()
+			.map(|(index, (xt, error))| {
+				let mut xt = json!({ "index": index, "extrinsic": xt.value });
+				if let Some(error) = error {
+					xt["error"] = error.into();
+				}
+				xt
+			})
+			.collect::<Vec<_>>(),
+		"events": events,
+		"justifications": justifications,
+	});
+	if let Some(error) = events_error {
+		line["eventsError"] = error.into();
+	}
+
+	Ok(Some(line))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_test_runtime::{Block, Extrinsic, Header};
+
+	fn extrinsic(pallet: &str, call: &str) -> DecodedExtrinsic {
+		DecodedExtrinsic {
+			pallet: Some(pallet.into()),
+			call: Some(call.into()),
+			value: Value::Null,
+		}
+	}
+
+	/// Decodes every extrinsic but the second one and fails to read the events.
+	struct PartialDecoder;
+
+	impl BlockDecoder<Block> for PartialDecoder {
+		fn extrinsics(&self, block: &Block) -> Vec<Result<DecodedExtrinsic, String>> {
+			(0..block.extrinsics().len())
+				.map(|index| match index {
+					1 => Err("Unknown call".into()),
+					_ => Ok(extrinsic("System", "remark")),
+				})
+				.collect()
+		}
+
+		fn events(&self, _: <Block as BlockT>::Hash) -> Result<Vec<DecodedEvent>, String> {
+			Err("State already discarded".into())
+		}
+	}
+
+	#[test]
+	fn undecodable_extrinsics_and_events_fall_back_per_item() {
+		let block = SignedBlock {
+			block: Block::new(
+				Header::new(
+					1,
+					Default::default(),
+					Default::default(),
+					Default::default(),
+					Default::default(),
+				),
+				vec![Extrinsic::IncludeData(vec![1]), Extrinsic::IncludeData(vec![2])],
+			),
+			justifications: None,
+		};
+
+		let line = block_line(&block, &PartialDecoder, &Default::default()).unwrap().unwrap();
+
+		assert_eq!(line["extrinsics"][0], json!({ "index": 0, "extrinsic": null }));
+		assert_eq!(
+			line["extrinsics"][1],
+			json!({
+				"index": 1,
+				"extrinsic": format!("0x{}", HexDisplay::from(&Extrinsic::IncludeData(vec![2]).encode())),
+				"error": "Unknown call",
+			}),
+		);
+		assert_eq!(line["events"], Value::Null);
+		assert_eq!(line["eventsError"], "State already discarded");
+	}
+
+	#[test]
+	fn extrinsic_filter_matches_pallets_and_calls() {
+		let filter = ExtrinsicFilter { pallets: vec!["Balances".into()], calls: vec![] };
+		assert!(filter.matches(&extrinsic("Balances", "transfer")));
+		assert!(!filter.matches(&extrinsic("System", "remark")));
+
+		let filter =
+			ExtrinsicFilter { pallets: vec!["Balances".into()], calls: vec!["transfer".into()] };
+		assert!(filter.matches(&extrinsic("Balances", "transfer")));
+		assert!(!filter.matches(&extrinsic("Balances", "transfer_all")));
+
+		let opaque = DecodedExtrinsic { pallet: None, call: None, value: Value::Null };
+		assert!(ExtrinsicFilter::default().matches(&opaque));
+		assert!(!filter.matches(&opaque));
+	}
+}
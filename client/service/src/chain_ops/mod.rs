@@ -20,12 +20,14 @@
 
 mod check_block;
 mod export_blocks;
+mod export_ndjson;
 mod export_raw_state;
 mod import_blocks;
 mod revert_chain;
 
 pub use check_block::*;
 pub use export_blocks::*;
+pub use export_ndjson::*;
 pub use export_raw_state::*;
 pub use import_blocks::*;
 pub use revert_chain::*;
@@ -18,10 +18,20 @@
 
 use super::{client::ClientConfig, wasm_override::WasmOverride, wasm_substitutes::WasmSubstitutes};
 use codec::{Decode, Encode};
-use sc_client_api::{backend, call_executor::CallExecutor, HeaderBackend};
-use sc_executor::{RuntimeVersion, RuntimeVersionOf};
+use parking_lot::Mutex;
+use prometheus_endpoint::{
+	exponential_buckets, register, HistogramOpts, HistogramVec, Opts, PrometheusError, Registry,
+};
+use sc_client_api::{
+	backend,
+	call_executor::{CallContext, CallExecutor, CallRecordingConfig, RecordedCall},
+	HeaderBackend,
+};
+use sc_executor::{InstantiationTimeExt, RuntimeVersion, RuntimeVersionOf};
 use sp_api::{ProofRecorder, StorageTransactionCache};
 use sp_core::{
+	hashing::blake2_64,
+	storage::well_known_keys,
 	traits::{CodeExecutor, RuntimeCode, SpawnNamed},
 	NativeOrEncoded, NeverNativeValue,
 };
@@ -29,9 +39,142 @@ use sp_externalities::Extensions;
 use sp_runtime::{generic::BlockId, traits::Block as BlockT};
 use sp_state_machine::{
 	self, backend::Backend as _, ExecutionManager, ExecutionStrategy, Ext, OverlayedChanges,
-	StateMachine, StorageProof,
+	StateMachine, StateMachineStats, StorageProof,
+};
+use std::{
+	cell::RefCell,
+	panic::UnwindSafe,
+	path::PathBuf,
+	result,
+	sync::Arc,
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
-use std::{cell::RefCell, panic::UnwindSafe, result, sync::Arc};
+
+/// Prometheus metrics of the runtime calls made through [`LocalCallExecutor`].
+#[derive(Clone)]
+struct RuntimeCallMetrics {
+	execution_time: HistogramVec,
+	instantiation_time: HistogramVec,
+	storage_reads: HistogramVec,
+	storage_writes: HistogramVec,
+}
+
+impl RuntimeCallMetrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			execution_time: register(
+				HistogramVec::new(
+					HistogramOpts {
+						common_opts: Opts::new(
+							"substrate_runtime_call_execution_time",
+							"Time taken to execute a runtime call, in seconds",
+						),
+						buckets: exponential_buckets(0.0001, 4.0, 10)
+							.expect("parameters are always valid values; qed"),
+					},
+					&["method", "context"],
+				)?,
+				registry,
+			)?,
+			instantiation_time: register(
+				HistogramVec::new(
+					HistogramOpts {
+						common_opts: Opts::new(
+							"substrate_runtime_call_instantiation_time",
+							"Time taken to prepare the wasm instance of a runtime call, in seconds",
+						),
+						buckets: exponential_buckets(0.00001, 4.0, 10)
+							.expect("parameters are always valid values; qed"),
+					},
+					&["method", "context"],
+				)?,
+				registry,
+			)?,
+			storage_reads: register(
+				HistogramVec::new(
+					HistogramOpts {
+						common_opts: Opts::new(
+							"substrate_runtime_call_storage_reads",
+							"Number of storage reads done by a runtime call",
+						),
+						buckets: exponential_buckets(1.0, 4.0, 10)
+							.expect("parameters are always valid values; qed"),
+					},
+					&["method", "context"],
+				)?,
+				registry,
+			)?,
+			storage_writes: register(
+				HistogramVec::new(
+					HistogramOpts {
+						common_opts: Opts::new(
+							"substrate_runtime_call_storage_writes",
+							"Number of storage writes done by a runtime call",
+						),
+						buckets: exponential_buckets(1.0, 4.0, 10)
+							.expect("parameters are always valid values; qed"),
+					},
+					&["method", "context"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Start measuring a runtime call.
+	///
+	/// Registers an [`InstantiationTimeExt`] with the given `extensions` and takes a snapshot of
+	/// the reads and writes already done through `changes`.
+	fn start_call(&self, changes: &OverlayedChanges, extensions: &mut Extensions) -> CallMeasure {
+		let instantiation_time = Arc::new(Mutex::new(Duration::default()));
+		extensions.register(InstantiationTimeExt(instantiation_time.clone()));
+
+		let (reads, writes) = reads_and_writes(changes.stats());
+		CallMeasure { started: Instant::now(), instantiation_time, reads, writes }
+	}
+
+	/// Report a runtime call that was started with [`Self::start_call`].
+	fn report_call(
+		&self,
+		measure: CallMeasure,
+		method: &str,
+		context: CallContext,
+		changes: &OverlayedChanges,
+	) {
+		let labels = [method, context.as_str()];
+		let instantiation_time = *measure.instantiation_time.lock();
+		let (reads, writes) = reads_and_writes(changes.stats());
+
+		self.execution_time
+			.with_label_values(&labels)
+			.observe(measure.started.elapsed().saturating_sub(instantiation_time).as_secs_f64());
+		self.instantiation_time
+			.with_label_values(&labels)
+			.observe(instantiation_time.as_secs_f64());
+		self.storage_reads
+			.with_label_values(&labels)
+			.observe(reads.saturating_sub(measure.reads) as f64);
+		self.storage_writes
+			.with_label_values(&labels)
+			.observe(writes.saturating_sub(measure.writes) as f64);
+	}
+}
+
+/// A runtime call being measured.
+struct CallMeasure {
+	started: Instant,
+	instantiation_time: Arc<Mutex<Duration>>,
+	reads: u64,
+	writes: u64,
+}
+
+/// Total number of storage reads and writes tallied in `stats`.
+fn reads_and_writes(stats: &StateMachineStats) -> (u64, u64) {
+	(*stats.reads_modified.borrow() + *stats.reads_backend.borrow(), *stats.writes_overlay.borrow())
+}
+
+/// Maximum number of characters of the method name in the file name of a recorded call.
+const MAX_RECORDED_NAME_LEN: usize = 64;
 
 /// Call executor that executes methods locally, querying all required
 /// data from local backend.
@@ -42,6 +185,7 @@ pub struct LocalCallExecutor<Block: BlockT, B, E> {
 	wasm_substitutes: WasmSubstitutes<Block, E, B>,
 	spawn_handle: Box<dyn SpawnNamed>,
 	client_config: ClientConfig<Block>,
+	metrics: Option<RuntimeCallMetrics>,
 }
 
 impl<Block: BlockT, B, E> LocalCallExecutor<Block, B, E>
@@ -75,17 +219,56 @@ where
 			spawn_handle,
 			client_config,
 			wasm_substitutes,
+			metrics: None,
 		})
 	}
 
+	/// Report the execution time, wasm instantiation time and storage accesses of every runtime
+	/// call to the given Prometheus registry.
+	pub fn with_prometheus_registry(mut self, registry: Option<&Registry>) -> Self {
+		self.metrics = registry.and_then(|registry| {
+			RuntimeCallMetrics::register(registry)
+				.map_err(|err| {
+					log::warn!(
+						target: "executor",
+						"Failed to register runtime call metrics: {}",
+						err,
+					)
+				})
+				.ok()
+		});
+		self
+	}
+
+	/// The label to report a call of `method` into the runtime of the given `version` under.
+	///
+	/// Only successful calls into an API of the runtime keep their method name; everything else
+	/// is reported as `other`, so that arbitrary method names passed in over RPC can't create
+	/// new metric series.
+	fn method_label<'a>(version: &RuntimeVersion, method: &'a str, succeeded: bool) -> &'a str {
+		let is_api_method = || match method.split_once('_') {
+			Some((trait_name, _)) =>
+				version.has_api_with(&blake2_64(trait_name.as_bytes()), |_| true),
+			None => false,
+		};
+
+		if succeeded && is_api_method() {
+			method
+		} else {
+			"other"
+		}
+	}
+
 	/// Check if local runtime code overrides are enabled and one is available
 	/// for the given `BlockId`. If yes, return it; otherwise return the same
 	/// `RuntimeCode` instance that was passed.
+	///
+	/// The on-chain runtime version the decision was based on is returned alongside.
 	fn check_override<'a>(
 		&'a self,
 		onchain_code: RuntimeCode<'a>,
 		id: &BlockId<Block>,
-	) -> sp_blockchain::Result<RuntimeCode<'a>>
+	) -> sp_blockchain::Result<(RuntimeCode<'a>, RuntimeVersion)>
 	where
 		Block: BlockT,
 		B: backend::Backend<Block>,
@@ -112,7 +295,113 @@ where
 				onchain_code
 			};
 
-		Ok(code)
+		Ok((code, spec))
+	}
+
+	/// A proof recorder for a call on `state` that may need to be recorded, if none is given.
+	///
+	/// `None` if calls aren't recorded or `state` can't generate proofs.
+	fn call_recorder(
+		&self,
+		state: &B::State,
+		recorder: &Option<ProofRecorder<Block>>,
+	) -> Option<ProofRecorder<Block>> {
+		let can_record =
+			self.client_config.call_recording.is_some() && state.as_trie_backend().is_some();
+		recorder.clone().or_else(|| can_record.then(Default::default))
+	}
+
+	/// Record the call of `method`, if the call recording configuration asks for it.
+	///
+	/// `recorder` holds the storage accessed by the call, `prior_changes` returns the changes the
+	/// call was executed on top of and `error` is the error it failed with, if any.
+	fn maybe_record_call(
+		&self,
+		state: &B::State,
+		runtime_code: &RuntimeCode,
+		method: &str,
+		call_data: &[u8],
+		recorder: Option<&ProofRecorder<Block>>,
+		prior_changes: impl FnOnce() -> OverlayedChanges,
+		error: Option<String>,
+	) {
+		let config = match &self.client_config.call_recording {
+			Some(config) if config.should_record(method, error.is_some()) => config,
+			_ => return,
+		};
+
+		match self.record_call(
+			config,
+			state,
+			runtime_code,
+			method,
+			call_data,
+			recorder,
+			prior_changes(),
+			error,
+		) {
+			Ok(path) => log::info!(
+				target: "executor",
+				"Recorded runtime call `{}` to {}",
+				method,
+				path.display(),
+			),
+			Err(e) => log::warn!(
+				target: "executor",
+				"Failed to record runtime call `{}`: {}",
+				method,
+				e,
+			),
+		}
+	}
+
+	/// Write the [`RecordedCall`] of `method` to the configured directory.
+	fn record_call(
+		&self,
+		config: &CallRecordingConfig,
+		state: &B::State,
+		runtime_code: &RuntimeCode,
+		method: &str,
+		call_data: &[u8],
+		recorder: Option<&ProofRecorder<Block>>,
+		prior_changes: OverlayedChanges,
+		error: Option<String>,
+	) -> Result<PathBuf, String> {
+		let (trie_state, recorder) = state
+			.as_trie_backend()
+			.zip(recorder)
+			.ok_or("The state is not backed by a trie")?;
+
+		// The runtime code is taken from the proof when replaying the call. It is proven
+		// separately to keep it out of a recorder the caller passed in.
+		let code_backend = sp_state_machine::ProvingBackend::new(trie_state);
+		for key in [well_known_keys::CODE, well_known_keys::HEAP_PAGES] {
+			code_backend.storage(key).map_err(|e| format!("{:?}", e))?;
+		}
+
+		let recorded = RecordedCall {
+			method: method.into(),
+			call_data: call_data.to_vec(),
+			code_hash: runtime_code.hash.clone(),
+			state_root: *trie_state.root(),
+			proof: StorageProof::merge([recorder.to_storage_proof(), code_backend.extract_proof()]),
+			prior_changes: RecordedCall::<Block::Hash>::collect_changes(&prior_changes),
+			error,
+		};
+
+		// The method name may come from an RPC request, so only keep its harmless characters.
+		let name = method
+			.chars()
+			.filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+			.take(MAX_RECORDED_NAME_LEN)
+			.collect::<String>();
+		let created = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+		let path = config.path.join(format!("{}-{}.call", name, created.as_nanos()));
+		std::fs::create_dir_all(&config.path)
+			.and_then(|_| std::fs::write(&path, recorded.encode()))
+			.map_err(|e| e.to_string())?;
+
+		Ok(path)
 	}
 }
 
@@ -128,6 +417,7 @@ where
 			spawn_handle: self.spawn_handle.clone(),
 			client_config: self.client_config.clone(),
 			wasm_substitutes: self.wasm_substitutes.clone(),
+			metrics: self.metrics.clone(),
 		}
 	}
 }
@@ -149,6 +439,7 @@ where
 		call_data: &[u8],
 		strategy: ExecutionStrategy,
 		extensions: Option<Extensions>,
+		context: CallContext,
 	) -> sp_blockchain::Result<Vec<u8>> {
 		let mut changes = OverlayedChanges::default();
 		let state = self.backend.state_at(*at)?;
@@ -156,29 +447,72 @@ where
 		let runtime_code =
 			state_runtime_code.runtime_code().map_err(sp_blockchain::Error::RuntimeCode)?;
 
-		let runtime_code = self.check_override(runtime_code, at)?;
+		let (runtime_code, runtime_version) = self.check_override(runtime_code, at)?;
 
 		let at_hash = self.backend.blockchain().block_hash_from_id(at)?.ok_or_else(|| {
 			sp_blockchain::Error::UnknownBlock(format!("Could not find block hash for {:?}", at))
 		})?;
 
-		let return_data = StateMachine::new(
+		let mut extensions = extensions.unwrap_or_default();
+		let measure = self.metrics.as_ref().map(|m| m.start_call(&changes, &mut extensions));
+		let recorder = self.call_recorder(&state, &None);
+
+		let return_data = match (&recorder, state.as_trie_backend()) {
+			(Some(recorder), Some(trie_state)) => {
+				let backend = sp_state_machine::ProvingBackend::new_with_recorder(
+					trie_state,
+					recorder.clone(),
+				);
+				StateMachine::new(
+					&backend,
+					&mut changes,
+					&self.executor,
+					method,
+					call_data,
+					extensions,
+					&runtime_code,
+					self.spawn_handle.clone(),
+				)
+				.set_parent_hash(at_hash)
+				.execute_using_consensus_failure_handler::<_, NeverNativeValue, fn() -> _>(
+					strategy.get_manager(),
+					None,
+				)
+			},
+			_ => StateMachine::new(
+				&state,
+				&mut changes,
+				&self.executor,
+				method,
+				call_data,
+				extensions,
+				&runtime_code,
+				self.spawn_handle.clone(),
+			)
+			.set_parent_hash(at_hash)
+			.execute_using_consensus_failure_handler::<_, NeverNativeValue, fn() -> _>(
+				strategy.get_manager(),
+				None,
+			),
+		};
+
+		if let (Some(metrics), Some(measure)) = (&self.metrics, measure) {
+			let label = Self::method_label(&runtime_version, method, return_data.is_ok());
+			metrics.report_call(measure, label, context, &changes);
+		}
+
+		// The call was made on top of empty changes.
+		self.maybe_record_call(
 			&state,
-			&mut changes,
-			&self.executor,
+			&runtime_code,
 			method,
 			call_data,
-			extensions.unwrap_or_default(),
-			&runtime_code,
-			self.spawn_handle.clone(),
-		)
-		.set_parent_hash(at_hash)
-		.execute_using_consensus_failure_handler::<_, NeverNativeValue, fn() -> _>(
-			strategy.get_manager(),
-			None,
-		)?;
+			recorder.as_ref(),
+			OverlayedChanges::default,
+			return_data.as_ref().err().map(|e| e.to_string()),
+		);
 
-		Ok(return_data.into_encoded())
+		Ok(return_data?.into_encoded())
 	}
 
 	fn contextual_call<
@@ -199,6 +533,7 @@ where
 		native_call: Option<NC>,
 		recorder: &Option<ProofRecorder<Block>>,
 		extensions: Option<Extensions>,
+		context: CallContext,
 	) -> Result<NativeOrEncoded<R>, sp_blockchain::Error>
 	where
 		ExecutionManager<EM>: Clone,
@@ -220,10 +555,21 @@ where
 
 		let runtime_code =
 			state_runtime_code.runtime_code().map_err(sp_blockchain::Error::RuntimeCode)?;
-		let runtime_code = self.check_override(runtime_code, at)?;
+		let (runtime_code, runtime_version) = self.check_override(runtime_code, at)?;
+
+		let mut extensions = extensions.unwrap_or_default();
+		let measure = self.metrics.as_ref().map(|m| m.start_call(changes, &mut extensions));
+
+		// Run a call that may need to be recorded in a transaction, to be able to tell the changes
+		// it was made on top of without copying them up front.
+		let call_recorder = self.call_recorder(&state, recorder);
+		let recording = self.client_config.call_recording.is_some();
+		if recording {
+			changes.start_transaction();
+		}
 
-		match recorder {
-			Some(recorder) => {
+		let result = match &call_recorder {
+			Some(call_recorder) => {
 				let trie_state = state.as_trie_backend().ok_or_else(|| {
 					Box::new(sp_state_machine::ExecutionError::UnableToGenerateProof)
 						as Box<dyn sp_state_machine::Error>
@@ -231,8 +577,11 @@ where
 
 				let backend = sp_state_machine::ProvingBackend::new_with_recorder(
 					trie_state,
-					recorder.clone(),
+					call_recorder.clone(),
 				);
+				if let Some(recorder) = recorder {
+					extensions.register(sp_state_machine::ProofSizeExt::new(recorder.clone()));
+				}
 
 				let mut state_machine = StateMachine::new(
 					&backend,
@@ -240,7 +589,7 @@ where
 					&self.executor,
 					method,
 					call_data,
-					extensions.unwrap_or_default(),
+					extensions,
 					&runtime_code,
 					self.spawn_handle.clone(),
 				)
@@ -258,7 +607,7 @@ where
 					&self.executor,
 					method,
 					call_data,
-					extensions.unwrap_or_default(),
+					extensions,
 					&runtime_code,
 					self.spawn_handle.clone(),
 				)
@@ -269,8 +618,35 @@ where
 					native_call.map(|n| || (n)().map_err(|e| Box::new(e) as Box<_>)),
 				)
 			},
+		};
+
+		if let (Some(metrics), Some(measure)) = (&self.metrics, measure) {
+			let label = Self::method_label(&runtime_version, method, result.is_ok());
+			metrics.report_call(measure, label, context, changes);
 		}
-		.map_err(Into::into)
+
+		if recording {
+			self.maybe_record_call(
+				&state,
+				&runtime_code,
+				method,
+				call_data,
+				call_recorder.as_ref(),
+				|| {
+					let mut prior_changes = changes.clone();
+					prior_changes
+						.rollback_transaction()
+						.expect("The transaction was started before the call; qed");
+					prior_changes
+				},
+				result.as_ref().err().map(|e| e.to_string()),
+			);
+			changes
+				.commit_transaction()
+				.expect("The transaction was started before the call; qed");
+		}
+
+		result.map_err(Into::into)
 	}
 
 	fn runtime_version(&self, id: &BlockId<Block>) -> sp_blockchain::Result<RuntimeVersion> {
@@ -302,7 +678,7 @@ where
 		let state_runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(trie_backend);
 		let runtime_code =
 			state_runtime_code.runtime_code().map_err(sp_blockchain::Error::RuntimeCode)?;
-		let runtime_code = self.check_override(runtime_code, at)?;
+		let (runtime_code, _) = self.check_override(runtime_code, at)?;
 
 		sp_state_machine::prove_execution_on_trie_backend(
 			trie_backend,
@@ -356,7 +732,7 @@ where
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use sc_client_api::in_mem;
+	use sc_client_api::{in_mem, ExecutorProvider};
 	use sc_executor::{NativeElseWasmExecutor, WasmExecutionMethod};
 	use sp_core::{
 		testing::TaskExecutor,
@@ -418,12 +794,206 @@ mod tests {
 				backend.clone(),
 			)
 			.unwrap(),
+			metrics: None,
 		};
 
-		let check = call_executor
+		let (check, _) = call_executor
 			.check_override(onchain_code, &BlockId::Number(Default::default()))
 			.expect("RuntimeCode override");
 
 		assert_eq!(Some(vec![2, 2, 2, 2, 2, 2, 2, 2]), check.fetch_runtime_code().map(Into::into));
 	}
+
+	#[test]
+	fn should_report_runtime_call_metrics() {
+		let executor = NativeElseWasmExecutor::<LocalExecutorDispatch>::new(
+			WasmExecutionMethod::Interpreted,
+			Some(128),
+			1,
+			2,
+		);
+		let backend = Arc::new(in_mem::Backend::<runtime::Block>::new());
+		let registry = Registry::new();
+
+		let client = substrate_test_runtime_client::client::new_with_backend::<
+			_,
+			_,
+			runtime::Block,
+			_,
+			runtime::RuntimeApi,
+		>(
+			backend,
+			executor,
+			&substrate_test_runtime_client::GenesisParameters::default().genesis_storage(),
+			None,
+			Box::new(TaskExecutor::new()),
+			Some(registry.clone()),
+			None,
+			Default::default(),
+		)
+		.expect("Creates a client");
+
+		client
+			.executor()
+			.call(
+				&BlockId::Number(Default::default()),
+				"Core_version",
+				&[],
+				ExecutionStrategy::AlwaysWasm,
+				None,
+				CallContext::Rpc,
+			)
+			.expect("Calls `Core_version`");
+
+		let families = registry.gather();
+		for name in [
+			"substrate_runtime_call_execution_time",
+			"substrate_runtime_call_instantiation_time",
+			"substrate_runtime_call_storage_reads",
+			"substrate_runtime_call_storage_writes",
+		] {
+			let family = families
+				.iter()
+				.find(|family| family.get_name() == name)
+				.expect("Metric is registered");
+			let metric = &family.get_metric()[0];
+			let labels = metric
+				.get_label()
+				.iter()
+				.map(|label| (label.get_name(), label.get_value()))
+				.collect::<Vec<_>>();
+
+			assert!(labels.contains(&("method", "Core_version")));
+			assert!(labels.contains(&("context", "rpc")));
+			assert_eq!(metric.get_histogram().get_sample_count(), 1);
+		}
+
+		client
+			.executor()
+			.call(
+				&BlockId::Number(Default::default()),
+				"Unknown_method",
+				&[],
+				ExecutionStrategy::AlwaysWasm,
+				None,
+				CallContext::Rpc,
+			)
+			.expect_err("`Unknown_method` is not exported by the runtime");
+
+		let families = registry.gather();
+		let family = families
+			.iter()
+			.find(|family| family.get_name() == "substrate_runtime_call_execution_time")
+			.expect("Metric is registered");
+		let methods = family
+			.get_metric()
+			.iter()
+			.flat_map(|metric| metric.get_label())
+			.filter(|label| label.get_name() == "method")
+			.map(|label| label.get_value())
+			.collect::<Vec<_>>();
+
+		assert_eq!(methods.len(), 2);
+		assert!(methods.contains(&"Core_version"));
+		assert!(methods.contains(&"other"));
+	}
+
+	#[test]
+	fn should_record_and_replay_runtime_calls() {
+		let executor = NativeElseWasmExecutor::<LocalExecutorDispatch>::new(
+			WasmExecutionMethod::Interpreted,
+			Some(128),
+			1,
+			2,
+		);
+		let backend = Arc::new(in_mem::Backend::<runtime::Block>::new());
+		let recording_dir = tempfile::tempdir().expect("Creates a temporary directory");
+
+		let client = substrate_test_runtime_client::client::new_with_backend::<
+			_,
+			_,
+			runtime::Block,
+			_,
+			runtime::RuntimeApi,
+		>(
+			backend,
+			executor.clone(),
+			&substrate_test_runtime_client::GenesisParameters::default().genesis_storage(),
+			None,
+			Box::new(TaskExecutor::new()),
+			None,
+			None,
+			ClientConfig {
+				call_recording: Some(CallRecordingConfig {
+					path: recording_dir.path().into(),
+					methods: vec!["Core_version".into()],
+				}),
+				..Default::default()
+			},
+		)
+		.expect("Creates a client");
+
+		let at = BlockId::Number(Default::default());
+		let version = client
+			.executor()
+			.call(&at, "Core_version", &[], ExecutionStrategy::AlwaysWasm, None, CallContext::Rpc)
+			.expect("Calls `Core_version`");
+		client
+			.executor()
+			.call(
+				&at,
+				"Core_initialize_block",
+				&[1, 2, 3],
+				ExecutionStrategy::AlwaysWasm,
+				None,
+				CallContext::Rpc,
+			)
+			.expect_err("Invalid header fails to decode");
+		client
+			.executor()
+			.call(
+				&at,
+				"../Core_version",
+				&[],
+				ExecutionStrategy::AlwaysWasm,
+				None,
+				CallContext::Rpc,
+			)
+			.expect_err("Method is not exported by the runtime");
+
+		let mut recorded = std::fs::read_dir(recording_dir.path())
+			.expect("Reads the recording directory")
+			.map(|entry| {
+				let data = std::fs::read(entry.unwrap().path()).unwrap();
+				RecordedCall::<runtime::Hash>::decode(&mut &data[..]).unwrap()
+			})
+			.collect::<Vec<_>>();
+		recorded.sort_by(|a, b| a.method.cmp(&b.method));
+
+		// The call of `../Core_version` is recorded inside of the directory as well.
+		assert_eq!(recorded.len(), 3);
+		assert_eq!(recorded[0].method, "../Core_version");
+		assert!(recorded[0].error.is_some());
+		recorded.remove(0);
+		assert_eq!(recorded[0].method, "Core_initialize_block");
+		assert!(recorded[0].error.is_some());
+		assert_eq!(recorded[1].method, "Core_version");
+		assert_eq!(recorded[1].error, None);
+
+		let replayed = recorded[1]
+			.replay::<sp_runtime::traits::BlakeTwo256, _>(
+				&executor,
+				ExecutionStrategy::AlwaysWasm,
+				TaskExecutor::new(),
+			)
+			.expect("Replays `Core_version` from the proof");
+		assert_eq!(replayed, version);
+		assert!(recorded[0]
+			.replay::<sp_runtime::traits::BlakeTwo256, _>(
+				&executor,
+				ExecutionStrategy::AlwaysWasm,
+				TaskExecutor::new(),
+			)
+			.is_err());
+	}
 }
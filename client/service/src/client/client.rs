@@ -40,8 +40,8 @@ use sc_client_api::{
 	},
 	execution_extensions::ExecutionExtensions,
 	notifications::{StorageEventStream, StorageNotifications},
-	CallExecutor, ExecutorProvider, KeyIterator, OnFinalityAction, OnImportAction, ProofProvider,
-	UsageProvider,
+	CallContext, CallExecutor, CallRecordingConfig, ExecutorProvider, KeyIterator,
+	OnFinalityAction, OnImportAction, ProofProvider, UsageProvider,
 };
 use sc_consensus::{
 	BlockCheckParams, BlockImportParams, ForkChoiceStrategy, ImportResult, StateAction,
@@ -198,6 +198,8 @@ pub struct ClientConfig<Block: BlockT> {
 	pub offchain_indexing_api: bool,
 	/// Path where WASM files exist to override the on-chain WASM.
 	pub wasm_runtime_overrides: Option<PathBuf>,
+	/// Runtime calls to record for offline replay.
+	pub call_recording: Option<CallRecordingConfig>,
 	/// Skip writing genesis state on first start.
 	pub no_genesis: bool,
 	/// Map of WASM runtime substitute starting at the child of the given block until the runtime
@@ -211,6 +213,7 @@ impl<Block: BlockT> Default for ClientConfig<Block> {
 			offchain_worker_enabled: false,
 			offchain_indexing_api: false,
 			wasm_runtime_overrides: None,
+			call_recording: None,
 			no_genesis: false,
 			wasm_runtime_substitutes: HashMap::new(),
 		}
@@ -237,7 +240,8 @@ where
 	B: backend::LocalBackend<Block> + 'static,
 {
 	let call_executor =
-		LocalCallExecutor::new(backend.clone(), executor, spawn_handle, config.clone())?;
+		LocalCallExecutor::new(backend.clone(), executor, spawn_handle, config.clone())?
+			.with_prometheus_registry(prometheus_registry.as_ref());
 	let extensions = ExecutionExtensions::new(
 		Default::default(),
 		keystore,
@@ -1667,6 +1671,7 @@ where
 		params: CallApiAtParams<Block, NC, B::State>,
 	) -> Result<NativeOrEncoded<R>, sp_api::ApiError> {
 		let at = params.at;
+		let context = CallContext::from_execution_context(&params.context, params.function);
 
 		let (manager, extensions) =
 			self.execution_extensions.manager_and_extensions(at, params.context);
@@ -1682,6 +1687,7 @@ where
 				params.native_call,
 				params.recorder,
 				Some(extensions),
+				context,
 			)
 			.map_err(Into::into)
 	}
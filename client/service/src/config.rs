@@ -18,7 +18,10 @@
 
 //! Service configuration.
 
-pub use sc_client_api::execution_extensions::{ExecutionStrategies, ExecutionStrategy};
+pub use sc_client_api::{
+	execution_extensions::{ExecutionStrategies, ExecutionStrategy},
+	CallRecordingConfig,
+};
 pub use sc_client_db::{Database, DatabaseSource, KeepBlocks, PruningMode};
 pub use sc_executor::WasmExecutionMethod;
 pub use sc_network::{
@@ -34,6 +37,10 @@ pub use sc_network_common::{
 		IncomingRequest, OutgoingResponse, ProtocolConfig as RequestResponseConfig,
 	},
 };
+pub use sc_offchain::{
+	HttpBackend as OffchainHttpBackend, HttpPolicy as OffchainHttpPolicy,
+	SchedulingPolicy as OffchainSchedulingPolicy,
+};
 
 use prometheus_endpoint::Registry;
 use sc_chain_spec::ChainSpec;
@@ -44,6 +51,7 @@ use std::{
 	io, iter,
 	net::SocketAddr,
 	path::{Path, PathBuf},
+	sync::Arc,
 };
 use tempfile::TempDir;
 
@@ -84,6 +92,8 @@ pub struct Configuration {
 	/// over on-chain runtimes when the spec version matches. Set to `None` to
 	/// disable overrides (default).
 	pub wasm_runtime_overrides: Option<PathBuf>,
+	/// Runtime calls to record for offline replay. Set to `None` to disable recording (default).
+	pub runtime_call_recording: Option<CallRecordingConfig>,
 	/// Execution strategies.
 	pub execution_strategies: ExecutionStrategies,
 	/// RPC over HTTP binding address. `None` if disabled.
@@ -190,6 +200,14 @@ pub struct OffchainWorkerConfig {
 	pub enabled: bool,
 	/// allow writes from the runtime to the offchain worker database.
 	pub indexing_enabled: bool,
+	/// Restrictions on the HTTP requests made by offchain workers.
+	pub http_policy: OffchainHttpPolicy,
+	/// Restrictions on when and for how long offchain workers run.
+	pub scheduling: OffchainSchedulingPolicy,
+	/// Backend performing the HTTP requests of offchain workers.
+	///
+	/// `None` for the default one, which sends them over the network.
+	pub http_backend: Option<Arc<dyn OffchainHttpBackend>>,
 }
 
 /// Configuration of the Prometheus endpoint.
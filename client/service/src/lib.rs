@@ -37,7 +37,7 @@ mod task_manager;
 use std::{collections::HashMap, net::SocketAddr};
 
 use codec::{Decode, Encode};
-use futures::{channel::mpsc, FutureExt, StreamExt};
+use futures::{channel::mpsc, stream::FuturesUnordered, FutureExt, StreamExt};
 use jsonrpsee::{core::Error as JsonRpseeError, RpcModule};
 use log::{debug, error, warn};
 use sc_client_api::{blockchain::HeaderBackend, BlockBackend, BlockchainEvents, ProofProvider};
@@ -52,9 +52,10 @@ use sp_runtime::{
 
 pub use self::{
 	builder::{
-		build_network, build_offchain_workers, new_client, new_db_backend, new_full_client,
-		new_full_parts, spawn_tasks, BuildNetworkParams, KeystoreContainer, NetworkStarter,
-		SpawnTasksParams, TFullBackend, TFullCallExecutor, TFullClient,
+		build_light_client_fetcher, build_network, build_offchain_workers, new_client,
+		new_db_backend, new_full_client, new_full_parts, spawn_tasks, BuildNetworkParams,
+		KeystoreContainer, NetworkStarter, SpawnTasksParams, TFullBackend, TFullCallExecutor,
+		TFullClient,
 	},
 	client::{ClientConfig, LocalCallExecutor},
 	error::Error,
@@ -160,6 +161,9 @@ async fn build_network_future<
 	// Stream of finalized blocks reported by the client.
 	let mut finality_notification_stream = client.finality_notification_stream().fuse();
 
+	// Bitswap fetches started by RPC requests.
+	let mut bitswap_fetches = FuturesUnordered::new();
+
 	loop {
 		futures::select! {
 			// List of blocks that the client has imported.
@@ -248,6 +252,24 @@ async fn build_network_future<
 
 						let _ = sender.send(reserved_peers);
 					}
+					sc_rpc::system::Request::BitswapFetch(cid, sender) => {
+						match sc_network::Cid::try_from(cid.as_str()) {
+							Ok(cid) => {
+								let service = network.service().clone();
+								bitswap_fetches.push(async move {
+									let result = service.bitswap_fetch(cid).await.map_err(|e| {
+										sc_rpc::system::error::Error::BitswapFetchFailed(e.to_string())
+									});
+									let _ = sender.send(result);
+								}.boxed());
+							}
+							Err(e) => {
+								let _ = sender.send(Err(
+									sc_rpc::system::error::Error::MalformattedCid(e.to_string()),
+								));
+							}
+						}
+					}
 					sc_rpc::system::Request::NodeRoles(sender) => {
 						use sc_rpc::system::NodeRole;
 
@@ -260,17 +282,30 @@ async fn build_network_future<
 						let _ = sender.send(vec![node_role]);
 					}
 					sc_rpc::system::Request::SyncState(sender) => {
-						use sc_rpc::system::SyncState;
-
+						use sc_rpc::system::{StateDownload, SyncState};
+
+						let state_download = network.sync_state().state_sync.map(|progress| {
+							StateDownload {
+								percentage: progress.percentage,
+								downloaded_bytes: progress.size,
+								ranges: progress.ranges,
+								completed_ranges: progress.completed_ranges,
+								active_ranges: progress.active_ranges,
+							}
+						});
 						let _ = sender.send(SyncState {
 							starting_block,
 							current_block: client.info().best_number,
 							highest_block: network.best_seen_block(),
+							state_download,
 						});
 					}
 				}
 			}
 
+			// A bitswap fetch has finished and its result was sent back.
+			_ = bitswap_fetches.select_next_some() => {}
+
 			// The network worker has done something. Nothing special to do, but could be
 			// used in the future to perform actions in response of things that happened on
 			// the network.
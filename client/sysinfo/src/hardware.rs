@@ -17,6 +17,7 @@
 
 //! Contains types to define hardware requirements.
 
+use crate::HwBench;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -35,10 +36,60 @@ lazy_static! {
 	};
 }
 
+/// The default fault tolerance in percent when checking benchmark results against requirements.
+///
+/// 10% means that a requirement is still met when only 90% of its minimum was archived. This
+/// mitigates outliers of the benchmarks.
+pub const DEFAULT_TOLERANCE: f64 = 10.0;
+
 /// Multiple requirements for the hardware.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Requirements(pub Vec<Requirement>);
 
+impl Requirements {
+	/// Check the results of the hardware benchmarks against these requirements.
+	///
+	/// Returns the requirements that are not met within the [`DEFAULT_TOLERANCE`]. Requirements
+	/// on metrics that are not measured by [`HwBench`] are skipped.
+	pub fn check_hardware(&self, hwbench: &HwBench) -> Vec<CheckFailure> {
+		self.0
+			.iter()
+			.filter_map(|requirement| {
+				let found = hwbench.score(requirement.metric)?;
+				(!requirement.is_met_by(found, DEFAULT_TOLERANCE)).then(|| CheckFailure {
+					metric: requirement.metric,
+					expected: requirement.minimum,
+					found,
+				})
+			})
+			.collect()
+	}
+}
+
+/// A hardware requirement that is not met.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CheckFailure {
+	/// The metric that was measured.
+	pub metric: Metric,
+	/// The minimal throughput that is required.
+	pub expected: Throughput,
+	/// The throughput that was measured.
+	pub found: Throughput,
+}
+
+impl fmt::Display for CheckFailure {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{} {}: {} (minimum {})",
+			self.metric.category(),
+			self.metric.name(),
+			self.found,
+			self.expected
+		)
+	}
+}
+
 /// A single requirement for the hardware.
 #[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
 pub struct Requirement {
@@ -48,9 +99,17 @@ pub struct Requirement {
 	pub minimum: Throughput,
 }
 
+impl Requirement {
+	/// Whether the `found` throughput meets this requirement with a fault tolerance of
+	/// `tolerance` percent.
+	pub fn is_met_by(&self, found: Throughput, tolerance: f64) -> bool {
+		found.to_bs() / self.minimum.to_bs() >= 1.0 - tolerance / 100.0
+	}
+}
+
 /// A single hardware metric.
 ///
-/// The implementation of these is in `sc-sysinfo`.
+/// The benchmarks measuring these are `benchmark_*` functions of this crate.
 #[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
 pub enum Metric {
 	/// SR25519 signature verification.
@@ -172,6 +231,34 @@ mod tests {
 		assert_eq!(decoded, SUBSTRATE_REFERENCE_HARDWARE.clone());
 	}
 
+	/// Only the measured metrics below their minimum, minus the tolerance, are reported.
+	#[test]
+	fn check_hardware_works() {
+		let requirements = Requirements(vec![
+			Requirement { metric: Metric::Blake2256, minimum: Throughput::MiBs(1000.0) },
+			Requirement { metric: Metric::MemCopy, minimum: Throughput::GiBs(10.0) },
+			// Within the tolerance.
+			Requirement { metric: Metric::DiskSeqWrite, minimum: Throughput::MiBs(1000.0) },
+			Requirement { metric: Metric::DiskRndWrite, minimum: Throughput::MiBs(400.0) },
+			Requirement { metric: Metric::Sr25519Verify, minimum: Throughput::MiBs(1.0) },
+		]);
+		let hwbench = HwBench {
+			cpu_hashrate_score: 1200,
+			memory_memcpy_score: 8192,
+			disk_sequential_write_score: Some(950),
+			disk_random_write_score: None,
+		};
+
+		assert_eq!(
+			requirements.check_hardware(&hwbench),
+			vec![CheckFailure {
+				metric: Metric::MemCopy,
+				expected: Throughput::GiBs(10.0),
+				found: Throughput::MiBs(8192.0),
+			}],
+		);
+	}
+
 	/// Test the [`Throughput`].
 	#[test]
 	fn throughput_works() {
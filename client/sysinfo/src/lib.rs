@@ -22,10 +22,15 @@
 use futures::prelude::*;
 use std::time::Duration;
 
+mod hardware;
 mod sysinfo;
 #[cfg(target_os = "linux")]
 mod sysinfo_linux;
 
+pub use hardware::{
+	CheckFailure, Metric, Requirement, Requirements, Throughput, DEFAULT_TOLERANCE,
+	SUBSTRATE_REFERENCE_HARDWARE,
+};
 pub use sysinfo::{
 	benchmark_cpu, benchmark_disk_random_writes, benchmark_disk_sequential_writes,
 	benchmark_memory, benchmark_sr25519_verify, gather_hwbench, gather_sysinfo,
@@ -53,6 +58,20 @@ pub struct HwBench {
 	pub disk_random_write_score: Option<u64>,
 }
 
+impl HwBench {
+	/// The score of the given metric, if it is measured.
+	pub fn score(&self, metric: Metric) -> Option<Throughput> {
+		let score = match metric {
+			Metric::Blake2256 => Some(self.cpu_hashrate_score),
+			Metric::MemCopy => Some(self.memory_memcpy_score),
+			Metric::DiskSeqWrite => self.disk_sequential_write_score,
+			Metric::DiskRndWrite => self.disk_random_write_score,
+			Metric::Sr25519Verify => None,
+		};
+		score.map(|score| Throughput::MiBs(score as f64))
+	}
+}
+
 /// Limit the execution time of a benchmark.
 pub enum ExecutionLimit {
 	/// Limit by the maximal duration.
@@ -23,6 +23,43 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use codec::{Decode, Encode};
+use sp_runtime::{traits::Block as BlockT, ApplyExtrinsicResult, RuntimeDebug};
+use sp_std::vec::Vec;
+
+/// The effects of applying a single extrinsic or call on top of a block, as reported by
+/// [`DryRunApi`].
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct DryRunEffects<Balance> {
+	/// The outcome of applying the extrinsic, including the dispatch result.
+	pub result: ApplyExtrinsicResult,
+	/// The weight actually consumed by the dispatch, after any refunds.
+	pub actual_weight: u64,
+	/// The fee that would be charged for the actual weight, excluding any tip.
+	pub fee: Balance,
+	/// All events emitted while applying, in the order they were deposited.
+	pub events: Vec<DryRunEvent>,
+}
+
+/// An event record emitted during a dry run, split into its parts.
+///
+/// The pallet and event index identify the event in the runtime metadata, which describes how
+/// to decode the `fields`.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct DryRunEvent {
+	/// Index of the extrinsic that emitted the event, `None` if it wasn't emitted by an
+	/// extrinsic.
+	pub extrinsic_index: Option<u32>,
+	/// Index of the emitting pallet in the runtime.
+	pub pallet_index: u8,
+	/// Index of the event variant in the pallet's event enum.
+	pub event_index: u8,
+	/// The SCALE-encoded fields of the event.
+	pub fields: Vec<u8>,
+	/// The SCALE-encoded topics of the event record.
+	pub topics: Vec<Vec<u8>>,
+}
+
 sp_api::decl_runtime_apis! {
 	/// The API to query account nonce (aka transaction index).
 	pub trait AccountNonceApi<AccountId, Index> where
@@ -32,4 +69,20 @@ sp_api::decl_runtime_apis! {
 		/// Get current account nonce of given `AccountId`.
 		fn account_nonce(account: AccountId) -> Index;
 	}
+
+	/// The API to apply an extrinsic without including it in a block and report its effects.
+	///
+	/// Any changes made to storage are only kept in the overlay of the caller, which can inspect
+	/// them, but they never get committed to the chain.
+	pub trait DryRunApi<AccountId, Balance> where
+		AccountId: codec::Codec,
+		Balance: codec::Codec,
+	{
+		/// Apply the given `extrinsic` on top of the current state and report its effects.
+		fn dry_run_extrinsic(extrinsic: <Block as BlockT>::Extrinsic) -> DryRunEffects<Balance>;
+
+		/// Dispatch the SCALE-encoded `call` with a signed `origin`, without any signature or
+		/// transaction validity checks, and report its effects.
+		fn dry_run_call(origin: AccountId, call: Vec<u8>) -> DryRunEffects<Balance>;
+	}
 }
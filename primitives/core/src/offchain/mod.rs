@@ -54,6 +54,30 @@ pub trait OffchainStorage: Clone + Send + Sync {
 		old_value: Option<&[u8]>,
 		new_value: &[u8],
 	) -> bool;
+
+	/// Retrieve up to `count` keys under given prefix that start with `key_prefix`.
+	///
+	/// Keys are returned in lexicographic order without the storage `prefix`, beginning with
+	/// the first key strictly after `start_key` if given.
+	///
+	/// Returns `None` if the storage can't enumerate its keys, which is the default.
+	fn keys_with_prefix(
+		&self,
+		_prefix: &[u8],
+		_key_prefix: &[u8],
+		_start_key: Option<&[u8]>,
+		_count: usize,
+	) -> Option<Vec<Vec<u8>>> {
+		None
+	}
+
+	/// Clear all storage entries under given prefix whose key starts with `key_prefix`.
+	///
+	/// Returns the number of removed entries, or `None` if the storage can't enumerate its keys,
+	/// which is the default.
+	fn clear_prefix(&mut self, _prefix: &[u8], _key_prefix: &[u8]) -> Option<u32> {
+		None
+	}
 }
 
 /// A type of supported crypto.
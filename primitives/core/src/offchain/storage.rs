@@ -87,4 +87,31 @@ impl OffchainStorage for InMemOffchainStorage {
 			_ => false,
 		}
 	}
+
+	fn keys_with_prefix(
+		&self,
+		prefix: &[u8],
+		key_prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Option<Vec<Vec<u8>>> {
+		let full_prefix: Vec<u8> = prefix.iter().chain(key_prefix).cloned().collect();
+		let mut keys = self
+			.storage
+			.keys()
+			.filter(|key| key.starts_with(&full_prefix))
+			.map(|key| key[prefix.len()..].to_vec())
+			.filter(|key| start_key.map_or(true, |start| key.as_slice() > start))
+			.collect::<Vec<_>>();
+		keys.sort();
+		keys.truncate(count);
+		Some(keys)
+	}
+
+	fn clear_prefix(&mut self, prefix: &[u8], key_prefix: &[u8]) -> Option<u32> {
+		let full_prefix: Vec<u8> = prefix.iter().chain(key_prefix).cloned().collect();
+		let before = self.storage.len();
+		self.storage.retain(|key, _| !key.starts_with(&full_prefix));
+		Some((before - self.storage.len()) as u32)
+	}
 }
@@ -115,6 +115,20 @@ impl OffchainStorage for TestPersistentOffchainDB {
 	) -> bool {
 		self.persistent.write().compare_and_set(prefix, key, old_value, new_value)
 	}
+
+	fn keys_with_prefix(
+		&self,
+		prefix: &[u8],
+		key_prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Option<Vec<Vec<u8>>> {
+		self.persistent.read().keys_with_prefix(prefix, key_prefix, start_key, count)
+	}
+
+	fn clear_prefix(&mut self, prefix: &[u8], key_prefix: &[u8]) -> Option<u32> {
+		self.persistent.write().clear_prefix(prefix, key_prefix)
+	}
 }
 
 /// Internal state of the externalities.
@@ -115,4 +115,13 @@ impl<D: KeyValueDB, H: Clone + AsRef<[u8]>> Database<H> for DbAdapter<D> {
 	fn contains(&self, col: ColumnId, key: &[u8]) -> bool {
 		handle_err(self.0.has_key(col, key))
 	}
+
+	fn supports_key_iteration(&self) -> bool {
+		true
+	}
+
+	fn iter_column(&self, col: ColumnId, f: &mut dyn FnMut(&[u8], &[u8])) -> error::Result<()> {
+		self.0.iter(col).for_each(|(key, value)| f(&key, &value));
+		Ok(())
+	}
 }
@@ -115,6 +115,25 @@ pub trait Database<H: Clone + AsRef<[u8]>>: Send + Sync {
 	///
 	/// Not all database implementations use a prefix for keys, so this function may be a noop.
 	fn sanitize_key(&self, _key: &mut Vec<u8>) {}
+
+	/// Check if [`Database::iter_column`] reports the keys entries were stored under.
+	///
+	/// Returns `false` by default, as some databases only keep the hash of each key.
+	fn supports_key_iteration(&self) -> bool {
+		false
+	}
+
+	/// Call `f` with the key and value of every entry stored in `col`.
+	///
+	/// The iteration order is unspecified and implementations that index values by hash may
+	/// report the hashed key, see [`Database::supports_key_iteration`]. Returns an error if the
+	/// database doesn't support iteration.
+	fn iter_column(&self, _col: ColumnId, _f: &mut dyn FnMut(&[u8], &[u8])) -> error::Result<()> {
+		Err(error::DatabaseError(Box::new(std::io::Error::new(
+			std::io::ErrorKind::Unsupported,
+			"Column iteration is not supported by this database",
+		))))
+	}
 }
 
 impl<H> std::fmt::Debug for dyn Database<H> {
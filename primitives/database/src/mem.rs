@@ -73,6 +73,16 @@ where
 		let s = self.0.read();
 		s.get(&col).and_then(|c| c.get(key).map(|(_, v)| v.clone()))
 	}
+
+	fn supports_key_iteration(&self) -> bool {
+		true
+	}
+
+	fn iter_column(&self, col: ColumnId, f: &mut dyn FnMut(&[u8], &[u8])) -> error::Result<()> {
+		let s = self.0.read();
+		s.get(&col).into_iter().flatten().for_each(|(key, (_, value))| f(key, value));
+		Ok(())
+	}
 }
 
 impl MemDb {
@@ -56,6 +56,9 @@ use sp_core::{
 	LogLevel, LogLevelFilter, OpaquePeerId, H256,
 };
 
+#[cfg(feature = "std")]
+use sp_state_machine::ProofSizeExt;
+
 #[cfg(feature = "std")]
 use sp_trie::{LayoutV0, LayoutV1, TrieConfiguration};
 
@@ -262,6 +265,14 @@ pub trait Storage {
 		self.storage_commit_transaction()
 			.expect("No open transaction that can be committed.");
 	}
+
+	/// Returns the size of the storage proof recorded so far during the current execution, in
+	/// bytes.
+	///
+	/// Returns `None` if no storage proof is being recorded.
+	fn proof_size(&mut self) -> Option<u64> {
+		self.extension::<ProofSizeExt>().map(|ext| ext.storage_proof_size())
+	}
 }
 
 /// Interface for accessing the child storage for default child trie,
@@ -182,11 +182,14 @@ where
 
 	fn storage(&self, key: &[u8]) -> Option<StorageValue> {
 		let _guard = guard();
-		let result = self
-			.overlay
-			.storage(key)
-			.map(|x| x.map(|x| x.to_vec()))
-			.unwrap_or_else(|| self.backend.storage(key).expect(EXT_NOT_ALLOWED_TO_FAIL));
+		let result =
+			self.overlay.storage(key).map(|x| x.map(|x| x.to_vec())).unwrap_or_else(|| {
+				let value = self.backend.storage(key).expect(EXT_NOT_ALLOWED_TO_FAIL);
+				self.overlay
+					.stats()
+					.tally_read_backend(value.as_ref().map_or(0, |v| v.len() as u64));
+				value
+			});
 
 		// NOTE: be careful about touching the key names – used outside substrate!
 		trace!(
@@ -231,7 +234,12 @@ where
 			.child_storage(child_info, key)
 			.map(|x| x.map(|x| x.to_vec()))
 			.unwrap_or_else(|| {
-				self.backend.child_storage(child_info, key).expect(EXT_NOT_ALLOWED_TO_FAIL)
+				let value =
+					self.backend.child_storage(child_info, key).expect(EXT_NOT_ALLOWED_TO_FAIL);
+				self.overlay
+					.stats()
+					.tally_read_backend(value.as_ref().map_or(0, |v| v.len() as u64));
+				value
 			});
 
 		trace!(
@@ -1054,6 +1062,34 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn storage_reads_are_tallied() {
+		let mut cache = StorageTransactionCache::default();
+		let mut overlay = OverlayedChanges::default();
+		overlay.set_storage(vec![20], Some(vec![21]));
+		let backend = (
+			Storage {
+				top: map![vec![10] => vec![10, 11], vec![20] => vec![20]],
+				children_default: map![],
+			},
+			StateVersion::default(),
+		)
+			.into();
+
+		let ext = TestExt::new(&mut overlay, &mut cache, &backend, None);
+
+		assert_eq!(ext.storage(&[10]), Some(vec![10, 11]));
+		assert_eq!(ext.storage(&[20]), Some(vec![21]));
+		assert_eq!(ext.storage(&[30]), None);
+
+		drop(ext);
+		let stats = overlay.stats();
+		assert_eq!(*stats.reads_backend.borrow(), 2);
+		assert_eq!(*stats.bytes_read_backend.borrow(), 2);
+		assert_eq!(*stats.reads_modified.borrow(), 1);
+		assert_eq!(*stats.writes_overlay.borrow(), 1);
+	}
+
 	#[test]
 	fn clear_prefix_cannot_delete_a_child_root() {
 		let child_info = ChildInfo::new_default(b"Child1");
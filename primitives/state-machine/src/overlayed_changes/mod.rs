@@ -212,11 +212,34 @@ impl<Transaction: Default, H: Hasher> Default for StorageChanges<Transaction, H>
 }
 
 impl OverlayedChanges {
+	/// Create an overlay holding the given committed `changes`, grouped by child trie.
+	///
+	/// Changes to the top trie are grouped under `None`.
+	pub fn from_changes(
+		changes: impl IntoIterator<Item = (Option<ChildInfo>, StorageCollection)>,
+	) -> Self {
+		let mut overlay = Self::default();
+		for (child_info, changes) in changes {
+			for (key, value) in changes {
+				match &child_info {
+					Some(child_info) => overlay.set_child_storage(child_info, key, value),
+					None => overlay.set_storage(key, value),
+				}
+			}
+		}
+		overlay
+	}
+
 	/// Whether no changes are contained in the top nor in any of the child changes.
 	pub fn is_empty(&self) -> bool {
 		self.top.is_empty() && self.children.is_empty()
 	}
 
+	/// Statistics on the reads and writes done through this overlay.
+	pub fn stats(&self) -> &StateMachineStats {
+		&self.stats
+	}
+
 	/// Ask to collect/not to collect extrinsics indices where key(s) has been changed.
 	pub fn set_collect_extrinsics(&mut self, collect_extrinsics: bool) {
 		self.collect_extrinsics = collect_extrinsics;
@@ -980,4 +1003,19 @@ mod tests {
 		assert_eq!(next_to_40.0.to_vec(), vec![50]);
 		assert_eq!(next_to_40.1.value(), Some(&vec![50]));
 	}
+
+	#[test]
+	fn from_changes_works() {
+		let child_info = ChildInfo::new_default(b"Child1");
+		let overlay = OverlayedChanges::from_changes(vec![
+			(None, vec![(vec![1], Some(vec![1])), (vec![2], None)]),
+			(Some(child_info.clone()), vec![(vec![3], Some(vec![3]))]),
+		]);
+
+		assert_eq!(overlay.storage(&[1]), Some(Some(&[1][..])));
+		assert_eq!(overlay.storage(&[2]), Some(None));
+		assert_eq!(overlay.storage(&[3]), None);
+		assert_eq!(overlay.child_storage(&child_info, &[3]), Some(Some(&[3][..])));
+		assert_eq!(overlay.transaction_depth(), 0);
+	}
 }
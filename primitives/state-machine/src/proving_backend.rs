@@ -118,6 +118,8 @@ struct ProofRecorderInner<Hash> {
 	records: HashMap<Hash, Option<DBValue>>,
 	/// The encoded size of all recorded values.
 	encoded_size: usize,
+	/// The keys recorded in each of the open transactions, the innermost one last.
+	transactions: Vec<Vec<Hash>>,
 }
 
 /// Global proof recorder, act as a layer over a hash db for recording queried data.
@@ -126,14 +128,17 @@ pub struct ProofRecorder<Hash> {
 	inner: Arc<RwLock<ProofRecorderInner<Hash>>>,
 }
 
-impl<Hash: std::hash::Hash + Eq> ProofRecorder<Hash> {
+impl<Hash: std::hash::Hash + Eq + Clone> ProofRecorder<Hash> {
 	/// Record the given `key` => `val` combination.
 	pub fn record(&self, key: Hash, val: Option<DBValue>) {
 		let mut inner = self.inner.write();
-		let encoded_size = if let Entry::Vacant(entry) = inner.records.entry(key) {
+		let encoded_size = if let Entry::Vacant(entry) = inner.records.entry(key.clone()) {
 			let encoded_size = val.as_ref().map(Encode::encoded_size).unwrap_or(0);
 
 			entry.insert(val);
+			if let Some(transaction) = inner.transactions.last_mut() {
+				transaction.push(key);
+			}
 			encoded_size
 		} else {
 			0
@@ -142,6 +147,34 @@ impl<Hash: std::hash::Hash + Eq> ProofRecorder<Hash> {
 		inner.encoded_size += encoded_size;
 	}
 
+	/// Start a new nested transaction.
+	///
+	/// Everything recorded from now on can be discarded again with
+	/// [`Self::rollback_transaction`].
+	pub fn start_transaction(&self) {
+		self.inner.write().transactions.push(Vec::new());
+	}
+
+	/// Discard everything recorded since the last call to [`Self::start_transaction`].
+	pub fn rollback_transaction(&self) {
+		let mut inner = self.inner.write();
+		for key in inner.transactions.pop().unwrap_or_default() {
+			if let Some(val) = inner.records.remove(&key) {
+				inner.encoded_size -= val.as_ref().map(Encode::encoded_size).unwrap_or(0);
+			}
+		}
+	}
+
+	/// Keep everything recorded since the last call to [`Self::start_transaction`].
+	pub fn commit_transaction(&self) {
+		let mut inner = self.inner.write();
+		if let Some(keys) = inner.transactions.pop() {
+			if let Some(parent) = inner.transactions.last_mut() {
+				parent.extend(keys);
+			}
+		}
+	}
+
 	/// Returns the value at the given `key`.
 	pub fn get(&self, key: &Hash) -> Option<Option<DBValue>> {
 		self.inner.read().records.get(key).cloned()
@@ -172,6 +205,36 @@ impl<Hash: std::hash::Hash + Eq> ProofRecorder<Hash> {
 		let mut inner = self.inner.write();
 		inner.records.clear();
 		inner.encoded_size = 0;
+		inner.transactions.clear();
+	}
+}
+
+/// Something that knows the size of the storage proof recorded so far.
+pub trait ProofSizeProvider {
+	/// Returns the estimated encoded size of the proof recorded so far.
+	fn estimate_encoded_size(&self) -> usize;
+}
+
+impl<Hash: std::hash::Hash + Eq + Clone> ProofSizeProvider for ProofRecorder<Hash> {
+	fn estimate_encoded_size(&self) -> usize {
+		ProofRecorder::estimate_encoded_size(self)
+	}
+}
+
+sp_externalities::decl_extension! {
+	/// The proof size extension to query the size of the storage proof recorded while executing.
+	pub struct ProofSizeExt(Box<dyn ProofSizeProvider + Send + Sync>);
+}
+
+impl ProofSizeExt {
+	/// Create a new instance of [`ProofSizeExt`].
+	pub fn new(provider: impl ProofSizeProvider + Send + Sync + 'static) -> Self {
+		Self(Box::new(provider))
+	}
+
+	/// Returns the size of the storage proof recorded so far, in bytes.
+	pub fn storage_proof_size(&self) -> u64 {
+		self.0.estimate_encoded_size() as u64
 	}
 }
 
@@ -401,6 +464,34 @@ mod tests {
 		ProvingBackend::new(trie_backend)
 	}
 
+	#[test]
+	fn proof_recorder_transactions_work() {
+		let recorder = ProofRecorder::<H256>::default();
+		recorder.record(H256::repeat_byte(1), Some(vec![1; 10]));
+		let size = recorder.estimate_encoded_size();
+
+		recorder.start_transaction();
+		recorder.record(H256::repeat_byte(2), Some(vec![2; 10]));
+		recorder.start_transaction();
+		recorder.record(H256::repeat_byte(3), Some(vec![3; 10]));
+		recorder.commit_transaction();
+		assert!(recorder.estimate_encoded_size() > size);
+		recorder.rollback_transaction();
+
+		assert_eq!(recorder.estimate_encoded_size(), size);
+		assert_eq!(recorder.get(&H256::repeat_byte(2)), None);
+		assert_eq!(recorder.get(&H256::repeat_byte(3)), None);
+
+		recorder.start_transaction();
+		recorder.record(H256::repeat_byte(4), Some(vec![4; 10]));
+		recorder.commit_transaction();
+		assert_eq!(recorder.get(&H256::repeat_byte(4)), Some(Some(vec![4; 10])));
+		assert_eq!(
+			ProofSizeExt::new(recorder.clone()).storage_proof_size(),
+			recorder.estimate_encoded_size() as u64,
+		);
+	}
+
 	#[test]
 	fn proof_is_empty_until_value_is_read() {
 		proof_is_empty_until_value_is_read_inner(StateVersion::V0);
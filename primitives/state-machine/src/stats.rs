@@ -76,6 +76,13 @@ pub struct StateMachineStats {
 	/// Size in bytes of the writes overlay
 	/// operation.
 	pub bytes_writes_overlay: RefCell<u64>,
+	/// Number of read query from runtime
+	/// that missed the overlay and went
+	/// to the backend.
+	pub reads_backend: RefCell<u64>,
+	/// Size in byte of read queries that
+	/// went to the backend.
+	pub bytes_read_backend: RefCell<u64>,
 }
 
 impl StateMachineStats {
@@ -85,6 +92,8 @@ impl StateMachineStats {
 		*self.bytes_read_modified.borrow_mut() += *other.bytes_read_modified.borrow();
 		*self.writes_overlay.borrow_mut() += *other.writes_overlay.borrow();
 		*self.bytes_writes_overlay.borrow_mut() += *other.bytes_writes_overlay.borrow();
+		*self.reads_backend.borrow_mut() += *other.reads_backend.borrow();
+		*self.bytes_read_backend.borrow_mut() += *other.bytes_read_backend.borrow();
 	}
 }
 
@@ -128,4 +137,10 @@ impl StateMachineStats {
 		*self.writes_overlay.borrow_mut() += 1;
 		*self.bytes_writes_overlay.borrow_mut() += data_bytes;
 	}
+
+	/// Tally one read backend operation, of some length.
+	pub fn tally_read_backend(&self, data_bytes: u64) {
+		*self.reads_backend.borrow_mut() += 1;
+		*self.bytes_read_backend.borrow_mut() += data_bytes;
+	}
 }
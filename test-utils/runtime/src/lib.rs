@@ -949,6 +949,21 @@ cfg_if! {
 					0
 				}
 			}
+
+			impl frame_system_rpc_runtime_api::DryRunApi<Block, AccountId, u64> for Runtime {
+				fn dry_run_extrinsic(
+					extrinsic: <Block as BlockT>::Extrinsic,
+				) -> frame_system_rpc_runtime_api::DryRunEffects<u64> {
+					system::dry_run_extrinsic(extrinsic)
+				}
+
+				fn dry_run_call(
+					origin: AccountId,
+					call: Vec<u8>,
+				) -> frame_system_rpc_runtime_api::DryRunEffects<u64> {
+					system::dry_run_call(origin, call)
+				}
+			}
 		}
 	} else {
 		impl_runtime_apis! {
@@ -1180,6 +1195,21 @@ cfg_if! {
 					0
 				}
 			}
+
+			impl frame_system_rpc_runtime_api::DryRunApi<Block, AccountId, u64> for Runtime {
+				fn dry_run_extrinsic(
+					extrinsic: <Block as BlockT>::Extrinsic,
+				) -> frame_system_rpc_runtime_api::DryRunEffects<u64> {
+					system::dry_run_extrinsic(extrinsic)
+				}
+
+				fn dry_run_call(
+					origin: AccountId,
+					call: Vec<u8>,
+				) -> frame_system_rpc_runtime_api::DryRunEffects<u64> {
+					system::dry_run_call(origin, call)
+				}
+			}
 		}
 	}
 }
@@ -24,6 +24,7 @@ use crate::{
 use codec::{Decode, Encode, KeyedVec};
 use frame_support::{decl_module, decl_storage, storage};
 use frame_system::Config;
+use frame_system_rpc_runtime_api::DryRunEffects;
 use sp_core::storage::well_known_keys;
 use sp_io::{hashing::blake2_256, storage::root as storage_root, trie};
 use sp_runtime::{
@@ -192,6 +193,22 @@ pub fn execute_transaction(utx: Extrinsic) -> ApplyExtrinsicResult {
 	result
 }
 
+/// Apply `utx` on top of the current state, for `DryRunApi`.
+///
+/// The test runtime has neither weights, fees nor events, so only the result is reported.
+pub fn dry_run_extrinsic(utx: Extrinsic) -> DryRunEffects<u64> {
+	DryRunEffects { result: execute_transaction(utx), actual_weight: 0, fee: 0, events: Vec::new() }
+}
+
+/// Enact the encoded `Transfer` as if it was sent by `origin`, without checking any signature.
+pub fn dry_run_call(origin: AccountId, call: Vec<u8>) -> DryRunEffects<u64> {
+	let result = match Transfer::decode(&mut &call[..]) {
+		Ok(transfer) => execute_transfer_backend(&Transfer { from: origin, ..transfer }),
+		Err(_) => Err(InvalidTransaction::Call.into()),
+	};
+	DryRunEffects { result, actual_weight: 0, fee: 0, events: Vec::new() }
+}
+
 /// Finalize the block.
 pub fn finalize_block() -> Header {
 	use sp_core::storage::StateVersion;
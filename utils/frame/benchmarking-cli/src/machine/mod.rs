@@ -18,22 +18,21 @@
 //! Contains the [`MachineCmd`] as entry point for the node
 //! and the core benchmarking logic.
 
-pub mod hardware;
-
 use sc_cli::{CliConfiguration, Result, SharedParams};
 use sc_service::Configuration;
 use sc_sysinfo::{
 	benchmark_cpu, benchmark_disk_random_writes, benchmark_disk_sequential_writes,
-	benchmark_memory, benchmark_sr25519_verify, ExecutionLimit,
+	benchmark_memory, benchmark_sr25519_verify, ExecutionLimit, DEFAULT_TOLERANCE,
 };
 
 use clap::Parser;
 use log::{error, info, warn};
 use prettytable::{cell, row, table};
+use serde::Serialize;
 use std::{boxed::Box, fmt::Debug, fs, path::Path};
 
 use crate::shared::check_build_profile;
-pub use hardware::{Metric, Requirement, Requirements, Throughput, SUBSTRATE_REFERENCE_HARDWARE};
+pub use sc_sysinfo::{Metric, Requirement, Requirements, Throughput, SUBSTRATE_REFERENCE_HARDWARE};
 
 /// Command to benchmark the hardware.
 ///
@@ -59,7 +58,7 @@ pub struct MachineCmd {
 	///
 	/// 10% means that the test would pass even when only 90% score was archived.
 	/// Can be used to mitigate outliers of the benchmarks.
-	#[clap(long, default_value = "10.0", value_name = "PERCENT")]
+	#[clap(long, default_value_t = DEFAULT_TOLERANCE, value_name = "PERCENT")]
 	pub tolerance: f64,
 
 	/// Time limit for the verification benchmark.
@@ -69,6 +68,10 @@ pub struct MachineCmd {
 	/// Time limit for each disk benchmark.
 	#[clap(long, default_value = "5.0", value_name = "SECONDS")]
 	pub disk_duration: f32,
+
+	/// Print the comparison with the requirements as JSON to stdout instead of a table.
+	#[clap(long)]
+	pub json: bool,
 }
 
 /// Helper for the result of a concrete benchmark.
@@ -85,6 +88,29 @@ struct BenchResult {
 	rel_score: f64,
 }
 
+/// Machine readable comparison of the benchmark results with the requirements.
+#[derive(Serialize)]
+struct Report<'a> {
+	/// Did the hardware pass all benchmarks?
+	passed: bool,
+	/// The fault tolerance in percent.
+	tolerance: f64,
+	/// The result of each benchmark.
+	results: Vec<ReportEntry<'a>>,
+}
+
+/// A single benchmark of a [`Report`].
+#[derive(Serialize)]
+struct ReportEntry<'a> {
+	metric: &'a Metric,
+	category: &'static str,
+	function: &'static str,
+	score: Throughput,
+	minimum: &'a Throughput,
+	relative_score: f64,
+	passed: bool,
+}
+
 /// Errors that can be returned by the this command.
 #[derive(Debug, thiserror::Error)]
 #[allow(missing_docs)]
@@ -126,7 +152,7 @@ impl MachineCmd {
 		if rel_score >= 100.0 || rel_score <= 0.01 {
 			self.check_failed(Error::BadResults)?;
 		}
-		let passed = rel_score >= (1.0 - (self.tolerance / 100.0));
+		let passed = requirement.is_met_by(score, self.tolerance);
 		Ok(BenchResult { passed, score, rel_score })
 	}
 
@@ -147,30 +173,42 @@ impl MachineCmd {
 		Ok(score)
 	}
 
-	/// Prints a human-readable summary.
+	/// Prints a human-readable summary, or a JSON report if [`self.json`] is set.
 	fn print_summary(&self, requirements: Requirements, results: Vec<BenchResult>) -> Result<()> {
-		// Use a table for nicer console output.
-		let mut table = table!(["Category", "Function", "Score", "Minimum", "Result"]);
 		// Count how many passed and how many failed.
-		let (mut passed, mut failed) = (0, 0);
-		for (requirement, result) in requirements.0.iter().zip(results.iter()) {
-			if result.passed {
-				passed += 1
-			} else {
-				failed += 1
-			}
+		let passed = results.iter().filter(|result| result.passed).count();
+		let failed = results.len() - passed;
 
-			table.add_row(result.to_row(requirement));
+		if self.json {
+			let report = Report {
+				passed: failed == 0,
+				tolerance: self.tolerance,
+				results: requirements
+					.0
+					.iter()
+					.zip(results.iter())
+					.map(|(requirement, result)| result.to_report_entry(requirement))
+					.collect(),
+			};
+			let json = serde_json::to_string_pretty(&report)
+				.map_err(|e| format!("Failed to serialize the report: {}", e))?;
+			println!("{}", json);
+		} else {
+			// Use a table for nicer console output.
+			let mut table = table!(["Category", "Function", "Score", "Minimum", "Result"]);
+			for (requirement, result) in requirements.0.iter().zip(results.iter()) {
+				table.add_row(result.to_row(requirement));
+			}
+			// Print the table and a summary.
+			info!(
+				"\n{}\nFrom {} benchmarks in total, {} passed and {} failed ({:.0?}% fault tolerance).",
+				table,
+				passed + failed,
+				passed,
+				failed,
+				self.tolerance
+			);
 		}
-		// Print the table and a summary.
-		info!(
-			"\n{}\nFrom {} benchmarks in total, {} passed and {} failed ({:.0?}% fault tolerance).",
-			table,
-			passed + failed,
-			passed,
-			failed,
-			self.tolerance
-		);
 		// Print the final result.
 		if failed != 0 {
 			info!("The hardware fails to meet the requirements");
@@ -206,6 +244,19 @@ impl MachineCmd {
 }
 
 impl BenchResult {
+	/// Format [`Self`] as entry of a JSON [`Report`].
+	fn to_report_entry<'a>(&self, req: &'a Requirement) -> ReportEntry<'a> {
+		ReportEntry {
+			metric: &req.metric,
+			category: req.metric.category(),
+			function: req.metric.name(),
+			score: self.score.normalize(),
+			minimum: &req.minimum,
+			relative_score: self.rel_score,
+			passed: self.passed,
+		}
+	}
+
 	/// Format [`Self`] as row that can be printed in a table.
 	fn to_row(&self, req: &Requirement) -> prettytable::Row {
 		let passed = if self.passed { "✅ Pass" } else { "❌ Fail" };
@@ -0,0 +1,224 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding of blocks with the help of the runtime metadata, see [`MetadataBlockDecoder`].
+
+use crate::metadata::{decode_metadata, decode_value};
+use frame_support::{
+	metadata::{RuntimeMetadataV14, StorageEntryType},
+	scale_info::TypeDef,
+};
+use parity_scale_codec::{Compact, Decode, Encode};
+use sc_client_api::{Backend, StorageProvider};
+use sc_service::chain_ops::{BlockDecoder, DecodedEvent, DecodedExtrinsic};
+use serde_json::{Map, Value};
+use sp_api::{Core, Metadata, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{storage::StorageKey, twox_128};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, Zero},
+};
+use std::{marker::PhantomData, sync::Arc};
+
+/// The only extrinsic format version that can be decoded.
+const EXTRINSIC_VERSION: u8 = 4;
+
+/// A [`BlockDecoder`] that decodes extrinsics and events using the runtime metadata.
+///
+/// The metadata is fetched from the runtime that executed the block and cached as long as the
+/// spec version doesn't change.
+pub struct MetadataBlockDecoder<B, BE, C> {
+	client: Arc<C>,
+	cache: parking_lot::Mutex<Option<(u32, Arc<RuntimeMetadataV14>)>>,
+	_phantom: PhantomData<(B, BE)>,
+}
+
+impl<B, BE, C> MetadataBlockDecoder<B, BE, C>
+where
+	B: BlockT,
+	BE: Backend<B>,
+	C: StorageProvider<B, BE> + HeaderBackend<B> + ProvideRuntimeApi<B>,
+	C::Api: Metadata<B>,
+{
+	/// Create a new decoder reading the metadata and events from `client`.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, cache: Default::default(), _phantom: PhantomData }
+	}
+
+	/// Returns the metadata of the runtime that executed the block with the given `header`.
+	fn metadata(&self, header: &B::Header) -> Result<Arc<RuntimeMetadataV14>, String> {
+		let at = if header.number().is_zero() {
+			BlockId::Hash(header.hash())
+		} else {
+			BlockId::Hash(*header.parent_hash())
+		};
+
+		let api = self.client.runtime_api();
+		let spec_version = api
+			.version(&at)
+			.map_err(|e| format!("Failed to fetch the runtime version: {}", e))?
+			.spec_version;
+
+		let mut cache = self.cache.lock();
+		match &*cache {
+			Some((version, metadata)) if *version == spec_version => Ok(metadata.clone()),
+			_ => {
+				let metadata = api
+					.metadata(&at)
+					.map_err(|e| format!("Failed to fetch the runtime metadata: {}", e))?;
+				let metadata = Arc::new(decode_metadata(&metadata)?);
+				*cache = Some((spec_version, metadata.clone()));
+				Ok(metadata)
+			},
+		}
+	}
+}
+
+impl<B, BE, C> BlockDecoder<B> for MetadataBlockDecoder<B, BE, C>
+where
+	B: BlockT,
+	BE: Backend<B>,
+	C: StorageProvider<B, BE> + HeaderBackend<B> + ProvideRuntimeApi<B>,
+	C::Api: Metadata<B>,
+{
+	fn extrinsics(&self, block: &B) -> Vec<Result<DecodedExtrinsic, String>> {
+		let metadata = self.metadata(block.header());
+
+		block
+			.extrinsics()
+			.iter()
+			.map(|xt| {
+				let metadata = metadata.as_ref().map_err(Clone::clone)?;
+				decode_extrinsic(metadata, &xt.encode())
+					.map_err(|e| format!("Failed to decode extrinsic: {}", e))
+			})
+			.collect()
+	}
+
+	fn events(&self, hash: B::Hash) -> Result<Vec<DecodedEvent>, String> {
+		let header = self
+			.client
+			.header(BlockId::Hash(hash))
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Unknown block {}", hash))?;
+		let metadata = self.metadata(&header)?;
+
+		let ty = metadata
+			.pallets
+			.iter()
+			.find(|p| p.name == "System")
+			.and_then(|p| p.storage.as_ref())
+			.and_then(|s| s.entries.iter().find(|e| e.name == "Events"))
+			.and_then(|e| match &e.ty {
+				StorageEntryType::Plain(ty) => Some(ty.id()),
+				_ => None,
+			})
+			.ok_or("Runtime metadata doesn't contain `System::Events`")?;
+
+		let key = [twox_128(b"System"), twox_128(b"Events")].concat();
+		let events = match self
+			.client
+			.storage(&BlockId::Hash(hash), &StorageKey(key))
+			.map_err(|e| e.to_string())?
+		{
+			Some(events) => events.0,
+			None => return Ok(Vec::new()),
+		};
+
+		let records = match decode_value(&metadata.types, ty, &mut &events[..])? {
+			Value::Array(records) => records,
+			_ => return Err("`System::Events` is not a sequence".into()),
+		};
+
+		Ok(records
+			.into_iter()
+			.map(|record| DecodedEvent {
+				extrinsic_index: record
+					.pointer("/phase/ApplyExtrinsic")
+					.and_then(Value::as_u64)
+					.map(|index| index as u32),
+				value: record,
+			})
+			.collect())
+	}
+}
+
+/// Decode an encoded extrinsic in the format described by the `metadata`.
+fn decode_extrinsic(
+	metadata: &RuntimeMetadataV14,
+	mut input: &[u8],
+) -> Result<DecodedExtrinsic, String> {
+	let registry = &metadata.types;
+	let input = &mut input;
+
+	// Extrinsics are encoded as `Vec<u8>`.
+	let _len = Compact::<u32>::decode(input).map_err(|e| e.to_string())?;
+	let version = u8::decode(input).map_err(|e| e.to_string())?;
+	let is_signed = version & 0b1000_0000 != 0;
+	if version & 0b0111_1111 != EXTRINSIC_VERSION {
+		return Err(format!("Unsupported extrinsic version {}", version & 0b0111_1111))
+	}
+
+	// The type parameters of `UncheckedExtrinsic<Address, Call, Signature, Extra>`.
+	let params = registry
+		.resolve(metadata.extrinsic.ty.id())
+		.ok_or("Unknown extrinsic type")?
+		.type_params()
+		.iter()
+		.map(|param| param.ty().map(|ty| ty.id()))
+		.collect::<Vec<_>>();
+	let param = |name: &str, index: usize| {
+		params
+			.get(index)
+			.copied()
+			.flatten()
+			.ok_or_else(|| format!("Extrinsic type doesn't specify the {} type", name))
+	};
+
+	let mut value = Map::new();
+	if is_signed {
+		value.insert("address".into(), decode_value(registry, param("address", 0)?, input)?);
+		value.insert("signature".into(), decode_value(registry, param("signature", 2)?, input)?);
+		value.insert("extra".into(), decode_value(registry, param("extra", 3)?, input)?);
+	}
+
+	// Look up the names before decoding the call, they are its first two bytes.
+	let (pallet, call) = match **input {
+		[pallet_index, call_index, ..] => {
+			let pallet = metadata.pallets.iter().find(|p| p.index == pallet_index);
+			let call = pallet
+				.and_then(|p| p.calls.as_ref())
+				.and_then(|calls| registry.resolve(calls.ty.id()))
+				.and_then(|ty| match ty.type_def() {
+					TypeDef::Variant(variant) =>
+						variant.variants().iter().find(|v| v.index() == call_index),
+					_ => None,
+				})
+				.map(|v| v.name().clone());
+			(pallet.map(|p| p.name.clone()), call)
+		},
+		_ => (None, None),
+	};
+
+	value.insert("call".into(), decode_value(registry, param("call", 1)?, input)?);
+	if !input.is_empty() {
+		return Err(format!("{} trailing bytes", input.len()))
+	}
+
+	Ok(DecodedExtrinsic { pallet, call, value: Value::Object(value) })
+}
@@ -0,0 +1,201 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of the `db stats` subcommand
+
+use crate::metadata::{child_storage_key_label, decode_metadata, StorageKeyDecoder};
+use clap::Parser;
+use sc_cli::{BlockNumberOrHash, CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+use sc_client_api::{Backend as _, StorageProvider};
+use sc_client_db::ColumnStats;
+use sp_api::{Metadata, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::{well_known_keys, ChildInfo};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, NumberFor},
+};
+use sp_state_machine::{Backend as _, ProofRecorder, ProvingBackend};
+use std::{collections::BTreeMap, fmt::Debug, str::FromStr, sync::Arc};
+
+/// Database related subcommands.
+#[derive(Debug, clap::Subcommand)]
+pub enum DbSubcommand {
+	/// Report the size of the database columns and of the state by pallet.
+	Stats(DbStatsCmd),
+}
+
+/// The `db stats` command
+///
+/// Reports the number and size of the entries of every database column. The state at the given
+/// block is additionally broken down by pallet and storage item, using the runtime metadata of
+/// that block, and every child trie is reported on its own. Besides the raw keys and values,
+/// every storage item is charged the encoded size of the trie nodes that are first visited when
+/// reading its keys in order, so nodes shared between items are counted for the first one only.
+#[derive(Debug, Parser)]
+pub struct DbStatsCmd {
+	/// Block hash or number whose state is broken down. Defaults to the best block.
+	#[clap(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// Only report the database columns and skip the state breakdown.
+	#[clap(long)]
+	pub columns_only: bool,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl DbStatsCmd {
+	/// Run the command
+	pub fn run<B, C>(
+		&self,
+		client: Arc<C>,
+		backend: Arc<sc_client_db::Backend<B>>,
+	) -> sc_cli::Result<()>
+	where
+		B: BlockT,
+		C: StorageProvider<B, sc_client_db::Backend<B>> + HeaderBackend<B> + ProvideRuntimeApi<B>,
+		C::Api: Metadata<B>,
+		B::Hash: FromStr,
+		<B::Hash as FromStr>::Err: Debug,
+		NumberFor<B>: FromStr,
+		<NumberFor<B> as FromStr>::Err: Debug,
+	{
+		println!("{:<16} {:>12} {:>16} {:>16}", "column", "keys", "key bytes", "value bytes");
+		for (name, stats) in backend.column_stats()? {
+			print_stats(name, 16, &stats);
+		}
+
+		if self.columns_only {
+			return Ok(())
+		}
+
+		let at = match &self.at {
+			Some(at) => at.parse::<B>()?,
+			None => BlockId::Hash(client.info().best_hash),
+		};
+
+		let metadata = client
+			.runtime_api()
+			.metadata(&at)
+			.map_err(|e| format!("Failed to fetch the runtime metadata: {}", e))?;
+		let decoder = StorageKeyDecoder::new(&decode_metadata(&metadata)?);
+
+		let state = backend.state_at(at)?;
+		let trie = state.as_trie_backend().ok_or("The state is not backed by a trie")?;
+		// Reads through the proving backend record every trie node they visit, each node only
+		// once, so the growth of the proof is the size of the nodes visited for the first time.
+		let recorder = ProofRecorder::<B::Hash>::default();
+		let proving = ProvingBackend::new_with_recorder(trie, recorder.clone());
+		let mut proof_size = recorder.estimate_encoded_size();
+		let mut new_node_bytes = || {
+			let previous = std::mem::replace(&mut proof_size, recorder.estimate_encoded_size());
+			proof_size.saturating_sub(previous) as u64
+		};
+
+		let mut items = BTreeMap::<String, ItemStats>::new();
+		for key in client.storage_keys_iter(&at, None, None)? {
+			let value = proving.storage(&key.0)?.unwrap_or_default();
+			let item = items.entry(decoder.describe(&key.0)).or_default();
+			item.entries.tally(&key.0, &value);
+			item.node_bytes += new_node_bytes();
+
+			if let Some(child_key) =
+				key.0.strip_prefix(well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX)
+			{
+				let child_info = ChildInfo::new_default(child_key);
+				let item = items
+					.entry(format!("ChildTrie({})", child_storage_key_label(child_key)))
+					.or_default();
+				for child_key in
+					client.child_storage_keys_iter(&at, child_info.clone(), None, None)?
+				{
+					let value =
+						proving.child_storage(&child_info, &child_key.0)?.unwrap_or_default();
+					item.entries.tally(&child_key.0, &value);
+					item.node_bytes += new_node_bytes();
+				}
+			}
+		}
+
+		let mut items = items.into_iter().collect::<Vec<_>>();
+		items.sort_by_key(|(_, item)| std::cmp::Reverse(item.node_bytes));
+
+		println!();
+		println!("State at {}, trie node bytes include the keys and values", at);
+		println!(
+			"{:<48} {:>12} {:>16} {:>16} {:>16}",
+			"storage item", "keys", "key bytes", "value bytes", "trie node bytes"
+		);
+		for (name, item) in &items {
+			println!(
+				"{:<48} {:>12} {:>16} {:>16} {:>16}",
+				name,
+				item.entries.keys,
+				item.entries.key_bytes,
+				item.entries.value_bytes,
+				item.node_bytes,
+			);
+		}
+
+		Ok(())
+	}
+}
+
+/// The state usage of a single storage item.
+#[derive(Default)]
+struct ItemStats {
+	/// The raw keys and values.
+	entries: ColumnStats,
+	/// Encoded size of the trie nodes first visited when reading the entries.
+	node_bytes: u64,
+}
+
+fn print_stats(name: &str, width: usize, stats: &ColumnStats) {
+	println!(
+		"{:<width$} {:>12} {:>16} {:>16}",
+		name,
+		stats.keys,
+		stats.key_bytes,
+		stats.value_bytes,
+		width = width,
+	);
+}
+
+impl CliConfiguration for DbStatsCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
@@ -0,0 +1,308 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of the `diff-spec` subcommand
+
+use crate::metadata::{child_storage_key_label, metadata_from_storage, to_hex, StorageKeyDecoder};
+use clap::Parser;
+use sc_cli::{Error, SubstrateCli};
+use serde::Serialize;
+use sp_core::storage::{well_known_keys, Storage};
+use sp_runtime::BuildStorage;
+use std::collections::BTreeMap;
+
+/// The `diff-spec` command
+///
+/// Compares the genesis storage of two chain specs. Plain specs are built into their raw genesis
+/// storage first, so both kinds can be compared with each other.
+#[derive(Debug, Parser)]
+#[clap(name = "diff-spec", about = "Compare the genesis state of two chain specs")]
+pub struct DiffSpecCmd {
+	/// The chain spec to compare against, either a path or the id of a known chain.
+	pub old: String,
+
+	/// The chain spec that is compared, either a path or the id of a known chain.
+	pub new: String,
+
+	/// Include the old and new values of every reported entry.
+	#[clap(long)]
+	pub values: bool,
+
+	/// Print the report as JSON.
+	#[clap(long)]
+	pub json: bool,
+}
+
+/// A change of a single storage entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryChange {
+	/// The entry only exists in the new state.
+	Added(Vec<u8>),
+	/// The entry only exists in the old state.
+	Removed(Vec<u8>),
+	/// The entry exists in both states with different values.
+	Changed {
+		/// The old value.
+		old: Vec<u8>,
+		/// The new value.
+		new: Vec<u8>,
+	},
+}
+
+/// Differences between two storage states.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StorageDiff {
+	/// Changes to the main trie.
+	pub top: BTreeMap<Vec<u8>, EntryChange>,
+	/// Changes to child tries, keyed by the child storage key.
+	pub children: BTreeMap<Vec<u8>, BTreeMap<Vec<u8>, EntryChange>>,
+}
+
+impl StorageDiff {
+	/// Compute the difference between `old` and `new`.
+	pub fn new(old: &Storage, new: &Storage) -> Self {
+		let empty = BTreeMap::new();
+		let child_keys = old
+			.children_default
+			.keys()
+			.chain(new.children_default.keys())
+			.collect::<std::collections::BTreeSet<_>>();
+
+		let children = child_keys
+			.into_iter()
+			.map(|key| {
+				let old = old.children_default.get(key).map_or(&empty, |c| &c.data);
+				let new = new.children_default.get(key).map_or(&empty, |c| &c.data);
+				(key.clone(), diff_maps(old, new))
+			})
+			.filter(|(_, changes)| !changes.is_empty())
+			.collect();
+
+		Self { top: diff_maps(&old.top, &new.top), children }
+	}
+
+	/// Returns `true` if both states are equal.
+	pub fn is_empty(&self) -> bool {
+		self.top.is_empty() && self.children.is_empty()
+	}
+}
+
+fn diff_maps(
+	old: &BTreeMap<Vec<u8>, Vec<u8>>,
+	new: &BTreeMap<Vec<u8>, Vec<u8>>,
+) -> BTreeMap<Vec<u8>, EntryChange> {
+	let mut changes = BTreeMap::new();
+
+	for (key, old_value) in old {
+		match new.get(key) {
+			None => {
+				changes.insert(key.clone(), EntryChange::Removed(old_value.clone()));
+			},
+			Some(new_value) if new_value != old_value => {
+				changes.insert(
+					key.clone(),
+					EntryChange::Changed { old: old_value.clone(), new: new_value.clone() },
+				);
+			},
+			Some(_) => {},
+		}
+	}
+
+	for (key, new_value) in new {
+		if !old.contains_key(key) {
+			changes.insert(key.clone(), EntryChange::Added(new_value.clone()));
+		}
+	}
+
+	changes
+}
+
+/// A reported entry, as printed in the JSON report.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportEntry {
+	child: Option<String>,
+	item: String,
+	key: String,
+	change: &'static str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	old: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	new: Option<String>,
+}
+
+impl DiffSpecCmd {
+	/// Run the command
+	pub fn run<C: SubstrateCli>(&self, cli: &C) -> Result<(), Error> {
+		let old = cli.load_spec(&self.old)?.build_storage()?;
+		let new = cli.load_spec(&self.new)?.build_storage()?;
+
+		// Prefer the new runtime for naming keys, it is the one that will interpret the state.
+		let decoder = metadata_from_storage(&new)
+			.or_else(|_| metadata_from_storage(&old))
+			.map(|metadata| StorageKeyDecoder::new(&metadata))
+			.unwrap_or_else(|e| {
+				eprintln!("Storage keys are not decoded: {}", e);
+				Default::default()
+			});
+
+		let diff = StorageDiff::new(&old, &new);
+		let entries = self.report(&diff, &decoder);
+
+		if self.json {
+			let json = serde_json::to_string_pretty(&entries)
+				.map_err(|e| format!("Failed to serialize the report: {}", e))?;
+			println!("{}", json);
+			return Ok(())
+		}
+
+		if let Some(EntryChange::Changed { old, new }) = diff.top.get(well_known_keys::CODE) {
+			println!(
+				"Runtime code changed: {} ({} bytes) -> {} ({} bytes)",
+				to_hex(&sp_core::blake2_256(old)),
+				old.len(),
+				to_hex(&sp_core::blake2_256(new)),
+				new.len(),
+			);
+		}
+
+		for entry in &entries {
+			println!("{} {} {}", entry.change, entry.item, entry.key);
+			if let Some(old) = &entry.old {
+				println!("    old: {}", old);
+			}
+			if let Some(new) = &entry.new {
+				println!("    new: {}", new);
+			}
+		}
+
+		let count = |change| entries.iter().filter(|e| e.change == change).count();
+		println!(
+			"{} added, {} removed, {} changed",
+			count("added"),
+			count("removed"),
+			count("changed"),
+		);
+
+		Ok(())
+	}
+
+	fn report(&self, diff: &StorageDiff, decoder: &StorageKeyDecoder) -> Vec<ReportEntry> {
+		let top = diff.top.iter().map(|(key, change)| (None, key, change));
+		let children = diff.children.iter().flat_map(|(child, changes)| {
+			changes.iter().map(move |(key, change)| (Some(child), key, change))
+		});
+
+		top.chain(children)
+			.map(|(child, key, change)| {
+				let (change, old, new) = match change {
+					EntryChange::Added(new) => ("added", None, Some(new)),
+					EntryChange::Removed(old) => ("removed", Some(old), None),
+					EntryChange::Changed { old, new } => ("changed", Some(old), Some(new)),
+				};
+				// Entries of child tries are not described by the metadata, name them after the
+				// child trie instead.
+				let item = match child {
+					Some(child) => format!("ChildTrie({})", child_storage_key_label(child)),
+					None => decoder.describe(key),
+				};
+
+				ReportEntry {
+					child: child.map(|c| to_hex(c)),
+					item,
+					key: to_hex(key),
+					change,
+					old: old.filter(|_| self.values).map(|v| to_hex(v)),
+					new: new.filter(|_| self.values).map(|v| to_hex(v)),
+				}
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::storage::{ChildInfo, StorageChild};
+
+	fn storage(top: &[(&[u8], &[u8])], child: Option<(&[u8], &[(&[u8], &[u8])])>) -> Storage {
+		let map = |entries: &[(&[u8], &[u8])]| {
+			entries
+				.iter()
+				.map(|(k, v)| (k.to_vec(), v.to_vec()))
+				.collect::<BTreeMap<_, _>>()
+		};
+
+		Storage {
+			top: map(top),
+			children_default: child
+				.into_iter()
+				.map(|(key, data)| {
+					let child_info = ChildInfo::new_default(key);
+					(key.to_vec(), StorageChild { data: map(data), child_info })
+				})
+				.collect(),
+		}
+	}
+
+	#[test]
+	fn diff_reports_added_removed_and_changed_entries() {
+		let old = storage(&[(b"a", b"1"), (b"b", b"2"), (b"c", b"3")], None);
+		let new = storage(&[(b"a", b"1"), (b"b", b"4"), (b"d", b"5")], None);
+
+		let diff = StorageDiff::new(&old, &new);
+
+		assert_eq!(
+			diff.top.into_iter().collect::<Vec<_>>(),
+			vec![
+				(b"b".to_vec(), EntryChange::Changed { old: b"2".to_vec(), new: b"4".to_vec() }),
+				(b"c".to_vec(), EntryChange::Removed(b"3".to_vec())),
+				(b"d".to_vec(), EntryChange::Added(b"5".to_vec())),
+			],
+		);
+		assert!(diff.children.is_empty());
+	}
+
+	#[test]
+	fn diff_includes_child_tries() {
+		let old = storage(&[], Some((b"child", &[(b"a", b"1")])));
+		let new = storage(&[], None);
+
+		let diff = StorageDiff::new(&old, &new);
+
+		assert!(diff.top.is_empty());
+		assert_eq!(
+			diff.children.get(&b"child"[..]).unwrap().get(&b"a"[..]),
+			Some(&EntryChange::Removed(b"1".to_vec())),
+		);
+		assert!(StorageDiff::new(&old, &old).is_empty());
+	}
+
+	#[test]
+	fn child_trie_entries_are_labeled_with_the_child_storage_key() {
+		let old = storage(&[], Some((b"child", &[(b"a", b"1")])));
+		let new = storage(&[], Some((b"child", &[(b"a", b"2")])));
+		let cmd = DiffSpecCmd { old: "old".into(), new: "new".into(), values: false, json: true };
+
+		let entries = cmd.report(&StorageDiff::new(&old, &new), &Default::default());
+
+		assert_eq!(entries.len(), 1);
+		assert_eq!(entries[0].item, "ChildTrie(child)");
+		assert_eq!(entries[0].child.as_deref(), Some("0x6368696c64"));
+		assert_eq!(child_storage_key_label(&[0, 1]), "0x0001");
+	}
+}
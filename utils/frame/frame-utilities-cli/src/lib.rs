@@ -17,6 +17,13 @@
 
 //! frame-system CLI utilities
 
+mod block_decoder;
+mod db_stats;
+mod diff_spec;
+pub mod metadata;
 mod pallet_id;
 
+pub use block_decoder::MetadataBlockDecoder;
+pub use db_stats::{DbStatsCmd, DbSubcommand};
+pub use diff_spec::{DiffSpecCmd, EntryChange, StorageDiff};
 pub use pallet_id::PalletIdCmd;
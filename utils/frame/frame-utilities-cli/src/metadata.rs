@@ -0,0 +1,361 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers to interpret raw runtime storage with the help of the runtime metadata.
+
+use frame_support::{
+	metadata::{RuntimeMetadata, RuntimeMetadataPrefixed, RuntimeMetadataV14},
+	scale_info::{form::PortableForm, Field, PortableRegistry, TypeDef, TypeDefPrimitive},
+};
+use parity_scale_codec::{Compact, Decode};
+use sc_executor::{WasmExecutionMethod, WasmExecutor};
+use serde_json::{Map, Value};
+use sp_core::{
+	hexdisplay::HexDisplay,
+	storage::{well_known_keys, Storage},
+	traits::{CodeExecutor, RuntimeCode, WrappedRuntimeCode},
+	twox_128, NeverNativeValue,
+};
+use sp_state_machine::BasicExternalities;
+use std::{collections::BTreeMap, fmt};
+
+/// Decode SCALE encoded `RuntimeMetadataPrefixed` into the V14 metadata.
+pub fn decode_metadata(mut encoded: &[u8]) -> Result<RuntimeMetadataV14, String> {
+	let metadata = RuntimeMetadataPrefixed::decode(&mut encoded)
+		.map_err(|e| format!("Failed to decode runtime metadata: {}", e))?;
+
+	match metadata.1 {
+		RuntimeMetadata::V14(metadata) => Ok(metadata),
+		_ => Err("Only V14 runtime metadata is supported".into()),
+	}
+}
+
+/// Fetch the metadata of the runtime found under `:code` in the given genesis `storage`.
+///
+/// The runtime is executed with the wasm interpreter, no native runtime is required.
+pub fn metadata_from_storage(storage: &Storage) -> Result<RuntimeMetadataV14, String> {
+	let code = storage
+		.top
+		.get(well_known_keys::CODE)
+		.ok_or("Storage does not contain the runtime code")?;
+	let runtime_code = RuntimeCode {
+		code_fetcher: &WrappedRuntimeCode(code.as_slice().into()),
+		hash: sp_core::blake2_256(code).to_vec(),
+		heap_pages: None,
+	};
+
+	let executor = WasmExecutor::<sp_io::SubstrateHostFunctions>::new(
+		WasmExecutionMethod::Interpreted,
+		None,
+		1,
+		None,
+		1,
+	);
+	let mut ext = BasicExternalities::new(storage.clone());
+	let encoded = executor
+		.call::<NeverNativeValue, fn() -> _>(
+			&mut ext,
+			&runtime_code,
+			"Metadata_metadata",
+			&[],
+			false,
+			None,
+		)
+		.0
+		.map_err(|e| format!("Failed to call `Metadata_metadata`: {}", e))?
+		.into_encoded();
+
+	// The runtime returns `OpaqueMetadata`, which is an encoded `Vec<u8>` holding the metadata.
+	let opaque = Vec::<u8>::decode(&mut &encoded[..])
+		.map_err(|e| format!("Failed to decode opaque metadata: {}", e))?;
+	decode_metadata(&opaque)
+}
+
+/// A storage item declared by a pallet.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct StorageItem {
+	/// Storage prefix of the pallet, usually the pallet name.
+	pub pallet: String,
+	/// Name of the storage item.
+	pub item: String,
+}
+
+impl fmt::Display for StorageItem {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}::{}", self.pallet, self.item)
+	}
+}
+
+/// Maps raw storage keys to the pallet storage items they belong to.
+///
+/// FRAME storage keys start with `twox128(pallet_prefix) ++ twox128(item_name)`, which is used
+/// to look up the item. Keys that don't follow this layout are reported as unknown.
+#[derive(Debug, Default, Clone)]
+pub struct StorageKeyDecoder {
+	pallets: BTreeMap<[u8; 16], String>,
+	items: BTreeMap<[u8; 32], StorageItem>,
+}
+
+impl StorageKeyDecoder {
+	/// Create a new decoder from the given runtime `metadata`.
+	pub fn new(metadata: &RuntimeMetadataV14) -> Self {
+		let mut decoder = Self::default();
+
+		for storage in metadata.pallets.iter().filter_map(|p| p.storage.as_ref()) {
+			let pallet_hash = twox_128(storage.prefix.as_bytes());
+			decoder.pallets.insert(pallet_hash, storage.prefix.clone());
+
+			for entry in &storage.entries {
+				let mut prefix = [0u8; 32];
+				prefix[..16].copy_from_slice(&pallet_hash);
+				prefix[16..].copy_from_slice(&twox_128(entry.name.as_bytes()));
+
+				decoder.items.insert(
+					prefix,
+					StorageItem { pallet: storage.prefix.clone(), item: entry.name.clone() },
+				);
+			}
+		}
+
+		decoder
+	}
+
+	/// Returns the storage item the given `key` belongs to.
+	pub fn item(&self, key: &[u8]) -> Option<&StorageItem> {
+		let prefix: [u8; 32] = key.get(..32)?.try_into().ok()?;
+		self.items.get(&prefix)
+	}
+
+	/// Returns the name of the pallet the given `key` belongs to.
+	pub fn pallet(&self, key: &[u8]) -> Option<&str> {
+		let prefix: [u8; 16] = key.get(..16)?.try_into().ok()?;
+		self.pallets.get(&prefix).map(String::as_str)
+	}
+
+	/// Returns a human readable description of the owner of `key`.
+	///
+	/// This is either `Pallet::Item`, `Pallet::?` if only the pallet is known, the key itself for
+	/// well known keys like `:code` or `?` if nothing is known about the key.
+	pub fn describe(&self, key: &[u8]) -> String {
+		if let Some(item) = self.item(key) {
+			item.to_string()
+		} else if let Some(pallet) = self.pallet(key) {
+			format!("{}::?", pallet)
+		} else if key.starts_with(b":") {
+			String::from_utf8_lossy(key).into_owned()
+		} else {
+			"?".into()
+		}
+	}
+}
+
+/// Format `data` as `0x` prefixed hex string.
+pub(crate) fn to_hex(data: &[u8]) -> String {
+	format!("0x{}", HexDisplay::from(&data))
+}
+
+/// Returns the child storage key as text if it is printable, otherwise as hex.
+pub(crate) fn child_storage_key_label(key: &[u8]) -> String {
+	match std::str::from_utf8(key) {
+		Ok(key) if !key.is_empty() && !key.chars().any(char::is_control) => key.into(),
+		_ => to_hex(key),
+	}
+}
+
+/// Decode a SCALE encoded value of type `ty` from `input` into JSON, using the type information
+/// in `registry`.
+///
+/// Composites with named fields become objects, tuples and composites with unnamed fields
+/// become arrays, newtypes are unwrapped. Variants without fields are encoded as their name,
+/// all other variants as object with the name as single key. Byte sequences are encoded as hex
+/// and integers that don't fit into a JSON number as strings.
+pub fn decode_value(
+	registry: &PortableRegistry,
+	ty: u32,
+	input: &mut &[u8],
+) -> Result<Value, String> {
+	let resolved = registry.resolve(ty).ok_or_else(|| format!("Unknown type id {}", ty))?;
+
+	match resolved.type_def() {
+		TypeDef::Composite(composite) => decode_fields(registry, composite.fields(), input),
+		TypeDef::Variant(variant) => {
+			let index = u8::decode(input).map_err(|e| e.to_string())?;
+			let variant = variant
+				.variants()
+				.iter()
+				.find(|v| v.index() == index)
+				.ok_or_else(|| format!("Unknown variant index {} of type id {}", index, ty))?;
+
+			if variant.fields().is_empty() {
+				Ok(Value::String(variant.name().clone()))
+			} else {
+				let mut map = Map::new();
+				map.insert(
+					variant.name().clone(),
+					decode_fields(registry, variant.fields(), input)?,
+				);
+				Ok(Value::Object(map))
+			}
+		},
+		TypeDef::Sequence(sequence) => {
+			let len = Compact::<u32>::decode(input).map_err(|e| e.to_string())?.0;
+			decode_items(registry, sequence.type_param().id(), len, input)
+		},
+		TypeDef::Array(array) =>
+			decode_items(registry, array.type_param().id(), array.len(), input),
+		TypeDef::Tuple(tuple) => tuple
+			.fields()
+			.iter()
+			.map(|field| decode_value(registry, field.id(), input))
+			.collect::<Result<_, _>>()
+			.map(Value::Array),
+		TypeDef::Primitive(primitive) => decode_primitive(primitive, input),
+		TypeDef::Compact(compact) => decode_compact(registry, compact.type_param().id(), input),
+		TypeDef::BitSequence(_) => Err(format!("Bit sequences are not supported (type id {})", ty)),
+	}
+}
+
+fn decode_fields(
+	registry: &PortableRegistry,
+	fields: &[Field<PortableForm>],
+	input: &mut &[u8],
+) -> Result<Value, String> {
+	match fields {
+		[] => Ok(Value::Null),
+		[field] if field.name().is_none() => decode_value(registry, field.ty().id(), input),
+		fields if fields.iter().all(|f| f.name().is_some()) => {
+			let mut map = Map::new();
+			for field in fields {
+				let name = field.name().cloned().unwrap_or_default();
+				map.insert(name, decode_value(registry, field.ty().id(), input)?);
+			}
+			Ok(Value::Object(map))
+		},
+		fields => fields
+			.iter()
+			.map(|field| decode_value(registry, field.ty().id(), input))
+			.collect::<Result<_, _>>()
+			.map(Value::Array),
+	}
+}
+
+fn decode_items(
+	registry: &PortableRegistry,
+	ty: u32,
+	len: u32,
+	input: &mut &[u8],
+) -> Result<Value, String> {
+	let is_byte = matches!(
+		registry.resolve(ty).map(|t| t.type_def()),
+		Some(TypeDef::Primitive(TypeDefPrimitive::U8))
+	);
+
+	if is_byte {
+		let len = len as usize;
+		if input.len() < len {
+			return Err("Not enough data to decode bytes".into())
+		}
+		let (bytes, rest) = input.split_at(len);
+		*input = rest;
+		return Ok(Value::String(to_hex(bytes)))
+	}
+
+	(0..len)
+		.map(|_| decode_value(registry, ty, input))
+		.collect::<Result<_, _>>()
+		.map(Value::Array)
+}
+
+fn decode_primitive(primitive: &TypeDefPrimitive, input: &mut &[u8]) -> Result<Value, String> {
+	fn decode<T: Decode>(input: &mut &[u8]) -> Result<T, String> {
+		T::decode(input).map_err(|e| e.to_string())
+	}
+
+	Ok(match primitive {
+		TypeDefPrimitive::Bool => decode::<bool>(input)?.into(),
+		TypeDefPrimitive::Char =>
+			char::from_u32(decode::<u32>(input)?).ok_or("Invalid char")?.to_string().into(),
+		TypeDefPrimitive::Str => decode::<String>(input)?.into(),
+		TypeDefPrimitive::U8 => decode::<u8>(input)?.into(),
+		TypeDefPrimitive::U16 => decode::<u16>(input)?.into(),
+		TypeDefPrimitive::U32 => decode::<u32>(input)?.into(),
+		TypeDefPrimitive::U64 => decode::<u64>(input)?.into(),
+		TypeDefPrimitive::U128 => decode::<u128>(input)?.to_string().into(),
+		TypeDefPrimitive::I8 => decode::<i8>(input)?.into(),
+		TypeDefPrimitive::I16 => decode::<i16>(input)?.into(),
+		TypeDefPrimitive::I32 => decode::<i32>(input)?.into(),
+		TypeDefPrimitive::I64 => decode::<i64>(input)?.into(),
+		TypeDefPrimitive::I128 => decode::<i128>(input)?.to_string().into(),
+		TypeDefPrimitive::U256 => to_hex(&decode::<[u8; 32]>(input)?).into(),
+		TypeDefPrimitive::I256 => to_hex(&decode::<[u8; 32]>(input)?).into(),
+	})
+}
+
+fn decode_compact(
+	registry: &PortableRegistry,
+	ty: u32,
+	input: &mut &[u8],
+) -> Result<Value, String> {
+	let resolved = registry.resolve(ty).ok_or_else(|| format!("Unknown type id {}", ty))?;
+
+	match resolved.type_def() {
+		TypeDef::Primitive(
+			TypeDefPrimitive::U8 |
+			TypeDefPrimitive::U16 |
+			TypeDefPrimitive::U32 |
+			TypeDefPrimitive::U64,
+		) => Ok(Compact::<u64>::decode(input).map_err(|e| e.to_string())?.0.into()),
+		TypeDef::Primitive(TypeDefPrimitive::U128) =>
+			Ok(Compact::<u128>::decode(input).map_err(|e| e.to_string())?.0.to_string().into()),
+		// Compact wrappers like `Perbill` are encoded as their single field.
+		TypeDef::Composite(composite) if composite.fields().len() == 1 =>
+			decode_compact(registry, composite.fields()[0].ty().id(), input),
+		TypeDef::Composite(composite) if composite.fields().is_empty() => Ok(Value::Null),
+		_ => Err(format!("Type id {} can not be compact encoded", ty)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::scale_info::{meta_type, Registry, TypeInfo};
+	use parity_scale_codec::Encode;
+	use serde_json::json;
+
+	fn decode<T: TypeInfo + Encode + 'static>(value: T) -> Value {
+		let mut registry = Registry::new();
+		let ty = registry.register_type(&meta_type::<T>()).id();
+		let registry = PortableRegistry::from(registry);
+
+		let encoded = value.encode();
+		let mut input = &encoded[..];
+		let decoded = decode_value(&registry, ty, &mut input).unwrap();
+		assert!(input.is_empty());
+		decoded
+	}
+
+	#[test]
+	fn decode_value_works() {
+		assert_eq!(decode((1u32, true, u128::MAX)), json!([1, true, u128::MAX.to_string()]));
+		assert_eq!(decode(vec![1u8, 2, 3]), json!("0x010203"));
+		assert_eq!(decode(vec![1u16, 2]), json!([1, 2]));
+		assert_eq!(decode(Some(5u64)), json!({ "Some": 5 }));
+		assert_eq!(decode(Option::<u64>::None), json!("None"));
+		assert_eq!(decode(Compact(1_000_000u64)), json!(1_000_000));
+		assert_eq!(decode("hello".to_string()), json!("hello"));
+	}
+}
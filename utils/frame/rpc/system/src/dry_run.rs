@@ -0,0 +1,405 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! RPC methods to apply an extrinsic without submitting it and report all of its effects.
+//!
+//! Events are returned as [`DryRunEventRecord`]s, which identify the pallet and the event while
+//! leaving the event fields SCALE-encoded, as decoding them requires the runtime metadata.
+
+use std::{fmt::Display, marker::PhantomData, sync::Arc};
+
+use codec::{Codec, Decode};
+use jsonrpsee::{
+	core::{async_trait, RpcResult},
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use serde::{Deserialize, Serialize};
+
+use sc_client_api::backend::Backend;
+use sc_rpc_api::DenyUnsafe;
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	storage::{StorageData, StorageKey},
+	Bytes,
+};
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{generic::BlockId, traits, ApplyExtrinsicResult};
+
+pub use frame_system_rpc_runtime_api::{DryRunApi, DryRunEffects, DryRunEvent};
+
+use crate::Error;
+
+/// A list of storage changes, where `None` means that the key was removed.
+pub type StorageChangeSet = Vec<(StorageKey, Option<StorageData>)>;
+
+/// The effects of a dry run, as returned over RPC.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunOutcome {
+	/// The validity of the extrinsic and, if valid, the dispatch result.
+	pub result: ApplyExtrinsicResult,
+	/// The weight actually consumed by the dispatch.
+	pub actual_weight: u64,
+	/// The fee that would be charged, excluding any tip.
+	pub fee: NumberOrHex,
+	/// The events emitted while applying.
+	pub events: Vec<DryRunEventRecord>,
+	/// The changes made to the main storage.
+	pub storage_changes: StorageChangeSet,
+	/// The changes made to child storages, grouped by child storage key.
+	pub child_storage_changes: Vec<(StorageKey, StorageChangeSet)>,
+}
+
+/// An event emitted during a dry run, as returned over RPC.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunEventRecord {
+	/// Index of the extrinsic that emitted the event, `None` if it wasn't emitted by an
+	/// extrinsic.
+	pub extrinsic_index: Option<u32>,
+	/// Index of the emitting pallet in the runtime metadata.
+	pub pallet_index: u8,
+	/// Index of the event variant in the pallet's event type.
+	pub event_index: u8,
+	/// The SCALE-encoded fields of the event, described by the runtime metadata.
+	pub fields: Bytes,
+	/// The SCALE-encoded topics of the event.
+	pub topics: Vec<Bytes>,
+}
+
+impl From<DryRunEvent> for DryRunEventRecord {
+	fn from(event: DryRunEvent) -> Self {
+		Self {
+			extrinsic_index: event.extrinsic_index,
+			pallet_index: event.pallet_index,
+			event_index: event.event_index,
+			fields: event.fields.into(),
+			topics: event.topics.into_iter().map(Into::into).collect(),
+		}
+	}
+}
+
+/// System RPC methods for dry running extrinsics.
+#[rpc(client, server)]
+pub trait SystemDryRunApi<BlockHash, AccountId> {
+	/// Apply an extrinsic on top of the given block, or the best block if none is given, without
+	/// submitting it to the pool and return all of its effects.
+	///
+	/// The event fields are SCALE-encoded, see [`DryRunEventRecord::fields`].
+	#[method(name = "system_dryRunWithEffects")]
+	async fn dry_run_with_effects(
+		&self,
+		extrinsic: Bytes,
+		at: Option<BlockHash>,
+	) -> RpcResult<DryRunOutcome>;
+
+	/// Dispatch a SCALE-encoded call as if it was signed by `origin` on top of the given block,
+	/// or the best block if none is given, and return all of its effects.
+	///
+	/// No signature, nonce or fee payment checks are performed.
+	#[method(name = "system_dryRunCallWithEffects")]
+	async fn dry_run_call_with_effects(
+		&self,
+		origin: AccountId,
+		call: Bytes,
+		at: Option<BlockHash>,
+	) -> RpcResult<DryRunOutcome>;
+}
+
+/// An implementation of the dry run RPC methods on full client.
+pub struct SystemDryRun<C, BE, Block, Balance> {
+	client: Arc<C>,
+	backend: Arc<BE>,
+	deny_unsafe: DenyUnsafe,
+	_marker: PhantomData<(Block, Balance)>,
+}
+
+impl<C, BE, Block, Balance> SystemDryRun<C, BE, Block, Balance> {
+	/// Create new `SystemDryRun` given client and backend.
+	pub fn new(client: Arc<C>, backend: Arc<BE>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { client, backend, deny_unsafe, _marker: Default::default() }
+	}
+}
+
+impl<C, BE, Block, Balance> SystemDryRun<C, BE, Block, Balance>
+where
+	Block: traits::Block,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: ApiExt<Block, StateBackend = BE::State>,
+	BE: Backend<Block>,
+	Balance: Display + TryInto<NumberOrHex>,
+{
+	/// Run `f` against a fresh runtime api instance at `at` and collect the storage changes it
+	/// made in its overlay.
+	fn dry_run<F>(&self, at: Option<Block::Hash>, f: F) -> RpcResult<DryRunOutcome>
+	where
+		F: FnOnce(&C::Api, &BlockId<Block>) -> Result<DryRunEffects<Balance>, sp_api::ApiError>,
+	{
+		self.deny_unsafe.check_if_safe()?;
+		// If the block hash is not supplied assume the best block.
+		let hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		let at = BlockId::<Block>::hash(hash);
+
+		let runtime_error = |e: String| {
+			CallError::Custom(ErrorObject::owned(
+				Error::RuntimeError.into(),
+				"Unable to dry run extrinsic.",
+				Some(e),
+			))
+		};
+
+		let state = self.backend.state_at(at).map_err(|e| runtime_error(e.to_string()))?;
+		let api = self.client.runtime_api();
+		let effects = f(&api, &at).map_err(|e| runtime_error(e.to_string()))?;
+		let changes = api.into_storage_changes(&state, hash).map_err(runtime_error)?;
+
+		let fee = effects.fee.to_string();
+		let fee = effects.fee.try_into().map_err(|_| {
+			runtime_error(format!("{} doesn't fit in NumberOrHex representation", fee))
+		})?;
+		let into_change_set = |changes: Vec<(Vec<u8>, Option<Vec<u8>>)>| {
+			changes
+				.into_iter()
+				.map(|(key, value)| (StorageKey(key), value.map(StorageData)))
+				.collect::<StorageChangeSet>()
+		};
+
+		Ok(DryRunOutcome {
+			result: effects.result,
+			actual_weight: effects.actual_weight,
+			fee,
+			events: effects.events.into_iter().map(Into::into).collect(),
+			storage_changes: into_change_set(changes.main_storage_changes),
+			child_storage_changes: changes
+				.child_storage_changes
+				.into_iter()
+				.map(|(child_key, changes)| (StorageKey(child_key), into_change_set(changes)))
+				.collect(),
+		})
+	}
+}
+
+#[async_trait]
+impl<C, BE, Block, AccountId, Balance>
+	SystemDryRunApiServer<<Block as traits::Block>::Hash, AccountId>
+	for SystemDryRun<C, BE, Block, Balance>
+where
+	Block: traits::Block,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: ApiExt<Block, StateBackend = BE::State> + DryRunApi<Block, AccountId, Balance>,
+	BE: Backend<Block> + Send + Sync + 'static,
+	AccountId: Codec + Send + 'static,
+	Balance: Codec + Display + TryInto<NumberOrHex> + Send + Sync + 'static,
+{
+	async fn dry_run_with_effects(
+		&self,
+		extrinsic: Bytes,
+		at: Option<<Block as traits::Block>::Hash>,
+	) -> RpcResult<DryRunOutcome> {
+		let uxt: <Block as traits::Block>::Extrinsic =
+			Decode::decode(&mut &*extrinsic).map_err(|e| {
+				CallError::Custom(ErrorObject::owned(
+					Error::DecodeError.into(),
+					"Unable to dry run extrinsic.",
+					Some(e.to_string()),
+				))
+			})?;
+
+		self.dry_run(at, |api, at| api.dry_run_extrinsic(at, uxt))
+	}
+
+	async fn dry_run_call_with_effects(
+		&self,
+		origin: AccountId,
+		call: Bytes,
+		at: Option<<Block as traits::Block>::Hash>,
+	) -> RpcResult<DryRunOutcome> {
+		self.dry_run(at, |api, at| api.dry_run_call(at, origin, call.0))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use assert_matches::assert_matches;
+	use codec::Encode;
+	use jsonrpsee::core::Error as JsonRpseeError;
+	use sc_client_api::StorageProvider;
+	use sp_core::hashing::blake2_256;
+	use sp_runtime::transaction_validity::InvalidTransaction;
+	use substrate_test_runtime_client::{
+		prelude::*,
+		runtime::{system::balance_of_key, AccountId, Block, Transfer},
+		Backend,
+	};
+
+	type TestDryRun = SystemDryRun<TestClient, Backend, Block, u64>;
+	type Hash = <Block as traits::Block>::Hash;
+
+	async fn dry_run_extrinsic(
+		dry_run: &TestDryRun,
+		extrinsic: Vec<u8>,
+	) -> RpcResult<DryRunOutcome> {
+		SystemDryRunApiServer::<Hash, AccountId>::dry_run_with_effects(
+			dry_run,
+			extrinsic.into(),
+			None,
+		)
+		.await
+	}
+
+	async fn dry_run_call(
+		dry_run: &TestDryRun,
+		origin: AccountKeyring,
+		call: Vec<u8>,
+	) -> RpcResult<DryRunOutcome> {
+		SystemDryRunApiServer::<Hash, AccountId>::dry_run_call_with_effects(
+			dry_run,
+			origin.into(),
+			call.into(),
+			None,
+		)
+		.await
+	}
+
+	fn dry_run_rpc(deny_unsafe: DenyUnsafe) -> (Arc<TestClient>, TestDryRun) {
+		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
+		let client = Arc::new(builder.build());
+		(client.clone(), SystemDryRun::new(client, backend, deny_unsafe))
+	}
+
+	fn balance_key(who: AccountKeyring) -> StorageKey {
+		StorageKey(blake2_256(&balance_of_key(who.into())).to_vec())
+	}
+
+	fn balance_at_genesis(client: &TestClient, who: AccountKeyring) -> u64 {
+		let balance = client.storage(&BlockId::Number(0), &balance_key(who)).unwrap().unwrap();
+		u64::decode(&mut &balance.0[..]).unwrap()
+	}
+
+	fn changed_balance(outcome: &DryRunOutcome, who: AccountKeyring) -> Option<u64> {
+		let key = balance_key(who);
+		outcome
+			.storage_changes
+			.iter()
+			.find(|(k, _)| *k == key)
+			.map(|(_, value)| u64::decode(&mut &value.as_ref().unwrap().0[..]).unwrap())
+	}
+
+	#[test]
+	fn dry_run_event_records_serialize_their_parts() {
+		let event = DryRunEvent {
+			extrinsic_index: Some(1),
+			pallet_index: 4,
+			event_index: 2,
+			fields: vec![0xab, 0xcd],
+			topics: vec![vec![0x01]],
+		};
+
+		let json = serde_json::to_value(DryRunEventRecord::from(event)).unwrap();
+
+		assert_eq!(
+			json,
+			serde_json::json!({
+				"extrinsicIndex": 1,
+				"palletIndex": 4,
+				"eventIndex": 2,
+				"fields": "0xabcd",
+				"topics": ["0x01"],
+			})
+		);
+	}
+
+	#[tokio::test]
+	async fn dry_run_with_effects_should_deny_unsafe() {
+		let (_, dry_run) = dry_run_rpc(DenyUnsafe::Yes);
+
+		let res = dry_run_extrinsic(&dry_run, vec![]).await;
+		assert_matches!(res, Err(JsonRpseeError::Call(CallError::Custom(e))) => {
+			assert!(e.message().contains("RPC call is unsafe to be called externally"));
+		});
+	}
+
+	#[tokio::test]
+	async fn dry_run_with_effects_reports_storage_changes() {
+		let (client, dry_run) = dry_run_rpc(DenyUnsafe::No);
+		let bob_balance = balance_at_genesis(&client, AccountKeyring::Bob);
+
+		let tx = Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Bob.into(),
+			amount: 5,
+			nonce: 0,
+		}
+		.into_signed_tx();
+
+		let outcome = dry_run_extrinsic(&dry_run, tx.encode()).await.unwrap();
+
+		assert_eq!(outcome.result, Ok(Ok(())));
+		assert_eq!(changed_balance(&outcome, AccountKeyring::Bob), Some(bob_balance + 5));
+		assert!(outcome.child_storage_changes.is_empty());
+		// Nothing is committed to the chain.
+		assert_eq!(balance_at_genesis(&client, AccountKeyring::Bob), bob_balance);
+	}
+
+	#[tokio::test]
+	async fn dry_run_with_effects_reports_invalid_extrinsics() {
+		let (_, dry_run) = dry_run_rpc(DenyUnsafe::No);
+
+		let tx = Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Bob.into(),
+			amount: 5,
+			nonce: 100,
+		}
+		.into_signed_tx();
+
+		let outcome = dry_run_extrinsic(&dry_run, tx.encode()).await.unwrap();
+
+		assert_eq!(outcome.result, Err(InvalidTransaction::Stale.into()));
+		assert_eq!(changed_balance(&outcome, AccountKeyring::Bob), None);
+	}
+
+	#[tokio::test]
+	async fn dry_run_call_with_effects_dispatches_from_origin() {
+		let (client, dry_run) = dry_run_rpc(DenyUnsafe::No);
+		let charlie_balance = balance_at_genesis(&client, AccountKeyring::Charlie);
+		let bob_balance = balance_at_genesis(&client, AccountKeyring::Bob);
+
+		// The sender of the transfer is replaced by the given origin.
+		let call = Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Bob.into(),
+			amount: 5,
+			nonce: 0,
+		};
+
+		let outcome = dry_run_call(&dry_run, AccountKeyring::Charlie, call.encode()).await.unwrap();
+
+		assert_eq!(outcome.result, Ok(Ok(())));
+		assert_eq!(changed_balance(&outcome, AccountKeyring::Charlie), Some(charlie_balance - 5));
+		assert_eq!(changed_balance(&outcome, AccountKeyring::Bob), Some(bob_balance + 5));
+		assert_eq!(changed_balance(&outcome, AccountKeyring::Alice), None);
+
+		let outcome = dry_run_call(&dry_run, AccountKeyring::Charlie, vec![1]).await.unwrap();
+		assert_eq!(outcome.result, Err(InvalidTransaction::Call.into()));
+	}
+}
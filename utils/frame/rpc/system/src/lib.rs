@@ -34,8 +34,14 @@ use sp_blockchain::HeaderBackend;
 use sp_core::{hexdisplay::HexDisplay, Bytes};
 use sp_runtime::{generic::BlockId, legacy, traits};
 
+pub use dry_run::{
+	DryRunApi, DryRunEffects, DryRunOutcome, StorageChangeSet, SystemDryRun, SystemDryRunApiClient,
+	SystemDryRunApiServer,
+};
 pub use frame_system_rpc_runtime_api::AccountNonceApi;
 
+mod dry_run;
+
 /// System RPC methods.
 #[rpc(client, server)]
 pub trait SystemApi<BlockHash, AccountId, Index> {